@@ -1,18 +1,21 @@
 use crate::common::CliError;
 
-use anyhow::anyhow;
+use anyhow::{anyhow, Context};
 use capstone::{
     arch::arm::ArchMode as armArchMode, arch::riscv::ArchMode as riscvArchMode, prelude::*,
     Capstone, Endian,
 };
 use num_traits::Num;
 use probe_rs::{
-    architecture::arm::Dump,
-    debug::{debug_info::DebugInfo, registers::Registers, stack_frame::StackFrame, VariableName},
-    Core, CoreRegisterAddress, CoreType, InstructionSet, MemoryInterface,
+    debug::{
+        debug_info::DebugInfo, evaluate_expression, registers::Registers, stack_frame::StackFrame,
+        ExpressionContext, VariableName,
+    },
+    Core, CoreDump, CoreDumpError, CoreType, InstructionSet, MemoryInterface, Target,
 };
 use std::fs::File;
-use std::{io::prelude::*, time::Duration};
+use std::io::BufWriter;
+use std::time::Duration;
 
 use parse_int::parse;
 
@@ -432,60 +435,60 @@ impl DebugCli {
             help_text: "List local variables",
 
             function: |cli_data, _args| {
+                print_variables(cli_data, &VariableName::LocalScopeRoot)?;
+                Ok(CliState::Continue)
+            },
+        });
+
+        cli.add_command(Command {
+            name: "args",
+            help_text: "List the arguments of the current function",
+
+            function: |cli_data, _args| {
+                print_variables(cli_data, &VariableName::ArgumentScopeRoot)?;
+                Ok(CliState::Continue)
+            },
+        });
+
+        cli.add_command(Command {
+            name: "print",
+            help_text:
+                "Evaluate an expression, e.g. `print buffer[2] + 1` or `print *(u32*)0x2000_0000`",
+
+            function: |cli_data, args| {
+                if args.is_empty() {
+                    return Err(CliError::MissingArgument);
+                }
+                let expression = args.join(" ");
+
                 match cli_data.state {
                     DebugState::Halted(ref mut halted_state) => {
-                        let current_frame =
-                            if let Some(current_frame) = halted_state.get_current_frame_mut() {
-                                current_frame
-                            } else {
-                                println!("StackFrame not found.");
-                                return Ok(CliState::Continue);
-                            };
-
-                        let local_variable_cache = if let Some(local_variable_cache) =
-                            &mut current_frame.local_variables
-                        {
-                            local_variable_cache
-                        } else {
-                            print!("No Local variables available");
-                            return Ok(CliState::Continue);
-                        };
-
-                        if let Some(mut locals) = local_variable_cache
-                            .get_variable_by_name_and_parent(&VariableName::LocalScopeRoot, None)
-                        {
-                            // By default, the first level children are always are lazy loaded, so we will force a load here.
-                            if locals.variable_node_type.is_deferred()
-                                && !local_variable_cache.has_children(&locals)?
-                            {
-                                if let Err(error) = cli_data
-                                    .debug_info
-                                    .as_ref()
-                                    .unwrap()
-                                    .cache_deferred_variables(
-                                        local_variable_cache,
-                                        &mut cli_data.core,
-                                        &mut locals,
-                                        &current_frame.registers,
-                                    )
-                                {
-                                    println!("Failed to cache local variables: {}", error);
-                                    return Ok(CliState::Continue);
-                                }
+                        // Variables are only available after unwinding the stack.
+                        if halted_state.stack_frames.is_empty() {
+                            if let Some(di) = &cli_data.debug_info {
+                                let regs = cli_data.core.registers();
+                                let program_counter =
+                                    cli_data.core.read_core_reg(regs.program_counter())?;
+                                halted_state.stack_frames =
+                                    di.unwind(&mut cli_data.core, u64::from(program_counter))?;
+                                halted_state.frame_indices = halted_state
+                                    .stack_frames
+                                    .iter()
+                                    .map(|sf| sf.id as i64)
+                                    .collect();
                             }
-                            let children =
-                                local_variable_cache.get_children(Some(locals.variable_key))?;
-
-                            for child in children {
-                                println!(
-                                    "{}: {} = {}",
-                                    child.name,
-                                    child.type_name,
-                                    child.get_value(local_variable_cache)
-                                );
+                        }
+
+                        let mut context = ExpressionContext {
+                            core: &mut cli_data.core,
+                            debug_info: cli_data.debug_info.as_ref(),
+                            stack_frame: halted_state.get_current_frame_mut(),
+                        };
+                        match evaluate_expression(&expression, &mut context) {
+                            Ok(result) => println!("({}) {}", result.type_name, result.value),
+                            Err(error) => {
+                                println!("Failed to evaluate '{}': {}", expression, error)
                             }
-                        } else {
-                            println!("Local variable cache was not initialized.")
                         }
                     }
                     DebugState::Running => println!("Core must be halted for this command."),
@@ -537,44 +540,23 @@ impl DebugCli {
 
         cli.add_command(Command {
             name: "dump",
-            help_text: "Store a dump of the current CPU state",
+            help_text: "Save a core dump of the registers and the RAM of the core to a file (default: core.dump)",
 
-            function: |cli_data, _args| {
-                // dump all relevant data, stack and regs for now..
-                //
-                // stack beginning -> assume beginning to be hardcoded
-
-                let stack_top: u32 = 0x2000_0000 + 0x4000;
-
-                let regs = cli_data.core.registers();
-
-                let stack_bot: u32 = cli_data.core.read_core_reg(regs.stack_pointer())?;
-                let pc: u32 = cli_data.core.read_core_reg(regs.program_counter())?;
-
-                let mut stack = vec![0u8; (stack_top - stack_bot) as usize];
-
-                cli_data.core.read(stack_bot, &mut stack[..])?;
-
-                let mut dump = Dump::new(stack_bot, stack);
-
-                for i in 0..12 {
-                    dump.regs[i as usize] =
-                        cli_data
-                            .core
-                            .read_core_reg(Into::<CoreRegisterAddress>::into(i))?;
-                }
+            function: |cli_data, args| {
+                let path = args.first().copied().unwrap_or("core.dump");
 
-                dump.regs[13] = stack_bot;
-                dump.regs[14] = cli_data.core.read_core_reg(regs.return_address())?;
-                dump.regs[15] = pc;
+                let target = cli_data.target.as_ref().ok_or_else(|| {
+                    CliError::Other(anyhow!("A core dump cannot be saved from a core dump"))
+                })?;
 
-                let serialized = ron::ser::to_string(&dump).expect("Failed to serialize dump");
+                let dump = CoreDump::capture_core(&mut cli_data.core, target)?;
 
-                let mut dump_file = File::create("dump.txt").expect("Failed to create file");
+                File::create(path)
+                    .map_err(CoreDumpError::from)
+                    .and_then(|file| dump.save(BufWriter::new(file)))
+                    .with_context(|| format!("Failed to write {}", path))?;
 
-                dump_file
-                    .write_all(serialized.as_bytes())
-                    .expect("Failed to write dump file");
+                println!("Saved a core dump to {}", path);
 
                 Ok(CliState::Continue)
             },
@@ -658,12 +640,18 @@ impl DebugCli {
 pub struct CliData<'p> {
     pub core: Core<'p>,
     pub debug_info: Option<DebugInfo>,
+    /// The description of the target, which is not available when a core dump is analysed.
+    pub target: Option<Target>,
 
     state: DebugState,
 }
 
 impl<'p> CliData<'p> {
-    pub fn new(mut core: Core<'p>, debug_info: Option<DebugInfo>) -> Result<CliData, CliError> {
+    pub fn new(
+        mut core: Core<'p>,
+        debug_info: Option<DebugInfo>,
+        target: Option<Target>,
+    ) -> Result<CliData, CliError> {
         let status = core.status()?;
 
         // TODO: In halted state we should get the backtrace here.
@@ -685,6 +673,7 @@ impl<'p> CliData<'p> {
         Ok(CliData {
             core,
             debug_info,
+            target,
             state: debug_state,
         })
     }
@@ -729,6 +718,67 @@ impl HaltedState {
     }
 }
 
+/// Print the variables below the given root in the local variables of the current frame.
+fn print_variables(cli_data: &mut CliData, root_name: &VariableName) -> Result<(), CliError> {
+    match cli_data.state {
+        DebugState::Halted(ref mut halted_state) => {
+            let current_frame = if let Some(current_frame) = halted_state.get_current_frame_mut() {
+                current_frame
+            } else {
+                println!("StackFrame not found.");
+                return Ok(());
+            };
+
+            let local_variable_cache =
+                if let Some(local_variable_cache) = &mut current_frame.local_variables {
+                    local_variable_cache
+                } else {
+                    print!("No Local variables available");
+                    return Ok(());
+                };
+
+            if let Some(mut locals) =
+                local_variable_cache.get_variable_by_name_and_parent(root_name, None)
+            {
+                // By default, the first level children are always are lazy loaded, so we will force a load here.
+                if locals.variable_node_type.is_deferred()
+                    && !local_variable_cache.has_children(&locals)?
+                {
+                    if let Err(error) = cli_data
+                        .debug_info
+                        .as_ref()
+                        .unwrap()
+                        .cache_deferred_variables(
+                            local_variable_cache,
+                            &mut cli_data.core,
+                            &mut locals,
+                            &current_frame.registers,
+                        )
+                    {
+                        println!("Failed to cache local variables: {}", error);
+                        return Ok(());
+                    }
+                }
+                let children = local_variable_cache.get_children(Some(locals.variable_key))?;
+
+                for child in children {
+                    println!(
+                        "{}: {} = {}",
+                        child.name,
+                        child.type_name,
+                        child.get_value(local_variable_cache)
+                    );
+                }
+            } else {
+                println!("Local variable cache was not initialized.")
+            }
+        }
+        DebugState::Running => println!("Core must be halted for this command."),
+    }
+
+    Ok(())
+}
+
 pub enum CliState {
     Continue,
     Stop,
@@ -0,0 +1,240 @@
+use anyhow::{anyhow, Context, Result};
+use probe_rs::config::{MemoryRegion, NvmRegion};
+use probe_rs::flashing::{self, FlashContents};
+use probe_rs_cli_util::common_options::ProbeOptions;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// The format the `flash-read` command writes the flash contents in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FlashReadFormat {
+    /// A single Intel HEX file with the contents of all regions.
+    Hex,
+    /// A binary file for each region.
+    Bin,
+}
+
+impl FromStr for FlashReadFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match &s.to_ascii_lowercase()[..] {
+            "hex" | "ihex" | "intelhex" => Ok(Self::Hex),
+            "bin" | "binary" => Ok(Self::Bin),
+            _ => Err(format!("{} is not a valid format (hex, bin)", s)),
+        }
+    }
+}
+
+/// Options of the `flash-read` command.
+#[derive(clap::StructOpt)]
+pub struct FlashReadOptions {
+    /// Only read the flash regions with these names. Can be given multiple times.
+    /// All flash regions of the target are read if it is not given.
+    #[structopt(long = "region")]
+    regions: Vec<String>,
+
+    /// Only read this part of the flash: a range like `0x08000000..0x08010000`,
+    /// or an address and a length like `0x08000000+0x10000`.
+    #[structopt(long)]
+    range: Option<String>,
+
+    /// Leave out the sectors which are erased. They are missing in the Intel HEX
+    /// file, and filled with the erased byte value in the binary files.
+    #[structopt(long)]
+    skip_erased: bool,
+
+    /// The output format: `hex` writes a single Intel HEX file, `bin` a binary file for each region.
+    #[structopt(long, default_value = "hex")]
+    format: FlashReadFormat,
+
+    /// The Intel HEX file to write, or the directory to write the binary files to.
+    /// The Intel HEX file is written to stdout if it is not given.
+    #[structopt(long, parse(from_os_str))]
+    output: Option<PathBuf>,
+}
+
+/// Reads the flash regions of the target, and saves them as Intel HEX or binary files.
+pub fn run(common: ProbeOptions, options: FlashReadOptions) -> Result<()> {
+    let mut session = common.simple_attach()?;
+    let memory_map = session.target().memory_map.clone();
+
+    let range = match &options.range {
+        Some(range) => Some(crate::memory::parse_range(range, &memory_map)?),
+        None => None,
+    };
+
+    let regions = select_regions(&memory_map, &options.regions, range.as_ref())?;
+
+    let read_options = flashing::FlashReadOptions {
+        range,
+        skip_erased: options.skip_erased,
+    };
+
+    let mut contents = Vec::new();
+    for region in &regions {
+        let region_contents = flashing::read_flash(&mut session, region, &read_options)
+            .with_context(|| format!("Failed to read the flash region {}", region_name(region)))?;
+
+        let read: usize = region_contents
+            .blocks
+            .iter()
+            .map(|block| block.data.len())
+            .sum();
+        log::info!(
+            "Read {} of {} bytes of the flash region {}",
+            read,
+            region_contents.range.end - region_contents.range.start,
+            region_name(region)
+        );
+
+        contents.push(region_contents);
+    }
+
+    match options.format {
+        FlashReadFormat::Hex => {
+            let blocks = contents.iter().flat_map(|region| &region.blocks);
+            match &options.output {
+                Some(path) => {
+                    let mut file = File::create(path)
+                        .with_context(|| format!("Failed to create {:?}", path))?;
+                    flashing::write_hex(blocks, &mut file)?;
+                }
+                None => flashing::write_hex(blocks, &mut std::io::stdout())?,
+            }
+        }
+        FlashReadFormat::Bin => {
+            let directory = options
+                .output
+                .ok_or_else(|| anyhow!("The directory to write the binary files to is required"))?;
+            fs::create_dir_all(&directory)?;
+
+            for region_contents in &contents {
+                let path = directory.join(bin_file_name(region_contents));
+                File::create(&path)
+                    .and_then(|mut file| file.write_all(&region_contents.to_bin()))
+                    .with_context(|| format!("Failed to write {:?}", path))?;
+                println!("Wrote {:?}", path);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Selects the flash regions by their names, and leaves out the regions outside of the range.
+fn select_regions(
+    memory_map: &[MemoryRegion],
+    names: &[String],
+    range: Option<&std::ops::Range<u32>>,
+) -> Result<Vec<NvmRegion>> {
+    let flash: Vec<&NvmRegion> = memory_map
+        .iter()
+        .filter_map(|region| match region {
+            MemoryRegion::Nvm(region) => Some(region),
+            _ => None,
+        })
+        .collect();
+
+    if let Some(missing) = names.iter().find(|name| {
+        !flash
+            .iter()
+            .any(|region| region.name.as_ref() == Some(*name))
+    }) {
+        return Err(anyhow!("The target has no flash region {}", missing));
+    }
+
+    let regions: Vec<NvmRegion> = flash
+        .into_iter()
+        .filter(|region| {
+            names.is_empty() || names.iter().any(|name| region.name.as_ref() == Some(name))
+        })
+        .filter(|region| {
+            range.map_or(true, |range| {
+                range.start < region.range.end && region.range.start < range.end
+            })
+        })
+        .cloned()
+        .collect();
+
+    if regions.is_empty() {
+        return Err(anyhow!("No flash region of the target is selected"));
+    }
+
+    Ok(regions)
+}
+
+fn region_name(region: &NvmRegion) -> String {
+    region
+        .name
+        .clone()
+        .unwrap_or_else(|| format!("at {:#010x}", region.range.start))
+}
+
+/// The name of the binary file of a region, which contains the start address of the range if only part of it was read.
+fn bin_file_name(contents: &FlashContents) -> String {
+    let name = contents
+        .region
+        .name
+        .clone()
+        .unwrap_or_else(|| format!("{:08x}", contents.region.range.start));
+
+    if contents.range == contents.region.range {
+        format!("{}.bin", name)
+    } else {
+        format!("{}_{:08x}.bin", name, contents.range.start)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::select_regions;
+    use probe_rs::config::{MemoryRegion, NvmRegion, RamRegion};
+
+    fn nvm(name: &str, range: std::ops::Range<u32>) -> MemoryRegion {
+        MemoryRegion::Nvm(NvmRegion {
+            name: Some(name.to_string()),
+            range,
+            is_boot_memory: false,
+            cores: vec!["main".to_string()],
+        })
+    }
+
+    #[test]
+    fn select_flash_regions() {
+        let memory_map = vec![
+            nvm("BANK_1", 0x0800_0000..0x0808_0000),
+            nvm("BANK_2", 0x0808_0000..0x0810_0000),
+            MemoryRegion::Ram(RamRegion {
+                name: Some("SRAM".to_string()),
+                range: 0x2000_0000..0x2002_0000,
+                is_boot_memory: false,
+                cores: vec!["main".to_string()],
+            }),
+        ];
+
+        let names = |regions: Vec<NvmRegion>| -> Vec<String> {
+            regions
+                .into_iter()
+                .filter_map(|region| region.name)
+                .collect()
+        };
+
+        assert_eq!(
+            names(select_regions(&memory_map, &[], None).unwrap()),
+            ["BANK_1", "BANK_2"]
+        );
+        assert_eq!(
+            names(select_regions(&memory_map, &["BANK_2".to_string()], None).unwrap()),
+            ["BANK_2"]
+        );
+        assert_eq!(
+            names(select_regions(&memory_map, &[], Some(&(0x0807_0000..0x0807_1000))).unwrap()),
+            ["BANK_1"]
+        );
+        assert!(select_regions(&memory_map, &["SRAM".to_string()], None).is_err());
+        assert!(select_regions(&memory_map, &[], Some(&(0x2000_0000..0x2000_1000))).is_err());
+    }
+}
@@ -1,12 +1,27 @@
-use std::{sync::Mutex, time::Duration};
+use std::{path::PathBuf, sync::Mutex, time::Duration};
 
+use probe_rs::flashing::DownloadOptions;
+use probe_rs::semihosting::SemihostingOptions;
 use probe_rs_cli_util::common_options::ProbeOptions;
 
 pub fn run_gdb_server(
     common: ProbeOptions,
     connection_string: Option<&str>,
     reset_halt: bool,
+    restore_unwritten: bool,
+    verify: bool,
+    semihosting_root: Option<PathBuf>,
 ) -> anyhow::Result<()> {
+    let mut download_options = DownloadOptions::new();
+    download_options.keep_unwritten_bytes = restore_unwritten;
+    download_options.verify = verify;
+    download_options.dry_run = common.dry_run;
+
+    let semihosting_options = SemihostingOptions {
+        root: semihosting_root,
+        ..Default::default()
+    };
+
     let session = Mutex::new(common.simple_attach()?);
 
     if reset_halt {
@@ -20,7 +35,12 @@ pub fn run_gdb_server(
     let gdb_connection_string = connection_string.unwrap_or("localhost:1337");
     println!("Firing up GDB stub at {}", gdb_connection_string);
 
-    if let Err(e) = probe_rs_gdb_server::run(Some(gdb_connection_string.to_owned()), &session) {
+    if let Err(e) = probe_rs_gdb_server::run_with_options(
+        Some(gdb_connection_string.to_owned()),
+        &session,
+        &download_options,
+        &semihosting_options,
+    ) {
         eprintln!("During the execution of GDB an error was encountered:");
         eprintln!("{:?}", e);
     }
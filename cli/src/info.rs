@@ -11,6 +11,7 @@ use probe_rs::{
         },
         riscv::communication_interface::RiscvCommunicationInterface,
     },
+    config::{identify_arm_target, identify_riscv_target, RegistryError, Target},
     CoreRegister, Probe, WireProtocol,
 };
 
@@ -78,6 +79,8 @@ fn try_show_info(
                     log::warn!("Error showing ARM chip information: {}", e);
                 }
 
+                show_detected_target(identify_arm_target(interface.as_mut(), DpAddress::Default));
+
                 probe = interface.close();
             }
             Err((interface_probe, _e)) => {
@@ -97,6 +100,8 @@ fn try_show_info(
                     log::warn!("Error showing RISCV chip information: {}", e);
                 }
 
+                show_detected_target(identify_riscv_target(&mut interface));
+
                 probe = interface.close();
             }
             Err((interface_probe, e)) => {
@@ -115,6 +120,13 @@ fn try_show_info(
     (probe, Ok(()))
 }
 
+fn show_detected_target(target: Result<Target, RegistryError>) {
+    match target {
+        Ok(target) => println!("\nDetected chip: {}", target.name),
+        Err(e) => println!("\nThe chip could not be identified: {}", e),
+    }
+}
+
 fn show_arm_info(interface: &mut Box<dyn ArmProbeInterface>) -> Result<()> {
     println!("\nAvailable Access Ports:");
 
@@ -0,0 +1,391 @@
+use anyhow::{anyhow, Result};
+use probe_rs::architecture::arm::swo::trace::{
+    DataAccess, ExceptionAction, TraceDecoder, TraceEvent,
+};
+use probe_rs::architecture::arm::SwoConfig;
+use probe_rs::Session;
+use probe_rs_cli_util::common_options::ProbeOptions;
+use serde::Serialize;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// How the decoded trace is written.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ItmFormat {
+    /// The text written to the stimulus ports, line by line.
+    Text,
+    /// Every event as a JSON object on its own line.
+    Json,
+    /// A timeline of the events with one row per event.
+    Csv,
+}
+
+impl FromStr for ItmFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match &s.to_ascii_lowercase()[..] {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            "csv" => Ok(Self::Csv),
+            _ => Err(format!("{} is not a valid format (text, json, csv)", s)),
+        }
+    }
+}
+
+/// Options of the `itm` command.
+#[derive(clap::StructOpt)]
+pub struct ItmOptions {
+    #[structopt(long, default_value = "0")]
+    core: usize,
+
+    /// The frequency of the clock of the trace port in Hz, which is usually the core clock.
+    /// It is also used to convert the timestamps of the events to seconds.
+    #[structopt(long, parse(try_from_str = crate::parse_u32))]
+    clock: u32,
+
+    /// The baud rate of SWO in Hz. It must divide the clock, and be supported by the probe.
+    #[structopt(long, default_value = "1000000", parse(try_from_str = crate::parse_u32))]
+    baud: u32,
+
+    /// Use Manchester encoding on the SWO pin instead of UART.
+    #[structopt(long)]
+    manchester: bool,
+
+    /// Trace the accesses to this address with a DWT comparator. Can be given once for each comparator.
+    #[structopt(long = "data-trace", parse(try_from_str = crate::parse_u32))]
+    data_traces: Vec<u32>,
+
+    /// Sample the program counter about every this many cycles.
+    #[structopt(long, parse(try_from_str = crate::parse_u32))]
+    pc_sampling: Option<u32>,
+
+    /// The output format: `text` prints the stimulus ports, `json` and `csv` export all events.
+    #[structopt(long, default_value = "text")]
+    format: ItmFormat,
+
+    /// Only print these stimulus ports in the text format. Can be given multiple times.
+    #[structopt(long = "port")]
+    ports: Vec<u8>,
+
+    /// Write the output to this file instead of stdout.
+    #[structopt(long, parse(from_os_str))]
+    output: Option<PathBuf>,
+}
+
+/// Configures SWO on the target and the probe, and prints the decoded trace until Ctrl-C is pressed.
+pub fn run(common: ProbeOptions, options: ItmOptions) -> Result<()> {
+    if options.baud == 0 || options.baud > options.clock {
+        return Err(anyhow!(
+            "The baud rate must be between 1 and the clock of {} Hz",
+            options.clock
+        ));
+    }
+    if options.clock % options.baud != 0 {
+        log::warn!(
+            "The baud rate {} does not divide the clock of {} Hz, the target will use {} Hz",
+            options.baud,
+            options.clock,
+            options.clock / (options.clock / options.baud)
+        );
+    }
+
+    let mut config = SwoConfig::new(options.clock)
+        .set_baud(options.baud)
+        .set_continuous_formatting(false);
+    if options.manchester {
+        config = config.set_mode_manchester();
+    }
+
+    let mut session = common.simple_attach()?;
+    session.setup_swv(options.core, &config)?;
+
+    let result = capture(&mut session, &options);
+
+    // Leave the DWT as it was before, also if the capture failed.
+    let cleanup = disable_tracing(&mut session, &options);
+
+    match (result, cleanup) {
+        (Err(error), Err(cleanup_error)) => {
+            log::warn!("Failed to disable the trace: {}", cleanup_error);
+            Err(error)
+        }
+        (result, cleanup) => result.and(cleanup),
+    }
+}
+
+/// Configures the data traces and PC sampling, and prints the decoded trace until Ctrl-C is pressed.
+fn capture(session: &mut Session, options: &ItmOptions) -> Result<()> {
+    for (unit, address) in options.data_traces.iter().enumerate() {
+        session.add_swv_data_trace(options.core, unit, *address)?;
+    }
+
+    if let Some(interval) = options.pc_sampling {
+        let interval = session.enable_swv_pc_sampling(options.core, interval)?;
+        log::info!("Sampling the program counter every {} cycles", interval);
+    }
+
+    let exit = Arc::new(AtomicBool::new(false));
+    {
+        let exit = exit.clone();
+        ctrlc::set_handler(move || exit.store(true, Ordering::SeqCst))?;
+    }
+
+    let output: Box<dyn Write> = match &options.output {
+        Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+        None => Box::new(std::io::stdout()),
+    };
+    let mut printer =
+        TracePrinter::new(output, options.format, options.clock, options.ports.clone());
+    printer.start()?;
+
+    let mut decoder = TraceDecoder::new();
+    while !exit.load(Ordering::SeqCst) {
+        decoder.push(&session.read_swo()?);
+
+        while let Some(event) = decoder.pull() {
+            printer.print(&event)?;
+        }
+        printer.flush()?;
+    }
+
+    Ok(())
+}
+
+/// Disables the PC sampling, the data traces and SWO which were enabled by [`run`].
+///
+/// All of them are disabled even if one fails, and the first error is returned.
+fn disable_tracing(session: &mut Session, options: &ItmOptions) -> Result<()> {
+    let mut results = Vec::new();
+
+    if options.pc_sampling.is_some() {
+        results.push(session.disable_swv_pc_sampling(options.core));
+    }
+    for unit in 0..options.data_traces.len() {
+        results.push(session.remove_swv_data_trace(options.core, unit));
+    }
+    results.push(session.disable_swv(options.core));
+
+    results.into_iter().collect::<Result<(), _>>()?;
+    Ok(())
+}
+
+/// An event with the time of the last local timestamp before it, for the JSON export.
+#[derive(Serialize)]
+struct TimedEvent<'a> {
+    time: f64,
+    #[serde(flatten)]
+    event: &'a TraceEvent,
+}
+
+/// Writes the decoded events in one of the output formats.
+struct TracePrinter<W: Write> {
+    output: W,
+    format: ItmFormat,
+    clock: u32,
+    /// The stimulus ports to print in the text format, or all ports if empty.
+    ports: Vec<u8>,
+    /// The sum of all local timestamps, in cycles of the clock.
+    cycles: u64,
+    /// Text of each of the 32 stimulus ports which does not form a complete line yet.
+    lines: Vec<Vec<u8>>,
+}
+
+impl<W: Write> TracePrinter<W> {
+    fn new(output: W, format: ItmFormat, clock: u32, ports: Vec<u8>) -> Self {
+        Self {
+            output,
+            format,
+            clock,
+            ports,
+            cycles: 0,
+            lines: vec![Vec::new(); 32],
+        }
+    }
+
+    /// Writes the header of the output format.
+    fn start(&mut self) -> Result<()> {
+        if self.format == ItmFormat::Csv {
+            writeln!(self.output, "time,event,id,value")?;
+        }
+        Ok(())
+    }
+
+    /// The time of the last local timestamp in seconds.
+    fn time(&self) -> f64 {
+        self.cycles as f64 / self.clock as f64
+    }
+
+    fn print(&mut self, event: &TraceEvent) -> Result<()> {
+        match event {
+            TraceEvent::LocalTimestamp { delta, .. } => self.cycles += *delta as u64,
+            TraceEvent::Overflow => log::warn!("The ITM overflowed, and trace packets were lost"),
+            _ => {}
+        }
+
+        match self.format {
+            ItmFormat::Text => self.print_text(event)?,
+            ItmFormat::Json => {
+                let time = self.time();
+                serde_json::to_writer(&mut self.output, &TimedEvent { time, event })?;
+                writeln!(self.output)?;
+            }
+            ItmFormat::Csv => {
+                if let Some((name, id, value)) = csv_fields(event) {
+                    writeln!(self.output, "{:.9},{},{},{}", self.time(), name, id, value)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Prints the complete lines written to a stimulus port, prefixed with the number of the port.
+    fn print_text(&mut self, event: &TraceEvent) -> Result<()> {
+        let (port, payload) = match event {
+            TraceEvent::Instrumentation { port, payload } => (*port, payload),
+            _ => return Ok(()),
+        };
+
+        if !self.ports.is_empty() && !self.ports.contains(&port) {
+            return Ok(());
+        }
+
+        let line = &mut self.lines[port as usize];
+        line.extend_from_slice(payload);
+
+        while let Some(end) = line.iter().position(|byte| *byte == b'\n') {
+            let text: Vec<u8> = line.drain(..=end).collect();
+            let text = String::from_utf8_lossy(&text);
+            writeln!(self.output, "{}> {}", port, text.trim_end())?;
+        }
+
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.output.flush()?;
+        Ok(())
+    }
+}
+
+/// Returns the name, the id and the value of an event in the CSV timeline.
+/// Timestamps are not listed, they are contained in the time of the events.
+fn csv_fields(event: &TraceEvent) -> Option<(&'static str, String, String)> {
+    let fields = match event {
+        TraceEvent::Instrumentation { port, payload } => {
+            let value = payload
+                .iter()
+                .rev()
+                .fold(0u32, |value, byte| value << 8 | *byte as u32);
+            ("instrumentation", port.to_string(), value.to_string())
+        }
+        TraceEvent::Exception { number, action } => {
+            let action = match action {
+                ExceptionAction::Entered => "entered",
+                ExceptionAction::Exited => "exited",
+                ExceptionAction::Returned => "returned",
+            };
+            ("exception", number.to_string(), action.to_string())
+        }
+        TraceEvent::PcSample { pc } => (
+            "pc_sample",
+            String::new(),
+            pc.map_or_else(|| "sleep".to_string(), |pc| format!("{:#010x}", pc)),
+        ),
+        TraceEvent::DataTracePc { comparator, pc } => (
+            "data_trace_pc",
+            comparator.to_string(),
+            format!("{:#010x}", pc),
+        ),
+        TraceEvent::DataTraceAddress {
+            comparator,
+            address,
+        } => (
+            "data_trace_address",
+            comparator.to_string(),
+            format!("{:#06x}", address),
+        ),
+        TraceEvent::DataTraceValue {
+            comparator,
+            access,
+            value,
+        } => (
+            match access {
+                DataAccess::Read => "data_trace_read",
+                DataAccess::Write => "data_trace_write",
+            },
+            comparator.to_string(),
+            value.to_string(),
+        ),
+        TraceEvent::Overflow => ("overflow", String::new(), String::new()),
+        _ => return None,
+    };
+
+    Some(fields)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ItmFormat, TracePrinter};
+    use probe_rs::architecture::arm::swo::trace::{ExceptionAction, TimestampRelation, TraceEvent};
+
+    fn print(format: ItmFormat, ports: Vec<u8>, events: &[TraceEvent]) -> String {
+        let mut printer = TracePrinter::new(Vec::new(), format, 1_000_000, ports);
+        printer.start().unwrap();
+        for event in events {
+            printer.print(event).unwrap();
+        }
+
+        String::from_utf8(printer.output).unwrap()
+    }
+
+    #[test]
+    fn print_stimulus_port_lines() {
+        let events = [
+            TraceEvent::Instrumentation {
+                port: 0,
+                payload: b"hel".to_vec(),
+            },
+            TraceEvent::Instrumentation {
+                port: 1,
+                payload: b"x\n".to_vec(),
+            },
+            TraceEvent::Instrumentation {
+                port: 0,
+                payload: b"lo\r\nw".to_vec(),
+            },
+        ];
+
+        assert_eq!(print(ItmFormat::Text, vec![], &events), "1> x\n0> hello\n");
+        assert_eq!(print(ItmFormat::Text, vec![0], &events), "0> hello\n");
+    }
+
+    #[test]
+    fn print_csv_timeline() {
+        let events = [
+            TraceEvent::Exception {
+                number: 15,
+                action: ExceptionAction::Entered,
+            },
+            TraceEvent::LocalTimestamp {
+                delta: 500,
+                relation: TimestampRelation::Sync,
+            },
+            TraceEvent::PcSample {
+                pc: Some(0x800_0100),
+            },
+        ];
+
+        assert_eq!(
+            print(ItmFormat::Csv, vec![], &events),
+            "time,event,id,value\n\
+             0.000000000,exception,15,entered\n\
+             0.000500000,pc_sample,,0x08000100\n"
+        );
+    }
+}
@@ -1,15 +1,20 @@
 mod common;
 mod debugger;
+mod flash_read;
 mod gdb;
 mod info;
+mod itm;
+mod memory;
+mod profile;
 mod run;
+mod test;
 
 use debugger::CliState;
 
 use probe_rs::{
     debug::debug_info::DebugInfo,
     flashing::{erase_all, BinOptions, FileDownloadError, Format},
-    MemoryInterface, Probe,
+    Core, CoreDump, CoreDumpError, MemoryInterface, Probe,
 };
 
 use probe_rs_cli_util::{
@@ -66,6 +71,19 @@ enum Cli {
             help = "Use this flag to reset and halt (instead of just a halt) the attached core after attaching to the target."
         )]
         reset_halt: bool,
+
+        /// Restore all bytes erased in the sector erase but not overwritten, when GDB loads a program.
+        #[structopt(long)]
+        restore_unwritten: bool,
+
+        /// Verify the flash contents after GDB loaded a program.
+        #[structopt(long)]
+        verify: bool,
+
+        /// The directory the target can access files in with semihosting.
+        #[structopt(long, parse(from_os_str))]
+        semihosting_root: Option<PathBuf>,
+
         #[clap(flatten)]
         common: ProbeOptions,
     },
@@ -80,6 +98,20 @@ enum Cli {
         #[structopt(long, parse(from_os_str))]
         /// Binary to debug
         exe: Option<PathBuf>,
+
+        /// Analyse a core dump saved with the `core-dump` command, instead of attaching to the target.
+        #[structopt(long, parse(from_os_str))]
+        core_dump: Option<PathBuf>,
+    },
+    /// Save the registers of all cores and the RAM of the target as a core dump, to analyse it with `debug --core-dump`
+    #[structopt(name = "core-dump")]
+    CoreDump {
+        #[structopt(flatten)]
+        common: ProbeOptions,
+
+        /// The file to write the core dump to.
+        #[structopt(parse(from_os_str))]
+        path: PathBuf,
     },
     /// Dump memory from attached target
     Dump {
@@ -96,6 +128,24 @@ enum Cli {
         #[structopt(parse(try_from_str = parse_u32))]
         words: u32,
     },
+    /// Read a range or a region of the target memory, and save it as binary, Intel HEX or a hexdump
+    #[structopt(name = "read")]
+    Read {
+        #[structopt(flatten)]
+        common: ProbeOptions,
+
+        #[structopt(flatten)]
+        options: memory::ReadOptions,
+    },
+    /// Write a binary, Intel HEX or ELF file to the target memory
+    #[structopt(name = "write")]
+    Write {
+        #[structopt(flatten)]
+        common: ProbeOptions,
+
+        #[structopt(flatten)]
+        options: memory::WriteOptions,
+    },
     /// Download memory to attached target
     Download {
         #[structopt(flatten)]
@@ -127,6 +177,15 @@ enum Cli {
         #[structopt(long = "disable-double-buffering")]
         disable_double_buffering: bool,
     },
+    /// Read the flash of the target, and save it as an Intel HEX file or a binary file for each region
+    #[structopt(name = "flash-read")]
+    FlashRead {
+        #[structopt(flatten)]
+        common: ProbeOptions,
+
+        #[structopt(flatten)]
+        options: flash_read::FlashReadOptions,
+    },
     /// Erase all nonvolatile memory of attached target
     Erase {
         #[structopt(flatten)]
@@ -148,6 +207,46 @@ enum Cli {
         /// Disable double-buffering when downloading flash.  If downloading times out, try this option.
         #[structopt(long = "disable-double-buffering")]
         disable_double_buffering: bool,
+
+        /// The directory the target can access files in with semihosting.
+        #[structopt(long, parse(from_os_str))]
+        semihosting_root: Option<PathBuf>,
+
+        /// Halt the core when a HardFault occurs, and exit with the cause of the fault.
+        #[structopt(long)]
+        catch_hardfault: bool,
+
+        #[structopt(flatten)]
+        terminal: run::TerminalOptions,
+    },
+    /// Flash and run test binaries, and report whether they passed
+    ///
+    /// The output of the firmware is read from RTT, including defmt, and semihosting.
+    #[structopt(name = "test")]
+    Test {
+        #[structopt(flatten)]
+        common: ProbeOptions,
+
+        #[structopt(flatten)]
+        options: test::TestOptions,
+    },
+    /// Decode the ITM and DWT trace of the target received over SWO
+    #[structopt(name = "itm")]
+    Itm {
+        #[structopt(flatten)]
+        common: ProbeOptions,
+
+        #[structopt(flatten)]
+        options: itm::ItmOptions,
+    },
+    /// Sample the program counter of the running target, and show the functions the time is spent in
+    #[structopt(name = "profile")]
+    Profile {
+        #[structopt(flatten)]
+        common: ProbeOptions,
+
+        #[structopt(flatten)]
+        options: profile::ProfileOptions,
     },
     /// Trace a memory location on the target
     #[structopt(name = "trace")]
@@ -200,7 +299,17 @@ fn main() -> Result<()> {
             gdb_connection_string,
             common,
             reset_halt,
-        } => gdb::run_gdb_server(common, gdb_connection_string.as_deref(), reset_halt),
+            restore_unwritten,
+            verify,
+            semihosting_root,
+        } => gdb::run_gdb_server(
+            common,
+            gdb_connection_string.as_deref(),
+            reset_halt,
+            restore_unwritten,
+            verify,
+            semihosting_root,
+        ),
         Cli::Reset {
             shared,
             common,
@@ -210,13 +319,17 @@ fn main() -> Result<()> {
             shared,
             common,
             exe,
-        } => debug(&shared, &common, exe),
+            core_dump,
+        } => debug(&shared, &common, exe, core_dump),
+        Cli::CoreDump { common, path } => save_core_dump(&common, &path),
         Cli::Dump {
             shared,
             common,
             loc,
             words,
         } => dump_memory(&shared, &common, loc, words),
+        Cli::Read { common, options } => memory::read(common, options),
+        Cli::Write { common, options } => memory::write(common, options),
         Cli::Download {
             common,
             format,
@@ -239,8 +352,23 @@ fn main() -> Result<()> {
             path,
             chip_erase,
             disable_double_buffering,
-        } => run::run(common, &path, chip_erase, disable_double_buffering),
+            semihosting_root,
+            catch_hardfault,
+            terminal,
+        } => run::run(
+            common,
+            &path,
+            chip_erase,
+            disable_double_buffering,
+            semihosting_root,
+            catch_hardfault,
+            terminal,
+        ),
+        Cli::Test { common, options } => test::run(common, options),
         Cli::Erase { common } => erase(&common),
+        Cli::FlashRead { common, options } => flash_read::run(common, options),
+        Cli::Itm { common, options } => itm::run(common, options),
+        Cli::Profile { common, options } => profile::run(common, options),
         Cli::Trace {
             shared,
             common,
@@ -418,18 +546,39 @@ fn trace_u32_on_target(
     }
 }
 
-fn debug(shared_options: &CoreOptions, common: &ProbeOptions, exe: Option<PathBuf>) -> Result<()> {
-    let mut session = common.simple_attach()?;
-
+fn debug(
+    shared_options: &CoreOptions,
+    common: &ProbeOptions,
+    exe: Option<PathBuf>,
+    core_dump: Option<PathBuf>,
+) -> Result<()> {
     let di = exe
         .as_ref()
         .and_then(|path| DebugInfo::from_file(path).ok());
 
-    let cli = debugger::DebugCli::new();
+    if let Some(path) = core_dump {
+        let dump = File::open(&path)
+            .map_err(CoreDumpError::from)
+            .and_then(|file| CoreDump::load(io::BufReader::new(file)))
+            .with_context(|| format!("Failed to load the core dump {:?}", path))?;
+        println!("Analysing the core dump of {}", dump.target);
+
+        let mut state = Core::create_state(shared_options.core);
+        let core = dump.core(shared_options.core, &mut state)?;
+
+        return run_debug_cli(debugger::CliData::new(core, di, None)?);
+    }
+
+    let mut session = common.simple_attach()?;
+    let target = session.target().clone();
 
     let core = session.core(shared_options.core)?;
 
-    let mut cli_data = debugger::CliData::new(core, di)?;
+    run_debug_cli(debugger::CliData::new(core, di, Some(target))?)
+}
+
+fn run_debug_cli(mut cli_data: debugger::CliData<'_>) -> Result<()> {
+    let cli = debugger::DebugCli::new();
 
     let mut rl = Editor::<()>::new();
 
@@ -467,6 +616,27 @@ fn debug(shared_options: &CoreOptions, common: &ProbeOptions, exe: Option<PathBu
     Ok(())
 }
 
+fn save_core_dump(common: &ProbeOptions, path: &Path) -> Result<()> {
+    let mut session = common.simple_attach()?;
+
+    let dump = CoreDump::capture(&mut session)?;
+
+    File::create(path)
+        .map_err(CoreDumpError::from)
+        .and_then(|file| dump.save(io::BufWriter::new(file)))
+        .with_context(|| format!("Failed to write the core dump to {:?}", path))?;
+
+    let memory: usize = dump.memory.iter().map(|block| block.data.len()).sum();
+    println!(
+        "Saved the registers of {} cores and {} bytes of RAM to {:?}",
+        dump.cores.len(),
+        memory,
+        path
+    );
+
+    Ok(())
+}
+
 #[derive(clap::ArgEnum, Debug, Clone, Copy)]
 enum DownloadFileType {
     Elf,
@@ -0,0 +1,660 @@
+use anyhow::{anyhow, bail, Context, Result};
+use ihex::Record;
+use probe_rs::config::MemoryRegion;
+use probe_rs::flashing::{self, FlashBlock};
+use probe_rs::{Core, MemoryInterface};
+use probe_rs_cli_util::common_options::ProbeOptions;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Instant;
+
+/// The width of the accesses to the target memory.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AccessWidth {
+    U8,
+    U16,
+    U32,
+}
+
+impl AccessWidth {
+    /// The size of one access in bytes.
+    fn bytes(self) -> u32 {
+        match self {
+            AccessWidth::U8 => 1,
+            AccessWidth::U16 => 2,
+            AccessWidth::U32 => 4,
+        }
+    }
+}
+
+impl FromStr for AccessWidth {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "8" => Ok(Self::U8),
+            "16" => Ok(Self::U16),
+            "32" => Ok(Self::U32),
+            _ => Err(format!("{} is not a valid access width (8, 16, 32)", s)),
+        }
+    }
+}
+
+/// The format the memory is written in by the `read` command.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    /// The raw bytes of the memory.
+    Bin,
+    /// An Intel HEX file.
+    Hex,
+    /// A hexdump of the words of the access width, with the bytes as ASCII.
+    Hexdump,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match &s.to_ascii_lowercase()[..] {
+            "bin" => Ok(Self::Bin),
+            "hex" | "ihex" | "intelhex" => Ok(Self::Hex),
+            "hexdump" => Ok(Self::Hexdump),
+            _ => Err(format!("{} is not a valid format (bin, hex, hexdump)", s)),
+        }
+    }
+}
+
+/// The format of the file written by the `write` command.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InputFormat {
+    /// The raw bytes, which are written to the given address.
+    Bin,
+    /// An Intel HEX file.
+    Hex,
+    /// The allocated sections of an ELF file, which are written to their addresses.
+    Elf,
+}
+
+impl FromStr for InputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match &s.to_ascii_lowercase()[..] {
+            "bin" => Ok(Self::Bin),
+            "hex" | "ihex" | "intelhex" => Ok(Self::Hex),
+            "elf" => Ok(Self::Elf),
+            _ => Err(format!("{} is not a valid format (bin, hex, elf)", s)),
+        }
+    }
+}
+
+/// Options of the `read` command.
+#[derive(clap::StructOpt)]
+pub struct ReadOptions {
+    #[structopt(long, default_value = "0")]
+    core: usize,
+
+    /// The memory to read: a range like `0x20000000..0x20000100`, an address and
+    /// a length like `0x20000000+0x100`, or the name of a memory region of the target.
+    location: String,
+
+    /// The width of the accesses in bits: 8, 16 or 32.
+    #[structopt(long, default_value = "32")]
+    width: AccessWidth,
+
+    /// The output format: `bin`, `hex` (Intel HEX) or `hexdump`.
+    #[structopt(long, default_value = "hexdump")]
+    format: OutputFormat,
+
+    /// Write the memory to this file instead of stdout.
+    #[structopt(long, parse(from_os_str))]
+    output: Option<PathBuf>,
+}
+
+/// Options of the `write` command.
+#[derive(clap::StructOpt)]
+pub struct WriteOptions {
+    #[structopt(long, default_value = "0")]
+    core: usize,
+
+    /// The file to write to the target memory.
+    #[structopt(parse(from_os_str))]
+    path: PathBuf,
+
+    /// The format of the file: `bin`, `hex` (Intel HEX) or `elf`.
+    /// If it is not given, it is guessed from the extension of the file.
+    #[structopt(long)]
+    format: Option<InputFormat>,
+
+    /// The address a binary file is written to, or the name of the memory region it is written to the start of.
+    #[structopt(long)]
+    address: Option<String>,
+
+    /// Only write these sections of an ELF file. Can be given multiple times.
+    /// All allocated sections with data are written if it is not given.
+    #[structopt(long = "section")]
+    sections: Vec<String>,
+
+    /// The width of the accesses in bits: 8, 16 or 32.
+    #[structopt(long, default_value = "32")]
+    width: AccessWidth,
+
+    /// Read the memory back after writing, and check that it contains the data of the file.
+    #[structopt(long)]
+    verify: bool,
+}
+
+/// Data which is written to a contiguous range of the target memory.
+#[derive(Debug, PartialEq)]
+struct Segment {
+    address: u32,
+    data: Vec<u8>,
+}
+
+/// Reads a range of the target memory, and writes it to a file or stdout.
+pub fn read(common: ProbeOptions, options: ReadOptions) -> Result<()> {
+    let mut session = common.simple_attach()?;
+    let memory_map = session.target().memory_map.clone();
+
+    let range = parse_range(&options.location, &memory_map)?;
+    check_alignment(range.start, range.len(), options.width)?;
+
+    let mut core = session.core(options.core)?;
+
+    let instant = Instant::now();
+    let data = read_memory(&mut core, range.start, range.len(), options.width)?;
+    log::info!("Read {} bytes in {:?}", data.len(), instant.elapsed());
+
+    let mut output: Box<dyn Write> = match &options.output {
+        Some(path) => {
+            Box::new(File::create(path).with_context(|| format!("Failed to create {:?}", path))?)
+        }
+        None => Box::new(std::io::stdout()),
+    };
+
+    match options.format {
+        OutputFormat::Bin => output.write_all(&data)?,
+        OutputFormat::Hex => {
+            let block = FlashBlock {
+                address: range.start,
+                data,
+            };
+            flashing::write_hex([&block], &mut output)?;
+        }
+        OutputFormat::Hexdump => write_hexdump(&mut output, range.start, &data, options.width)?,
+    }
+    output.flush()?;
+
+    Ok(())
+}
+
+/// Writes a file to the target memory, and optionally verifies it.
+pub fn write(common: ProbeOptions, options: WriteOptions) -> Result<()> {
+    let format = match options.format {
+        Some(format) => format,
+        None => guess_format(&options.path)?,
+    };
+
+    let mut buffer = Vec::new();
+    File::open(&options.path)
+        .and_then(|mut file| file.read_to_end(&mut buffer))
+        .with_context(|| format!("Failed to read {:?}", options.path))?;
+
+    let mut session = common.simple_attach()?;
+    let memory_map = session.target().memory_map.clone();
+
+    let segments = match format {
+        InputFormat::Bin => {
+            let address = match &options.address {
+                Some(address) => parse_address(address, &memory_map)?,
+                None => bail!("The address to write a binary file to is required"),
+            };
+            vec![Segment {
+                address,
+                data: buffer,
+            }]
+        }
+        InputFormat::Hex => hex_segments(std::str::from_utf8(&buffer)?)?,
+        InputFormat::Elf => elf_segments(&buffer, &options.sections)?,
+    };
+
+    if segments.is_empty() {
+        bail!("{:?} does not contain any data to write", options.path);
+    }
+
+    for segment in &segments {
+        check_alignment(segment.address, segment.data.len(), options.width)?;
+        check_not_flash(segment, &memory_map)?;
+    }
+
+    let mut core = session.core(options.core)?;
+
+    let instant = Instant::now();
+    for segment in &segments {
+        log::info!(
+            "Writing {} bytes to {:#010x}",
+            segment.data.len(),
+            segment.address
+        );
+        write_memory(&mut core, segment.address, &segment.data, options.width)?;
+    }
+    core.flush()?;
+    log::info!(
+        "Wrote {} segments in {:?}",
+        segments.len(),
+        instant.elapsed()
+    );
+
+    if options.verify {
+        for segment in &segments {
+            let data = read_memory(
+                &mut core,
+                segment.address,
+                segment.data.len(),
+                options.width,
+            )?;
+
+            if let Some(offset) = data.iter().zip(&segment.data).position(|(a, b)| a != b) {
+                bail!(
+                    "Verification failed at {:#010x}: expected {:#04x}, read {:#04x}",
+                    segment.address + offset as u32,
+                    segment.data[offset],
+                    data[offset]
+                );
+            }
+        }
+        println!("Verified {} segments", segments.len());
+    }
+
+    Ok(())
+}
+
+/// Guesses the format of the input file from its extension.
+fn guess_format(path: &Path) -> Result<InputFormat> {
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("bin") => Ok(InputFormat::Bin),
+        Some("hex") | Some("ihex") => Ok(InputFormat::Hex),
+        Some("elf") | None => Ok(InputFormat::Elf),
+        Some(extension) => Err(anyhow!(
+            "The format of {:?} cannot be guessed from the extension '{}', use --format",
+            path,
+            extension
+        )),
+    }
+}
+
+/// Returns the name and the address range of a memory region.
+fn region_name_and_range(region: &MemoryRegion) -> (Option<&str>, &Range<u32>) {
+    match region {
+        MemoryRegion::Ram(region) => (region.name.as_deref(), &region.range),
+        MemoryRegion::Nvm(region) => (region.name.as_deref(), &region.range),
+        MemoryRegion::Generic(region) => (region.name.as_deref(), &region.range),
+    }
+}
+
+/// Looks up a memory region of the target by its name.
+fn find_region(name: &str, memory_map: &[MemoryRegion]) -> Result<Range<u32>> {
+    memory_map
+        .iter()
+        .map(region_name_and_range)
+        .find(|(region, _)| *region == Some(name))
+        .map(|(_, range)| range.clone())
+        .ok_or_else(|| {
+            let names: Vec<&str> = memory_map
+                .iter()
+                .filter_map(|region| region_name_and_range(region).0)
+                .collect();
+            anyhow!(
+                "'{}' is neither an address nor a memory region of the target ({})",
+                name,
+                names.join(", ")
+            )
+        })
+}
+
+/// Parses an address, or the name of a memory region which stands for its start address.
+fn parse_address(location: &str, memory_map: &[MemoryRegion]) -> Result<u32> {
+    match crate::parse_u32(location) {
+        Ok(address) => Ok(address),
+        Err(_) => Ok(find_region(location, memory_map)?.start),
+    }
+}
+
+/// Parses a range of memory: `start..end`, `start+length`, or the name of a memory region.
+pub(crate) fn parse_range(location: &str, memory_map: &[MemoryRegion]) -> Result<Range<u32>> {
+    let range = if let Some((start, end)) = location.split_once("..") {
+        parse_address(start, memory_map)?..parse_address(end, memory_map)?
+    } else if let Some((start, length)) = location.split_once('+') {
+        let start = parse_address(start, memory_map)?;
+        let length = crate::parse_u32(length)
+            .with_context(|| format!("'{}' is not a valid length", length))?;
+        let end = start
+            .checked_add(length)
+            .ok_or_else(|| anyhow!("The range {} exceeds the address space", location))?;
+        start..end
+    } else if crate::parse_u32(location).is_ok() {
+        bail!(
+            "The length of the memory to read is missing, use e.g. {}+0x100",
+            location
+        );
+    } else {
+        find_region(location, memory_map)?
+    };
+
+    if range.is_empty() {
+        bail!(
+            "The range {:#010x}..{:#010x} is empty",
+            range.start,
+            range.end
+        );
+    }
+
+    Ok(range)
+}
+
+/// Checks that data can be accessed with the access width.
+fn check_alignment(address: u32, length: usize, width: AccessWidth) -> Result<()> {
+    let bytes = width.bytes();
+    if address % bytes != 0 || length as u32 % bytes != 0 {
+        bail!(
+            "The address {:#010x} and the length {} must be multiples of {} for {}-bit accesses",
+            address,
+            length,
+            bytes,
+            bytes * 8
+        );
+    }
+
+    Ok(())
+}
+
+/// Flash cannot be written with plain memory accesses, it has to be programmed with the flash algorithm.
+fn check_not_flash(segment: &Segment, memory_map: &[MemoryRegion]) -> Result<()> {
+    let end = segment.address.saturating_add(segment.data.len() as u32);
+
+    for region in memory_map {
+        if let MemoryRegion::Nvm(region) = region {
+            if segment.address < region.range.end && region.range.start < end {
+                bail!(
+                    "The data at {:#010x} is in the flash region {}, use the download command to program flash",
+                    segment.address,
+                    region.name.as_deref().unwrap_or("without a name")
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads `length` bytes of memory with accesses of the given width.
+fn read_memory(
+    core: &mut Core,
+    address: u32,
+    length: usize,
+    width: AccessWidth,
+) -> Result<Vec<u8>> {
+    let data = match width {
+        AccessWidth::U8 => {
+            let mut data = vec![0u8; length];
+            core.read_8(address, &mut data)?;
+            data
+        }
+        AccessWidth::U16 => {
+            let mut words = vec![0u16; length / 2];
+            core.read_16(address, &mut words)?;
+            words.iter().flat_map(|word| word.to_le_bytes()).collect()
+        }
+        AccessWidth::U32 => {
+            let mut words = vec![0u32; length / 4];
+            core.read_32(address, &mut words)?;
+            words.iter().flat_map(|word| word.to_le_bytes()).collect()
+        }
+    };
+
+    Ok(data)
+}
+
+/// Writes bytes to memory with accesses of the given width.
+fn write_memory(core: &mut Core, address: u32, data: &[u8], width: AccessWidth) -> Result<()> {
+    match width {
+        AccessWidth::U8 => core.write_8(address, data)?,
+        AccessWidth::U16 => {
+            let words: Vec<u16> = data
+                .chunks_exact(2)
+                .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+                .collect();
+            core.write_16(address, &words)?;
+        }
+        AccessWidth::U32 => {
+            let words: Vec<u32> = data
+                .chunks_exact(4)
+                .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+                .collect();
+            core.write_32(address, &words)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes lines of 16 bytes, with the words of the access width and the bytes as ASCII.
+fn write_hexdump(
+    output: &mut dyn Write,
+    address: u32,
+    data: &[u8],
+    width: AccessWidth,
+) -> Result<()> {
+    let bytes = width.bytes() as usize;
+
+    for (index, line) in data.chunks(16).enumerate() {
+        write!(output, "{:08x}:", address as usize + index * 16)?;
+
+        for word in line.chunks(bytes) {
+            let value = word
+                .iter()
+                .rev()
+                .fold(0u32, |value, byte| value << 8 | *byte as u32);
+            write!(output, " {:0width$x}", value, width = bytes * 2)?;
+        }
+
+        // Align the ASCII column of a short last line.
+        let missing = (16 - line.len()) / bytes;
+        write!(output, "{:width$}", "", width = missing * (bytes * 2 + 1))?;
+
+        let text: String = line
+            .iter()
+            .map(|byte| {
+                if byte.is_ascii_graphic() || *byte == b' ' {
+                    *byte as char
+                } else {
+                    '.'
+                }
+            })
+            .collect();
+        writeln!(output, "  {}", text)?;
+    }
+
+    Ok(())
+}
+
+/// Reads the data of an Intel HEX file, merging contiguous records into one segment.
+fn hex_segments(hex: &str) -> Result<Vec<Segment>> {
+    let mut segments: Vec<Segment> = Vec::new();
+    let mut base_address = 0;
+
+    for record in ihex::Reader::new(hex) {
+        match record? {
+            Record::Data { offset, value } => {
+                let address = base_address + offset as u32;
+                match segments.last_mut() {
+                    Some(last) if last.address + last.data.len() as u32 == address => {
+                        last.data.extend_from_slice(&value)
+                    }
+                    _ => segments.push(Segment {
+                        address,
+                        data: value,
+                    }),
+                }
+            }
+            Record::ExtendedSegmentAddress(address) => base_address = (address as u32) * 16,
+            Record::ExtendedLinearAddress(address) => base_address = (address as u32) << 16,
+            _ => {}
+        }
+    }
+
+    Ok(segments)
+}
+
+/// Reads the allocated sections with data of an ELF file, or only the sections with the given names.
+fn elf_segments(buffer: &[u8], sections: &[String]) -> Result<Vec<Segment>> {
+    use goblin::elf::section_header::{SHF_ALLOC, SHT_NOBITS};
+
+    let elf = goblin::elf::Elf::parse(buffer)?;
+    let mut segments = Vec::new();
+
+    for header in &elf.section_headers {
+        let name = elf.shdr_strtab.get_at(header.sh_name).unwrap_or("");
+
+        let selected = if sections.is_empty() {
+            header.sh_flags & u64::from(SHF_ALLOC) != 0 && header.sh_type != SHT_NOBITS
+        } else {
+            sections.iter().any(|section| section == name)
+        };
+        if !selected || header.sh_size == 0 {
+            continue;
+        }
+
+        if header.sh_type == SHT_NOBITS {
+            bail!("The section {} has no data in the ELF file", name);
+        }
+
+        let data = header
+            .file_range()
+            .and_then(|range| buffer.get(range))
+            .ok_or_else(|| anyhow!("The section {} is outside of the ELF file", name))?;
+
+        log::debug!(
+            "Found section {} with {} bytes at {:#010x}",
+            name,
+            data.len(),
+            header.sh_addr
+        );
+        segments.push(Segment {
+            address: header.sh_addr as u32,
+            data: data.to_vec(),
+        });
+    }
+
+    if let Some(missing) = sections.iter().find(|section| {
+        !elf.section_headers
+            .iter()
+            .any(|header| elf.shdr_strtab.get_at(header.sh_name) == Some(section.as_str()))
+    }) {
+        bail!("The ELF file has no section {}", missing);
+    }
+
+    Ok(segments)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use probe_rs::config::{NvmRegion, RamRegion};
+
+    fn memory_map() -> Vec<MemoryRegion> {
+        vec![
+            MemoryRegion::Nvm(NvmRegion {
+                name: Some("FLASH".to_string()),
+                range: 0x0800_0000..0x0802_0000,
+                is_boot_memory: true,
+                cores: vec!["main".to_string()],
+            }),
+            MemoryRegion::Ram(RamRegion {
+                name: Some("SRAM".to_string()),
+                range: 0x2000_0000..0x2000_8000,
+                is_boot_memory: false,
+                cores: vec!["main".to_string()],
+            }),
+        ]
+    }
+
+    #[test]
+    fn parse_ranges() {
+        let map = memory_map();
+
+        assert_eq!(
+            parse_range("0x20000000..0x20000100", &map).unwrap(),
+            0x2000_0000..0x2000_0100
+        );
+        assert_eq!(
+            parse_range("0x20000000+256", &map).unwrap(),
+            0x2000_0000..0x2000_0100
+        );
+        assert_eq!(
+            parse_range("SRAM+0x10", &map).unwrap(),
+            0x2000_0000..0x2000_0010
+        );
+        assert_eq!(parse_range("SRAM", &map).unwrap(), 0x2000_0000..0x2000_8000);
+        assert!(parse_range("CCMRAM", &map).is_err());
+        assert!(parse_range("0x100..0x100", &map).is_err());
+
+        let segment = Segment {
+            address: 0x0801_fffc,
+            data: vec![0; 8],
+        };
+        assert!(check_not_flash(&segment, &map).is_err());
+        assert!(check_alignment(0x2000_0002, 4, AccessWidth::U32).is_err());
+        assert!(check_alignment(0x2000_0002, 4, AccessWidth::U16).is_ok());
+    }
+
+    #[test]
+    fn write_hexdump_lines() {
+        let data: Vec<u8> = (0x41..0x55).collect();
+        let mut output = Vec::new();
+        write_hexdump(&mut output, 0x2000_0000, &data, AccessWidth::U32).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "20000000: 44434241 48474645 4c4b4a49 504f4e4d  ABCDEFGHIJKLMNOP\n\
+             20000010: 54535251                             QRST\n"
+        );
+
+        let mut output = Vec::new();
+        write_hexdump(
+            &mut output,
+            0x10,
+            &[0x01, 0x02, 0x03, 0x04],
+            AccessWidth::U16,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "00000010: 0201 0403                                ....\n"
+        );
+    }
+
+    #[test]
+    fn ihex_round_trip() {
+        let data: Vec<u8> = (0..40).collect();
+        let block = FlashBlock {
+            address: 0x2000_fff0,
+            data: data.clone(),
+        };
+
+        let mut hex = Vec::new();
+        flashing::write_hex([&block], &mut hex).unwrap();
+        let hex = String::from_utf8(hex).unwrap();
+
+        assert_eq!(
+            hex_segments(&hex).unwrap(),
+            vec![Segment {
+                address: 0x2000_fff0,
+                data,
+            }]
+        );
+    }
+}
@@ -0,0 +1,470 @@
+use anyhow::{anyhow, Context, Result};
+use probe_rs::architecture::arm::component::{disable_swv, enable_tracing, read_pc_sample};
+use probe_rs::architecture::arm::swo::trace::{TraceDecoder, TraceEvent};
+use probe_rs::architecture::arm::SwoConfig;
+use probe_rs::debug::debug_info::DebugInfo;
+use probe_rs::{Core, Session};
+use probe_rs_cli_util::common_options::ProbeOptions;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How the program counter of the target is sampled.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SampleMethod {
+    /// Read the DWT program counter sample register, without halting the core.
+    Pcsr,
+    /// Let the DWT send periodic program counter samples over SWO.
+    Swo,
+    /// Halt the core for every sample, and unwind the complete call stack.
+    Halt,
+}
+
+impl FromStr for SampleMethod {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match &s.to_ascii_lowercase()[..] {
+            "pcsr" => Ok(Self::Pcsr),
+            "swo" => Ok(Self::Swo),
+            "halt" => Ok(Self::Halt),
+            _ => Err(format!(
+                "{} is not a valid sample method (pcsr, swo, halt)",
+                s
+            )),
+        }
+    }
+}
+
+/// Options of the `profile` command.
+#[derive(clap::StructOpt)]
+pub struct ProfileOptions {
+    /// The ELF file of the program running on the target, used to find the functions of the samples.
+    #[structopt(parse(from_os_str))]
+    elf: PathBuf,
+
+    #[structopt(long, default_value = "0")]
+    core: usize,
+
+    /// How long to collect samples, in seconds.
+    #[structopt(long, default_value = "5")]
+    duration: f64,
+
+    /// How the program counter is sampled: `pcsr` reads the DWT sample register without halting the core,
+    /// `swo` receives the samples of the DWT over SWO, and `halt` halts the core to record complete call stacks.
+    #[structopt(long, default_value = "pcsr")]
+    method: SampleMethod,
+
+    /// The number of samples per second for the `pcsr` and `halt` methods.
+    #[structopt(long, default_value = "1000")]
+    rate: u32,
+
+    /// The frequency of the core clock in Hz, required for the `swo` method.
+    #[structopt(long, parse(try_from_str = crate::parse_u32))]
+    clock: Option<u32>,
+
+    /// The baud rate of SWO in Hz, for the `swo` method.
+    #[structopt(long, default_value = "1000000", parse(try_from_str = crate::parse_u32))]
+    baud: u32,
+
+    /// The number of cycles between two samples of the `swo` method.
+    #[structopt(long, default_value = "16384", parse(try_from_str = crate::parse_u32))]
+    interval: u32,
+
+    /// The number of functions listed in the table.
+    #[structopt(long, default_value = "25")]
+    limit: usize,
+
+    /// Write the call stacks of the samples to this file, in the folded format of flamegraph tools.
+    #[structopt(long, parse(from_os_str))]
+    folded: Option<PathBuf>,
+}
+
+/// Collects samples of the program counter of the target, and prints the functions the time is spent in.
+pub fn run(common: ProbeOptions, options: ProfileOptions) -> Result<()> {
+    let debug_info = DebugInfo::from_file(&options.elf)
+        .map_err(|e| anyhow!("Failed to read the debug info of {:?}: {}", options.elf, e))?;
+    let mut symbolizer = Symbolizer::new(debug_info);
+
+    let mut session = common.simple_attach()?;
+    let duration = Duration::from_secs_f64(options.duration);
+
+    let samples = match options.method {
+        SampleMethod::Pcsr => {
+            let mut core = session.core(options.core)?;
+            enable_tracing(&mut core)?;
+            let samples = sample_periodically(options.rate, duration, || {
+                Ok(read_pc_sample(&mut core)?.map(|pc| vec![pc as u64]))
+            });
+
+            // Disable the trace again, also if sampling failed.
+            with_cleanup(samples, disable_swv(&mut core))?
+        }
+        SampleMethod::Halt => {
+            let mut core = session.core(options.core)?;
+            sample_periodically(options.rate, duration, || {
+                sample_call_stack(&mut core, &symbolizer.debug_info)
+            })?
+        }
+        SampleMethod::Swo => sample_swo(&mut session, &options, duration)?,
+    };
+
+    let profile = Profile::from_samples(&samples, |pc| symbolizer.function(pc));
+
+    if profile.total == 0 {
+        return Err(anyhow!(
+            "No samples of the program counter were collected. Is the core running?"
+        ));
+    }
+
+    let mut stdout = std::io::stdout();
+    profile.write_table(&mut stdout, options.limit)?;
+
+    if let Some(path) = &options.folded {
+        let mut file = BufWriter::new(
+            File::create(path).with_context(|| format!("Failed to create {:?}", path))?,
+        );
+        profile.write_folded(&mut file)?;
+        file.flush()?;
+    }
+
+    Ok(())
+}
+
+/// Calls `sample` `rate` times per second, until `duration` has passed.
+///
+/// A sample is the call stack of the core, with the innermost frame first,
+/// or `None` if the program counter could not be sampled.
+fn sample_periodically(
+    rate: u32,
+    duration: Duration,
+    mut sample: impl FnMut() -> Result<Option<Vec<u64>>>,
+) -> Result<Vec<Option<Vec<u64>>>> {
+    let period = Duration::from_secs(1) / rate.max(1);
+    let start = Instant::now();
+    let mut next = start;
+    let mut samples = Vec::new();
+
+    while start.elapsed() < duration {
+        samples.push(sample()?);
+
+        // If the probe is slower than the requested rate, sample as fast as it allows.
+        next += period;
+        if let Some(remaining) = next.checked_duration_since(Instant::now()) {
+            thread::sleep(remaining);
+        }
+    }
+
+    Ok(samples)
+}
+
+/// Halts the core, unwinds its call stack and lets it continue.
+fn sample_call_stack(core: &mut Core, debug_info: &DebugInfo) -> Result<Option<Vec<u64>>> {
+    let pc = core.halt(Duration::from_millis(100))?.pc;
+    let stack = debug_info.unwind(core, pc as u64);
+    core.run()?;
+
+    // Inlined functions share the program counter of the function containing them,
+    // and are found again when the address is symbolized.
+    let pcs: Vec<u64> = match stack {
+        Ok(frames) => frames
+            .iter()
+            .filter(|frame| !frame.is_inlined)
+            .map(|frame| frame.pc as u64)
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+
+    Ok(Some(if pcs.is_empty() { vec![pc as u64] } else { pcs }))
+}
+
+/// Receives the program counter samples of the DWT over SWO.
+fn sample_swo(
+    session: &mut Session,
+    options: &ProfileOptions,
+    duration: Duration,
+) -> Result<Vec<Option<Vec<u64>>>> {
+    let clock = options
+        .clock
+        .ok_or_else(|| anyhow!("The core clock must be given with --clock to sample over SWO"))?;
+
+    let config = SwoConfig::new(clock)
+        .set_baud(options.baud)
+        .set_continuous_formatting(false);
+    session.setup_swv(options.core, &config)?;
+
+    let samples = receive_swo_samples(session, options, duration);
+
+    // Leave the DWT and ITM as they were before, also if sampling failed.
+    let cleanup = session
+        .disable_swv_pc_sampling(options.core)
+        .and(session.disable_swv(options.core));
+
+    with_cleanup(samples, cleanup)
+}
+
+/// Enables the program counter sampling of the DWT, and decodes the samples received over SWO until `duration` has passed.
+fn receive_swo_samples(
+    session: &mut Session,
+    options: &ProfileOptions,
+    duration: Duration,
+) -> Result<Vec<Option<Vec<u64>>>> {
+    let interval = session.enable_swv_pc_sampling(options.core, options.interval)?;
+    log::info!("Sampling the program counter every {} cycles", interval);
+
+    let mut decoder = TraceDecoder::new();
+    let mut samples = Vec::new();
+    let start = Instant::now();
+
+    while start.elapsed() < duration {
+        decoder.push(&session.read_swo()?);
+
+        while let Some(event) = decoder.pull() {
+            match event {
+                TraceEvent::PcSample { pc } => samples.push(pc.map(|pc| vec![pc as u64])),
+                TraceEvent::Overflow => log::warn!(
+                    "The ITM overflowed, and samples were lost. Try a larger --interval or a higher --baud."
+                ),
+                _ => {}
+            }
+        }
+    }
+
+    Ok(samples)
+}
+
+/// Returns `result`, unless only the `cleanup` after it failed.
+///
+/// If both failed, the error of the cleanup is logged, and the error of `result` is returned.
+fn with_cleanup<T>(result: Result<T>, cleanup: Result<(), probe_rs::Error>) -> Result<T> {
+    match (result, cleanup) {
+        (Err(error), Err(cleanup_error)) => {
+            log::warn!("Failed to disable the trace: {}", cleanup_error);
+            Err(error)
+        }
+        (result, cleanup) => {
+            cleanup?;
+            result
+        }
+    }
+}
+
+/// Finds the function names of addresses, and remembers them, because the lookup in the debug info is slow.
+struct Symbolizer {
+    debug_info: DebugInfo,
+    functions: HashMap<u64, String>,
+}
+
+impl Symbolizer {
+    fn new(debug_info: DebugInfo) -> Self {
+        Self {
+            debug_info,
+            functions: HashMap::new(),
+        }
+    }
+
+    /// Returns the name of the innermost function at `address`, or the address itself if it is not part of a known function.
+    fn function(&mut self, address: u64) -> String {
+        let debug_info = &self.debug_info;
+
+        self.functions
+            .entry(address)
+            .or_insert_with(|| {
+                let name = debug_info.function_name(address, true).ok().flatten();
+
+                match (name, debug_info.get_source_location(address)) {
+                    (Some(name), _) => name,
+                    (None, Some(location)) => format!(
+                        "{:#010x} ({}:{})",
+                        address,
+                        location.file.unwrap_or_default(),
+                        location.line.unwrap_or_default()
+                    ),
+                    (None, None) => format!("{:#010x}", address),
+                }
+            })
+            .clone()
+    }
+}
+
+/// The samples, grouped by the functions of their call stacks.
+#[derive(Debug, Default)]
+struct Profile {
+    /// The number of samples of each call stack, with the outermost function first.
+    stacks: HashMap<Vec<String>, usize>,
+    /// The number of samples with a program counter.
+    total: usize,
+    /// The number of samples without a program counter, e.g. because the core was sleeping.
+    missed: usize,
+}
+
+/// A row of the function table.
+#[derive(Debug, PartialEq)]
+struct FunctionCount {
+    name: String,
+    /// The number of samples in the function itself.
+    flat: usize,
+    /// The number of samples in the function and the functions called by it.
+    cumulative: usize,
+}
+
+impl Profile {
+    fn from_samples(samples: &[Option<Vec<u64>>], mut function: impl FnMut(u64) -> String) -> Self {
+        let mut profile = Profile::default();
+
+        for sample in samples {
+            match sample {
+                Some(stack) if !stack.is_empty() => {
+                    let frames = stack.iter().rev().map(|pc| function(*pc)).collect();
+                    *profile.stacks.entry(frames).or_default() += 1;
+                    profile.total += 1;
+                }
+                _ => profile.missed += 1,
+            }
+        }
+
+        profile
+    }
+
+    /// Returns the functions, sorted by the number of samples in the function itself.
+    fn functions(&self) -> Vec<FunctionCount> {
+        let mut counts: HashMap<&str, (usize, usize)> = HashMap::new();
+
+        for (stack, samples) in &self.stacks {
+            if let Some(leaf) = stack.last() {
+                counts.entry(leaf).or_default().0 += samples;
+            }
+
+            // Recursive functions are only counted once per stack.
+            let unique: HashSet<&str> = stack.iter().map(String::as_str).collect();
+            for function in unique {
+                counts.entry(function).or_default().1 += samples;
+            }
+        }
+
+        let mut functions: Vec<_> = counts
+            .into_iter()
+            .map(|(name, (flat, cumulative))| FunctionCount {
+                name: name.to_string(),
+                flat,
+                cumulative,
+            })
+            .collect();
+
+        functions.sort_by(|a, b| {
+            b.flat
+                .cmp(&a.flat)
+                .then(b.cumulative.cmp(&a.cumulative))
+                .then(a.name.cmp(&b.name))
+        });
+        functions
+    }
+
+    fn write_table(&self, output: &mut impl Write, limit: usize) -> Result<()> {
+        writeln!(
+            output,
+            "{} samples, {} without a program counter",
+            self.total, self.missed
+        )?;
+        writeln!(
+            output,
+            "{:>8} {:>7} {:>8} {:>7}  Function",
+            "Flat", "Flat%", "Cum", "Cum%"
+        )?;
+
+        let percent = |count: usize| 100.0 * count as f64 / self.total as f64;
+
+        for function in self.functions().iter().take(limit) {
+            writeln!(
+                output,
+                "{:>8} {:>6.2}% {:>8} {:>6.2}%  {}",
+                function.flat,
+                percent(function.flat),
+                function.cumulative,
+                percent(function.cumulative),
+                function.name
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes one line per call stack, with the functions separated by `;` and the number of samples.
+    fn write_folded(&self, output: &mut impl Write) -> Result<()> {
+        let mut stacks: Vec<_> = self
+            .stacks
+            .iter()
+            .map(|(stack, samples)| (stack.join(";"), samples))
+            .collect();
+        stacks.sort();
+
+        for (stack, samples) in stacks {
+            writeln!(output, "{} {}", stack, samples)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{FunctionCount, Profile};
+
+    fn profile() -> Profile {
+        let samples = vec![
+            Some(vec![0x10, 0x20]),
+            Some(vec![0x11, 0x20]),
+            Some(vec![0x20]),
+            Some(vec![0x30, 0x10, 0x20]),
+            None,
+        ];
+
+        Profile::from_samples(&samples, |pc| match pc & !0xf {
+            0x10 => "work".to_string(),
+            0x20 => "main".to_string(),
+            _ => "memcpy".to_string(),
+        })
+    }
+
+    #[test]
+    fn count_functions() {
+        let profile = profile();
+        assert_eq!((profile.total, profile.missed), (4, 1));
+
+        assert_eq!(
+            profile.functions(),
+            vec![
+                FunctionCount {
+                    name: "work".to_string(),
+                    flat: 2,
+                    cumulative: 3,
+                },
+                FunctionCount {
+                    name: "main".to_string(),
+                    flat: 1,
+                    cumulative: 4,
+                },
+                FunctionCount {
+                    name: "memcpy".to_string(),
+                    flat: 1,
+                    cumulative: 1,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn write_folded_stacks() {
+        let mut output = Vec::new();
+        profile().write_folded(&mut output).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "main 1\nmain;work 2\nmain;work;memcpy 1\n"
+        );
+    }
+}
@@ -1,17 +1,67 @@
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
 use probe_rs::flashing::FileDownloadError;
+use probe_rs::semihosting::{SemihostingEvent, SemihostingHost, SemihostingOptions};
+use probe_rs::VectorCatchCondition;
 use probe_rs_cli_util::common_options::{CargoOptions, FlashOptions, ProbeOptions};
 use probe_rs_cli_util::flash::run_flash_download;
 use probe_rs_cli_util::rtt;
 use std::fs::File;
-use std::io::Write;
-use std::path::Path;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::terminal;
+
+/// The longest time a semihosting request of the target waits to be handled.
+const SEMIHOSTING_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// How input from stdin is forwarded to the RTT down channel of the target.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InputMode {
+    /// Complete lines are sent, including the line ending.
+    Line,
+    /// The terminal is put into raw mode, and every key is sent as soon as it is pressed.
+    Raw,
+}
+
+impl FromStr for InputMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match &s.to_ascii_lowercase()[..] {
+            "line" => Ok(Self::Line),
+            "raw" => Ok(Self::Raw),
+            _ => Err(format!("{} is not a valid input mode (line, raw)", s)),
+        }
+    }
+}
+
+/// Options for the interaction with the running target.
+#[derive(clap::StructOpt)]
+pub struct TerminalOptions {
+    /// How stdin is forwarded to the first RTT down channel: `line` or `raw`.
+    #[structopt(long, default_value = "line")]
+    input_mode: InputMode,
+
+    /// Reset and halt the core when exiting with Ctrl-C, instead of leaving it running.
+    #[structopt(long)]
+    reset_halt_on_exit: bool,
+}
 
 pub fn run(
     common: ProbeOptions,
     path: &str,
     chip_erase: bool,
     disable_double_buffering: bool,
+    semihosting_root: Option<PathBuf>,
+    catch_hardfault: bool,
+    terminal_options: TerminalOptions,
 ) -> Result<()> {
     let mut session = common.simple_attach()?;
 
@@ -49,8 +99,20 @@ pub fn run(
 
     let memory_map = session.target().memory_map.clone();
 
+    let mut semihosting = SemihostingHost::new(SemihostingOptions {
+        root: semihosting_root,
+        command_line: path.to_owned(),
+    });
+
     let mut core = session.core(0)?;
-    core.reset()?;
+    core.reset_and_halt(Duration::from_millis(500))?;
+    // Enable the catch while the core is still halted at the reset vector, so it also
+    // covers faults during the startup code.
+    if catch_hardfault {
+        core.enable_vector_catch(VectorCatchCondition::HardFault)?;
+    }
+    semihosting.enable(&mut core)?;
+    core.run()?;
 
     let mut rtta = match rtt::attach_to_rtt(&mut core, &memory_map, Path::new(path), &rtt_config) {
         Ok(target_rtt) => Some(target_rtt),
@@ -60,14 +122,225 @@ pub fn run(
         }
     };
 
-    if let Some(rtta) = &mut rtta {
-        let mut stdout = std::io::stdout();
+    let exit = Arc::new(AtomicBool::new(false));
+    {
+        let exit = exit.clone();
+        ctrlc::set_handler(move || exit.store(true, Ordering::SeqCst))?;
+    }
+
+    let raw_mode = terminal_options.input_mode == InputMode::Raw;
+    let raw_terminal = if raw_mode {
+        Some(RawTerminal::enable()?)
+    } else {
+        None
+    };
+    let input = spawn_input_reader(terminal_options.input_mode, exit.clone());
+
+    let mut stdout = std::io::stdout();
+    let mut halt_reported = false;
+    loop {
+        if exit.load(Ordering::SeqCst) {
+            if terminal_options.reset_halt_on_exit {
+                core.reset_and_halt(Duration::from_millis(500))?;
+            }
+
+            // Dropping the session detaches from the target.
+            log::info!("Exiting");
+            return Ok(());
+        }
+
         loop {
-            for (_ch, data) in rtta.poll_rtt(&mut core) {
-                stdout.write_all(data.as_bytes()).unwrap();
+            match input.try_recv() {
+                Ok(data) => match &mut rtta {
+                    Some(rtta) => {
+                        if let Err(error) = rtta.push_rtt(&mut core, None, &data) {
+                            log::warn!("Discarding input: {}", error);
+                        }
+                    }
+                    None => log::warn!("Discarding input, because RTT is not available"),
+                },
+                Err(TryRecvError::Empty) | Err(TryRecvError::Disconnected) => break,
+            }
+        }
+
+        let poll_interval = match &mut rtta {
+            Some(rtta) => {
+                for (_ch, data) in rtta.poll_rtt(&mut core) {
+                    write_output(&mut stdout, data.as_bytes(), raw_mode)?;
+                }
+                rtta.poll_interval()
+            }
+            None => SEMIHOSTING_POLL_INTERVAL,
+        };
+
+        if !core.core_halted()? {
+            // Semihosting requests halt the core, and are only handled between polls.
+            halt_reported = false;
+            thread::sleep(poll_interval.min(SEMIHOSTING_POLL_INTERVAL));
+            continue;
+        }
+
+        match semihosting.handle(&mut core)? {
+            Some(SemihostingEvent::Stdout(data)) => write_output(&mut stdout, &data, raw_mode)?,
+            Some(SemihostingEvent::Stderr(data)) => {
+                write_output(&mut std::io::stderr(), &data, raw_mode)?
+            }
+            Some(SemihostingEvent::Handled) => {}
+            Some(SemihostingEvent::Exit(code)) => {
+                stdout.flush()?;
+                log::info!("Target exited with code {}", code);
+                drop(raw_terminal);
+                std::process::exit(code);
+            }
+            None => {
+                // The core might be halted by a breakpoint, or by another debugger. RTT output
+                // which is still buffered is printed until exit, as long as the core stays halted.
+                if !halt_reported {
+                    halt_reported = true;
+
+                    match core.fault_info()? {
+                        Some(fault) if catch_hardfault => {
+                            return Err(anyhow!("The core halted due to a fault.\n{}", fault));
+                        }
+                        Some(fault) => log::warn!("The core halted due to a fault.\n{}", fault),
+                        None => {
+                            let pc = core.read_core_reg(core.registers().program_counter())?;
+                            log::warn!("The core halted at {:#010x}", pc);
+                        }
+                    }
+                }
+
+                thread::sleep(poll_interval);
             }
         }
     }
+}
 
-    Ok(())
+/// Writes output of the target. In raw mode, the terminal does not return the cursor on a line feed.
+fn write_output(output: &mut impl Write, data: &[u8], raw_mode: bool) -> std::io::Result<()> {
+    if raw_mode {
+        for line in data.split_inclusive(|byte| *byte == b'\n') {
+            match line.strip_suffix(b"\n") {
+                Some(line) => {
+                    output.write_all(line)?;
+                    output.write_all(b"\r\n")?;
+                }
+                None => output.write_all(line)?,
+            }
+        }
+    } else {
+        output.write_all(data)?;
+    }
+    output.flush()
+}
+
+/// Reads stdin on a separate thread, so the target can be polled while waiting for input.
+fn spawn_input_reader(mode: InputMode, exit: Arc<AtomicBool>) -> Receiver<Vec<u8>> {
+    let (sender, receiver) = mpsc::channel();
+
+    thread::spawn(move || match mode {
+        InputMode::Line => {
+            let stdin = std::io::stdin();
+            let mut stdin = stdin.lock();
+            let mut line = String::new();
+
+            // Stop at the end of the input, or when the main thread is gone.
+            while stdin.read_line(&mut line).map_or(false, |count| count > 0) {
+                if sender.send(line.clone().into_bytes()).is_err() {
+                    break;
+                }
+                line.clear();
+            }
+        }
+        InputMode::Raw => loop {
+            let key = match event::read() {
+                Ok(Event::Key(key)) => key,
+                Ok(_) => continue,
+                Err(error) => {
+                    log::error!("Error reading from the terminal: {}", error);
+                    break;
+                }
+            };
+
+            // Ctrl-C does not raise a signal in raw mode.
+            if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+                exit.store(true, Ordering::SeqCst);
+                break;
+            }
+
+            if let Some(data) = key_to_bytes(key) {
+                if sender.send(data).is_err() {
+                    break;
+                }
+            }
+        },
+    });
+
+    receiver
+}
+
+/// Converts a key press into the bytes a serial terminal would send.
+fn key_to_bytes(key: KeyEvent) -> Option<Vec<u8>> {
+    let bytes = match key.code {
+        KeyCode::Char(c) if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            if c.is_ascii_alphabetic() {
+                vec![c.to_ascii_uppercase() as u8 & 0x1f]
+            } else {
+                return None;
+            }
+        }
+        KeyCode::Char(c) => c.to_string().into_bytes(),
+        KeyCode::Enter => vec![b'\n'],
+        KeyCode::Tab => vec![b'\t'],
+        KeyCode::Backspace => vec![0x08],
+        KeyCode::Esc => vec![0x1b],
+        KeyCode::Delete => vec![0x7f],
+        KeyCode::Up => b"\x1b[A".to_vec(),
+        KeyCode::Down => b"\x1b[B".to_vec(),
+        KeyCode::Right => b"\x1b[C".to_vec(),
+        KeyCode::Left => b"\x1b[D".to_vec(),
+        _ => return None,
+    };
+
+    Some(bytes)
+}
+
+/// Puts the terminal into raw mode, and restores it when dropped.
+struct RawTerminal;
+
+impl RawTerminal {
+    fn enable() -> Result<Self> {
+        terminal::enable_raw_mode()?;
+        Ok(RawTerminal)
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        if let Err(error) = terminal::disable_raw_mode() {
+            log::error!("Failed to restore the terminal: {}", error);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn raw_output_returns_the_cursor() {
+        let mut output = Vec::new();
+        write_output(&mut output, b"first\nsecond\nthird", true).unwrap();
+
+        assert_eq!(output, b"first\r\nsecond\r\nthird");
+    }
+
+    #[test]
+    fn control_keys() {
+        let ctrl_d = KeyEvent::new(KeyCode::Char('d'), KeyModifiers::CONTROL);
+        let enter = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+
+        assert_eq!(key_to_bytes(ctrl_d), Some(vec![0x04]));
+        assert_eq!(key_to_bytes(enter), Some(vec![b'\n']));
+    }
 }
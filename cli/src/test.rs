@@ -0,0 +1,454 @@
+use anyhow::{anyhow, Context, Result};
+use probe_rs::flashing::{download_file_with_options, DownloadOptions, Format};
+use probe_rs::semihosting::{SemihostingEvent, SemihostingHost, SemihostingOptions};
+use probe_rs::{Core, Session};
+use probe_rs_cli_util::common_options::ProbeOptions;
+use probe_rs_cli_util::rtt;
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Options of the `test` command.
+///
+/// The output of the firmware is read from RTT and semihosting. RTT channels named `defmt`
+/// are decoded with the defmt table of the ELF file, like in the `run` command.
+#[derive(clap::StructOpt)]
+pub struct TestOptions {
+    /// The paths to the test ELF files, which are flashed and run one after another.
+    #[structopt(required = true, parse(from_os_str))]
+    paths: Vec<PathBuf>,
+
+    /// The time in seconds after which a test is aborted and considered failed.
+    #[structopt(long, default_value = "60")]
+    timeout: u64,
+
+    /// A symbol which ends a test as passed when the core reaches it.
+    #[structopt(long)]
+    pass_symbol: Option<String>,
+
+    /// A symbol which ends a test as failed when the core reaches it, for example a panic handler.
+    #[structopt(long)]
+    fail_symbol: Option<String>,
+
+    /// Write a JUnit XML report to this file.
+    #[structopt(long, parse(from_os_str))]
+    junit: Option<PathBuf>,
+
+    /// Write a JSON report to this file.
+    #[structopt(long, parse(from_os_str))]
+    json: Option<PathBuf>,
+
+    /// Whether to erase the entire chip before downloading
+    #[structopt(long)]
+    chip_erase: bool,
+
+    /// Disable double-buffering when downloading flash.  If downloading times out, try this option.
+    #[structopt(long = "disable-double-buffering")]
+    disable_double_buffering: bool,
+
+    /// The directory the target can access files in with semihosting.
+    #[structopt(long, parse(from_os_str))]
+    semihosting_root: Option<PathBuf>,
+}
+
+/// The outcome of a single test.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+enum TestOutcome {
+    /// The firmware reported success.
+    Passed,
+    /// The firmware reported a failure, or halted unexpectedly.
+    Failed { message: String },
+    /// The firmware did not finish before the timeout.
+    TimedOut,
+    /// The test could not be run, for example because flashing failed.
+    Error { message: String },
+}
+
+/// The result of a single test, as it is written to the reports.
+#[derive(Debug, Serialize)]
+struct TestResult {
+    name: String,
+    path: PathBuf,
+    #[serde(flatten)]
+    outcome: TestOutcome,
+    /// The duration of the test in seconds, including flashing.
+    duration: f64,
+    /// The RTT and semihosting output of the firmware.
+    output: String,
+}
+
+/// The summary of all tests, as it is written to the JSON report.
+#[derive(Debug, Serialize)]
+struct TestReport {
+    passed: usize,
+    failed: usize,
+    tests: Vec<TestResult>,
+}
+
+pub fn run(common: ProbeOptions, options: TestOptions) -> Result<()> {
+    let mut session = common.simple_attach()?;
+
+    let mut tests = Vec::new();
+    for path in &options.paths {
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.display().to_string());
+
+        println!("Running {}", name);
+
+        let start = Instant::now();
+        let mut output = String::new();
+
+        let outcome = match run_test(&mut session, path, &options, &mut output) {
+            Ok(outcome) => outcome,
+            Err(error) => TestOutcome::Error {
+                message: format!("{:?}", error),
+            },
+        };
+
+        match &outcome {
+            TestOutcome::Passed => println!("{} ... passed", name),
+            TestOutcome::Failed { message } => println!("{} ... failed: {}", name, message),
+            TestOutcome::TimedOut => println!("{} ... timed out", name),
+            TestOutcome::Error { message } => println!("{} ... error: {}", name, message),
+        }
+
+        tests.push(TestResult {
+            name,
+            path: path.clone(),
+            outcome,
+            duration: start.elapsed().as_secs_f64(),
+            output,
+        });
+    }
+
+    let passed = tests
+        .iter()
+        .filter(|test| test.outcome == TestOutcome::Passed)
+        .count();
+    let report = TestReport {
+        passed,
+        failed: tests.len() - passed,
+        tests,
+    };
+
+    println!(
+        "\nTest result: {} passed, {} failed",
+        report.passed, report.failed
+    );
+
+    if let Some(path) = &options.junit {
+        fs::write(path, junit_report(&report.tests))
+            .with_context(|| format!("Failed to write the JUnit report to {:?}", path))?;
+    }
+
+    if let Some(path) = &options.json {
+        let file = File::create(path)
+            .with_context(|| format!("Failed to write the JSON report to {:?}", path))?;
+        serde_json::to_writer_pretty(file, &report)?;
+    }
+
+    if report.failed > 0 {
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
+
+/// Flashes and runs the test at `path`, until it reports its result or the timeout expires.
+///
+/// The output of the firmware is printed, and appended to `output`.
+fn run_test(
+    session: &mut Session,
+    path: &Path,
+    options: &TestOptions,
+    output: &mut String,
+) -> Result<TestOutcome> {
+    let mut download_options = DownloadOptions::default();
+    download_options.do_chip_erase = options.chip_erase;
+    download_options.disable_double_buffering = options.disable_double_buffering;
+    download_file_with_options(session, path, Format::Elf, download_options)
+        .with_context(|| format!("Failed to flash {:?}", path))?;
+
+    let pass_address = symbol_address(path, options.pass_symbol.as_deref())?;
+    let fail_address = symbol_address(path, options.fail_symbol.as_deref())?;
+
+    let mut semihosting = SemihostingHost::new(SemihostingOptions {
+        root: options.semihosting_root.clone(),
+        command_line: path.display().to_string(),
+    });
+
+    let memory_map = session.target().memory_map.clone();
+    let mut core = session.core(0)?;
+    core.reset_and_halt(Duration::from_millis(500))?;
+
+    for address in pass_address.iter().chain(fail_address.iter()) {
+        core.set_breakpoint(*address)?;
+    }
+
+    semihosting.enable(&mut core)?;
+    core.run()?;
+
+    // The defmt table is loaded from the ELF file if the firmware has a `defmt` channel.
+    let mut rtta =
+        match rtt::attach_to_rtt(&mut core, &memory_map, path, &rtt::RttConfig::default()) {
+            Ok(target_rtt) => Some(target_rtt),
+            Err(error) => {
+                log::warn!("{:?} Continuing without RTT... ", error);
+                None
+            }
+        };
+
+    let undecodable_channels: Vec<String> = match &rtta {
+        Some(rtta) if rtta.defmt_state.is_none() => rtta
+            .active_channels
+            .iter()
+            .filter(|channel| channel.data_format == rtt::DataFormat::Defmt)
+            .map(|channel| channel.channel_name.clone())
+            .collect(),
+        _ => Vec::new(),
+    };
+    if !undecodable_channels.is_empty() {
+        log::warn!(
+            "The defmt table could not be loaded from {:?}, the output of the RTT channels {:?} is not shown",
+            path,
+            undecodable_channels
+        );
+    }
+
+    let timeout = Duration::from_secs(options.timeout);
+    let start = Instant::now();
+
+    let outcome = loop {
+        if let Some(rtta) = &mut rtta {
+            for (channel, data) in rtta.poll_rtt(&mut core) {
+                if !undecodable_channels.contains(&channel) {
+                    print_output(output, data.as_bytes());
+                }
+            }
+        }
+
+        if core.core_halted()? {
+            match semihosting.handle(&mut core)? {
+                Some(SemihostingEvent::Stdout(data)) | Some(SemihostingEvent::Stderr(data)) => {
+                    print_output(output, &data)
+                }
+                Some(SemihostingEvent::Handled) => {}
+                Some(SemihostingEvent::Exit(0)) => break TestOutcome::Passed,
+                Some(SemihostingEvent::Exit(code)) => {
+                    break TestOutcome::Failed {
+                        message: format!("The firmware exited with code {}", code),
+                    }
+                }
+                None => break halted_outcome(&mut core, pass_address, fail_address)?,
+            }
+        }
+
+        if start.elapsed() > timeout {
+            break TestOutcome::TimedOut;
+        }
+
+        thread::sleep(Duration::from_millis(1));
+    };
+
+    // Leave the core halted, and remove the breakpoints for the next test.
+    core.halt(Duration::from_millis(100))?;
+    for address in pass_address.iter().chain(fail_address.iter()) {
+        core.clear_breakpoint(*address)?;
+    }
+
+    Ok(outcome)
+}
+
+/// Determines the outcome of a test, after the core halted without a semihosting request.
+fn halted_outcome(
+    core: &mut Core,
+    pass_address: Option<u32>,
+    fail_address: Option<u32>,
+) -> Result<TestOutcome> {
+    let pc = core.read_core_reg(core.registers().program_counter())?;
+
+    Ok(if Some(pc) == pass_address {
+        TestOutcome::Passed
+    } else if Some(pc) == fail_address {
+        TestOutcome::Failed {
+            message: format!("The firmware reached the failure symbol at {:#010x}", pc),
+        }
+    } else {
+        TestOutcome::Failed {
+            message: format!("The core halted unexpectedly at {:#010x}", pc),
+        }
+    })
+}
+
+/// Prints the output of the firmware, and records it for the reports.
+fn print_output(output: &mut String, data: &[u8]) {
+    let data = String::from_utf8_lossy(data);
+    print!("{}", data);
+    let _ = std::io::stdout().flush();
+    output.push_str(&data);
+}
+
+/// Looks up the address of the function `symbol` in the ELF file at `path`.
+fn symbol_address(path: &Path, symbol: Option<&str>) -> Result<Option<u32>> {
+    let symbol = match symbol {
+        Some(symbol) => symbol,
+        None => return Ok(None),
+    };
+
+    let mut buffer = Vec::new();
+    File::open(path)?.read_to_end(&mut buffer)?;
+    let elf = goblin::elf::Elf::parse(&buffer)?;
+
+    elf.syms
+        .iter()
+        .find(|sym| elf.strtab.get_at(sym.st_name) == Some(symbol))
+        // The lowest bit of Thumb function addresses is set, but it is not part of the address.
+        .map(|sym| Some(sym.st_value as u32 & !1))
+        .ok_or_else(|| anyhow!("The symbol '{}' was not found in {:?}", symbol, path))
+}
+
+/// Creates a JUnit XML report of the test results.
+fn junit_report(tests: &[TestResult]) -> String {
+    let failures = tests
+        .iter()
+        .filter(|test| {
+            matches!(
+                test.outcome,
+                TestOutcome::Failed { .. } | TestOutcome::TimedOut
+            )
+        })
+        .count();
+    let errors = tests
+        .iter()
+        .filter(|test| matches!(test.outcome, TestOutcome::Error { .. }))
+        .count();
+    let time: f64 = tests.iter().map(|test| test.duration).sum();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuites tests=\"{}\" failures=\"{}\" errors=\"{}\" time=\"{:.3}\">\n",
+        tests.len(),
+        failures,
+        errors,
+        time
+    ));
+    xml.push_str(&format!(
+        "  <testsuite name=\"probe-rs\" tests=\"{}\" failures=\"{}\" errors=\"{}\" time=\"{:.3}\">\n",
+        tests.len(),
+        failures,
+        errors,
+        time
+    ));
+
+    for test in tests {
+        xml.push_str(&format!(
+            "    <testcase name=\"{}\" classname=\"probe-rs\" time=\"{:.3}\">\n",
+            escape_xml(&test.name),
+            test.duration
+        ));
+
+        match &test.outcome {
+            TestOutcome::Passed => {}
+            TestOutcome::Failed { message } => xml.push_str(&format!(
+                "      <failure message=\"{}\"/>\n",
+                escape_xml(message)
+            )),
+            TestOutcome::TimedOut => {
+                xml.push_str("      <failure message=\"The test timed out\"/>\n")
+            }
+            TestOutcome::Error { message } => xml.push_str(&format!(
+                "      <error message=\"{}\"/>\n",
+                escape_xml(message)
+            )),
+        }
+
+        if !test.output.is_empty() {
+            xml.push_str(&format!(
+                "      <system-out>{}</system-out>\n",
+                escape_xml(&test.output)
+            ));
+        }
+
+        xml.push_str("    </testcase>\n");
+    }
+
+    xml.push_str("  </testsuite>\n</testsuites>\n");
+    xml
+}
+
+/// Escapes the characters which are not allowed in XML attributes and text.
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // Control characters other than whitespace are not allowed in XML 1.0.
+            c if c.is_control() && !matches!(c, '\n' | '\r' | '\t') => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod test {
+    use super::{escape_xml, junit_report, TestOutcome, TestResult};
+    use std::path::PathBuf;
+
+    #[test]
+    fn escape_special_characters() {
+        assert_eq!(
+            escape_xml("<a href=\"x\">&'\u{1b}[0m"),
+            "&lt;a href=&quot;x&quot;&gt;&amp;&apos;[0m"
+        );
+    }
+
+    #[test]
+    fn junit_report_with_failure() {
+        let tests = vec![
+            TestResult {
+                name: "blinky".to_string(),
+                path: PathBuf::from("blinky"),
+                outcome: TestOutcome::Passed,
+                duration: 1.5,
+                output: String::new(),
+            },
+            TestResult {
+                name: "uart".to_string(),
+                path: PathBuf::from("uart"),
+                outcome: TestOutcome::Failed {
+                    message: "The firmware exited with code 3".to_string(),
+                },
+                duration: 0.25,
+                output: "assertion failed\n".to_string(),
+            },
+        ];
+
+        assert_eq!(
+            junit_report(&tests),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites tests="2" failures="1" errors="0" time="1.750">
+  <testsuite name="probe-rs" tests="2" failures="1" errors="0" time="1.750">
+    <testcase name="blinky" classname="probe-rs" time="1.500">
+    </testcase>
+    <testcase name="uart" classname="probe-rs" time="0.250">
+      <failure message="The firmware exited with code 3"/>
+      <system-out>assertion failed
+</system-out>
+    </testcase>
+  </testsuite>
+</testsuites>
+"#
+        );
+    }
+}
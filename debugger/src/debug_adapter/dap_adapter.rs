@@ -1,6 +1,10 @@
 use crate::{
     debug_adapter::{dap_types, protocol::ProtocolAdapter},
-    debugger::{configuration::ConsoleLog, core_data::CoreHandle, session_data::BreakpointType},
+    debugger::{
+        configuration::ConsoleLog,
+        core_data::CoreHandle,
+        session_data::{BreakpointConditions, BreakpointType},
+    },
     DebuggerError,
 };
 use anyhow::{anyhow, Result};
@@ -13,10 +17,11 @@ use num_traits::Zero;
 use parse_int::parse;
 use probe_rs::{
     debug::{
-        registers::Registers, stepping_mode::SteppingMode, ColumnType, SourceLocation,
-        VariableName, VariableNodeType,
+        evaluate_expression, registers::Registers, stack_frame::StackFrame,
+        stepping_mode::SteppingMode, ColumnType, ExpressionContext, SourceLocation, VariableName,
+        VariableNodeType,
     },
-    CoreStatus, HaltReason, InstructionSet, MemoryInterface,
+    CoreStatus, HaltReason, InstructionSet, MemoryInterface, VectorCatchCondition, WatchpointKind,
 };
 use probe_rs_cli_util::rtt;
 use serde::{de::DeserializeOwned, Serialize};
@@ -294,7 +299,6 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
             variables_reference: 0_i64,
         };
 
-        // The Variables request sometimes returns the variable name, and other times the variable id, so this expression will be tested to determine if it is an id or not.
         let expression = arguments.expression.clone();
 
         // Make sure we have a valid StackFrame
@@ -309,69 +313,93 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
                 target_core.core_data.stack_frames.first_mut()
             }
         } {
-            // Always search the registers first, because we don't have a VariableCache for them.
-            if let Some((_register_number, register_value)) =
-                stack_frame.registers.registers().into_iter().find(
-                    |(register_number, _register_value)| {
-                        let register_number = **register_number;
-                        let register_name = stack_frame
-                            .registers
-                            .get_name_by_dwarf_register_number(register_number)
-                            .unwrap_or_else(|| format!("r{:#}", register_number));
-                        register_name == expression
-                    },
-                )
-            {
-                response_body.type_ = Some(format!("{}", VariableName::RegistersRoot));
-                response_body.result = format!("{:#010x}", register_value);
-            } else {
-                // If the expression wasn't pointing to a register, then check if is a local or static variable in our stack_frame
-                let mut variable: Option<probe_rs::debug::Variable> = None;
-                let mut variable_cache: Option<&mut probe_rs::debug::VariableCache> = None;
-                // Search through available caches and stop as soon as the variable is found
-                #[allow(clippy::manual_flatten)]
-                for variable_cache_entry in [
-                    stack_frame.local_variables.as_mut(),
-                    stack_frame.static_variables.as_mut(),
+            // The `variables` request sometimes uses the variable id as the expression, and SVD variables use their
+            // `Peripheral.Register.Field` path, so these are looked up directly before the expression is evaluated.
+            let mut variable: Option<probe_rs::debug::Variable> = None;
+            let mut variable_cache: Option<&mut probe_rs::debug::VariableCache> = None;
+            let expression_as_key = expression.parse::<i64>().ok();
+            // Search through available caches and stop as soon as the variable is found
+            #[allow(clippy::manual_flatten)]
+            for (variable_cache_entry, is_svd_cache) in [
+                (stack_frame.local_variables.as_mut(), false),
+                (stack_frame.static_variables.as_mut(), false),
+                (
                     target_core
                         .core_data
                         .core_peripherals
                         .as_mut()
                         .map(|core_peripherals| &mut core_peripherals.svd_variable_cache),
-                ] {
-                    if let Some(search_cache) = variable_cache_entry {
-                        if let Ok(expression_as_key) = expression.parse::<i64>() {
-                            variable = search_cache.get_variable_by_key(expression_as_key);
-                        } else {
-                            variable = search_cache
-                                .get_variable_by_name(&VariableName::Named(expression.clone()));
+                    true,
+                ),
+            ] {
+                if let Some(search_cache) = variable_cache_entry {
+                    variable = match expression_as_key {
+                        Some(expression_as_key) => {
+                            search_cache.get_variable_by_key(expression_as_key)
                         }
-                        if let Some(variable) = &mut variable {
-                            if variable.variable_node_type == VariableNodeType::SvdRegister
-                                || variable.variable_node_type == VariableNodeType::SvdField
-                            {
-                                variable.extract_value(&mut target_core.core, search_cache)
-                            }
-                            variable_cache = Some(search_cache);
-                            break;
+                        None if is_svd_cache => search_cache
+                            .get_variable_by_name(&VariableName::Named(expression.clone())),
+                        None => None,
+                    };
+                    if let Some(variable) = &mut variable {
+                        if variable.variable_node_type == VariableNodeType::SvdRegister
+                            || variable.variable_node_type == VariableNodeType::SvdField
+                        {
+                            variable.extract_value(&mut target_core.core, search_cache)
                         }
+                        variable_cache = Some(search_cache);
+                        break;
                     }
                 }
-                // Check if we found a variable.
-                if let (Some(variable), Some(variable_cache)) = (variable, variable_cache) {
-                    let (
-                        variables_reference,
-                        named_child_variables_cnt,
-                        indexed_child_variables_cnt,
-                    ) = self.get_variable_reference(&variable, variable_cache);
-                    response_body.indexed_variables = Some(indexed_child_variables_cnt);
-                    response_body.memory_reference = Some(format!("{}", variable.memory_location));
-                    response_body.named_variables = Some(named_child_variables_cnt);
-                    response_body.result = variable.get_value(variable_cache);
-                    response_body.type_ = Some(format!("{:?}", variable.type_name));
-                    response_body.variables_reference = variables_reference;
-                } else {
-                    // If we made it to here, no register or variable matched the expression.
+            }
+            // Check if we found a variable.
+            if let (Some(variable), Some(variable_cache)) = (variable, variable_cache) {
+                let (variables_reference, named_child_variables_cnt, indexed_child_variables_cnt) =
+                    self.get_variable_reference(&variable, variable_cache);
+                response_body.indexed_variables = Some(indexed_child_variables_cnt);
+                response_body.memory_reference = Some(format!("{}", variable.memory_location));
+                response_body.named_variables = Some(named_child_variables_cnt);
+                response_body.result = variable.get_value(variable_cache);
+                response_body.type_ = Some(format!("{:?}", variable.type_name));
+                response_body.variables_reference = variables_reference;
+            } else {
+                let mut context = ExpressionContext {
+                    core: &mut target_core.core,
+                    debug_info: Some(&target_core.core_data.debug_info),
+                    stack_frame: Some(&mut *stack_frame),
+                };
+                match evaluate_expression(&expression, &mut context) {
+                    Ok(result) => {
+                        response_body.result = result.value;
+                        response_body.type_ = Some(result.type_name);
+                        response_body.memory_reference = result
+                            .memory_address
+                            .map(|memory_address| format!("{:#010x}", memory_address));
+                        // Variables can be expanded in the client, from the cache that holds them.
+                        if let Some(variable) = result.variable {
+                            if let Some(variable_cache) = [
+                                stack_frame.local_variables.as_mut(),
+                                stack_frame.static_variables.as_mut(),
+                            ]
+                            .into_iter()
+                            .flatten()
+                            .find(|variable_cache| {
+                                variable_cache
+                                    .get_variable_by_key(variable.variable_key)
+                                    .is_some()
+                            }) {
+                                let (
+                                    variables_reference,
+                                    named_child_variables_cnt,
+                                    indexed_child_variables_cnt,
+                                ) = self.get_variable_reference(&variable, variable_cache);
+                                response_body.indexed_variables = Some(indexed_child_variables_cnt);
+                                response_body.named_variables = Some(named_child_variables_cnt);
+                                response_body.variables_reference = variables_reference;
+                            }
+                        }
+                    }
+                    Err(error) => response_body.result = format!("<{}>", error),
                 }
             }
         }
@@ -686,6 +714,11 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
                                 match target_core.set_breakpoint(
                                     breakpoint_address as u32,
                                     BreakpointType::SourceBreakpoint(args.source.clone()),
+                                    BreakpointConditions {
+                                        condition: non_empty(&bp.condition),
+                                        hit_condition: non_empty(&bp.hit_condition),
+                                        log_message: non_empty(&bp.log_message),
+                                    },
                                 ) {
                                     Ok(_) => (
                                         Some(valid_breakpoint_location),
@@ -810,9 +843,15 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
                     requested_breakpoint.instruction_reference.parse()
                 }
             {
-                match target_core
-                    .set_breakpoint(memory_reference, BreakpointType::InstructionBreakpoint)
-                {
+                match target_core.set_breakpoint(
+                    memory_reference,
+                    BreakpointType::InstructionBreakpoint,
+                    BreakpointConditions {
+                        condition: non_empty(&requested_breakpoint.condition),
+                        hit_condition: non_empty(&requested_breakpoint.hit_condition),
+                        log_message: None,
+                    },
+                ) {
                     Ok(_) => {
                         breakpoint_response.verified = true;
                         breakpoint_response.instruction_reference =
@@ -869,6 +908,232 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
         self.send_response(request, Ok(Some(instruction_breakpoint_body)))
     }
 
+    /// Determine whether a data breakpoint can be set on a variable. The returned `data_id` identifies the watched memory range in [`DebugAdapter::set_data_breakpoints`].
+    pub(crate) fn data_breakpoint_info(
+        &mut self,
+        target_core: &mut CoreHandle,
+        request: Request,
+    ) -> Result<()> {
+        let arguments: DataBreakpointInfoArguments = match get_arguments(&request) {
+            Ok(arguments) => arguments,
+            Err(error) => return self.send_response::<()>(request, Err(error)),
+        };
+
+        let variable = find_variable(
+            &target_core.core_data.stack_frames,
+            arguments.variables_reference,
+            &arguments.name,
+        );
+
+        let response_body = match variable {
+            Some(variable) => match variable.memory_location.memory_address() {
+                Ok(address) if variable.byte_size > 0 => {
+                    let (watch_address, watch_length) =
+                        watchpoint_range(address, variable.byte_size as u32);
+                    DataBreakpointInfoResponseBody {
+                        data_id: Some(format!("{:#010x}/{}", watch_address, watch_length)),
+                        description: format!(
+                            "{} ({} bytes at {:#010x})",
+                            arguments.name, watch_length, watch_address
+                        ),
+                        access_types: Some(vec![
+                            DataBreakpointAccessType::Write,
+                            DataBreakpointAccessType::Read,
+                            DataBreakpointAccessType::ReadWrite,
+                        ]),
+                        can_persist: Some(false),
+                    }
+                }
+                _ => DataBreakpointInfoResponseBody {
+                    data_id: None,
+                    description: format!(
+                        "Data breakpoints are only supported for variables stored in memory, and {} is at {}",
+                        arguments.name, variable.memory_location
+                    ),
+                    access_types: None,
+                    can_persist: None,
+                },
+            },
+            None => DataBreakpointInfoResponseBody {
+                data_id: None,
+                description: format!("Variable {} not found", arguments.name),
+                access_types: None,
+                can_persist: None,
+            },
+        };
+
+        self.send_response(request, Ok(Some(response_body)))
+    }
+
+    /// Replace all data breakpoints with the requested ones, using the data watchpoint units of the core.
+    pub(crate) fn set_data_breakpoints(
+        &mut self,
+        target_core: &mut CoreHandle,
+        request: Request,
+    ) -> Result<()> {
+        let arguments: SetDataBreakpointsArguments = match get_arguments(&request) {
+            Ok(arguments) => arguments,
+            Err(error) => return self.send_response::<()>(request, Err(error)),
+        };
+
+        // Always clear existing data breakpoints before setting new ones.
+        for kind in [
+            WatchpointKind::Write,
+            WatchpointKind::Read,
+            WatchpointKind::Access,
+        ] {
+            if let Err(error) = target_core.clear_breakpoints(BreakpointType::DataBreakpoint(kind))
+            {
+                log::warn!("Failed to clear data breakpoints. {}", error);
+            }
+        }
+
+        let mut created_breakpoints: Vec<Breakpoint> = Vec::new();
+        for requested_breakpoint in arguments.breakpoints {
+            let mut breakpoint_response = Breakpoint {
+                column: None,
+                end_column: None,
+                end_line: None,
+                id: None,
+                instruction_reference: None,
+                line: None,
+                message: None,
+                offset: None,
+                source: None,
+                verified: false,
+            };
+
+            if let Some((address, length)) = parse_data_id(&requested_breakpoint.data_id) {
+                let kind = match requested_breakpoint.access_type {
+                    Some(DataBreakpointAccessType::Read) => WatchpointKind::Read,
+                    Some(DataBreakpointAccessType::ReadWrite) => WatchpointKind::Access,
+                    Some(DataBreakpointAccessType::Write) | None => WatchpointKind::Write,
+                };
+                match target_core.set_data_breakpoint(
+                    address,
+                    length,
+                    kind,
+                    BreakpointConditions {
+                        condition: non_empty(&requested_breakpoint.condition),
+                        hit_condition: non_empty(&requested_breakpoint.hit_condition),
+                        log_message: None,
+                    },
+                ) {
+                    Ok(()) => {
+                        breakpoint_response.verified = true;
+                        breakpoint_response.message = Some(format!(
+                            "Data breakpoint on {} bytes at {:#010x}",
+                            length, address
+                        ));
+                    }
+                    Err(error) => {
+                        let message = format!(
+                            "Warning: Could not set data breakpoint at memory address: {:#010x}: {}",
+                            address, error
+                        );
+                        self.log_to_console(message.clone());
+                        breakpoint_response.message = Some(message);
+                    }
+                }
+            } else {
+                breakpoint_response.message = Some(format!(
+                    "Invalid data breakpoint id: {}",
+                    requested_breakpoint.data_id
+                ));
+            }
+
+            created_breakpoints.push(breakpoint_response);
+        }
+
+        self.send_response(
+            request,
+            Ok(Some(SetDataBreakpointsResponseBody {
+                breakpoints: created_breakpoints,
+            })),
+        )
+    }
+
+    /// Exception breakpoints halt the core when one of the exceptions in [`EXCEPTION_BREAKPOINT_FILTERS`] occurs,
+    /// using the vector catch feature of the core. All filters which are not requested are disabled.
+    pub(crate) fn set_exception_breakpoints(
+        &mut self,
+        target_core: &mut CoreHandle,
+        request: Request,
+    ) -> Result<()> {
+        let arguments: SetExceptionBreakpointsArguments = match get_arguments(&request) {
+            Ok(arguments) => arguments,
+            Err(error) => return self.send_response::<()>(request, Err(error)),
+        };
+
+        for (filter, _, condition) in EXCEPTION_BREAKPOINT_FILTERS {
+            if !arguments
+                .filters
+                .iter()
+                .any(|requested| requested == filter)
+            {
+                // Not every core supports every condition, so failing to disable one is expected.
+                if let Err(error) = target_core.core.disable_vector_catch(*condition) {
+                    log::debug!(
+                        "Failed to disable vector catch for {:?}: {}",
+                        condition,
+                        error
+                    );
+                }
+            }
+        }
+
+        let mut created_breakpoints: Vec<Breakpoint> = Vec::new();
+        for requested_filter in &arguments.filters {
+            let mut breakpoint_response = Breakpoint {
+                column: None,
+                end_column: None,
+                end_line: None,
+                id: None,
+                instruction_reference: None,
+                line: None,
+                message: None,
+                offset: None,
+                source: None,
+                verified: false,
+            };
+
+            match EXCEPTION_BREAKPOINT_FILTERS
+                .iter()
+                .find(|(filter, _, _)| filter == requested_filter)
+            {
+                Some((_, label, condition)) => {
+                    match target_core.core.enable_vector_catch(*condition) {
+                        Ok(()) => {
+                            breakpoint_response.verified = true;
+                            breakpoint_response.message = Some(format!("Halting on {}", label));
+                        }
+                        Err(error) => {
+                            let message = format!(
+                                "Warning: Could not enable the {} exception breakpoint: {}",
+                                label, error
+                            );
+                            self.log_to_console(message.clone());
+                            breakpoint_response.message = Some(message);
+                        }
+                    }
+                }
+                None => {
+                    breakpoint_response.message =
+                        Some(format!("Unknown exception filter: {}", requested_filter));
+                }
+            }
+
+            created_breakpoints.push(breakpoint_response);
+        }
+
+        self.send_response(
+            request,
+            Ok(Some(SetExceptionBreakpointsResponseBody {
+                breakpoints: Some(created_breakpoints),
+            })),
+        )
+    }
+
     pub(crate) fn threads(&mut self, target_core: &mut CoreHandle, request: Request) -> Result<()> {
         // TODO: Implement actual thread resolution. For now, we just use the core id as the thread id.
         let mut threads: Vec<Thread> = vec![];
@@ -1206,6 +1471,30 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
                 });
             };
 
+            if let Some(arguments_root_variable) =
+                stack_frame
+                    .local_variables
+                    .as_ref()
+                    .and_then(|stack_frame| {
+                        stack_frame
+                            .get_variable_by_name_and_parent(&VariableName::ArgumentScopeRoot, None)
+                    })
+            {
+                dap_scopes.push(Scope {
+                    line: None,
+                    column: None,
+                    end_column: None,
+                    end_line: None,
+                    expensive: false, // VSCode will open this tree by default.
+                    indexed_variables: None,
+                    name: "Arguments".to_string(),
+                    presentation_hint: Some("arguments".to_string()),
+                    named_variables: None,
+                    source: None,
+                    variables_reference: arguments_root_variable.variable_key,
+                });
+            };
+
             if let Some(locals_root_variable) =
                 stack_frame
                     .local_variables
@@ -1717,6 +2006,14 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
         target_core: &mut CoreHandle,
         request: Request,
     ) -> Result<()> {
+        if let Err(error) = target_core
+            .core_data
+            .semihosting
+            .enable(&mut target_core.core)
+        {
+            log::warn!("Unable to enable semihosting: {}", error);
+        }
+
         match target_core.core.run() {
             Ok(_) => {
                 self.last_known_status = target_core.core.status().unwrap_or(CoreStatus::Unknown);
@@ -1735,6 +2032,10 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
                 thread::sleep(Duration::from_millis(100)); // Small delay to make sure the MCU hits user breakpoints early in `main()`.
                 let core_status = match target_core.core.status() {
                     Ok(new_status) => match new_status {
+                        // Breakpoints are reported when the core status is polled, after their conditions have been evaluated.
+                        CoreStatus::Halted(HaltReason::Breakpoint | HaltReason::Watchpoint) => {
+                            CoreStatus::Running
+                        }
                         CoreStatus::Halted(_) => {
                             let event_body = Some(StoppedEventBody {
                                 reason: new_status.short_long_status().0.to_owned(),
@@ -2003,6 +2304,26 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
             .is_ok()
     }
 
+    /// Send an `output` event to the MS DAP Client, with the output of the program in the given `category` (`stdout` or `stderr`).
+    pub fn program_output(&mut self, category: &str, output: String) -> bool {
+        let event_body = match serde_json::to_value(OutputEventBody {
+            output,
+            category: Some(category.to_owned()),
+            variables_reference: None,
+            source: None,
+            line: None,
+            column: None,
+            data: None,
+            group: None,
+        }) {
+            Ok(event_body) => event_body,
+            Err(_) => {
+                return false;
+            }
+        };
+        self.send_event("output", Some(event_body)).is_ok()
+    }
+
     fn new_progress_id(&mut self) -> ProgressId {
         let id = self.progress_id;
 
@@ -2126,6 +2447,105 @@ fn get_dap_source(source_location: &SourceLocation) -> Option<Source> {
     })
 }
 
+/// Find a variable for a `dataBreakpointInfo` request, either as the child of the container `variables_reference`, or by name in the top stack frame.
+fn find_variable(
+    stack_frames: &[StackFrame],
+    variables_reference: Option<i64>,
+    name: &str,
+) -> Option<probe_rs::debug::Variable> {
+    match variables_reference {
+        Some(variables_reference) => stack_frames
+            .iter()
+            .flat_map(|stack_frame| {
+                [
+                    stack_frame.local_variables.as_ref(),
+                    stack_frame.static_variables.as_ref(),
+                ]
+            })
+            .flatten()
+            .find_map(|variable_cache| {
+                variable_cache
+                    .get_children(Some(variables_reference))
+                    .ok()?
+                    .into_iter()
+                    .find(|variable| variable.name.to_string() == name)
+            }),
+        None => stack_frames.first().and_then(|stack_frame| {
+            [
+                stack_frame.local_variables.as_ref(),
+                stack_frame.static_variables.as_ref(),
+            ]
+            .into_iter()
+            .flatten()
+            .find_map(|variable_cache| {
+                variable_cache.get_variable_by_name(&VariableName::Named(name.to_string()))
+            })
+        }),
+    }
+}
+
+/// Data watchpoints cover a power of two sized memory range, which is aligned to its size.
+/// Returns the address and length of the smallest such range that contains the `length` bytes at `address`.
+fn watchpoint_range(address: u32, length: u32) -> (u32, u32) {
+    let mut watch_length = length.max(1).next_power_of_two();
+    loop {
+        let watch_address = address & !(watch_length - 1);
+        if u64::from(watch_address) + u64::from(watch_length)
+            >= u64::from(address) + u64::from(length)
+            || watch_length == 1 << 31
+        {
+            return (watch_address, watch_length);
+        }
+        watch_length *= 2;
+    }
+}
+
+/// The exception breakpoints offered to the client, as `(filter, label, condition)`.
+pub(crate) const EXCEPTION_BREAKPOINT_FILTERS: &[(&str, &str, VectorCatchCondition)] = &[
+    ("hardfault", "HardFault", VectorCatchCondition::HardFault),
+    ("reset", "Reset", VectorCatchCondition::CoreReset),
+    ("memmanage", "MemManage", VectorCatchCondition::MemManage),
+    ("busfault", "BusFault", VectorCatchCondition::BusFault),
+    ("usagefault", "UsageFault", VectorCatchCondition::UsageFault),
+    (
+        "securefault",
+        "SecureFault",
+        VectorCatchCondition::SecureFault,
+    ),
+];
+
+/// The [`Capabilities::exception_breakpoint_filters`] which belong to [`EXCEPTION_BREAKPOINT_FILTERS`].
+pub(crate) fn exception_breakpoint_filters() -> Vec<ExceptionBreakpointsFilter> {
+    EXCEPTION_BREAKPOINT_FILTERS
+        .iter()
+        .map(|(filter, label, _)| ExceptionBreakpointsFilter {
+            filter: filter.to_string(),
+            label: label.to_string(),
+            description: Some(format!(
+                "Halt the core when a {} exception occurs, before its handler runs",
+                label
+            )),
+            default: Some(false),
+            supports_condition: None,
+            condition_description: None,
+        })
+        .collect()
+}
+
+/// Parse the `data_id` of a data breakpoint, as created by [`DebugAdapter::data_breakpoint_info`].
+fn parse_data_id(data_id: &str) -> Option<(u32, u32)> {
+    let (address, length) = data_id.split_once('/')?;
+    Some((parse(address).ok()?, length.parse().ok()?))
+}
+
+/// Breakpoint conditions from the client are ignored if they are empty.
+fn non_empty(value: &Option<String>) -> Option<String> {
+    value
+        .as_ref()
+        .filter(|value| !value.trim().is_empty())
+        .cloned()
+}
+
 /// Provides halt functionality that is re-used elsewhere, in context of multiple DAP Requests
 pub(crate) fn halt_core(
     target_core: &mut probe_rs::Core,
@@ -2183,3 +2603,44 @@ impl DapStatus for CoreStatus {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{parse_data_id, watchpoint_range};
+
+    #[test]
+    fn watchpoint_range_aligned() {
+        assert_eq!(watchpoint_range(0x2000_0004, 4), (0x2000_0004, 4));
+        assert_eq!(watchpoint_range(0x2000_0001, 1), (0x2000_0001, 1));
+        assert_eq!(watchpoint_range(0x2000_0001, 0), (0x2000_0001, 1));
+    }
+
+    #[test]
+    fn watchpoint_range_unaligned() {
+        // A halfword straddling a word boundary is only covered by the surrounding double word.
+        assert_eq!(watchpoint_range(0x2000_0003, 2), (0x2000_0000, 8));
+        // A 12 byte structure needs a 16 byte range.
+        assert_eq!(watchpoint_range(0x2000_0010, 12), (0x2000_0010, 16));
+        assert_eq!(watchpoint_range(0x2000_0018, 12), (0x2000_0000, 64));
+    }
+
+    #[test]
+    fn watchpoint_range_limit() {
+        assert_eq!(watchpoint_range(0x1000_0000, 0x8000_0000), (0, 0x8000_0000));
+    }
+
+    #[test]
+    fn data_id_round_trip() {
+        let (address, length) = watchpoint_range(0x2000_0003, 2);
+        let data_id = format!("{:#010x}/{}", address, length);
+
+        assert_eq!(parse_data_id(&data_id), Some((0x2000_0000, 8)));
+    }
+
+    #[test]
+    fn invalid_data_id() {
+        assert_eq!(parse_data_id("0x20000000"), None);
+        assert_eq!(parse_data_id("variable/4"), None);
+        assert_eq!(parse_data_id("0x20000000/four"), None);
+    }
+}
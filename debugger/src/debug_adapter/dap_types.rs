@@ -51,6 +51,32 @@ pub struct RttWindowOpenedArguments {
     pub window_is_open: bool,
 }
 
+/// Custom [`RttWindowInput`] request, so that VSCode can send user input from an RTT channel's window to the target.
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct RttWindowInput {
+    /// Object containing arguments for the command.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub arguments: Option<RttWindowInputArguments>,
+    /// The command to execute.
+    pub command: String,
+    /// Sequence number (also known as message ID). For protocol messages of type `request` this ID
+    /// can be used to cancel the request.
+    pub seq: i64,
+    /// Message type.
+    #[serde(rename = "type")]
+    pub type_: String,
+}
+///  Arguments for [`RttWindowInput`] request.
+#[derive(Clone, PartialEq, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RttWindowInputArguments {
+    /// The RTT channel number of the window.
+    /// The input is written to the down channel with the same number, or to the first down channel if there is none.
+    pub channel_number: usize,
+    /// The input, which is written to the target unchanged.
+    pub data: String,
+}
+
 #[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RttChannelEventBody {
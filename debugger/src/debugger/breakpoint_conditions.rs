@@ -0,0 +1,142 @@
+use anyhow::{anyhow, Result};
+
+/// Checks the hit count of a breakpoint against a DAP `hitCondition`.
+///
+/// The condition is either a plain number, which is met when the hit count is equal to it,
+/// a comparison like `>= 5`, or a modulo like `% 3`, which is met on every third hit.
+pub(crate) fn hit_condition_met(hit_condition: &str, hit_count: u64) -> Result<bool> {
+    let hit_condition = hit_condition.trim();
+    let (operator, operand) = ["==", "!=", "<=", ">=", "<", ">", "%", "="]
+        .iter()
+        .find_map(|operator| {
+            hit_condition
+                .strip_prefix(operator)
+                .map(|operand| (*operator, operand))
+        })
+        .unwrap_or(("==", hit_condition));
+
+    let operand = parse_integer(operand.trim())
+        .and_then(|operand| u64::try_from(operand).ok())
+        .ok_or_else(|| anyhow!("Invalid hit condition '{}'", hit_condition))?;
+
+    Ok(match operator {
+        "==" | "=" => hit_count == operand,
+        "!=" => hit_count != operand,
+        "<=" => hit_count <= operand,
+        ">=" => hit_count >= operand,
+        "<" => hit_count < operand,
+        ">" => hit_count > operand,
+        "%" => operand != 0 && hit_count % operand == 0,
+        _ => unreachable!(),
+    })
+}
+
+/// Replaces the `{expression}` placeholders in a logpoint message with their values.
+///
+/// The expressions are evaluated with `evaluate`, which returns their formatted value.
+/// Use `{{` and `}}` for literal braces. Expressions which can't be evaluated are replaced by an error message.
+pub(crate) fn interpolate_log_message<F>(message: &str, mut evaluate: F) -> String
+where
+    F: FnMut(&str) -> Result<String>,
+{
+    let mut output = String::with_capacity(message.len());
+    let mut chars = message.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                output.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                output.push('}');
+            }
+            '{' => {
+                let mut expression = String::new();
+                let mut closed = false;
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        closed = true;
+                        break;
+                    }
+                    expression.push(c);
+                }
+
+                if !closed {
+                    output.push('{');
+                    output.push_str(&expression);
+                    break;
+                }
+
+                match evaluate(&expression) {
+                    Ok(value) => output.push_str(&value),
+                    Err(error) => output.push_str(&format!("<{}>", error)),
+                }
+            }
+            c => output.push(c),
+        }
+    }
+
+    output
+}
+
+/// Parses decimal and `0x` prefixed hexadecimal integers, with optional `_` separators.
+fn parse_integer(value: &str) -> Option<i128> {
+    let (negative, value) = match value.strip_prefix('-') {
+        Some(value) => (true, value),
+        None => (false, value),
+    };
+    let value = value.replace('_', "");
+    let integer = if let Some(hex) = value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        i128::from_str_radix(hex, 16).ok()?
+    } else {
+        value.parse::<i128>().ok()?
+    };
+    Some(if negative { -integer } else { integer })
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+
+    fn evaluate(expression: &str) -> Result<String> {
+        match expression {
+            "count" => Ok("5".to_string()),
+            "done" => Ok("false".to_string()),
+            "count > 3" => Ok("true".to_string()),
+            _ => Err(anyhow!("Variable '{}' not found", expression)),
+        }
+    }
+
+    #[test]
+    fn hit_conditions() {
+        assert!(hit_condition_met("3", 3).unwrap());
+        assert!(!hit_condition_met("3", 4).unwrap());
+        assert!(hit_condition_met(">= 3", 4).unwrap());
+        assert!(!hit_condition_met("< 3", 3).unwrap());
+        assert!(hit_condition_met("%2", 4).unwrap());
+        assert!(!hit_condition_met("% 2", 3).unwrap());
+        assert!(hit_condition_met("foo", 1).is_err());
+    }
+
+    #[test]
+    fn log_messages() {
+        assert_eq!(
+            interpolate_log_message("count={count}, done={done} {{literal}}", evaluate),
+            "count=5, done=false {literal}"
+        );
+        assert_eq!(
+            interpolate_log_message("{count > 3} {missing}", evaluate),
+            "true <Variable 'missing' not found>"
+        );
+        assert_eq!(
+            interpolate_log_message("open {count", evaluate),
+            "open {count"
+        );
+    }
+}
@@ -40,6 +40,9 @@ pub struct SessionConfig {
     #[serde(default)]
     pub(crate) allow_erase_all: bool,
 
+    /// Debug a core dump saved with `probe-rs-cli core-dump`, instead of attaching to a probe.
+    pub(crate) core_dump: Option<PathBuf>,
+
     /// Flashing configuration
     pub(crate) flashing_config: FlashingConfig,
 
@@ -55,6 +58,23 @@ impl SessionConfig {
         // Update the `cwd`.
         self.cwd = self.resolve_cwd()?;
 
+        // Update the `core_dump` and validate that the file exists.
+        if let Some(core_dump) = &self.core_dump {
+            let core_dump = get_absolute_path(self.cwd.clone(), Some(core_dump))?;
+            if !core_dump.is_file() {
+                return Err(DebuggerError::Other(anyhow!(
+                    "Invalid core dump file specified '{:?}'",
+                    core_dump
+                )));
+            }
+            if self.flashing_config.flashing_enabled {
+                return Err(DebuggerError::Other(anyhow!(
+                    "Please do not use the `flashing_enabled` option when debugging a core dump."
+                )));
+            }
+            self.core_dump = Some(core_dump);
+        }
+
         for target_core_config in &mut self.core_configs {
             // Update the `program_binary` and validate that the file exists.
             target_core_config.program_binary = match get_absolute_path(
@@ -94,6 +114,12 @@ impl SessionConfig {
                         None
                     }
                 };
+            // Update the `semihosting_root`. Without it, the target can only use the console with semihosting.
+            target_core_config.semihosting_root = get_absolute_path(
+                self.cwd.clone(),
+                target_core_config.semihosting_root.as_ref(),
+            )
+            .ok();
         }
 
         Ok(())
@@ -188,6 +214,9 @@ pub struct CoreConfig {
     /// CMSIS-SVD file for the target. Relative to `cwd`, or fully qualified.
     pub(crate) svd_file: Option<PathBuf>,
 
+    /// The directory the target can access files in with semihosting. Relative to `cwd`, or fully qualified.
+    pub(crate) semihosting_root: Option<PathBuf>,
+
     #[serde(flatten)]
     pub(crate) rtt_config: rtt::RttConfig,
 }
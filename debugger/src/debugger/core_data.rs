@@ -1,4 +1,7 @@
-use super::session_data;
+use super::{
+    breakpoint_conditions::{hit_condition_met, interpolate_log_message},
+    session_data,
+};
 use crate::{
     debug_adapter::{dap_adapter::DebugAdapter, protocol::ProtocolAdapter},
     debugger::debug_rtt,
@@ -6,7 +9,11 @@ use crate::{
     DebuggerError,
 };
 use anyhow::Result;
-use probe_rs::{debug::debug_info::DebugInfo, Core};
+use probe_rs::{
+    debug::{debug_info::DebugInfo, evaluate_condition, evaluate_expression, ExpressionContext},
+    semihosting::SemihostingHost,
+    Core, Watchpoint, WatchpointKind,
+};
 use probe_rs_cli_util::rtt;
 
 /// [CoreData] is used to cache data needed by the debugger, on a per-core basis.
@@ -18,6 +25,18 @@ pub struct CoreData {
     pub(crate) stack_frames: Vec<probe_rs::debug::stack_frame::StackFrame>,
     pub(crate) breakpoints: Vec<session_data::ActiveBreakpoint>,
     pub(crate) rtt_connection: Option<debug_rtt::RttConnection>,
+    pub(crate) semihosting: SemihostingHost,
+}
+
+/// What to do after the core halted on a breakpoint, as determined by [`CoreHandle::breakpoint_hit`].
+#[derive(Debug, PartialEq)]
+pub(crate) enum BreakpointHit {
+    /// Report the halt to the client.
+    Halt,
+    /// The condition of the breakpoint is not met, so the core should be resumed silently.
+    Continue,
+    /// The breakpoint is a logpoint. The message should be written to the debug console, and the core resumed.
+    Log(String),
 }
 
 /// [CoreHandle] provides handles to various data structures required to debug a single instance of a core. The actual state is stored in [SessionData].
@@ -87,35 +106,80 @@ impl<'p> CoreHandle<'p> {
         &mut self,
         address: u32,
         breakpoint_type: session_data::BreakpointType,
+        conditions: session_data::BreakpointConditions,
     ) -> Result<(), DebuggerError> {
+        // `Core::set_breakpoint` inserts a software breakpoint once all hardware breakpoints are in use,
+        // which only works in RAM.
         self.core
-            .set_hw_breakpoint(address)
+            .set_breakpoint(address)
             .map_err(DebuggerError::ProbeRs)?;
         self.core_data
             .breakpoints
             .push(session_data::ActiveBreakpoint {
                 breakpoint_type,
                 breakpoint_address: address,
+                conditions,
+                hit_count: 0,
             });
         Ok(())
     }
 
-    /// Clear a single breakpoint from target configuration as well as [`CoreHandle::breakpoints`]
-    pub(crate) fn clear_breakpoint(&mut self, address: u32) -> Result<()> {
+    /// Set a data breakpoint, which halts the core when the `length` bytes at `address` are accessed as given by `kind`.
+    pub(crate) fn set_data_breakpoint(
+        &mut self,
+        address: u32,
+        length: u32,
+        kind: WatchpointKind,
+        conditions: session_data::BreakpointConditions,
+    ) -> Result<(), DebuggerError> {
         self.core
-            .clear_hw_breakpoint(address)
+            .set_hw_watchpoint(address, length, kind)
             .map_err(DebuggerError::ProbeRs)?;
-        let mut breakpoint_position: Option<usize> = None;
-        for (position, active_breakpoint) in self.core_data.breakpoints.iter().enumerate() {
-            if active_breakpoint.breakpoint_address == address {
-                breakpoint_position = Some(position);
-                break;
+        self.core_data
+            .breakpoints
+            .push(session_data::ActiveBreakpoint {
+                breakpoint_type: session_data::BreakpointType::DataBreakpoint(kind),
+                breakpoint_address: address,
+                conditions,
+                hit_count: 0,
+            });
+        Ok(())
+    }
+
+    /// Clear the breakpoint of `breakpoint_type` at `address` from target configuration as well as [`CoreHandle::breakpoints`]
+    pub(crate) fn clear_breakpoint(
+        &mut self,
+        address: u32,
+        breakpoint_type: &session_data::BreakpointType,
+    ) -> Result<()> {
+        if let session_data::BreakpointType::DataBreakpoint(kind) = breakpoint_type {
+            self.core
+                .clear_hw_watchpoint(address, *kind)
+                .map_err(DebuggerError::ProbeRs)?;
+        } else {
+            // Instruction and source breakpoints at the same address share the breakpoint on the core.
+            let shared = self.core_data.breakpoints.iter().any(|breakpoint| {
+                breakpoint.breakpoint_address == address
+                    && breakpoint.breakpoint_type != *breakpoint_type
+                    && !matches!(
+                        breakpoint.breakpoint_type,
+                        session_data::BreakpointType::DataBreakpoint(_)
+                    )
+            });
+            if !shared {
+                self.core
+                    .clear_breakpoint(address)
+                    .map_err(DebuggerError::ProbeRs)?;
             }
         }
-        if let Some(breakpoint_position) = breakpoint_position {
-            self.core_data
-                .breakpoints
-                .remove(breakpoint_position as usize);
+
+        if let Some(breakpoint_position) =
+            self.core_data.breakpoints.iter().position(|breakpoint| {
+                breakpoint.breakpoint_address == address
+                    && breakpoint.breakpoint_type == *breakpoint_type
+            })
+        {
+            self.core_data.breakpoints.remove(breakpoint_position);
         }
         Ok(())
     }
@@ -133,8 +197,82 @@ impl<'p> CoreHandle<'p> {
             .map(|breakpoint| breakpoint.breakpoint_address)
             .collect::<Vec<u32>>();
         for breakpoint in target_breakpoints {
-            self.clear_breakpoint(breakpoint).ok();
+            self.clear_breakpoint(breakpoint, &breakpoint_type).ok();
         }
         Ok(())
     }
+
+    /// Update the hit count of the breakpoint the core halted on at `pc`, and evaluate its conditions against the registers and variables of the top stack frame.
+    ///
+    /// If the core halted on a data breakpoint, `watchpoint` is the triggered watchpoint. Otherwise the core halted on the instruction or source breakpoint at `pc`.
+    pub(crate) fn breakpoint_hit(
+        &mut self,
+        watchpoint: Option<Watchpoint>,
+        pc: u32,
+    ) -> Result<BreakpointHit> {
+        let breakpoint = match self
+            .core_data
+            .breakpoints
+            .iter_mut()
+            .find(|breakpoint| breakpoint.is_hit(watchpoint, pc))
+        {
+            Some(breakpoint) => breakpoint,
+            None => return Ok(BreakpointHit::Halt),
+        };
+
+        let conditions = breakpoint.conditions.clone();
+        if conditions == session_data::BreakpointConditions::default() {
+            breakpoint.hit_count += 1;
+            return Ok(BreakpointHit::Halt);
+        }
+
+        if conditions.condition.is_some() || conditions.log_message.is_some() {
+            self.core_data.stack_frames = self
+                .core_data
+                .debug_info
+                .unwind(&mut self.core, u64::from(pc))?;
+        }
+
+        let mut context = ExpressionContext {
+            core: &mut self.core,
+            debug_info: Some(&self.core_data.debug_info),
+            stack_frame: self.core_data.stack_frames.first_mut(),
+        };
+
+        if let Some(condition) = &conditions.condition {
+            if !evaluate_condition(condition, &mut context)? {
+                return Ok(BreakpointHit::Continue);
+            }
+        }
+
+        let log_message = conditions.log_message.as_ref().map(|log_message| {
+            interpolate_log_message(log_message, |expression| {
+                Ok(evaluate_expression(expression, &mut context)?.value)
+            })
+        });
+
+        let hit_count = match self
+            .core_data
+            .breakpoints
+            .iter_mut()
+            .find(|breakpoint| breakpoint.is_hit(watchpoint, pc))
+        {
+            Some(breakpoint) => {
+                breakpoint.hit_count += 1;
+                breakpoint.hit_count
+            }
+            None => return Ok(BreakpointHit::Halt),
+        };
+
+        if let Some(hit_condition) = &conditions.hit_condition {
+            if !hit_condition_met(hit_condition, hit_count)? {
+                return Ok(BreakpointHit::Continue);
+            }
+        }
+
+        Ok(match log_message {
+            Some(log_message) => BreakpointHit::Log(log_message),
+            None => BreakpointHit::Halt,
+        })
+    }
 }
@@ -1,4 +1,7 @@
-use super::session_data;
+use super::{
+    core_data::{BreakpointHit, CoreHandle},
+    session_data,
+};
 use crate::{
     debug_adapter::{
         dap_adapter::*,
@@ -12,7 +15,8 @@ use crate::{
 use anyhow::{anyhow, Context, Result};
 use probe_rs::{
     flashing::{download_file_with_options, DownloadOptions, FlashProgress, Format},
-    CoreStatus, Probe,
+    semihosting::SemihostingEvent,
+    CoreStatus, HaltReason, Probe, Watchpoint,
 };
 use serde::Deserialize;
 use std::{
@@ -160,6 +164,83 @@ impl Debugger {
                             }
                         };
 
+                        // Semihosting requests halt the core on a breakpoint. They are serviced without notifying the client, and the core is resumed.
+                        // This is only done once per halt, so that the hit counts of breakpoints are only incremented once.
+                        if new_status == CoreStatus::Halted(HaltReason::Breakpoint)
+                            && new_status != debug_adapter.last_known_status
+                        {
+                            match target_core
+                                .core_data
+                                .semihosting
+                                .handle(&mut target_core.core)
+                            {
+                                Ok(Some(SemihostingEvent::Stdout(data))) => {
+                                    debug_adapter.program_output(
+                                        "stdout",
+                                        String::from_utf8_lossy(&data).into_owned(),
+                                    );
+                                    return Ok(DebuggerStatus::ContinueSession);
+                                }
+                                Ok(Some(SemihostingEvent::Stderr(data))) => {
+                                    debug_adapter.program_output(
+                                        "stderr",
+                                        String::from_utf8_lossy(&data).into_owned(),
+                                    );
+                                    return Ok(DebuggerStatus::ContinueSession);
+                                }
+                                Ok(Some(SemihostingEvent::Handled)) => {
+                                    return Ok(DebuggerStatus::ContinueSession);
+                                }
+                                Ok(Some(SemihostingEvent::Exit(exit_code))) => {
+                                    debug_adapter.log_to_console(format!(
+                                        "The target exited with code {}",
+                                        exit_code
+                                    ));
+                                    debug_adapter.send_event(
+                                        "exited",
+                                        Some(ExitedEventBody {
+                                            exit_code: exit_code as i64,
+                                        }),
+                                    )?;
+                                    debug_adapter.send_event(
+                                        "terminated",
+                                        Some(TerminatedEventBody { restart: None }),
+                                    )?;
+                                    return Ok(DebuggerStatus::TerminateSession);
+                                }
+                                Ok(None) => {
+                                    // A regular breakpoint. Conditional breakpoints and logpoints resume the core without notifying the client.
+                                    if resume_after_breakpoint(
+                                        &mut target_core,
+                                        debug_adapter,
+                                        None,
+                                    )? {
+                                        return Ok(DebuggerStatus::ContinueSession);
+                                    }
+                                }
+                                Err(error) => {
+                                    let error = DebuggerError::ProbeRs(error);
+                                    let _ = debug_adapter.send_error_response(&error);
+                                    return Err(error);
+                                }
+                            }
+                        }
+
+                        // Data breakpoints can have conditions as well, if the hardware can tell which one was triggered.
+                        if new_status == CoreStatus::Halted(HaltReason::Watchpoint)
+                            && new_status != debug_adapter.last_known_status
+                        {
+                            if let Ok(Some(watchpoint)) = target_core.core.triggered_watchpoint() {
+                                if resume_after_breakpoint(
+                                    &mut target_core,
+                                    debug_adapter,
+                                    Some(watchpoint),
+                                )? {
+                                    return Ok(DebuggerStatus::ContinueSession);
+                                }
+                            }
+                        }
+
                         // Only sleep (nap for a short duration) IF the probe's status hasn't changed AND there was no RTT data in the last poll.
                         // Otherwise loop again to keep things flowing as fast as possible.
                         // The justification is that any client side CPU used to keep polling is a small price to pay for maximum throughput of debug requests and RTT from the probe.
@@ -181,22 +262,39 @@ impl Debugger {
                                 debug_adapter.send_event("continued", event_body)?;
                             }
                             CoreStatus::Halted(_) => {
-                                let event_body = Some(StoppedEventBody {
-                                    reason: new_status.short_long_status().0.to_owned(),
-                                    description: Some(new_status.short_long_status().1.to_owned()),
-                                    thread_id: Some(target_core.core.id() as i64),
-                                    preserve_focus_hint: Some(false),
-                                    text: None,
-                                    all_threads_stopped: Some(true),
-                                    hit_breakpoint_ids: None,
-                                });
-                                debug_adapter.send_event("stopped", event_body)?;
+                                // Report faults as exceptions, with a description of their cause.
+                                let event_body = match target_core.core.fault_info() {
+                                    Ok(Some(fault)) => StoppedEventBody {
+                                        reason: "exception".to_owned(),
+                                        description: Some(fault.summary()),
+                                        thread_id: Some(target_core.core.id() as i64),
+                                        preserve_focus_hint: Some(false),
+                                        text: Some(fault.to_string()),
+                                        all_threads_stopped: Some(true),
+                                        hit_breakpoint_ids: None,
+                                    },
+                                    _ => StoppedEventBody {
+                                        reason: new_status.short_long_status().0.to_owned(),
+                                        description: Some(
+                                            new_status.short_long_status().1.to_owned(),
+                                        ),
+                                        thread_id: Some(target_core.core.id() as i64),
+                                        preserve_focus_hint: Some(false),
+                                        text: None,
+                                        all_threads_stopped: Some(true),
+                                        hit_breakpoint_ids: None,
+                                    },
+                                };
+                                debug_adapter.send_event("stopped", Some(event_body))?;
                             }
                             CoreStatus::LockedUp => {
-                                debug_adapter.show_message(
-                                    MessageSeverity::Error,
-                                    new_status.short_long_status().1.to_owned(),
-                                );
+                                let message = match target_core.core.fault_info() {
+                                    Ok(Some(fault)) => {
+                                        format!("{}\n{}", new_status.short_long_status().1, fault)
+                                    }
+                                    _ => new_status.short_long_status().1.to_owned(),
+                                };
+                                debug_adapter.show_message(MessageSeverity::Error, message);
                                 return Err(DebuggerError::Other(anyhow!(new_status
                                     .short_long_status()
                                     .1
@@ -245,6 +343,8 @@ impl Debugger {
                     | "setBreakpoint"
                     | "setBreakpoints"
                     | "setInstructionBreakpoints"
+                    | "setDataBreakpoints"
+                    | "setExceptionBreakpoints"
                     | "clearBreakpoint"
                     | "stackTrace"
                     | "threads"
@@ -323,6 +423,28 @@ impl Debugger {
                         }
                         Ok(DebuggerStatus::ContinueSession)
                     }
+                    "rttWindowInput" => {
+                        let result = match get_arguments::<RttWindowInputArguments>(&request) {
+                            Ok(arguments) => match target_core.core_data.rtt_connection.as_mut() {
+                                Some(debugger_rtt_target) => debugger_rtt_target
+                                    .push_input(
+                                        &mut target_core.core,
+                                        arguments.channel_number,
+                                        arguments.data.as_bytes(),
+                                    )
+                                    .map_err(DebuggerError::Other),
+                                None => Err(DebuggerError::Other(anyhow!(
+                                    "RTT is not active on the target"
+                                ))),
+                            },
+                            Err(error) => Err(DebuggerError::Other(anyhow!(
+                                "Could not deserialize arguments for RttWindowInput : {:?}.",
+                                error
+                            ))),
+                        };
+                        debug_adapter.send_response::<()>(request, result.map(|()| None))?;
+                        Ok(DebuggerStatus::ContinueSession)
+                    }
                     "disconnect" => debug_adapter
                         .send_response::<()>(request, Ok(None))
                         .and(Ok(DebuggerStatus::TerminateSession)),
@@ -369,6 +491,15 @@ impl Debugger {
                     "setBreakpoints" => debug_adapter
                         .set_breakpoints(&mut target_core, request)
                         .and(Ok(DebuggerStatus::ContinueSession)),
+                    "dataBreakpointInfo" => debug_adapter
+                        .data_breakpoint_info(&mut target_core, request)
+                        .and(Ok(DebuggerStatus::ContinueSession)),
+                    "setDataBreakpoints" => debug_adapter
+                        .set_data_breakpoints(&mut target_core, request)
+                        .and(Ok(DebuggerStatus::ContinueSession)),
+                    "setExceptionBreakpoints" => debug_adapter
+                        .set_exception_breakpoints(&mut target_core, request)
+                        .and(Ok(DebuggerStatus::ContinueSession)),
                     "setInstructionBreakpoints" => debug_adapter
                         .set_instruction_breakpoints(&mut target_core, request)
                         .and(Ok(DebuggerStatus::ContinueSession)),
@@ -503,12 +634,14 @@ impl Debugger {
             supports_clipboard_context: Some(true),
             supports_disassemble_request: Some(true),
             supports_instruction_breakpoints: Some(true),
+            supports_conditional_breakpoints: Some(true),
+            supports_hit_conditional_breakpoints: Some(true),
+            supports_log_points: Some(true),
+            supports_data_breakpoints: Some(true),
             supports_stepping_granularity: Some(true),
             // supports_value_formatting_options: Some(true),
             // supports_function_breakpoints: Some(true),
-            // TODO: Use DEMCR register to implement exception breakpoints
-            // supports_exception_options: Some(true),
-            // supports_exception_filter_options: Some (true),
+            exception_breakpoint_filters: Some(exception_breakpoint_filters()),
             ..Default::default()
         };
         debug_adapter.send_response(initialize_request, Ok(Some(capabilities)))?;
@@ -623,6 +756,13 @@ impl Debugger {
         // TODO: Multi-core ... needs to flash multiple binaries
         {
             if self.config.flashing_config.flashing_enabled {
+                let session = match session_data.target.session() {
+                    Ok(session) => session,
+                    Err(error) => {
+                        debug_adapter.send_error_response(&error)?;
+                        return Err(error);
+                    }
+                };
                 let path_to_elf = match &target_core_config.program_binary {
                     Some(program_binary) => program_binary,
                     None => {
@@ -829,7 +969,7 @@ impl Debugger {
                         };
                         download_options.progress = Some(&flash_progress);
                         download_file_with_options(
-                            &mut session_data.session,
+                            session,
                             &path_to_elf,
                             Format::Elf,
                             download_options,
@@ -958,6 +1098,54 @@ impl Debugger {
     }
 }
 
+/// Evaluates the conditions of the breakpoint the core halted on, or of the data breakpoint of the triggered `watchpoint`, and resumes the core if the halt should not be reported to the client.
+///
+/// Returns `true` if the core was resumed.
+fn resume_after_breakpoint<P: ProtocolAdapter>(
+    target_core: &mut CoreHandle,
+    debug_adapter: &mut DebugAdapter<P>,
+    watchpoint: Option<Watchpoint>,
+) -> Result<bool, DebuggerError> {
+    let pc = match target_core
+        .core
+        .read_core_reg(target_core.core.registers().program_counter())
+    {
+        Ok(pc) => pc,
+        Err(error) => {
+            let error = DebuggerError::ProbeRs(error);
+            let _ = debug_adapter.send_error_response(&error);
+            return Err(error);
+        }
+    };
+
+    let breakpoint_address = watchpoint.map_or(pc, |watchpoint| watchpoint.address);
+    let resume = match target_core.breakpoint_hit(watchpoint, pc) {
+        Ok(BreakpointHit::Halt) => false,
+        Ok(BreakpointHit::Continue) => true,
+        Ok(BreakpointHit::Log(message)) => {
+            debug_adapter.log_to_console(message);
+            true
+        }
+        Err(error) => {
+            debug_adapter.log_to_console(format!(
+                "WARNING: Failed to evaluate the breakpoint condition at {:#010x}: {}",
+                breakpoint_address, error
+            ));
+            false
+        }
+    };
+
+    if resume {
+        if let Err(error) = target_core.core.run() {
+            let error = DebuggerError::ProbeRs(error);
+            let _ = debug_adapter.send_error_response(&error);
+            return Err(error);
+        }
+    }
+
+    Ok(resume)
+}
+
 pub fn list_connected_devices() -> Result<()> {
     let connected_devices = Probe::list_all();
 
@@ -18,13 +18,57 @@ impl RttConnection {
         debug_adapter: &mut DebugAdapter<P>,
         target_core: &mut Core,
     ) -> bool {
+        if !self
+            .debugger_rtt_channels
+            .iter()
+            .any(|debugger_rtt_channel| debugger_rtt_channel.has_client_window)
+        {
+            return false;
+        }
+
+        // Read the state of all channels at once, instead of once per channel.
+        let snapshot = match self.target_rtt.snapshot(target_core) {
+            Ok(snapshot) => snapshot,
+            Err(error) => {
+                log::error!("Error reading from RTT: {}", error);
+                return false;
+            }
+        };
+
         let mut at_least_one_channel_had_data = false;
         for debugger_rtt_channel in self.debugger_rtt_channels.iter_mut() {
-            at_least_one_channel_had_data |=
-                debugger_rtt_channel.poll_rtt_data(target_core, debug_adapter, &mut self.target_rtt)
+            at_least_one_channel_had_data |= debugger_rtt_channel.poll_rtt_data(
+                target_core,
+                debug_adapter,
+                &mut self.target_rtt,
+                &snapshot,
+            )
         }
         at_least_one_channel_had_data
     }
+
+    /// Writes input from the window of the RTT channel `channel_number` to the target.
+    /// The down channel with the same number is used if it exists, otherwise the first down channel.
+    pub fn push_input(
+        &mut self,
+        target_core: &mut Core,
+        channel_number: usize,
+        data: &[u8],
+    ) -> anyhow::Result<()> {
+        let has_matching_down_channel = self
+            .target_rtt
+            .active_channels
+            .iter()
+            .any(|active_channel| active_channel.down_channel_number() == Some(channel_number));
+        let down_channel_number = if has_matching_down_channel {
+            Some(channel_number)
+        } else {
+            None
+        };
+
+        self.target_rtt
+            .push_rtt(target_core, down_channel_number, data)
+    }
 }
 
 pub(crate) struct DebuggerRttChannel {
@@ -42,6 +86,7 @@ impl DebuggerRttChannel {
         core: &mut Core,
         debug_adapter: &mut DebugAdapter<P>,
         rtt_target: &mut rtt::RttActiveTarget,
+        snapshot: &rtt::ChannelSnapshot,
     ) -> bool {
         if self.has_client_window {
             rtt_target
@@ -55,7 +100,11 @@ impl DebuggerRttChannel {
                     }
                 })
                 .and_then(|rtt_channel| {
-                    rtt_channel.get_rtt_data(core, rtt_target.defmt_state.as_ref())
+                    rtt_channel.get_rtt_data_snapshot(
+                        core,
+                        snapshot,
+                        rtt_target.defmt_state.as_ref(),
+                    )
                 })
                 .and_then(|(channel_number, channel_data)| {
                     if debug_adapter
@@ -8,16 +8,32 @@ use crate::{
 };
 use anyhow::{anyhow, Result};
 use probe_rs::{
-    config::TargetSelector, debug::debug_info::DebugInfo, CoreStatus, DebugProbeError, Permissions,
-    Probe, ProbeCreationError, Session,
+    config::TargetSelector,
+    debug::debug_info::DebugInfo,
+    semihosting::{SemihostingHost, SemihostingOptions},
+    CoreDump, CoreState, CoreStatus, DebugProbeError, Error, Permissions, Probe,
+    ProbeCreationError, Session, Watchpoint, WatchpointKind,
 };
-use std::env::set_current_dir;
+use std::{env::set_current_dir, fs::File, io::BufReader, path::Path};
 
 /// The supported breakpoint types
 #[derive(Debug, PartialEq)]
 pub enum BreakpointType {
     InstructionBreakpoint,
     SourceBreakpoint(Source),
+    /// A data watchpoint of the given kind, where `breakpoint_address` is the start of the watched memory range.
+    DataBreakpoint(WatchpointKind),
+}
+
+/// The optional conditions of a breakpoint, which are evaluated every time it is hit, before the client is notified.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct BreakpointConditions {
+    /// Only halt if this expression evaluates to `true`.
+    pub(crate) condition: Option<String>,
+    /// Only halt if the hit count satisfies this condition, e.g. `>= 5` or `% 2`.
+    pub(crate) hit_condition: Option<String>,
+    /// Instead of halting, write this message to the debug console and continue. `{expression}` placeholders are replaced by their values.
+    pub(crate) log_message: Option<String>,
 }
 
 /// Provide the storage and methods to handle various [`BreakPointType`]
@@ -25,91 +41,38 @@ pub enum BreakpointType {
 pub struct ActiveBreakpoint {
     pub(crate) breakpoint_type: BreakpointType,
     pub(crate) breakpoint_address: u32,
+    pub(crate) conditions: BreakpointConditions,
+    /// The number of times the breakpoint was hit, while its `condition` was met.
+    pub(crate) hit_count: u64,
+}
+
+impl ActiveBreakpoint {
+    /// Whether this is the breakpoint the core halted on at `pc`, where `watchpoint` is the triggered watchpoint if the core halted on a data breakpoint.
+    pub(crate) fn is_hit(&self, watchpoint: Option<Watchpoint>, pc: u32) -> bool {
+        match (&self.breakpoint_type, watchpoint) {
+            (BreakpointType::DataBreakpoint(kind), Some(watchpoint)) => {
+                *kind == watchpoint.kind && self.breakpoint_address == watchpoint.address
+            }
+            (BreakpointType::DataBreakpoint(_), None) | (_, Some(_)) => false,
+            (_, None) => self.breakpoint_address == pc,
+        }
+    }
 }
 
 /// SessionData is designed to be similar to [probe_rs::Session], in as much that it provides handles to the [CoreHandle] instances for each of the available [probe_rs::Core] involved in the debug session.
 /// To get access to the [CoreHandle] for a specific [Core], the
 /// TODO: Adjust [SessionConfig] to allow multiple cores (and if appropriate, their binaries) to be specified.
 pub struct SessionData {
-    pub(crate) session: Session,
+    pub(crate) target: DebugTarget,
     /// [SessionData] will manage one [CoreData] per target core, that is also present in [SessionConfig::core_configs]
     pub(crate) core_data: Vec<CoreData>,
 }
 
 impl SessionData {
     pub(crate) fn new(config: &configuration::SessionConfig) -> Result<Self, DebuggerError> {
-        // `SessionConfig` Probe/Session level configurations initialization.
-        let mut target_probe = match config.probe_selector.clone() {
-            Some(selector) => Probe::open(selector.clone()).map_err(|e| match e {
-                DebugProbeError::ProbeCouldNotBeCreated(ProbeCreationError::NotFound) => {
-                    DebuggerError::Other(anyhow!(
-                        "Could not find the probe_selector specified as {:04x}:{:04x}:{:?}",
-                        selector.vendor_id,
-                        selector.product_id,
-                        selector.serial_number
-                    ))
-                }
-                other_error => DebuggerError::DebugProbe(other_error),
-            }),
-            None => {
-                // Only automatically select a probe if there is only a single probe detected.
-                let list = Probe::list_all();
-                if list.len() > 1 {
-                    return Err(DebuggerError::Other(anyhow!(
-                        "Found multiple ({}) probes",
-                        list.len()
-                    )));
-                }
-
-                if let Some(info) = list.first() {
-                    Probe::open(info).map_err(DebuggerError::DebugProbe)
-                } else {
-                    return Err(DebuggerError::Other(anyhow!(
-                        "No probes found. Please check your USB connections."
-                    )));
-                }
-            }
-        }?;
-
-        let target_selector = match &config.chip {
-            Some(identifier) => identifier.into(),
-            None => TargetSelector::Auto,
-        };
-
-        // Set the protocol, if the user explicitly selected a protocol. Otherwise, use the default protocol of the probe.
-        if let Some(wire_protocol) = config.wire_protocol {
-            target_probe.select_protocol(wire_protocol)?;
-        }
-
-        // Set the speed.
-        if let Some(speed) = config.speed {
-            let actual_speed = target_probe.set_speed(speed)?;
-            if actual_speed != speed {
-                log::warn!(
-                    "Protocol speed {} kHz not supported, actual speed is {} kHz",
-                    speed,
-                    actual_speed
-                );
-            }
-        }
-
-        let mut permissions = Permissions::new();
-        if config.allow_erase_all {
-            permissions = permissions.allow_erase_all();
-        }
-
-        // Attach to the probe.
-        let target_session = if config.connect_under_reset {
-            target_probe.attach_under_reset(target_selector, permissions)?
-        } else {
-            target_probe
-                .attach(target_selector, permissions)
-                .map_err(|err| {
-                    anyhow!(
-                        "Error attaching to the probe: {:?}.\nTry the --connect-under-reset option",
-                        err
-                    )
-                })?
+        let target = match &config.core_dump {
+            Some(path) => DebugTarget::load_core_dump(path)?,
+            None => DebugTarget::Probe(attach_to_probe(config)?),
         };
 
         // Change the current working directory if `config.cwd` is `Some(T)`.
@@ -135,16 +98,7 @@ impl SessionData {
         let valid_core_configs = config
             .core_configs
             .iter()
-            .filter(|&core_config| {
-                matches!(
-                        target_session
-                            .list_cores()
-                            .iter()
-                            .find(|(target_core_index, _)| *target_core_index
-                                == core_config.core_index),
-                        Some(_)
-                    )
-            })
+            .filter(|&core_config| target.has_core(core_config.core_index))
             .cloned()
             .collect::<Vec<CoreConfig>>();
 
@@ -165,21 +119,25 @@ impl SessionData {
 
             core_data_vec.push(CoreData {
                 core_index: core_configuration.core_index,
-                target_name: format!(
-                    "{}-{}",
-                    core_configuration.core_index,
-                    target_session.target().name
-                ),
+                target_name: format!("{}-{}", core_configuration.core_index, target.name()),
                 debug_info,
                 core_peripherals: None,
                 stack_frames: Vec::<probe_rs::debug::stack_frame::StackFrame>::new(),
                 breakpoints: Vec::<ActiveBreakpoint>::new(),
                 rtt_connection: None,
+                semihosting: SemihostingHost::new(SemihostingOptions {
+                    root: core_configuration.semihosting_root.clone(),
+                    command_line: core_configuration
+                        .program_binary
+                        .as_ref()
+                        .map(|program_binary| program_binary.display().to_string())
+                        .unwrap_or_default(),
+                }),
             })
         }
 
         Ok(SessionData {
-            session: target_session,
+            target,
             core_data: core_data_vec,
         })
     }
@@ -187,7 +145,7 @@ impl SessionData {
     /// Do a 'light weight'(just get references to existing data structures) attach to the core and return relevant debug data.
     pub(crate) fn attach_core(&mut self, core_index: usize) -> Result<CoreHandle, DebuggerError> {
         if let (Ok(target_core), Some(core_data)) = (
-            self.session.core(core_index),
+            self.target.core(core_index),
             self.core_data
                 .iter_mut()
                 .find(|core_data| core_data.core_index == core_index),
@@ -213,7 +171,11 @@ impl SessionData {
         let mut at_least_one_channel_had_data = false;
         for core_config in session_config.core_configs.iter() {
             if core_config.rtt_config.enabled {
-                let target_memory_map = self.session.target().memory_map.clone();
+                let target_memory_map = match &self.target {
+                    DebugTarget::Probe(session) => session.target().memory_map.clone(),
+                    // RTT needs to write to the target, which is not possible with a core dump.
+                    DebugTarget::CoreDump { .. } => continue,
+                };
                 if let Ok(mut target_core) = self.attach_core(core_config.core_index) {
                     if let Some(core_rtt) = &mut target_core.core_data.rtt_connection {
                         // We should poll the target for rtt data.
@@ -255,3 +217,209 @@ impl SessionData {
         at_least_one_channel_had_data
     }
 }
+
+/// Opens the probe selected in the [SessionConfig], and attaches to the target.
+fn attach_to_probe(config: &configuration::SessionConfig) -> Result<Session, DebuggerError> {
+    // `SessionConfig` Probe/Session level configurations initialization.
+    let mut target_probe = match config.probe_selector.clone() {
+        Some(selector) => Probe::open(selector.clone()).map_err(|e| match e {
+            DebugProbeError::ProbeCouldNotBeCreated(ProbeCreationError::NotFound) => {
+                DebuggerError::Other(anyhow!(
+                    "Could not find the probe_selector specified as {:04x}:{:04x}:{:?}",
+                    selector.vendor_id,
+                    selector.product_id,
+                    selector.serial_number
+                ))
+            }
+            other_error => DebuggerError::DebugProbe(other_error),
+        }),
+        None => {
+            // Only automatically select a probe if there is only a single probe detected.
+            let list = Probe::list_all();
+            if list.len() > 1 {
+                return Err(DebuggerError::Other(anyhow!(
+                    "Found multiple ({}) probes",
+                    list.len()
+                )));
+            }
+
+            if let Some(info) = list.first() {
+                Probe::open(info).map_err(DebuggerError::DebugProbe)
+            } else {
+                return Err(DebuggerError::Other(anyhow!(
+                    "No probes found. Please check your USB connections."
+                )));
+            }
+        }
+    }?;
+
+    let target_selector = match &config.chip {
+        Some(identifier) => identifier.into(),
+        None => TargetSelector::Auto,
+    };
+
+    // Set the protocol, if the user explicitly selected a protocol. Otherwise, use the default protocol of the probe.
+    if let Some(wire_protocol) = config.wire_protocol {
+        target_probe.select_protocol(wire_protocol)?;
+    }
+
+    // Set the speed.
+    if let Some(speed) = config.speed {
+        let actual_speed = target_probe.set_speed(speed)?;
+        if actual_speed != speed {
+            log::warn!(
+                "Protocol speed {} kHz not supported, actual speed is {} kHz",
+                speed,
+                actual_speed
+            );
+        }
+    }
+
+    let mut permissions = Permissions::new();
+    if config.allow_erase_all {
+        permissions = permissions.allow_erase_all();
+    }
+
+    // Attach to the probe.
+    let target_session = if config.connect_under_reset {
+        target_probe.attach_under_reset(target_selector, permissions)?
+    } else {
+        target_probe
+            .attach(target_selector, permissions)
+            .map_err(|err| {
+                anyhow!(
+                    "Error attaching to the probe: {:?}.\nTry the --connect-under-reset option",
+                    err
+                )
+            })?
+    };
+
+    Ok(target_session)
+}
+
+/// The target of a debug session: either a target attached with a probe, or a [CoreDump] which replays the state of a target.
+pub(crate) enum DebugTarget {
+    Probe(Session),
+    CoreDump {
+        core_dump: CoreDump,
+        /// The [CoreState] of each core in the dump, which is required to create a [probe_rs::Core].
+        core_states: Vec<CoreState>,
+    },
+}
+
+impl DebugTarget {
+    fn load_core_dump(path: &Path) -> Result<Self, DebuggerError> {
+        let core_dump = File::open(path)
+            .map_err(probe_rs::CoreDumpError::from)
+            .and_then(|file| CoreDump::load(BufReader::new(file)))
+            .map_err(|error| {
+                DebuggerError::Other(anyhow!(
+                    "Failed to load the core dump {:?}: {}",
+                    path,
+                    error
+                ))
+            })?;
+        let core_states = (0..core_dump.cores.len()).map(CoreState::new).collect();
+
+        Ok(DebugTarget::CoreDump {
+            core_dump,
+            core_states,
+        })
+    }
+
+    fn name(&self) -> &str {
+        match self {
+            DebugTarget::Probe(session) => &session.target().name,
+            DebugTarget::CoreDump { core_dump, .. } => &core_dump.target,
+        }
+    }
+
+    fn has_core(&self, core_index: usize) -> bool {
+        match self {
+            DebugTarget::Probe(session) => session
+                .list_cores()
+                .iter()
+                .any(|(target_core_index, _)| *target_core_index == core_index),
+            DebugTarget::CoreDump { core_dump, .. } => core_index < core_dump.cores.len(),
+        }
+    }
+
+    fn core(&mut self, core_index: usize) -> Result<probe_rs::Core, Error> {
+        match self {
+            DebugTarget::Probe(session) => session.core(core_index),
+            DebugTarget::CoreDump {
+                core_dump,
+                core_states,
+            } => {
+                let state = core_states
+                    .get_mut(core_index)
+                    .ok_or(Error::CoreNotFound(core_index))?;
+                core_dump.core(core_index, state)
+            }
+        }
+    }
+
+    /// Returns the session, or an error if a core dump is debugged.
+    pub(crate) fn session(&mut self) -> Result<&mut Session, DebuggerError> {
+        match self {
+            DebugTarget::Probe(session) => Ok(session),
+            DebugTarget::CoreDump { .. } => Err(DebuggerError::Other(anyhow!(
+                "This operation requires a probe, and is not possible with a core dump"
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use probe_rs::{Watchpoint, WatchpointKind};
+
+    use super::{ActiveBreakpoint, BreakpointConditions, BreakpointType};
+
+    fn breakpoint(breakpoint_type: BreakpointType, breakpoint_address: u32) -> ActiveBreakpoint {
+        ActiveBreakpoint {
+            breakpoint_type,
+            breakpoint_address,
+            conditions: BreakpointConditions::default(),
+            hit_count: 0,
+        }
+    }
+
+    #[test]
+    fn instruction_breakpoint_hit() {
+        let breakpoint = breakpoint(BreakpointType::InstructionBreakpoint, 0x0800_0100);
+
+        assert!(breakpoint.is_hit(None, 0x0800_0100));
+        assert!(!breakpoint.is_hit(None, 0x0800_0104));
+    }
+
+    #[test]
+    fn data_breakpoint_hit() {
+        let breakpoint = breakpoint(
+            BreakpointType::DataBreakpoint(WatchpointKind::Write),
+            0x2000_0000,
+        );
+        let watchpoint = |kind| Watchpoint {
+            address: 0x2000_0000,
+            length: 4,
+            kind,
+        };
+
+        assert!(breakpoint.is_hit(Some(watchpoint(WatchpointKind::Write)), 0x0800_0100));
+        assert!(!breakpoint.is_hit(Some(watchpoint(WatchpointKind::Read)), 0x0800_0100));
+        // A breakpoint at the watched address is not a data breakpoint.
+        assert!(!breakpoint.is_hit(None, 0x2000_0000));
+    }
+
+    #[test]
+    fn instruction_breakpoint_not_hit_by_watchpoint() {
+        let breakpoint = breakpoint(BreakpointType::InstructionBreakpoint, 0x2000_0000);
+        let watchpoint = Watchpoint {
+            address: 0x2000_0000,
+            length: 4,
+            kind: WatchpointKind::Access,
+        };
+
+        assert!(!breakpoint.is_hit(Some(watchpoint), 0x2000_0000));
+    }
+}
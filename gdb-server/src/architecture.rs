@@ -1,7 +1,8 @@
 use probe_rs::{
-    config::{CoreType, MemoryRegion},
+    config::{CoreType, FlashProperties, MemoryRegion},
     Core, CoreRegisterAddress,
 };
+use std::ops::Range;
 
 /// Extension trait for probe_rs::Core, which adds some GDB -> probe-rs internal translation functions.
 ///
@@ -95,10 +96,10 @@ pub trait GdbTargetExt {
     /// See https://sourceware.org/gdb/onlinedocs/gdb/Memory-Map-Format.html#Memory-Map-Format
     fn gdb_memory_map(&self) -> String;
 
-    /// Target description in GDB XML Format.
+    /// Target description in GDB XML Format, for the core with index `core_index`.
     ///
     /// See https://sourceware.org/gdb/onlinedocs/gdb/Target-Descriptions.html#Target-Descriptions
-    fn target_description(&self) -> String;
+    fn target_description(&self, core_index: usize) -> String;
 }
 
 impl GdbTargetExt for probe_rs::Target {
@@ -121,12 +122,23 @@ impl GdbTargetExt for probe_rs::Target {
                     region.range.end - region.range.start
                 ),
                 MemoryRegion::Nvm(region) => {
-                    // TODO: Use flash with block size
-                    format!(
-                        r#"<memory type="rom" start="{:#x}" length="{:#x}"/>\n"#,
-                        region.range.start,
-                        region.range.end - region.range.start
-                    )
+                    let algorithm = self.flash_algorithms.iter().find(|algorithm| {
+                        let flash_range = &algorithm.flash_properties.address_range;
+                        flash_range.start <= region.range.start
+                            && region.range.end <= flash_range.end
+                    });
+
+                    match algorithm {
+                        Some(algorithm) => {
+                            gdb_flash_entries(&region.range, &algorithm.flash_properties)
+                        }
+                        // Without a flash algorithm, the region can't be programmed by GDB.
+                        None => format!(
+                            r#"<memory type="rom" start="{:#x}" length="{:#x}"/>\n"#,
+                            region.range.start,
+                            region.range.end - region.range.start
+                        ),
+                    }
                 }
             };
 
@@ -138,7 +150,7 @@ impl GdbTargetExt for probe_rs::Target {
         xml_map
     }
 
-    fn target_description(&self) -> String {
+    fn target_description(&self, core_index: usize) -> String {
         // GDB-architectures
         //
         // - armv6-m      -> Core-M0
@@ -148,8 +160,7 @@ impl GdbTargetExt for probe_rs::Target {
         // - armv8-m.main -> Core-M33
         // - riscv:rv32   -> RISCV
 
-        // TODO: what if they're not all equal?
-        let architecture = match self.cores[0].core_type {
+        let architecture = match self.cores[core_index].core_type {
             CoreType::Armv6m => "armv6-m",
             CoreType::Armv7a => "armv7",
             CoreType::Armv7m => "armv7",
@@ -173,16 +184,82 @@ impl GdbTargetExt for probe_rs::Target {
     }
 }
 
+/// Describes the flash in `range` for the GDB memory map.
+///
+/// GDB erases flash in blocks, so an entry is created for every group of equally sized sectors.
+fn gdb_flash_entries(range: &Range<u32>, properties: &FlashProperties) -> String {
+    let flash_start = properties.address_range.start;
+    let mut entries = String::new();
+
+    for (index, sectors) in properties.sectors.iter().enumerate() {
+        // Sector addresses are relative to the start of the flash,
+        // and a group of sectors extends up to the next group.
+        let group_start = flash_start + sectors.address;
+        let group_end = properties
+            .sectors
+            .get(index + 1)
+            .map(|next| flash_start + next.address)
+            .unwrap_or(properties.address_range.end);
+
+        let start = group_start.max(range.start);
+        let end = group_end.min(range.end);
+
+        if start < end {
+            entries.push_str(&format!(
+                r#"<memory type="flash" start="{:#x}" length="{:#x}"><property name="blocksize">{:#x}</property></memory>"#,
+                start,
+                end - start,
+                sectors.size
+            ));
+        }
+    }
+
+    entries
+}
+
 #[cfg(test)]
 mod test {
+    use super::gdb_flash_entries;
     use crate::architecture::GdbTargetExt;
+    use probe_rs::config::{FlashProperties, SectorDescription};
 
     #[test]
     fn test_target_description_microbit() {
         let target = probe_rs::config::get_target_by_name("nrf51822_xxAA").unwrap();
 
-        let description = target.target_description();
+        let description = target.target_description(0);
 
         insta::assert_snapshot!(description);
     }
+
+    #[test]
+    fn flash_entries_per_sector_size() {
+        let properties = FlashProperties {
+            address_range: 0x0800_0000..0x0810_0000,
+            page_size: 0x400,
+            erased_byte_value: 0xff,
+            program_page_timeout: 100,
+            erase_sector_timeout: 1000,
+            sectors: vec![
+                SectorDescription {
+                    size: 0x4000,
+                    address: 0,
+                },
+                SectorDescription {
+                    size: 0x20000,
+                    address: 0x20000,
+                },
+            ],
+        };
+
+        let entries = gdb_flash_entries(&(0x0800_0000..0x0808_0000), &properties);
+
+        assert_eq!(
+            entries,
+            concat!(
+                r#"<memory type="flash" start="0x8000000" length="0x20000"><property name="blocksize">0x4000</property></memory>"#,
+                r#"<memory type="flash" start="0x8020000" length="0x60000"><property name="blocksize">0x20000</property></memory>"#,
+            )
+        );
+    }
 }
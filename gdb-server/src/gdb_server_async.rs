@@ -7,7 +7,7 @@ use async_std::{
 };
 use futures::channel::mpsc;
 use gdb_protocol::packet::CheckedPacket;
-use probe_rs::Session;
+use probe_rs::{flashing::DownloadOptions, semihosting::SemihostingOptions, Session};
 
 type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync>>;
 type Sender<T> = mpsc::UnboundedSender<T>;
@@ -19,20 +19,49 @@ const CONNECTION_STRING: &str = "127.0.0.1:1337";
 /// This function is blocking. If you would like to use it concurently to other users of the session,
 /// please use a thread.
 pub fn run(connection_string: Option<impl Into<String>>, session: &Mutex<Session>) -> Result<()> {
+    run_with_options(
+        connection_string,
+        session,
+        &DownloadOptions::default(),
+        &SemihostingOptions::default(),
+    )
+}
+
+/// Same as [run], but programs the flash with the given `download_options`
+/// when GDB loads a program with the `vFlash` packets,
+/// and services semihosting requests of the target with the given `semihosting_options`.
+pub fn run_with_options(
+    connection_string: Option<impl Into<String>>,
+    session: &Mutex<Session>,
+    download_options: &DownloadOptions<'_>,
+    semihosting_options: &SemihostingOptions,
+) -> Result<()> {
     let connection_string = connection_string
         .map(|cs| cs.into())
         .unwrap_or_else(|| CONNECTION_STRING.to_owned());
     log::info!("GDB stub listening on {}", connection_string);
-    task::block_on(accept_loop(connection_string, session))
+    task::block_on(accept_loop(
+        connection_string,
+        session,
+        download_options,
+        semihosting_options,
+    ))
 }
 
 /// This function accepts any incomming connection.
-async fn accept_loop(addr: impl ToSocketAddrs, session: &Mutex<Session>) -> Result<()> {
+async fn accept_loop(
+    addr: impl ToSocketAddrs,
+    session: &Mutex<Session>,
+    download_options: &DownloadOptions<'_>,
+    semihosting_options: &SemihostingOptions,
+) -> Result<()> {
     let listener = TcpListener::bind(addr).await?;
 
     let mut incoming = listener.incoming();
     while let Some(stream) = incoming.next().await {
-        if let Err(e) = handle_connection(stream?, session).await {
+        if let Err(e) =
+            handle_connection(stream?, session, download_options, semihosting_options).await
+        {
             log::error!(
                 "An error with the current connection has been encountered. It has been closed."
             );
@@ -43,7 +72,12 @@ async fn accept_loop(addr: impl ToSocketAddrs, session: &Mutex<Session>) -> Resu
 }
 
 /// Handle a single connection of a client
-async fn handle_connection(stream: TcpStream, session: &Mutex<Session>) -> Result<()> {
+async fn handle_connection(
+    stream: TcpStream,
+    session: &Mutex<Session>,
+    download_options: &DownloadOptions<'_>,
+    semihosting_options: &SemihostingOptions,
+) -> Result<()> {
     let (packet_stream_sender, packet_stream_receiver) = mpsc::unbounded();
     let (tbd_sender, tbd_receiver) = mpsc::unbounded();
 
@@ -55,7 +89,14 @@ async fn handle_connection(stream: TcpStream, session: &Mutex<Session>) -> Resul
         packet_stream_receiver,
     ));
 
-    super::worker::worker(tbd_receiver, packet_stream_sender, session).await?;
+    super::worker::worker(
+        tbd_receiver,
+        packet_stream_sender,
+        session,
+        download_options,
+        semihosting_options,
+    )
+    .await?;
 
     inbound_broker_handle.await?;
 
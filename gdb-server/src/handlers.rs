@@ -1,5 +1,9 @@
 use crate::architecture::{GdbArchitectureExt, GdbTargetExt};
-use probe_rs::{Core, CoreStatus, MemoryInterface, Session};
+use crate::parser::v_packet::{Action, ThreadAction};
+use crate::parser::{ThreadId, ThreadOperation};
+use crate::worker::ConnectionState;
+use probe_rs::flashing::{DownloadOptions, FlashError};
+use probe_rs::{Core, CoreStatus, HaltReason, MemoryInterface, Session, WatchpointKind};
 use std::time::Duration;
 
 pub(crate) fn q_supported() -> Option<String> {
@@ -21,8 +25,107 @@ pub(crate) fn q_attached() -> Option<String> {
     Some("1".into())
 }
 
-pub(crate) fn halt_reason() -> Option<String> {
-    Some("S05".into())
+pub(crate) fn halt_reason(state: &ConnectionState) -> Option<String> {
+    Some(format!("T05thread:{:x};", thread_id(state.general_core)))
+}
+
+/// Returns the GDB thread ID of the core with index `core_index`.
+///
+/// GDB reserves thread ID 0 for "any thread", so the thread IDs start at 1.
+fn thread_id(core_index: usize) -> usize {
+    core_index + 1
+}
+
+/// Returns the index of the core which is selected by `thread_id`, if it exists.
+fn core_index(thread_id: u32, session: &Session) -> Option<usize> {
+    let core_index = (thread_id as usize).checked_sub(1)?;
+
+    session
+        .list_cores()
+        .iter()
+        .any(|(index, _)| *index == core_index)
+        .then(|| core_index)
+}
+
+/// Checks if the core with index `core_index` is selected by `thread_id`.
+fn thread_matches(thread_id: ThreadId, core_index: usize) -> bool {
+    match thread_id {
+        ThreadId::All | ThreadId::Any => true,
+        ThreadId::Id(id) => id as usize == self::thread_id(core_index),
+    }
+}
+
+pub(crate) fn select_thread(
+    operation: ThreadOperation,
+    thread_id: ThreadId,
+    session: &Session,
+    state: &mut ConnectionState,
+) -> Option<String> {
+    let core = match thread_id {
+        ThreadId::All | ThreadId::Any => None,
+        ThreadId::Id(id) => match core_index(id, session) {
+            Some(core_index) => Some(core_index),
+            None => return Some("E22".to_string()),
+        },
+    };
+
+    match operation {
+        // Register and memory accesses need a single core, so "any" and "all" keep the current selection.
+        ThreadOperation::General => {
+            if let Some(core_index) = core {
+                state.general_core = core_index;
+            }
+        }
+        ThreadOperation::Continue => state.continue_core = core,
+    }
+
+    reply_ok()
+}
+
+pub(crate) fn thread_alive(thread_id: ThreadId, session: &Session) -> Option<String> {
+    match thread_id {
+        ThreadId::Id(id) if core_index(id, session).is_none() => Some("E22".to_string()),
+        _ => reply_ok(),
+    }
+}
+
+pub(crate) fn current_thread(state: &ConnectionState) -> Option<String> {
+    Some(format!("QC{:x}", thread_id(state.general_core)))
+}
+
+pub(crate) fn first_thread_info(session: &Session) -> Option<String> {
+    let thread_ids: Vec<String> = session
+        .list_cores()
+        .iter()
+        .map(|(core_index, _)| format!("{:x}", thread_id(*core_index)))
+        .collect();
+
+    Some(format!("m{}", thread_ids.join(",")))
+}
+
+pub(crate) fn subsequent_thread_info() -> Option<String> {
+    // All threads are reported in the reply to qfThreadInfo.
+    Some("l".into())
+}
+
+pub(crate) fn thread_extra_info(thread_id: ThreadId, session: &Session) -> Option<String> {
+    let core_type = match thread_id {
+        ThreadId::Id(id) => core_index(id, session).and_then(|core_index| {
+            session
+                .list_cores()
+                .into_iter()
+                .find(|(index, _)| *index == core_index)
+        }),
+        _ => None,
+    };
+
+    match core_type {
+        Some((core_index, core_type)) => Some(hex::encode(format!(
+            "Core {} ({:?})",
+            core_index, core_type
+        ))),
+        None => Some("E22".to_string()),
+    }
 }
 
 pub(crate) fn read_general_registers(mut core: Core) -> Option<String> {
@@ -268,32 +371,284 @@ pub(crate) fn host_info() -> Option<String> {
     Some("cputype:12;cpusubtype:14;triple:armv6m--none-eabi;endian:litte;ptrsize:4".to_string())
 }
 
-pub(crate) fn run(mut core: Core, awaits_halt: &mut bool) -> Option<String> {
-    core.run().unwrap();
-    *awaits_halt = true;
+pub(crate) fn run(session: &mut Session, state: &mut ConnectionState) -> Option<String> {
+    let cores: Vec<usize> = match state.continue_core {
+        Some(core_index) => vec![core_index],
+        None => session
+            .list_cores()
+            .into_iter()
+            .map(|(core_index, _)| core_index)
+            .collect(),
+    };
+
+    for &core_index in &cores {
+        if let Err(e) = resume(session, core_index, state) {
+            log::warn!("Unable to resume core {}: {}", core_index, e);
+            return reply_error_and_halt(session, &cores);
+        }
+    }
+
+    state.running_cores = cores;
     None
 }
 
-pub(crate) fn stop(mut core: Core, awaits_halt: &mut bool) -> Option<String> {
-    core.halt(Duration::from_millis(100)).unwrap();
-    *awaits_halt = false;
-    Some("OK".into())
+/// Resumes the core with index `core_index`, making sure its semihosting requests halt it.
+fn resume(
+    session: &mut Session,
+    core_index: usize,
+    state: &ConnectionState,
+) -> Result<(), probe_rs::Error> {
+    let mut core = session.core(core_index)?;
+
+    if let Err(e) = state.semihosting.enable(&mut core) {
+        log::warn!("Unable to enable semihosting on core {}: {}", core_index, e);
+    }
+
+    core.run()
 }
 
-pub(crate) fn step(mut core: Core, awaits_halt: &mut bool) -> Option<String> {
-    core.step().unwrap();
-    *awaits_halt = false;
-    Some("S05".into())
+/// Halts `cores` after resuming or stepping them failed, and builds the error reply.
+///
+/// GDB considers all threads stopped after an error reply, so no core may be left running.
+fn reply_error_and_halt(session: &mut Session, cores: &[usize]) -> Option<String> {
+    for &core_index in cores {
+        if let Err(e) = session
+            .core(core_index)
+            .and_then(|mut core| core.halt(Duration::from_millis(100)))
+        {
+            log::warn!("Unable to halt core {}: {}", core_index, e);
+        }
+    }
+
+    // Tell GDB that the cores could not be resumed with an EIO response.
+    Some("E05".to_string())
 }
 
-pub(crate) fn insert_hardware_break(address: u32, _kind: u32, mut core: Core) -> Option<String> {
-    core.set_hw_breakpoint(address).unwrap();
-    Some("OK".into())
+pub(crate) fn v_cont(
+    actions: &[ThreadAction],
+    session: &mut Session,
+    state: &mut ConnectionState,
+) -> Option<String> {
+    let mut cores_to_step = Vec::new();
+    let mut cores_to_resume = Vec::new();
+    let mut cores_to_stop = Vec::new();
+
+    for (core_index, _) in session.list_cores() {
+        // The first action which matches a core applies to it.
+        let action = actions.iter().find(|action| {
+            action
+                .thread_id
+                .map_or(true, |thread_id| thread_matches(thread_id, core_index))
+        });
+
+        match action.map(|action| &action.action) {
+            Some(Action::Step | Action::StepSignal(_)) => cores_to_step.push(core_index),
+            Some(Action::Continue | Action::ContinueSignal(_)) => cores_to_resume.push(core_index),
+            Some(Action::Stop) => cores_to_stop.push(core_index),
+            Some(other) => {
+                log::warn!("vCont with action {:?} not supported", other);
+                return reply_empty();
+            }
+            None => {}
+        }
+    }
+
+    match apply_actions(
+        session,
+        state,
+        &cores_to_stop,
+        &cores_to_resume,
+        &cores_to_step,
+    ) {
+        Ok(reply) => reply,
+        Err(e) => {
+            log::warn!("Unable to apply the vCont actions: {}", e);
+            reply_error_and_halt(session, &cores_to_resume)
+        }
+    }
 }
 
-pub(crate) fn remove_hardware_break(address: u32, _kind: u32, mut core: Core) -> Option<String> {
-    core.clear_hw_breakpoint(address).unwrap();
-    Some("OK".into())
+/// Stops, resumes and steps the cores as requested by a `vCont` packet, and returns the reply.
+fn apply_actions(
+    session: &mut Session,
+    state: &mut ConnectionState,
+    cores_to_stop: &[usize],
+    cores_to_resume: &[usize],
+    cores_to_step: &[usize],
+) -> Result<Option<String>, probe_rs::Error> {
+    for &core_index in cores_to_stop {
+        session.core(core_index)?.halt(Duration::from_millis(100))?;
+    }
+
+    for &core_index in cores_to_resume {
+        resume(session, core_index, state)?;
+    }
+
+    for &core_index in cores_to_step {
+        session.core(core_index)?.step()?;
+    }
+
+    // A step finishes right away, and GDB expects a stop reply for it.
+    // GDB runs in all-stop mode, so the resumed cores have to be stopped again
+    // before the stop reply is sent.
+    if let Some(&stepped_core) = cores_to_step.first() {
+        for &core_index in cores_to_resume {
+            session.core(core_index)?.halt(Duration::from_millis(100))?;
+        }
+
+        state.general_core = stepped_core;
+        return Ok(Some(stop_reply(session.core(stepped_core)?, stepped_core)));
+    }
+
+    if !cores_to_resume.is_empty() {
+        state.running_cores = cores_to_resume.to_vec();
+        return Ok(None);
+    }
+
+    Ok(reply_ok())
+}
+
+/// Applies `operation` to all cores, because breakpoints and watchpoints are not specific to a thread in GDB.
+///
+/// The reply is `OK` only if the operation succeeded on every core.
+fn for_all_cores(
+    session: &mut Session,
+    description: &str,
+    mut operation: impl FnMut(&mut Session, usize) -> Result<(), probe_rs::Error>,
+) -> Option<String> {
+    let mut errors = Vec::new();
+
+    for (core_index, _) in session.list_cores() {
+        if let Err(e) = operation(session, core_index) {
+            errors.push((core_index, e));
+        }
+    }
+
+    if errors.is_empty() {
+        return reply_ok();
+    }
+
+    for (core_index, e) in errors {
+        log::warn!("Unable to {} on core {}: {}", description, core_index, e);
+    }
+
+    // Tell GDB that the operation failed with an EINVAL response.
+    Some("E22".to_string())
+}
+
+pub(crate) fn insert_hardware_break(
+    address: u32,
+    _kind: u32,
+    session: &mut Session,
+) -> Option<String> {
+    for_all_cores(
+        session,
+        &format!("set hardware breakpoint at {:#010x}", address),
+        |session, core_index| session.core(core_index)?.set_hw_breakpoint(address),
+    )
+}
+
+pub(crate) fn remove_hardware_break(
+    address: u32,
+    _kind: u32,
+    session: &mut Session,
+) -> Option<String> {
+    for_all_cores(
+        session,
+        &format!("remove hardware breakpoint at {:#010x}", address),
+        |session, core_index| session.core(core_index)?.clear_hw_breakpoint(address),
+    )
+}
+
+pub(crate) fn insert_software_break(
+    address: u32,
+    _kind: u32,
+    session: &mut Session,
+) -> Option<String> {
+    // Hardware breakpoints are preferred, software breakpoints are only used once they run out.
+    // Software breakpoints are shared by all cores, so the breakpoint instruction is only inserted once.
+    for_all_cores(
+        session,
+        &format!("set breakpoint at {:#010x}", address),
+        |session, core_index| session.set_breakpoint(core_index, address),
+    )
+}
+
+pub(crate) fn remove_software_break(
+    address: u32,
+    _kind: u32,
+    session: &mut Session,
+) -> Option<String> {
+    for_all_cores(
+        session,
+        &format!("remove breakpoint at {:#010x}", address),
+        |session, core_index| {
+            let has_breakpoint = {
+                let mut core = session.core(core_index)?;
+                core.sw_breakpoints().contains(&address)
+                    || core.hw_breakpoints()?.contains(&Some(address))
+            };
+
+            // Software breakpoints are shared by all cores, so the breakpoint
+            // might have been removed through another core already.
+            if has_breakpoint {
+                session.clear_breakpoint(core_index, address)
+            } else {
+                Ok(())
+            }
+        },
+    )
+}
+
+pub(crate) fn insert_watchpoint(
+    address: u32,
+    length: u32,
+    kind: WatchpointKind,
+    session: &mut Session,
+) -> Option<String> {
+    for_all_cores(
+        session,
+        &format!("set {:?} watchpoint at {:#010x}", kind, address),
+        |session, core_index| {
+            session
+                .core(core_index)?
+                .set_hw_watchpoint(address, length, kind)
+        },
+    )
+}
+
+pub(crate) fn remove_watchpoint(
+    address: u32,
+    kind: WatchpointKind,
+    session: &mut Session,
+) -> Option<String> {
+    for_all_cores(
+        session,
+        &format!("remove {:?} watchpoint at {:#010x}", kind, address),
+        |session, core_index| session.core(core_index)?.clear_hw_watchpoint(address, kind),
+    )
+}
+
+/// Builds the stop reply packet which is sent to GDB after the core with index `core_index` halted.
+pub(crate) fn stop_reply(mut core: Core, core_index: usize) -> String {
+    let thread = format!("thread:{:x};", thread_id(core_index));
+
+    if let Ok(CoreStatus::Halted(HaltReason::Watchpoint | HaltReason::Multiple)) = core.status() {
+        match core.triggered_watchpoint() {
+            Ok(Some(watchpoint)) => {
+                let reason = match watchpoint.kind {
+                    WatchpointKind::Write => "watch",
+                    WatchpointKind::Read => "rwatch",
+                    WatchpointKind::Access => "awatch",
+                };
+                return format!("T05{}{}:{:x};", thread, reason, watchpoint.address);
+            }
+            Ok(None) => log::debug!("Core halted on a watchpoint, but no comparator matched"),
+            Err(e) => log::warn!("Unable to determine the triggered watchpoint: {}", e),
+        }
+    }
+
+    format!("T05{}hwbreak:;", thread)
 }
 
 pub(crate) fn write_memory(address: u32, data: &[u8], mut core: Core) -> Option<String> {
@@ -302,19 +657,162 @@ pub(crate) fn write_memory(address: u32, data: &[u8], mut core: Core) -> Option<
     Some("OK".into())
 }
 
+pub(crate) fn flash_erase(
+    address: u32,
+    length: u32,
+    session: &Session,
+    state: &mut ConnectionState,
+) -> Option<String> {
+    log::debug!("Erasing {:#x} bytes of flash at {:#010x}", length, address);
+
+    // The flash loader erases all sectors which are written when the data is committed,
+    // so the erase itself is deferred until `vFlashDone`.
+    state
+        .flash_loader
+        .get_or_insert_with(|| session.target().flash_loader());
+    state.flash_erased.push((address, length));
+
+    reply_ok()
+}
+
+pub(crate) fn flash_write(
+    address: u32,
+    data: &[u8],
+    session: &Session,
+    state: &mut ConnectionState,
+) -> Option<String> {
+    let loader = state
+        .flash_loader
+        .get_or_insert_with(|| session.target().flash_loader());
+
+    match loader.add_data(address, data) {
+        Ok(()) => {
+            state.flash_written.push((address, data.len() as u32));
+            reply_ok()
+        }
+        Err(e @ FlashError::NoSuitableNvm { .. }) => {
+            log::warn!("Unable to write flash at {:#010x}: {}", address, e);
+            // Tell GDB that the memory at this address is not flash.
+            Some("E.memtype".to_string())
+        }
+        Err(e) => {
+            log::warn!("Unable to write flash at {:#010x}: {}", address, e);
+            Some("E14".to_string())
+        }
+    }
+}
+
+pub(crate) fn flash_done(
+    session: &mut Session,
+    state: &mut ConnectionState,
+    download_options: &DownloadOptions<'_>,
+) -> Option<String> {
+    let mut loader = match state.flash_loader.take() {
+        Some(loader) => loader,
+        None => return reply_ok(),
+    };
+
+    let erased = std::mem::take(&mut state.flash_erased);
+    let written = std::mem::take(&mut state.flash_written);
+
+    // The flash loader only erases the sectors it writes, so the parts which GDB erased
+    // without writing to them are written with the value of erased flash.
+    for (address, length) in unwritten_ranges(&erased, &written) {
+        let erased_byte_value = session
+            .target()
+            .flash_algorithms
+            .iter()
+            .find(|algorithm| algorithm.flash_properties.address_range.contains(&address))
+            .map_or(0xff, |algorithm| {
+                algorithm.flash_properties.erased_byte_value
+            });
+
+        if let Err(e) = loader.add_data(address, &vec![erased_byte_value; length as usize]) {
+            log::warn!("Unable to erase flash at {:#010x}: {}", address, e);
+            return Some("E14".to_string());
+        }
+    }
+
+    if let Err(e) = loader.commit(session, download_options.clone()) {
+        log::warn!("Unable to program flash: {}", e);
+        return Some("E14".to_string());
+    }
+
+    // The flash algorithm leaves the cores in an undefined state.
+    for (core_index, _) in session.list_cores() {
+        if let Err(e) = session
+            .core(core_index)
+            .and_then(|mut core| core.reset_and_halt(Duration::from_millis(500)))
+        {
+            log::warn!("Unable to reset core {} after flashing: {}", core_index, e);
+        }
+    }
+
+    reply_ok()
+}
+
+/// Returns the parts of the `erased` ranges which are not covered by the `written` ranges.
+///
+/// All ranges are given as start address and length.
+fn unwritten_ranges(erased: &[(u32, u32)], written: &[(u32, u32)]) -> Vec<(u32, u32)> {
+    // The end of a range at the end of the address space does not fit into an u32.
+    let mut written: Vec<(u64, u64)> = written
+        .iter()
+        .map(|&(address, length)| (address as u64, address as u64 + length as u64))
+        .collect();
+    written.sort_unstable();
+
+    let mut unwritten = Vec::new();
+
+    for &(address, length) in erased {
+        let mut start = address as u64;
+        let end = address as u64 + length as u64;
+
+        for &(written_start, written_end) in &written {
+            if written_start >= end {
+                break;
+            }
+            if written_start > start {
+                unwritten.push((start as u32, (written_start - start) as u32));
+            }
+            start = start.max(written_end);
+        }
+
+        if start < end {
+            unwritten.push((start as u32, (end - start) as u32));
+        }
+    }
+
+    unwritten
+}
+
 pub(crate) fn get_memory_map(session: &Session) -> Option<String> {
     let memory_map = session.target().gdb_memory_map();
 
     Some(String::from_utf8(gdb_sanitize_file(memory_map.as_bytes(), 0, 1000)).unwrap())
 }
 
-pub(crate) fn user_halt(mut core: Core, awaits_halt: &mut bool) -> Option<String> {
-    let _ = core.halt(Duration::from_millis(100));
-    *awaits_halt = false;
-    Some("T02".into())
+pub(crate) fn user_halt(session: &mut Session, state: &mut ConnectionState) -> Option<String> {
+    for &core_index in &state.running_cores {
+        if let Ok(mut core) = session.core(core_index) {
+            let _ = core.halt(Duration::from_millis(100));
+        }
+    }
+
+    if let Some(&core_index) = state.running_cores.first() {
+        state.general_core = core_index;
+    }
+    state.running_cores.clear();
+
+    Some(format!("T02thread:{:x};", thread_id(state.general_core)))
 }
 
-pub(crate) fn detach(break_due: &mut bool) -> Option<String> {
+pub(crate) fn detach(break_due: &mut bool, session: &mut Session) -> Option<String> {
+    // Software breakpoints replace instructions of the program, so they must not be left behind.
+    if let Err(e) = session.clear_all_sw_breakpoints() {
+        log::warn!("Unable to clear the software breakpoints: {}", e);
+    }
+
     *break_due = true;
     Some("OK".into())
 }
@@ -324,6 +822,16 @@ pub(crate) fn reset_halt(mut core: Core) -> Option<String> {
     Some("OK".into())
 }
 
+pub(crate) fn fault_info(mut core: Core) -> Option<String> {
+    let output = match core.fault_info() {
+        Ok(Some(fault)) => format!("{}\n", fault),
+        Ok(None) => "The core is not handling a fault\n".to_string(),
+        Err(e) => format!("Unable to read the fault state: {}\n", e),
+    };
+
+    Some(hex::encode(output.as_bytes()))
+}
+
 fn gdb_sanitize_file(data: &[u8], offset: u32, len: u32) -> Vec<u8> {
     let offset = offset as usize;
     let len = len as usize;
@@ -345,13 +853,47 @@ fn gdb_sanitize_file(data: &[u8], offset: u32, len: u32) -> Vec<u8> {
     }
 }
 
-pub(crate) fn read_target_description(session: &Session, annex: &[u8]) -> Option<String> {
+pub(crate) fn read_target_description(
+    session: &Session,
+    annex: &[u8],
+    core_index: usize,
+) -> Option<String> {
     // Only target.xml is supported
     if annex == b"target.xml" {
-        let description = session.target().target_description();
+        // The description is built for the selected thread, so the registers of
+        // cores of different types are described when their thread is selected.
+        let description = session.target().target_description(core_index);
 
         Some(String::from_utf8(gdb_sanitize_file(description.as_bytes(), 0, 1000)).unwrap())
     } else {
         None
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::unwritten_ranges;
+
+    #[test]
+    fn erased_ranges_without_writes() {
+        let erased = [(0x0800_0000, 0x1000), (0x0800_2000, 0x1000)];
+        let written = [
+            (0x0800_0000, 0x100),
+            (0x0800_0200, 0x100),
+            (0x0800_2000, 0x1000),
+        ];
+
+        assert_eq!(
+            unwritten_ranges(&erased, &written),
+            vec![(0x0800_0100, 0x100), (0x0800_0300, 0xd00)]
+        );
+    }
+
+    #[test]
+    fn erased_range_at_end_of_address_space() {
+        assert_eq!(
+            unwritten_ranges(&[(0xffff_f000, 0x1000)], &[(0xffff_f000, 0x800)]),
+            vec![(0xffff_f800, 0x800)]
+        );
+    }
+}
@@ -8,4 +8,4 @@ mod reader;
 mod worker;
 mod writer;
 
-pub use gdb_server_async::run;
+pub use gdb_server_async::{run, run_with_options};
@@ -10,7 +10,7 @@ use nom::{
     branch::alt,
     bytes::complete::{tag, take},
     character::complete::{char, hex_digit1},
-    combinator::value,
+    combinator::{map, value},
     number::complete::hex_u32,
     IResult,
 };
@@ -61,7 +61,10 @@ pub enum Packet {
         reg_values: String,
     },
     /// Packet `H`
-    SelectThread,
+    SelectThread {
+        operation: ThreadOperation,
+        thread_id: ThreadId,
+    },
     /// Packet `i`
     StepClockCycle,
     /// Packet `I`
@@ -97,7 +100,7 @@ pub enum Packet {
     // Packet 't'
     SearchBackwards,
     // Packet 'T'
-    ThreadInfo,
+    ThreadInfo(ThreadId),
     // Packet 'v'
     V(VPacket),
     // Packet 'X'
@@ -130,11 +133,33 @@ pub enum BreakpointType {
     AccessWatchpoint,
 }
 
+/// A thread ID, as used by the `H`, `T` and `vCont` packets.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ThreadId {
+    /// `-1`, which selects all threads.
+    All,
+    /// `0`, which selects an arbitrary thread.
+    Any,
+    /// A specific thread.
+    Id(u32),
+}
+
+/// The operations a thread can be selected for with the `H` packet.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ThreadOperation {
+    /// `Hc`, select the thread for step and continue operations.
+    Continue,
+    /// `Hg`, select the thread for all other operations.
+    General,
+}
+
 pub fn parse_packet(input: &[u8]) -> Result<Packet> {
     let parse_result = alt((
         extended_mode,
         detach,
         halt_reason,
+        select_thread,
+        thread_info,
         read_register,
         read_register_hex,
         read_memory,
@@ -173,6 +198,43 @@ fn detach(input: &[u8]) -> IResult<&[u8], Packet> {
     value(Packet::Detach, char('D'))(input)
 }
 
+fn thread_id(input: &[u8]) -> IResult<&[u8], ThreadId> {
+    alt((
+        value(ThreadId::All, tag("-1")),
+        map(hex_u32, |id| match id {
+            0 => ThreadId::Any,
+            id => ThreadId::Id(id),
+        }),
+    ))(input)
+}
+
+fn select_thread(input: &[u8]) -> IResult<&[u8], Packet> {
+    let (input, _) = char('H')(input)?;
+
+    let (input, operation) = alt((
+        value(ThreadOperation::Continue, char('c')),
+        value(ThreadOperation::General, char('g')),
+    ))(input)?;
+
+    let (input, thread_id) = thread_id(input)?;
+
+    Ok((
+        input,
+        Packet::SelectThread {
+            operation,
+            thread_id,
+        },
+    ))
+}
+
+fn thread_info(input: &[u8]) -> IResult<&[u8], Packet> {
+    let (input, _) = char('T')(input)?;
+
+    let (input, thread_id) = thread_id(input)?;
+
+    Ok((input, Packet::ThreadInfo(thread_id)))
+}
+
 fn read_register(input: &[u8]) -> IResult<&[u8], Packet> {
     let (input, _) = char('g')(input)?;
 
@@ -472,6 +534,39 @@ mod test {
         );
     }
 
+    #[test]
+    fn parse_select_thread() {
+        assert_eq!(
+            parse_packet(b"Hg2").unwrap(),
+            Packet::SelectThread {
+                operation: ThreadOperation::General,
+                thread_id: ThreadId::Id(2),
+            }
+        );
+        assert_eq!(
+            parse_packet(b"Hc-1").unwrap(),
+            Packet::SelectThread {
+                operation: ThreadOperation::Continue,
+                thread_id: ThreadId::All,
+            }
+        );
+        assert_eq!(
+            parse_packet(b"Hg0").unwrap(),
+            Packet::SelectThread {
+                operation: ThreadOperation::General,
+                thread_id: ThreadId::Any,
+            }
+        );
+    }
+
+    #[test]
+    fn parse_thread_info() {
+        assert_eq!(
+            parse_packet(b"T1").unwrap(),
+            Packet::ThreadInfo(ThreadId::Id(1))
+        );
+    }
+
     #[test]
     fn parse_query_crc_packet() {
         assert_eq!(
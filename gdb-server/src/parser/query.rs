@@ -1,9 +1,10 @@
 use super::util::hex_bytes;
+use super::{thread_id, ThreadId};
 use nom::{
     branch::alt,
     bytes::complete::{tag, take_until, take_while},
     character::complete::char,
-    combinator::{all_consuming, opt, peek},
+    combinator::{all_consuming, opt, peek, value},
     error::ErrorKind,
     multi::separated_list1,
     number::complete::hex_u32,
@@ -14,6 +15,12 @@ use nom::{
 #[derive(Debug, PartialEq, Clone)]
 pub enum QueryPacket {
     ThreadId,
+    /// qfThreadInfo command
+    FirstThreadInfo,
+    /// qsThreadInfo command
+    SubsequentThreadInfo,
+    /// qThreadExtraInfo command
+    ThreadExtraInfo(ThreadId),
     Attached(Option<Pid>),
     Command(Vec<u8>),
     Supported(Vec<String>),
@@ -53,6 +60,8 @@ pub fn pid(input: &[u8]) -> IResult<&[u8], Pid> {
 pub fn query_packet(input: &[u8]) -> IResult<&[u8], QueryPacket> {
     let (input, query_packet) = alt((
         query_thread_id,
+        query_thread_info,
+        query_thread_extra_info,
         query_attached,
         query_command,
         query_crc,
@@ -73,6 +82,21 @@ fn query_thread_id(input: &[u8]) -> IResult<&[u8], QueryPacket> {
     Ok((input, QueryPacket::ThreadId))
 }
 
+fn query_thread_info(input: &[u8]) -> IResult<&[u8], QueryPacket> {
+    alt((
+        value(QueryPacket::FirstThreadInfo, tag("fThreadInfo")),
+        value(QueryPacket::SubsequentThreadInfo, tag("sThreadInfo")),
+    ))(input)
+}
+
+fn query_thread_extra_info(input: &[u8]) -> IResult<&[u8], QueryPacket> {
+    let (input, _) = tag("ThreadExtraInfo,")(input)?;
+
+    let (input, thread_id) = thread_id(input)?;
+
+    Ok((input, QueryPacket::ThreadExtraInfo(thread_id)))
+}
+
 fn query_command(input: &[u8]) -> IResult<&[u8], QueryPacket> {
     let (input, _) = tag("Rcmd,")(input)?;
 
@@ -223,6 +247,26 @@ mod test {
         );
     }
 
+    #[test]
+    fn parse_thread_info() {
+        assert_eq!(
+            query_packet(b"fThreadInfo").unwrap(),
+            (EMPTY, QueryPacket::FirstThreadInfo)
+        );
+        assert_eq!(
+            query_packet(b"sThreadInfo").unwrap(),
+            (EMPTY, QueryPacket::SubsequentThreadInfo)
+        );
+    }
+
+    #[test]
+    fn parse_thread_extra_info() {
+        assert_eq!(
+            query_packet(b"ThreadExtraInfo,2").unwrap(),
+            (EMPTY, QueryPacket::ThreadExtraInfo(ThreadId::Id(2)))
+        );
+    }
+
     #[test]
     fn parse_query_supported_example() {
         // Note: Initial q of packet removed
@@ -1,14 +1,24 @@
-use super::{query::pid, Pid};
+use super::{query::pid, thread_id, Pid, ThreadId};
 use nom::{
-    branch::alt, bytes::complete::tag, character::complete::char, combinator::value, IResult,
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::char,
+    combinator::{map, opt, rest, value},
+    multi::many1,
+    number::complete::hex_u32,
+    sequence::preceded,
+    IResult,
 };
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum VPacket {
     Attach(Pid),
-    Continue(Action),
+    Continue(Vec<ThreadAction>),
     Unknown(Vec<u8>),
     QueryContSupport,
+    FlashErase { address: u32, length: u32 },
+    FlashWrite { address: u32, data: Vec<u8> },
+    FlashDone,
 }
 
 #[allow(dead_code)]
@@ -17,13 +27,29 @@ pub enum Action {
     Continue,
     ContinueSignal(u8),
     Step,
-    StepSignal,
+    StepSignal(u8),
     Stop,
     RangeStep { start: u32, end: u32 },
 }
 
+/// An action of a `vCont` packet, and the thread it applies to.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ThreadAction {
+    pub action: Action,
+    /// If no thread is given, the action applies to all threads
+    /// which don't have an action of their own.
+    pub thread_id: Option<ThreadId>,
+}
+
 pub fn v_packet(input: &[u8]) -> IResult<&[u8], VPacket> {
-    let parse_result = alt((v_attach, v_cont_support, v_cont))(input);
+    let parse_result = alt((
+        v_attach,
+        v_cont_support,
+        v_cont,
+        v_flash_erase,
+        v_flash_write,
+        v_flash_done,
+    ))(input);
 
     match parse_result {
         Ok((input, packet)) => Ok((input, packet)),
@@ -51,21 +77,87 @@ fn v_cont_support(input: &[u8]) -> IResult<&[u8], VPacket> {
 }
 
 fn v_cont(input: &[u8]) -> IResult<&[u8], VPacket> {
-    let (input, _) = tag("Cont;")(input)?;
+    let (input, _) = tag("Cont")(input)?;
+
+    let (input, actions) = many1(preceded(char(';'), v_cont_thread_action))(input)?;
 
+    Ok((input, VPacket::Continue(actions)))
+}
+
+fn v_cont_thread_action(input: &[u8]) -> IResult<&[u8], ThreadAction> {
     let (input, action) = v_cont_action(input)?;
 
-    Ok((input, VPacket::Continue(action)))
+    let (input, thread_id) = opt(preceded(char(':'), thread_id))(input)?;
+
+    Ok((input, ThreadAction { action, thread_id }))
 }
 
 fn v_cont_action(input: &[u8]) -> IResult<&[u8], Action> {
     alt((
         value(Action::Continue, char('c')),
+        map(preceded(char('C'), signal), Action::ContinueSignal),
         value(Action::Step, char('s')),
+        map(preceded(char('S'), signal), Action::StepSignal),
         value(Action::Stop, char('t')),
     ))(input)
 }
 
+fn signal(input: &[u8]) -> IResult<&[u8], u8> {
+    map(hex_u32, |signal| signal as u8)(input)
+}
+
+fn v_flash_erase(input: &[u8]) -> IResult<&[u8], VPacket> {
+    let (input, _) = tag("FlashErase:")(input)?;
+
+    let (input, address) = hex_u32(input)?;
+    let (input, _) = char(',')(input)?;
+    let (input, length) = hex_u32(input)?;
+
+    Ok((input, VPacket::FlashErase { address, length }))
+}
+
+fn v_flash_write(input: &[u8]) -> IResult<&[u8], VPacket> {
+    let (input, _) = tag("FlashWrite:")(input)?;
+
+    let (input, address) = hex_u32(input)?;
+    let (input, _) = char(':')(input)?;
+
+    // The binary data extends to the end of the packet.
+    let (input, data) = rest(input)?;
+
+    Ok((
+        input,
+        VPacket::FlashWrite {
+            address,
+            data: unescape(data),
+        },
+    ))
+}
+
+/// Removes the escaping from binary data.
+///
+/// The bytes `#`, `$`, `}` and `*` are sent as `}` followed by the original byte XORed with 0x20.
+fn unescape(data: &[u8]) -> Vec<u8> {
+    let mut unescaped = Vec::with_capacity(data.len());
+    let mut bytes = data.iter();
+
+    while let Some(&byte) = bytes.next() {
+        if byte == b'}' {
+            if let Some(&escaped) = bytes.next() {
+                unescaped.push(escaped ^ 0x20);
+            }
+        } else {
+            unescaped.push(byte);
+        }
+    }
+
+    unescaped
+}
+
+fn v_flash_done(input: &[u8]) -> IResult<&[u8], VPacket> {
+    value(VPacket::FlashDone, tag("FlashDone"))(input)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -89,7 +181,13 @@ mod test {
     fn parse_v_cont_cont() {
         assert_eq!(
             v_packet(b"Cont;c").unwrap(),
-            (EMPTY, VPacket::Continue(Action::Continue))
+            (
+                EMPTY,
+                VPacket::Continue(vec![ThreadAction {
+                    action: Action::Continue,
+                    thread_id: None
+                }])
+            )
         );
     }
 
@@ -97,7 +195,13 @@ mod test {
     fn parse_v_cont_step() {
         assert_eq!(
             v_packet(b"Cont;s").unwrap(),
-            (EMPTY, VPacket::Continue(Action::Step))
+            (
+                EMPTY,
+                VPacket::Continue(vec![ThreadAction {
+                    action: Action::Step,
+                    thread_id: None
+                }])
+            )
         );
     }
 
@@ -105,7 +209,84 @@ mod test {
     fn parse_v_cont_stop() {
         assert_eq!(
             v_packet(b"Cont;t").unwrap(),
-            (EMPTY, VPacket::Continue(Action::Stop))
+            (
+                EMPTY,
+                VPacket::Continue(vec![ThreadAction {
+                    action: Action::Stop,
+                    thread_id: None
+                }])
+            )
+        );
+    }
+
+    #[test]
+    fn parse_v_cont_per_thread() {
+        assert_eq!(
+            v_packet(b"Cont;s:2;C05:1;c").unwrap(),
+            (
+                EMPTY,
+                VPacket::Continue(vec![
+                    ThreadAction {
+                        action: Action::Step,
+                        thread_id: Some(ThreadId::Id(2))
+                    },
+                    ThreadAction {
+                        action: Action::ContinueSignal(5),
+                        thread_id: Some(ThreadId::Id(1))
+                    },
+                    ThreadAction {
+                        action: Action::Continue,
+                        thread_id: None
+                    },
+                ])
+            )
+        );
+    }
+
+    #[test]
+    fn parse_v_flash_erase() {
+        assert_eq!(
+            v_packet(b"FlashErase:8000000,4000").unwrap(),
+            (
+                EMPTY,
+                VPacket::FlashErase {
+                    address: 0x800_0000,
+                    length: 0x4000
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn parse_v_flash_write() {
+        assert_eq!(
+            v_packet(b"FlashWrite:8000000:\x01\x02:3").unwrap(),
+            (
+                EMPTY,
+                VPacket::FlashWrite {
+                    address: 0x800_0000,
+                    data: b"\x01\x02:3".to_vec()
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn parse_v_flash_write_escaped() {
+        assert_eq!(
+            v_packet(b"FlashWrite:8000000:\x01}\x03}\x04}]}\x0a\x02").unwrap(),
+            (
+                EMPTY,
+                VPacket::FlashWrite {
+                    address: 0x800_0000,
+                    data: vec![0x01, 0x23, 0x24, 0x7d, 0x2a, 0x02]
+                }
+            )
         );
     }
+
+    #[test]
+    fn parse_v_flash_done() {
+        assert_eq!(v_packet(b"FlashDone").unwrap(), (EMPTY, VPacket::FlashDone));
+    }
 }
@@ -4,7 +4,11 @@ use futures::channel::mpsc;
 use futures::future::FutureExt;
 use futures::select;
 use gdb_protocol::packet::{CheckedPacket, Kind as PacketKind};
-use probe_rs::Session;
+use probe_rs::{
+    flashing::{DownloadOptions, FlashLoader},
+    semihosting::{SemihostingEvent, SemihostingHost, SemihostingOptions},
+    Session, WatchpointKind,
+};
 use std::convert::TryFrom;
 use std::{sync::Mutex, time::Duration};
 
@@ -16,34 +20,82 @@ type ServerResult<T> = std::result::Result<T, Box<dyn std::error::Error + Send +
 type Sender<T> = mpsc::UnboundedSender<T>;
 type Receiver<T> = mpsc::UnboundedReceiver<T>;
 
+/// The state of a GDB connection, which is kept between packets.
+///
+/// Every core of the session is presented to GDB as a thread.
+/// The thread ID of a core is its index plus one, because GDB reserves thread ID 0.
+#[derive(Default)]
+pub(crate) struct ConnectionState {
+    /// The cores which have been resumed, and are awaited to halt.
+    pub(crate) running_cores: Vec<usize>,
+    /// The core selected for register and memory accesses with the `Hg` packet.
+    pub(crate) general_core: usize,
+    /// The core selected for the `c` packet with the `Hc` packet,
+    /// or `None` if all cores are selected.
+    pub(crate) continue_core: Option<usize>,
+    /// The data received with `vFlashWrite` packets, which is programmed on `vFlashDone`.
+    pub(crate) flash_loader: Option<FlashLoader>,
+    /// The ranges requested with `vFlashErase` packets, as start address and length.
+    /// They are erased on `vFlashDone`, even if no data is written to them.
+    pub(crate) flash_erased: Vec<(u32, u32)>,
+    /// The ranges written with `vFlashWrite` packets, as start address and length.
+    pub(crate) flash_written: Vec<(u32, u32)>,
+    /// Services the semihosting requests of all cores.
+    pub(crate) semihosting: SemihostingHost,
+}
+
+impl ConnectionState {
+    /// Whether any core has been resumed, and is awaited to halt.
+    pub(crate) fn awaits_halt(&self) -> bool {
+        !self.running_cores.is_empty()
+    }
+}
+
 pub async fn worker(
     mut input_stream: Receiver<CheckedPacket>,
     output_stream: Sender<CheckedPacket>,
     session: &Mutex<Session>,
+    download_options: &DownloadOptions<'_>,
+    semihosting_options: &SemihostingOptions,
 ) -> ServerResult<()> {
-    // When we first attach to the core, GDB expects us to halt the core, so we do this here when a new client connects.
-    // If the core is already halted, nothing happens if we issue a halt command again, so we always do this no matter of core state.
-    session
-        .lock()
-        .unwrap()
-        .core(0)?
-        .halt(Duration::from_millis(100))?;
+    // When we first attach to the cores, GDB expects us to halt them, so we do this here when a new client connects.
+    // If a core is already halted, nothing happens if we issue a halt command again, so we always do this no matter of core state.
+    {
+        let mut session = session.lock().unwrap();
 
-    let mut awaits_halt = false;
+        // GDB reads the target description of the thread which is selected when it connects.
+        let cores = session.list_cores();
+        if let Some((_, first_type)) = cores.first() {
+            if cores.iter().any(|(_, core_type)| core_type != first_type) {
+                log::info!(
+                    "The cores have different types, the target description is built for the selected thread"
+                );
+            }
+        }
+
+        for (core_index, _) in cores {
+            session.core(core_index)?.halt(Duration::from_millis(100))?;
+        }
+    }
+
+    let mut state = ConnectionState {
+        semihosting: SemihostingHost::new(semihosting_options.clone()),
+        ..Default::default()
+    };
 
     loop {
         select! {
             potential_packet = input_stream.next().fuse() => {
                 if let Some(packet) = potential_packet {
                     log::warn!("WORKING {}", String::from_utf8_lossy(&packet.data));
-                    if handler(session, &output_stream, &mut awaits_halt, packet).await? {
+                    if handler(session, &output_stream, &mut state, download_options, packet).await? {
                         break;
                     }
                 } else {
                     break
                 }
             },
-            _ = await_halt(session, &output_stream, &mut awaits_halt).fuse() => {}
+            _ = await_halt(session, &output_stream, &mut state).fuse() => {}
         }
     }
     Ok(())
@@ -52,13 +104,13 @@ pub async fn worker(
 pub async fn handler(
     session: &Mutex<Session>,
     output_stream: &Sender<CheckedPacket>,
-    awaits_halt: &mut bool,
+    state: &mut ConnectionState,
+    download_options: &DownloadOptions<'_>,
     packet: CheckedPacket,
 ) -> ServerResult<bool> {
     let parsed_packet = parse_packet(&packet.data);
     let mut break_due = false;
 
-    use crate::parser::v_packet::Action;
     use crate::parser::BreakpointType;
     use crate::parser::Packet::*;
     use crate::parser::QueryPacket;
@@ -69,60 +121,100 @@ pub async fn handler(
             log::debug!("Parsed packet: {:?}", parsed_packet);
             let mut session = session.lock().expect("Poisoned Mutex");
             match parsed_packet {
-                HaltReason => handlers::halt_reason(),
-                Continue => handlers::run(session.core(0)?, awaits_halt),
+                HaltReason => handlers::halt_reason(state),
+                Continue => handlers::run(&mut session, state),
+                SelectThread {
+                    operation,
+                    thread_id,
+                } => handlers::select_thread(operation, thread_id, &session, state),
+                ThreadInfo(thread_id) => handlers::thread_alive(thread_id, &session),
+                Query(QueryPacket::ThreadId) => handlers::current_thread(state),
+                Query(QueryPacket::FirstThreadInfo) => handlers::first_thread_info(&session),
+                Query(QueryPacket::SubsequentThreadInfo) => handlers::subsequent_thread_info(),
+                Query(QueryPacket::ThreadExtraInfo(thread_id)) => {
+                    handlers::thread_extra_info(thread_id, &session)
+                }
                 V(VPacket::QueryContSupport) => handlers::vcont_supported(),
                 Query(QueryPacket::Supported { .. }) => handlers::q_supported(),
                 Query(QueryPacket::Attached { .. }) => handlers::q_attached(),
                 Query(QueryPacket::Command(cmd)) => {
                     if cmd == b"reset" {
-                        handlers::reset_halt(session.core(0)?)
+                        handlers::reset_halt(session.core(state.general_core)?)
+                    } else if cmd == b"fault" {
+                        handlers::fault_info(session.core(state.general_core)?)
                     } else {
                         log::debug!("Unknown monitor command: '{:?}'", cmd);
                         Some(hex::encode(
-                            "Unknown monitor command\nOnly 'reset' is currently supported\n"
+                            "Unknown monitor command\nOnly 'reset' and 'fault' are currently supported\n"
                                 .as_bytes(),
                         ))
                     }
                 }
                 Query(QueryPacket::HostInfo) => handlers::host_info(),
-                ReadGeneralRegister => handlers::read_general_registers(session.core(0)?),
-                ReadRegisterHex(register) => handlers::read_register(register, session.core(0)?),
-                WriteGeneralRegister { reg_values } => {
-                    handlers::write_general_registers(&reg_values, session.core(0)?)
+                ReadGeneralRegister => {
+                    handlers::read_general_registers(session.core(state.general_core)?)
+                }
+                ReadRegisterHex(register) => {
+                    handlers::read_register(register, session.core(state.general_core)?)
                 }
+                WriteGeneralRegister { reg_values } => handlers::write_general_registers(
+                    &reg_values,
+                    session.core(state.general_core)?,
+                ),
                 WriteRegisterHex { address, value } => {
-                    handlers::write_register(address, &value, session.core(0)?)
+                    handlers::write_register(address, &value, session.core(state.general_core)?)
                 }
                 ReadMemory { address, length } => {
                     // LLDB will send 64 bit addresses, which are not supported by probe-rs
                     // yet.
 
                     if let Ok(address) = u32::try_from(address) {
-                        handlers::read_memory(address, length, session.core(0)?)
+                        handlers::read_memory(address, length, session.core(state.general_core)?)
                     } else {
                         //
                         handlers::reply_empty()
                     }
                 }
-                Detach => handlers::detach(&mut break_due),
-                V(VPacket::Continue(action)) => match action {
-                    Action::Continue => handlers::run(session.core(0)?, awaits_halt),
-                    Action::Stop => handlers::stop(session.core(0)?, awaits_halt),
-                    Action::Step => handlers::step(session.core(0)?, awaits_halt),
-                    other => {
-                        log::warn!("vCont with action {:?} not supported", other);
-                        handlers::reply_empty()
-                    }
-                },
+                Detach => handlers::detach(&mut break_due, &mut session),
+                V(VPacket::Continue(actions)) => handlers::v_cont(&actions, &mut session, state),
+                V(VPacket::FlashErase { address, length }) => {
+                    handlers::flash_erase(address, length, &session, state)
+                }
+                V(VPacket::FlashWrite { address, data }) => {
+                    handlers::flash_write(address, &data, &session, state)
+                }
+                V(VPacket::FlashDone) => {
+                    handlers::flash_done(&mut session, state, download_options)
+                }
                 InsertBreakpoint {
                     breakpoint_type,
                     address,
                     kind,
                 } => match breakpoint_type {
+                    BreakpointType::Software => {
+                        handlers::insert_software_break(address, kind, &mut session)
+                    }
                     BreakpointType::Hardware => {
-                        handlers::insert_hardware_break(address, kind, session.core(0)?)
+                        handlers::insert_hardware_break(address, kind, &mut session)
                     }
+                    BreakpointType::WriteWatchpoint => handlers::insert_watchpoint(
+                        address,
+                        kind,
+                        WatchpointKind::Write,
+                        &mut session,
+                    ),
+                    BreakpointType::ReadWatchpoint => handlers::insert_watchpoint(
+                        address,
+                        kind,
+                        WatchpointKind::Read,
+                        &mut session,
+                    ),
+                    BreakpointType::AccessWatchpoint => handlers::insert_watchpoint(
+                        address,
+                        kind,
+                        WatchpointKind::Access,
+                        &mut session,
+                    ),
                     other => {
                         log::warn!("Breakpoint type {:?} is not supported.", other);
                         handlers::reply_empty()
@@ -133,8 +225,20 @@ pub async fn handler(
                     address,
                     kind,
                 } => match breakpoint_type {
+                    BreakpointType::Software => {
+                        handlers::remove_software_break(address, kind, &mut session)
+                    }
                     BreakpointType::Hardware => {
-                        handlers::remove_hardware_break(address, kind, session.core(0)?)
+                        handlers::remove_hardware_break(address, kind, &mut session)
+                    }
+                    BreakpointType::WriteWatchpoint => {
+                        handlers::remove_watchpoint(address, WatchpointKind::Write, &mut session)
+                    }
+                    BreakpointType::ReadWatchpoint => {
+                        handlers::remove_watchpoint(address, WatchpointKind::Read, &mut session)
+                    }
+                    BreakpointType::AccessWatchpoint => {
+                        handlers::remove_watchpoint(address, WatchpointKind::Access, &mut session)
                     }
                     other => {
                         log::warn!("Breakpoint type {:?} is not supported.", other);
@@ -142,7 +246,7 @@ pub async fn handler(
                     }
                 },
                 WriteMemoryBinary { address, data } => {
-                    handlers::write_memory(address, &data, session.core(0)?)
+                    handlers::write_memory(address, &data, session.core(state.general_core)?)
                 }
                 Query(QueryPacket::Transfer { object, operation }) => {
                     use crate::parser::query::TransferOperation;
@@ -162,7 +266,11 @@ pub async fn handler(
                         b"features" => {
                             match operation {
                                 TransferOperation::Read { annex, .. } => {
-                                    handlers::read_target_description(&session, &annex)
+                                    handlers::read_target_description(
+                                        &session,
+                                        &annex,
+                                        state.general_core,
+                                    )
                                 }
                                 TransferOperation::Write { .. } => {
                                     // not supported
@@ -176,7 +284,7 @@ pub async fn handler(
                         }
                     }
                 }
-                Interrupt => handlers::user_halt(session.core(0)?, awaits_halt),
+                Interrupt => handlers::user_halt(&mut session, state),
                 other => {
                     log::warn!("Unknown command: '{:?}'", other);
 
@@ -214,17 +322,57 @@ pub async fn handler(
 pub async fn await_halt(
     session: &Mutex<Session>,
     output_stream: &Sender<CheckedPacket>,
-    await_halt: &mut bool,
+    state: &mut ConnectionState,
 ) -> ServerResult<()> {
     task::sleep(Duration::from_millis(10)).await;
-    if *await_halt {
+    if state.awaits_halt() {
         let mut session = session.lock().expect("Poisoned Mutex");
-        if session.core(0)?.core_halted().unwrap() {
-            let response = CheckedPacket::from_data(PacketKind::Packet, b"T05hwbreak:;".to_vec());
+
+        let mut halted_core = None;
+        let mut exit_code = None;
+        for &core_index in &state.running_cores {
+            let mut core = session.core(core_index)?;
+            if !core.core_halted()? {
+                continue;
+            }
+
+            // Semihosting requests are serviced without involving GDB, and the core is resumed.
+            match state.semihosting.handle(&mut core)? {
+                Some(SemihostingEvent::Stdout(data)) | Some(SemihostingEvent::Stderr(data)) => {
+                    send_console_output(output_stream, &data);
+                }
+                Some(SemihostingEvent::Handled) => {}
+                Some(SemihostingEvent::Exit(code)) => {
+                    exit_code = Some(code);
+                    halted_core = Some(core_index);
+                    break;
+                }
+                None => {
+                    halted_core = Some(core_index);
+                    break;
+                }
+            }
+        }
+
+        if let Some(halted_core) = halted_core {
+            // GDB runs in all-stop mode, so all other cores have to be stopped as well.
+            for &core_index in &state.running_cores {
+                if core_index != halted_core {
+                    session.core(core_index)?.halt(Duration::from_millis(100))?;
+                }
+            }
+
+            let stop_reply = match exit_code {
+                // The exit status is reported to GDB as the exit status of the process.
+                Some(code) => format!("W{:02x}", code as u8),
+                None => handlers::stop_reply(session.core(halted_core)?, halted_core),
+            };
+            let response = CheckedPacket::from_data(PacketKind::Packet, stop_reply.into_bytes());
 
             let mut bytes = Vec::new();
             response.encode(&mut bytes).unwrap();
-            *await_halt = false;
+            state.running_cores.clear();
+            state.general_core = halted_core;
 
             let _ = output_stream.unbounded_send(response);
         }
@@ -232,3 +380,11 @@ pub async fn await_halt(
 
     Ok(())
 }
+
+/// Sends `data` to GDB with an `O` packet, which GDB prints on its console.
+fn send_console_output(output_stream: &Sender<CheckedPacket>, data: &[u8]) {
+    let packet = format!("O{}", hex::encode(data));
+    let response = CheckedPacket::from_data(PacketKind::Packet, packet.into_bytes());
+
+    let _ = output_stream.unbounded_send(response);
+}
@@ -1,11 +1,15 @@
+pub mod sink;
+
 use crate::*;
 use anyhow::{anyhow, Result};
 use chrono::Local;
 use num_traits::Zero;
 use probe_rs::config::MemoryRegion;
 use probe_rs::Core;
-use probe_rs_rtt::{DownChannel, Rtt, ScanRegion, UpChannel};
+pub use probe_rs_rtt::ChannelSnapshot;
+use probe_rs_rtt::{ControlBlock, DownChannel, Rtt, ScanRegion, UpChannel};
 use serde::Deserialize;
+use sink::{RttSink, RttSinkConfig};
 use std::collections::HashMap;
 use std::fs::File;
 use std::{
@@ -14,6 +18,7 @@ use std::{
     fs,
     io::{Read, Seek},
     str::FromStr,
+    time::Duration,
 };
 
 pub fn attach_to_rtt(
@@ -92,6 +97,22 @@ pub struct RttConfig {
     pub channels: Vec<RttChannelConfig>,
 }
 
+impl RttConfig {
+    /// Reads the configuration from a TOML file, or from a JSON file with the same structure as in the debugger configuration.
+    /// The format is selected by the extension of the file.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .map_err(|err| anyhow!("Failed to read '{}': {}", path.display(), err))?;
+
+        let config = match path.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => toml::from_str(&contents)?,
+            _ => serde_json::from_str(&contents)?,
+        };
+
+        Ok(config)
+    }
+}
+
 /// The User specified configuration for each active RTT Channel. The configuration is passed via a DAP Client configuration (`launch.json`). If no configuration is specified, the defaults will be `Dataformat::String` and `show_timestamps=false`.
 #[derive(clap::Parser, Debug, Clone, serde::Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
@@ -108,6 +129,10 @@ pub struct RttChannelConfig {
     #[serde(default = "default_include_location")]
     // Control the inclusion of source location information for DataFormat::Defmt.
     pub show_location: bool,
+    /// Files and sockets the data of the channel is written to, in addition to the display.
+    #[structopt(skip)]
+    #[serde(default)]
+    pub sinks: Vec<RttSinkConfig>,
 }
 
 /// This is the primary interface through which RTT channel data is read and written. Every actual RTT channel has a configuration and buffer that is used for this purpose.
@@ -117,11 +142,13 @@ pub struct RttActiveChannel {
     pub down_channel: Option<DownChannel>,
     pub channel_name: String,
     pub data_format: DataFormat,
-    /// Data that will be written to the down_channel (host to target)
-    _input_data: String,
+    /// Data that will be written to the down_channel (host to target), but did not fit into its buffer yet.
+    input_data: Vec<u8>,
     rtt_buffer: RttBuffer,
     show_timestamps: bool,
     show_location: bool,
+    /// The sinks the data of the `up_channel` is written to, in addition to the display.
+    sinks: Vec<RttSink>,
 }
 
 /// A fully configured RttActiveChannel. The configuration will always try to 'default' based on information read from the RTT control block in the binary. Where insufficient information is available, it will use the supplied configuration, with final hardcoded defaults where no other information was available.
@@ -171,15 +198,32 @@ impl RttActiveChannel {
         } else {
             (full_config.data_format, false)
         };
+        let sinks = full_config
+            .sinks
+            .iter()
+            .filter_map(|sink_config| match RttSink::open(sink_config) {
+                Ok(sink) => Some(sink),
+                Err(err) => {
+                    log::error!(
+                        "Failed to open the sink {:?} of RTT channel '{}': {}",
+                        sink_config,
+                        name,
+                        err
+                    );
+                    None
+                }
+            })
+            .collect();
         Self {
             up_channel,
             down_channel,
             channel_name: name,
             data_format,
-            _input_data: String::new(),
+            input_data: Vec::new(),
             rtt_buffer: RttBuffer::new(buffer_size),
             show_timestamps: full_config.show_timestamps,
             show_location,
+            sinks,
         }
     }
 
@@ -188,116 +232,246 @@ impl RttActiveChannel {
         self.up_channel.as_ref().map(|uc| uc.number())
     }
 
+    /// Returns the number of the `DownChannel`.
+    pub fn down_channel_number(&self) -> Option<usize> {
+        self.down_channel.as_ref().map(|dc| dc.number())
+    }
+
     /// Polls the RTT target for new data on the channel represented by `self`.
     /// Processes all the new data into the channel internal buffer and returns the number of bytes that was read.
     pub fn poll_rtt(&mut self, core: &mut Core) -> Option<usize> {
-        if let Some(channel) = self.up_channel.as_mut() {
-            // Retry loop, in case the probe is temporarily unavailable, e.g. user pressed the `reset` button.
-            for _loop_count in 0..10 {
-                match channel.read(core, self.rtt_buffer.0.as_mut()) {
-                    Ok(count) => {
-                        if count.is_zero() {
-                            return None;
-                        } else {
-                            return Some(count);
-                        }
-                    }
-                    Err(err) => {
-                        if matches!(err, probe_rs_rtt::Error::Probe(_)) {
-                            std::thread::sleep(std::time::Duration::from_millis(50));
-                        } else {
-                            log::error!("\nError reading from RTT: {}", err);
-                            return None;
-                        }
-                    }
-                }
+        self.read_up_channel(core, None)
+    }
+
+    /// Reads new data of the channel like [`RttActiveChannel::poll_rtt`],
+    /// with the state of the channel taken from a snapshot of the control block.
+    pub fn poll_rtt_snapshot(
+        &mut self,
+        core: &mut Core,
+        snapshot: &ChannelSnapshot,
+    ) -> Option<usize> {
+        self.read_up_channel(core, Some(snapshot))
+    }
+
+    fn read_up_channel(
+        &mut self,
+        core: &mut Core,
+        snapshot: Option<&ChannelSnapshot>,
+    ) -> Option<usize> {
+        let channel = self.up_channel.as_ref()?;
+        let buffer = self.rtt_buffer.0.as_mut();
+
+        let result = retry_on_probe_error(|| match snapshot {
+            Some(snapshot) => channel.read_snapshot(core, snapshot, buffer),
+            None => channel.read(core, buffer),
+        });
+
+        match result {
+            Ok(count) if count.is_zero() => None,
+            Ok(count) => Some(count),
+            Err(err) => {
+                log::error!("\nError reading from RTT: {}", err);
+                None
             }
         }
-        None
     }
 
     /// Retrieves available data from the channel and if available, returns `Some(channel_number:String, formatted_data:String)`.
+    /// The data is also written to the sinks of the channel.
     pub fn get_rtt_data(
         &mut self,
         core: &mut Core,
         defmt_state: Option<&(defmt_decoder::Table, Option<defmt_decoder::Locations>)>,
     ) -> Option<(String, String)> {
-        self
-            .poll_rtt(core)
-            .map(|bytes_read| {
-                (
-                    self.number().unwrap_or(0).to_string(), // If the Channel doesn't have a number, then send the output to channel 0
-                    {
-                        let mut formatted_data = String::new();
-                        match self.data_format {
-                            DataFormat::String => {
-                                let incoming = String::from_utf8_lossy(&self.rtt_buffer.0[..bytes_read]).to_string();
-                                for (_i, line) in incoming.split_terminator('\n').enumerate() {
-                                    if self.show_timestamps {
-                                        write!(formatted_data, "{} :", Local::now())
-                                            .map_or_else(|err| log::error!("Failed to format RTT data - {:?}", err), |r|r);
-                                    }
-                                    writeln!(formatted_data, "{}", line).map_or_else(|err| log::error!("Failed to format RTT data - {:?}", err), |r|r);
-                                }
-                            }
-                            DataFormat::BinaryLE => {
-                                for element in &self.rtt_buffer.0[..bytes_read] {
-                                    // Width of 4 allows 0xFF to be printed.
-                                    write!(formatted_data, "{:#04x}", element).map_or_else(|err| log::error!("Failed to format RTT data - {:?}", err), |r|r);
-                                }
-                            }
-                            DataFormat::Defmt => {
-                                match defmt_state {
-                                    Some((table, locs)) => {
-
-                                        let mut stream_decoder = table.new_stream_decoder();
-                                        stream_decoder.received(&self.rtt_buffer.0[..bytes_read]);
-                                        while let Ok(frame) = stream_decoder.decode()
-                                        {
-                                            // NOTE(`[]` indexing) all indices in `table` have already been
-                                            // verified to exist in the `locs` map.
-                                            let loc = locs.as_ref().map(|locs| &locs[&frame.index()]);
-                                            writeln!(formatted_data, "{}", frame.display(false)).map_or_else(|err| log::error!("Failed to format RTT data - {:?}", err), |r|r);
-                                            if self.show_location {
-                                                if let Some(loc) = loc {
-                                                    let relpath = if let Ok(relpath) =
-                                                        loc.file.strip_prefix(&std::env::current_dir().unwrap())
-                                                    {
-                                                        relpath
-                                                    } else {
-                                                        // not relative; use full path
-                                                        &loc.file
-                                                    };
-                                                    writeln!(formatted_data,
-                                                        "└─ {}:{}",
-                                                        relpath.display(),
-                                                        loc.line
-                                                    ).map_or_else(|err| log::error!("Failed to format RTT data - {:?}", err), |r|r);
-                                                }
-                                            }
-                                        }
-                                    }
-                                    None => {
-                                        write!(formatted_data, "Running rtt in defmt mode but table or locations could not be loaded.")
-                                            .map_or_else(|err| log::error!("Failed to format RTT data - {:?}", err), |r|r);
-                                    }
+        let bytes_read = self.poll_rtt(core)?;
+        Some(self.process_rtt_data(bytes_read, defmt_state))
+    }
+
+    /// Retrieves available data from the channel like [`RttActiveChannel::get_rtt_data`],
+    /// with the state of the channel taken from a snapshot of the control block.
+    pub fn get_rtt_data_snapshot(
+        &mut self,
+        core: &mut Core,
+        snapshot: &ChannelSnapshot,
+        defmt_state: Option<&(defmt_decoder::Table, Option<defmt_decoder::Locations>)>,
+    ) -> Option<(String, String)> {
+        let bytes_read = self.poll_rtt_snapshot(core, snapshot)?;
+        Some(self.process_rtt_data(bytes_read, defmt_state))
+    }
+
+    /// Decodes the data read into the channel buffer, writes it to the sinks of the channel
+    /// and formats it for display.
+    fn process_rtt_data(
+        &mut self,
+        bytes_read: usize,
+        defmt_state: Option<&(defmt_decoder::Table, Option<defmt_decoder::Locations>)>,
+    ) -> (String, String) {
+        // The data is decoded only once, for both the display and the sinks.
+        let decoded_data = self.decode_rtt_data(bytes_read, defmt_state);
+        self.write_to_sinks(bytes_read, &decoded_data);
+        let formatted_data = self.format_rtt_data(decoded_data);
+
+        // If the Channel doesn't have a number, then send the output to channel 0
+        (self.number().unwrap_or(0).to_string(), formatted_data)
+    }
+
+    /// Formats the decoded data of the channel for display.
+    ///
+    /// String data is split into lines, which are prefixed with a timestamp if enabled.
+    fn format_rtt_data(&self, decoded_data: String) -> String {
+        if self.data_format != DataFormat::String {
+            return decoded_data;
+        }
+
+        let mut formatted_data = String::new();
+        for line in decoded_data.split_terminator('\n') {
+            if self.show_timestamps {
+                write!(formatted_data, "{} :", Local::now()).map_or_else(
+                    |err| log::error!("Failed to format RTT data - {:?}", err),
+                    |r| r,
+                );
+            }
+            writeln!(formatted_data, "{}", line).map_or_else(
+                |err| log::error!("Failed to format RTT data - {:?}", err),
+                |r| r,
+            );
+        }
+        formatted_data
+    }
+
+    /// Decodes the `bytes_read` bytes at the start of the channel buffer according to the data format of the channel.
+    ///
+    /// String data is returned as it is, including a partial line at the end.
+    fn decode_rtt_data(
+        &self,
+        bytes_read: usize,
+        defmt_state: Option<&(defmt_decoder::Table, Option<defmt_decoder::Locations>)>,
+    ) -> String {
+        let mut formatted_data = String::new();
+        match self.data_format {
+            DataFormat::String => {
+                formatted_data =
+                    String::from_utf8_lossy(&self.rtt_buffer.0[..bytes_read]).to_string();
+            }
+            DataFormat::BinaryLE => {
+                for element in &self.rtt_buffer.0[..bytes_read] {
+                    // Width of 4 allows 0xFF to be printed.
+                    write!(formatted_data, "{:#04x}", element).map_or_else(
+                        |err| log::error!("Failed to format RTT data - {:?}", err),
+                        |r| r,
+                    );
+                }
+            }
+            DataFormat::Defmt => {
+                match defmt_state {
+                    Some((table, locs)) => {
+                        let mut stream_decoder = table.new_stream_decoder();
+                        stream_decoder.received(&self.rtt_buffer.0[..bytes_read]);
+                        while let Ok(frame) = stream_decoder.decode() {
+                            // NOTE(`[]` indexing) all indices in `table` have already been
+                            // verified to exist in the `locs` map.
+                            let loc = locs.as_ref().map(|locs| &locs[&frame.index()]);
+                            writeln!(formatted_data, "{}", frame.display(false)).map_or_else(
+                                |err| log::error!("Failed to format RTT data - {:?}", err),
+                                |r| r,
+                            );
+                            if self.show_location {
+                                if let Some(loc) = loc {
+                                    let relpath = if let Ok(relpath) =
+                                        loc.file.strip_prefix(&std::env::current_dir().unwrap())
+                                    {
+                                        relpath
+                                    } else {
+                                        // not relative; use full path
+                                        &loc.file
+                                    };
+                                    writeln!(
+                                        formatted_data,
+                                        "└─ {}:{}",
+                                        relpath.display(),
+                                        loc.line
+                                    )
+                                    .map_or_else(
+                                        |err| log::error!("Failed to format RTT data - {:?}", err),
+                                        |r| r,
+                                    );
                                 }
                             }
-                        };
-                        formatted_data
+                        }
                     }
-                )
-            })
+                    None => {
+                        write!(
+                            formatted_data,
+                            "Running rtt in defmt mode but table or locations could not be loaded."
+                        )
+                        .map_or_else(
+                            |err| log::error!("Failed to format RTT data - {:?}", err),
+                            |r| r,
+                        );
+                    }
+                }
+            }
+        };
+        formatted_data
+    }
+
+    /// Writes the `bytes_read` bytes at the start of the channel buffer, and the same data decoded
+    /// by [`RttActiveChannel::decode_rtt_data`], to the sinks of the channel.
+    /// A sink which fails is closed.
+    fn write_to_sinks(&mut self, bytes_read: usize, decoded_data: &str) {
+        if self.sinks.is_empty() {
+            return;
+        }
+
+        // Binary data has no lines, so each read is written as one line.
+        let binary_line;
+        let formatted_data = if self.data_format == DataFormat::BinaryLE {
+            binary_line = format!("{}\n", decoded_data);
+            &binary_line
+        } else {
+            decoded_data
+        };
+
+        let raw_data = &self.rtt_buffer.0[..bytes_read];
+        let mut index = 0;
+        while index < self.sinks.len() {
+            match self.sinks[index].write(raw_data, formatted_data) {
+                Ok(()) => index += 1,
+                Err(err) => {
+                    log::error!(
+                        "Failed to write RTT channel '{}' to a sink, closing it: {}",
+                        self.channel_name,
+                        err
+                    );
+                    self.sinks.remove(index);
+                }
+            }
+        }
     }
 
-    pub fn _push_rtt(&mut self, core: &mut Core) {
-        if let Some(down_channel) = self.down_channel.as_mut() {
-            self._input_data += "\n";
-            down_channel
-                .write(core, self._input_data.as_bytes())
-                .unwrap();
-            self._input_data.clear();
+    /// Queues `data` for the down channel of `self`, and writes as much of the queued data as fits into the buffer on the target.
+    /// The rest is written by later calls to [`RttActiveChannel::push_rtt`] or [`RttActiveChannel::flush_input`].
+    pub fn push_rtt(&mut self, core: &mut Core, data: &[u8]) -> Result<(), probe_rs_rtt::Error> {
+        if self.down_channel.is_some() {
+            self.input_data.extend_from_slice(data);
         }
+        self.flush_input(core)
+    }
+
+    /// Writes queued data to the down channel, as far as the buffer on the target has space for it.
+    pub fn flush_input(&mut self, core: &mut Core) -> Result<(), probe_rs_rtt::Error> {
+        if let Some(down_channel) = self.down_channel.as_ref() {
+            if !self.input_data.is_empty() {
+                let written = down_channel.write(core, &self.input_data)?;
+                self.input_data.drain(..written);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns true if there is data which was not written to the target yet.
+    pub fn has_pending_input(&self) -> bool {
+        !self.input_data.is_empty()
     }
 }
 
@@ -306,6 +480,9 @@ impl RttActiveChannel {
 pub struct RttActiveTarget {
     pub active_channels: Vec<RttActiveChannel>,
     pub defmt_state: Option<(defmt_decoder::Table, Option<defmt_decoder::Locations>)>,
+    /// The control block on the target, from which the state of all channels is read at once.
+    control_block: ControlBlock,
+    poll_interval: PollInterval,
 }
 
 impl RttActiveTarget {
@@ -316,6 +493,7 @@ impl RttActiveTarget {
         rtt_config: &RttConfig,
     ) -> Result<Self> {
         let mut active_channels = Vec::new();
+        let control_block = rtt.control_block();
         // For each channel configured in the RTT Control Block (`Rtt`), check if there are additional user configuration in a `RttChannelConfig`. If not, apply defaults.
         let up_channels = rtt.up_channels().drain();
         let down_channels = rtt.down_channels().drain();
@@ -384,6 +562,8 @@ impl RttActiveTarget {
         Ok(Self {
             active_channels,
             defmt_state,
+            control_block,
+            poll_interval: PollInterval::default(),
         })
     }
 
@@ -406,17 +586,133 @@ impl RttActiveTarget {
     }
 
     /// Polls the RTT target on all channels and returns available data.
+    /// Input which did not fit into the down channels before is written as well.
+    ///
+    /// The state of all channels is read with a single memory transfer, and only channels with new data are read.
     pub fn poll_rtt(&mut self, core: &mut Core) -> HashMap<String, String> {
+        for active_channel in self.active_channels.iter_mut() {
+            if let Err(err) = active_channel.flush_input(core) {
+                log::error!("Error writing to RTT: {}", err);
+            }
+        }
+
+        let snapshot = match self.snapshot(core) {
+            Ok(snapshot) => snapshot,
+            Err(err) => {
+                log::error!("\nError reading from RTT: {}", err);
+                self.poll_interval.update(0);
+                return HashMap::new();
+            }
+        };
+
         let defmt_state = self.defmt_state.as_ref();
-        self.active_channels
+        let mut max_fill_percent = 0;
+        let data = self
+            .active_channels
+            .iter_mut()
+            .filter_map(|active_channel| {
+                let bytes_read = active_channel.poll_rtt_snapshot(core, &snapshot)?;
+                let capacity = active_channel.rtt_buffer.0.len().max(1);
+                max_fill_percent = max_fill_percent.max(bytes_read * 100 / capacity);
+
+                Some(active_channel.process_rtt_data(bytes_read, defmt_state))
+            })
+            .collect::<HashMap<_, _>>();
+
+        self.poll_interval.update(max_fill_percent);
+        data
+    }
+
+    /// Reads the state of all channels from the control block on the target, with a single memory transfer.
+    pub fn snapshot(&self, core: &mut Core) -> Result<ChannelSnapshot, probe_rs_rtt::Error> {
+        retry_on_probe_error(|| self.control_block.snapshot(core))
+    }
+
+    /// Returns how long to wait before the next call to [`RttActiveTarget::poll_rtt`].
+    ///
+    /// The interval gets shorter while the target sends data, so the buffers on the target do not overflow,
+    /// and longer while the channels are idle, so the probe is not kept busy.
+    pub fn poll_interval(&self) -> Duration {
+        if self.has_pending_input() {
+            MIN_POLL_INTERVAL
+        } else {
+            self.poll_interval.0
+        }
+    }
+
+    /// Writes `data` to the down channel with the given number, or to the first down channel if `channel_number` is `None`.
+    pub fn push_rtt(
+        &mut self,
+        core: &mut Core,
+        channel_number: Option<usize>,
+        data: &[u8],
+    ) -> Result<()> {
+        let active_channel = self
+            .active_channels
             .iter_mut()
-            .filter_map(|active_channel| active_channel.get_rtt_data(core, defmt_state))
-            .collect::<HashMap<_, _>>()
+            .filter(|active_channel| active_channel.down_channel.is_some())
+            .find(|active_channel| {
+                channel_number.is_none() || active_channel.down_channel_number() == channel_number
+            })
+            .ok_or_else(|| match channel_number {
+                Some(number) => anyhow!("There is no RTT down channel with number {}", number),
+                None => anyhow!("The target does not have an RTT down channel"),
+            })?;
+
+        active_channel.push_rtt(core, data)?;
+        Ok(())
+    }
+
+    /// Returns true if there is input for any down channel which was not written to the target yet.
+    pub fn has_pending_input(&self) -> bool {
+        self.active_channels
+            .iter()
+            .any(|active_channel| active_channel.has_pending_input())
+    }
+}
+
+/// Retries an RTT operation, in case the probe is temporarily unavailable, e.g. user pressed the `reset` button.
+fn retry_on_probe_error<T>(
+    mut operation: impl FnMut() -> Result<T, probe_rs_rtt::Error>,
+) -> Result<T, probe_rs_rtt::Error> {
+    let mut result = operation();
+    for _loop_count in 1..10 {
+        match result {
+            Err(probe_rs_rtt::Error::Probe(_)) => {
+                std::thread::sleep(Duration::from_millis(50));
+                result = operation();
+            }
+            _ => break,
+        }
+    }
+    result
+}
+
+const MIN_POLL_INTERVAL: Duration = Duration::from_millis(1);
+const MAX_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// The interval between polls of RTT, adapted to the traffic on the channels.
+#[derive(Debug)]
+struct PollInterval(Duration);
+
+impl Default for PollInterval {
+    fn default() -> Self {
+        PollInterval(MIN_POLL_INTERVAL)
     }
+}
 
-    // pub fn push_rtt(&mut self) {
-    //     self.tabs[self.current_tab].push_rtt();
-    // }
+impl PollInterval {
+    /// Updates the interval after a poll, in which the fullest channel buffer was filled to `max_fill_percent`.
+    fn update(&mut self, max_fill_percent: usize) {
+        self.0 = if max_fill_percent >= 50 {
+            // The target may fill the rest of the buffer before the next poll, so poll again right away.
+            Duration::ZERO
+        } else if max_fill_percent > 0 {
+            MIN_POLL_INTERVAL
+        } else {
+            (self.0 * 2).clamp(MIN_POLL_INTERVAL, MAX_POLL_INTERVAL)
+        };
+    }
 }
 
 struct RttBuffer(Vec<u8>);
@@ -436,3 +732,31 @@ impl fmt::Debug for RttBuffer {
         self.0.fmt(f)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn adapt_poll_interval() {
+        let mut interval = PollInterval::default();
+
+        interval.update(0);
+        interval.update(0);
+        assert_eq!(interval.0, MIN_POLL_INTERVAL * 4);
+
+        for _ in 0..10 {
+            interval.update(0);
+        }
+        assert_eq!(interval.0, MAX_POLL_INTERVAL);
+
+        interval.update(10);
+        assert_eq!(interval.0, MIN_POLL_INTERVAL);
+
+        interval.update(75);
+        assert_eq!(interval.0, Duration::ZERO);
+
+        interval.update(0);
+        assert_eq!(interval.0, MIN_POLL_INTERVAL);
+    }
+}
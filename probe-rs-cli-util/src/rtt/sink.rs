@@ -0,0 +1,325 @@
+//! Destinations for RTT channel data, in addition to the display of the data.
+
+use chrono::Local;
+use serde::Deserialize;
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// Used by serde to provide defaults for `RttSinkConfig::Log`
+fn default_max_size() -> u64 {
+    10 * 1024 * 1024
+}
+
+/// Used by serde to provide defaults for `RttSinkConfig::Log`
+fn default_max_files() -> usize {
+    5
+}
+
+/// The configuration of a sink for the data of an RTT channel.
+///
+/// In a JSON configuration, a sink is written as e.g. `{ "type": "log", "path": "rtt.log", "maxFiles": 3 }`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum RttSinkConfig {
+    /// Writes the raw bytes of the channel to a file.
+    #[serde(rename_all = "camelCase")]
+    File {
+        path: PathBuf,
+        /// Append to an existing file instead of truncating it.
+        #[serde(default)]
+        append: bool,
+    },
+    /// Writes the formatted lines of the channel to a log file, with a host timestamp on each line.
+    /// When the file reaches `max_size` bytes, it is renamed to `<path>.1`, older files are renamed
+    /// to `<path>.2` and so on, and only `max_files` old files are kept.
+    #[serde(rename_all = "camelCase")]
+    Log {
+        path: PathBuf,
+        #[serde(default = "default_max_size")]
+        max_size: u64,
+        #[serde(default = "default_max_files")]
+        max_files: usize,
+    },
+    /// Sends the raw bytes of the channel to all clients connected to a local TCP socket.
+    #[serde(rename_all = "camelCase")]
+    Tcp { address: SocketAddr },
+}
+
+/// An open sink for the data of an RTT channel.
+#[derive(Debug)]
+pub enum RttSink {
+    File(File),
+    Log(RotatingLog),
+    Tcp(TcpSink),
+}
+
+impl RttSink {
+    /// Opens the file or socket of the sink.
+    pub fn open(config: &RttSinkConfig) -> io::Result<Self> {
+        match config {
+            RttSinkConfig::File { path, append } => Ok(RttSink::File(
+                OpenOptions::new()
+                    .create(true)
+                    .write(true)
+                    .append(*append)
+                    .truncate(!*append)
+                    .open(path)?,
+            )),
+            RttSinkConfig::Log {
+                path,
+                max_size,
+                max_files,
+            } => Ok(RttSink::Log(RotatingLog::open(
+                path.clone(),
+                *max_size,
+                *max_files,
+            )?)),
+            RttSinkConfig::Tcp { address } => Ok(RttSink::Tcp(TcpSink::bind(*address)?)),
+        }
+    }
+
+    /// Writes new data of the channel. `raw` are the bytes read from the target,
+    /// `formatted` is the same data decoded according to the data format of the channel, without timestamps.
+    /// It can end with a partial line, which is continued by the next write.
+    pub fn write(&mut self, raw: &[u8], formatted: &str) -> io::Result<()> {
+        match self {
+            RttSink::File(file) => file.write_all(raw),
+            RttSink::Log(log) => log.write(formatted),
+            RttSink::Tcp(tcp) => {
+                tcp.write(raw);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// A log file which is rotated when it reaches a maximum size.
+#[derive(Debug)]
+pub struct RotatingLog {
+    path: PathBuf,
+    max_size: u64,
+    max_files: usize,
+    file: File,
+    size: u64,
+    /// The start of a line, which is written when the rest of the line arrives.
+    partial_line: String,
+}
+
+impl RotatingLog {
+    fn open(path: PathBuf, max_size: u64, max_files: usize) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+
+        Ok(Self {
+            path,
+            max_size,
+            max_files,
+            file,
+            size,
+            partial_line: String::new(),
+        })
+    }
+
+    fn write(&mut self, formatted: &str) -> io::Result<()> {
+        self.partial_line.push_str(formatted);
+
+        let complete = match self.partial_line.rfind('\n') {
+            Some(end) => self.partial_line.drain(..=end).collect::<String>(),
+            None => return Ok(()),
+        };
+
+        let timestamp = Local::now().format("%Y-%m-%d %H:%M:%S%.3f");
+        for line in complete.lines() {
+            self.write_line(&format!("{} {}\n", timestamp, line))?;
+        }
+
+        self.file.flush()
+    }
+
+    fn write_line(&mut self, line: &str) -> io::Result<()> {
+        if self.size > 0 && self.size + line.len() as u64 > self.max_size {
+            self.rotate()?;
+        }
+
+        self.file.write_all(line.as_bytes())?;
+        self.size += line.len() as u64;
+
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+
+        if self.max_files == 0 {
+            self.file = File::create(&self.path)?;
+        } else {
+            let oldest = rotated_path(&self.path, self.max_files);
+            if oldest.exists() {
+                fs::remove_file(oldest)?;
+            }
+
+            for index in (1..self.max_files).rev() {
+                let path = rotated_path(&self.path, index);
+                if path.exists() {
+                    fs::rename(path, rotated_path(&self.path, index + 1))?;
+                }
+            }
+
+            fs::rename(&self.path, rotated_path(&self.path, 1))?;
+            self.file = File::create(&self.path)?;
+        }
+
+        self.size = 0;
+        Ok(())
+    }
+}
+
+impl Drop for RotatingLog {
+    fn drop(&mut self) {
+        // Keep the last line, even if the target never finished it.
+        if !self.partial_line.is_empty() {
+            let line = std::mem::take(&mut self.partial_line) + "\n";
+            if let Err(err) = self.write(&line) {
+                log::warn!("Failed to write the last line of the RTT log: {}", err);
+            }
+        }
+    }
+}
+
+fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut rotated = path.as_os_str().to_owned();
+    rotated.push(format!(".{}", index));
+    rotated.into()
+}
+
+/// Sends data to all clients connected to a TCP socket.
+#[derive(Debug)]
+pub struct TcpSink {
+    listener: TcpListener,
+    clients: Vec<TcpStream>,
+}
+
+impl TcpSink {
+    fn bind(address: SocketAddr) -> io::Result<Self> {
+        let listener = TcpListener::bind(address)?;
+        // Clients are accepted while polling RTT, which must not block.
+        listener.set_nonblocking(true)?;
+
+        Ok(Self {
+            listener,
+            clients: Vec::new(),
+        })
+    }
+
+    fn write(&mut self, data: &[u8]) {
+        while let Ok((client, address)) = self.listener.accept() {
+            log::info!("RTT client connected from {}", address);
+
+            // A slow client must not stall the polling of RTT.
+            let configured = client
+                .set_nonblocking(false)
+                .and_then(|()| client.set_write_timeout(Some(Duration::from_millis(100))));
+            match configured {
+                Ok(()) => self.clients.push(client),
+                Err(err) => log::warn!("Failed to configure the RTT client connection: {}", err),
+            }
+        }
+
+        // Clients which closed the connection, or cannot keep up, are dropped.
+        self.clients
+            .retain(|mut client| client.write_all(data).is_ok());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn deserialize_sinks() {
+        let sinks: Vec<RttSinkConfig> = serde_json::from_str(
+            r#"[
+                { "type": "file", "path": "defmt.bin" },
+                { "type": "log", "path": "rtt.log", "maxFiles": 3 },
+                { "type": "tcp", "address": "127.0.0.1:19021" }
+            ]"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            sinks,
+            vec![
+                RttSinkConfig::File {
+                    path: "defmt.bin".into(),
+                    append: false,
+                },
+                RttSinkConfig::Log {
+                    path: "rtt.log".into(),
+                    max_size: default_max_size(),
+                    max_files: 3,
+                },
+                RttSinkConfig::Tcp {
+                    address: "127.0.0.1:19021".parse().unwrap(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn rotate_log() {
+        let directory = std::env::temp_dir().join(format!("rtt-sink-test-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("rtt.log");
+
+        let mut log = RotatingLog::open(path.clone(), 40, 2).unwrap();
+        for line in ["first", "second", "third", "fourth"] {
+            log.write(&format!("{}\n", line)).unwrap();
+        }
+
+        let newest = fs::read_to_string(&path).unwrap();
+        let oldest = fs::read_to_string(rotated_path(&path, 2)).unwrap();
+        assert!(newest.ends_with(" fourth\n"));
+        assert!(oldest.ends_with(" second\n"));
+        assert!(!rotated_path(&path, 3).exists());
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn log_partial_lines() {
+        let directory =
+            std::env::temp_dir().join(format!("rtt-sink-partial-test-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("rtt.log");
+
+        let mut log = RotatingLog::open(path.clone(), default_max_size(), 1).unwrap();
+        log.write("Hello, ").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "");
+
+        log.write("world!\nSecond").unwrap();
+        log.write(" line\nunfinished").unwrap();
+        drop(log);
+
+        let lines: Vec<_> = fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| {
+                line.split_once(' ')
+                    .unwrap()
+                    .1
+                    .split_once(' ')
+                    .unwrap()
+                    .1
+                    .to_owned()
+            })
+            .collect();
+        assert_eq!(lines, vec!["Hello, world!", "Second line", "unfinished"]);
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
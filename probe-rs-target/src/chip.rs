@@ -1,4 +1,6 @@
 use super::memory::MemoryRegion;
+use crate::detection::DetectionRule;
+use crate::sequence::ChipDebugSequence;
 use crate::CoreType;
 use serde::{Deserialize, Serialize};
 
@@ -30,6 +32,34 @@ pub struct Chip {
     ///
     /// [`ChipFamily::flash_algorithms`]: crate::ChipFamily::flash_algorithms
     pub flash_algorithms: Vec<String>,
+    /// The debug variables of the chip, as `__var` declarations in the CMSIS-Pack sequence language.
+    ///
+    /// They are evaluated once before any of the [`ChipFamily::debug_sequences`] is executed.
+    ///
+    /// [`ChipFamily::debug_sequences`]: crate::ChipFamily::debug_sequences
+    #[cfg_attr(
+        not(feature = "bincode"),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub debug_vars: Option<String>,
+    /// The debug sequence used for this chip, selected by the name it is registered under.
+    ///
+    /// If not set, the [`ChipFamily::debug_sequences`] or the default sequence of the
+    /// architecture are used.
+    ///
+    /// [`ChipFamily::debug_sequences`]: crate::ChipFamily::debug_sequences
+    #[cfg_attr(
+        not(feature = "bincode"),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub debug_sequence: Option<ChipDebugSequence>,
+    /// Rules to detect this chip variant when the target is selected automatically.
+    ///
+    /// This is needed for chips which cannot be identified by the part number in their ROM table,
+    /// e.g. because all variants of a family share the same part number.
+    #[serde(default)]
+    #[cfg_attr(not(feature = "bincode"), serde(skip_serializing_if = "Vec::is_empty"))]
+    pub detection: Vec<DetectionRule>,
 }
 
 impl Chip {
@@ -47,6 +77,9 @@ impl Chip {
             }],
             memory_map: vec![],
             flash_algorithms: vec![],
+            debug_vars: None,
+            debug_sequence: None,
+            detection: vec![],
         }
     }
 }
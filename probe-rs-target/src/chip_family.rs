@@ -1,5 +1,6 @@
 use super::chip::Chip;
 use super::flash_algorithm::RawFlashAlgorithm;
+use super::sequence::SequenceDescription;
 use jep106::JEP106Code;
 
 use serde::{Deserialize, Serialize};
@@ -99,6 +100,11 @@ pub struct ChipFamily {
     pub variants: Vec<Chip>,
     /// This vector holds all available algorithms.
     pub flash_algorithms: Vec<RawFlashAlgorithm>,
+    /// The CMSIS-Pack debug sequences of this family, which are interpreted
+    /// instead of the default debug sequences.
+    #[serde(default)]
+    #[cfg_attr(not(feature = "bincode"), serde(skip_serializing_if = "Vec::is_empty"))]
+    pub debug_sequences: Vec<SequenceDescription>,
 
     #[serde(skip, default = "default_source")]
     /// Source of the target description, used for diagnostics
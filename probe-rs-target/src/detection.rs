@@ -0,0 +1,81 @@
+use serde::{Deserialize, Serialize};
+
+/// A rule which identifies a chip variant, when the connected chip cannot be identified
+/// by its ROM table alone.
+///
+/// A chip is detected if all of its rules match.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum DetectionRule {
+    /// Compares a 32-bit word read from the memory of the chip, e.g. the `DBGMCU_IDCODE` register
+    /// of STM32 chips or the `FICR` of nRF chips.
+    Memory(MemoryDetectionRule),
+    /// Compares the JTAG `IDCODE` of the chip, as used for RISC-V chips.
+    JtagIdcode(IdcodeDetectionRule),
+}
+
+/// Reads a 32-bit word from memory and compares it with an expected value.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemoryDetectionRule {
+    /// The access port used to read the memory.
+    /// If not set, the access port of the first core is used.
+    /// The memory is always read through the debug port of the first core.
+    #[cfg_attr(
+        not(feature = "bincode"),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub ap: Option<u8>,
+    /// The address of the word.
+    pub address: u64,
+    /// The bits of the word which are compared.
+    #[serde(default = "all_bits")]
+    pub mask: u32,
+    /// The expected value of the masked word.
+    pub value: u32,
+}
+
+impl MemoryDetectionRule {
+    /// Returns true if the word read from memory matches this rule.
+    pub fn matches(&self, word: u32) -> bool {
+        word & self.mask == self.value & self.mask
+    }
+}
+
+/// Compares the JTAG `IDCODE` with an expected value.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IdcodeDetectionRule {
+    /// The bits of the `IDCODE` which are compared.
+    /// The version bits can be excluded to match all revisions of a chip.
+    #[serde(default = "all_bits")]
+    pub mask: u32,
+    /// The expected value of the masked `IDCODE`.
+    pub value: u32,
+}
+
+impl IdcodeDetectionRule {
+    /// Returns true if the `IDCODE` matches this rule.
+    pub fn matches(&self, idcode: u32) -> bool {
+        idcode & self.mask == self.value & self.mask
+    }
+}
+
+fn all_bits() -> u32 {
+    0xffff_ffff
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn memory_rule_is_masked() {
+        let rule = MemoryDetectionRule {
+            ap: None,
+            address: 0xE004_2000,
+            mask: 0x0000_0fff,
+            value: 0x0000_0413,
+        };
+
+        assert!(rule.matches(0x1001_6413));
+        assert!(!rule.matches(0x1001_6419));
+    }
+}
@@ -13,16 +13,23 @@
 
 mod chip;
 mod chip_family;
+mod detection;
 mod flash_algorithm;
 mod flash_properties;
 mod memory;
+mod sequence;
 
 pub use chip::{ArmCoreAccessOptions, Chip, Core, CoreAccessOptions, RiscvCoreAccessOptions};
 pub use chip_family::{
     Architecture, ChipFamily, CoreType, InstructionSet, TargetDescriptionSource,
 };
+pub use detection::{DetectionRule, IdcodeDetectionRule, MemoryDetectionRule};
 pub use flash_algorithm::RawFlashAlgorithm;
 pub use flash_properties::FlashProperties;
 pub use memory::{
     MemoryRange, MemoryRegion, NvmRegion, PageInfo, RamRegion, SectorDescription, SectorInfo,
 };
+pub use sequence::{
+    ChipDebugSequence, DebugSequenceParameters, SequenceBlock, SequenceControl,
+    SequenceDescription, SequenceElement,
+};
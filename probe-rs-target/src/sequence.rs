@@ -0,0 +1,115 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// A debug sequence as described in the `<sequences>` section of a CMSIS-Pack.
+///
+/// Debug sequences replace the default steps a debugger takes to connect to, reset or unlock a chip.
+/// They are written in the sequence language of the [CMSIS Debug Description] and are interpreted
+/// by probe-rs, so chips which need vendor specific steps can be supported without new code.
+///
+/// [CMSIS Debug Description]: https://open-cmsis-pack.github.io/Open-CMSIS-Pack-Spec/main/html/debug_description.html
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SequenceDescription {
+    /// The name of the sequence, e.g. `ResetSystem` or `DebugPortStart`.
+    pub name: String,
+    /// The name of the processor this sequence applies to.
+    /// If not set, the sequence applies to all processors of the chip.
+    #[cfg_attr(
+        not(feature = "bincode"),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub processor: Option<String>,
+    /// Disabled sequences are not executed, and the debugger also does not fall back to its default behaviour.
+    #[serde(default)]
+    pub disabled: bool,
+    /// The blocks and control elements of the sequence, in the order they are executed.
+    pub body: Vec<SequenceElement>,
+}
+
+/// An element of a [`SequenceDescription`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SequenceElement {
+    /// A `<block>` of statements.
+    Block(SequenceBlock),
+    /// A `<control>` element, which executes its body conditionally or in a loop.
+    Control(SequenceControl),
+}
+
+/// A `<block>` of statements in a debug sequence.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SequenceBlock {
+    /// If set, the statements should be executed without interruption by other debug accesses.
+    #[serde(default)]
+    pub atomic: bool,
+    /// The statements of the block, separated by `;`.
+    pub code: String,
+}
+
+/// A `<control>` element in a debug sequence.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SequenceControl {
+    /// The body is only executed if this expression evaluates to a non-zero value.
+    #[cfg_attr(
+        not(feature = "bincode"),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    #[serde(rename = "if")]
+    pub if_condition: Option<String>,
+    /// The body is executed repeatedly as long as this expression evaluates to a non-zero value.
+    #[cfg_attr(
+        not(feature = "bincode"),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    #[serde(rename = "while")]
+    pub while_condition: Option<String>,
+    /// The timeout for the `while` loop in microseconds. A timeout of 0 means no timeout.
+    #[cfg_attr(
+        not(feature = "bincode"),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub timeout: Option<u64>,
+    /// The nested blocks and control elements.
+    pub body: Vec<SequenceElement>,
+}
+
+/// A built-in or user registered debug sequence, selected by name.
+///
+/// The named sequence takes precedence over the [`ChipFamily::debug_sequences`] of the family.
+///
+/// [`ChipFamily::debug_sequences`]: crate::ChipFamily::debug_sequences
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChipDebugSequence {
+    /// The name under which the sequence is registered, e.g. `LPC55S69`.
+    pub name: String,
+    /// Parameters which are passed to the sequence when it is created.
+    #[serde(default)]
+    pub parameters: DebugSequenceParameters,
+}
+
+/// Chip specific parameters of a [`ChipDebugSequence`].
+///
+/// Which parameters are used depends on the sequence, unused parameters are ignored.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DebugSequenceParameters {
+    /// The access port of a debug mailbox, e.g. on the NXP LPC55 series.
+    #[cfg_attr(
+        not(feature = "bincode"),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub mailbox_ap: Option<u8>,
+    /// The address of the ROM table, for chips where the base address read from the
+    /// access port is wrong or zero.
+    #[cfg_attr(
+        not(feature = "bincode"),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub rom_table_base: Option<u64>,
+    /// Further parameters for user registered sequences.
+    #[serde(default)]
+    #[cfg_attr(
+        not(feature = "bincode"),
+        serde(skip_serializing_if = "BTreeMap::is_empty")
+    )]
+    pub custom: BTreeMap<String, u64>,
+}
@@ -321,6 +321,13 @@ impl<S: ArmDebugState> SwdSequence for ArmCommunicationInterface<S> {
     }
 }
 
+impl<S: ArmDebugState> ArmCommunicationInterface<S> {
+    /// The wire protocol used by the probe, if it is known.
+    pub(crate) fn active_protocol(&self) -> Option<crate::WireProtocol> {
+        self.probe.active_protocol()
+    }
+}
+
 impl<'interface> ArmCommunicationInterface<Uninitialized> {
     pub(crate) fn new(probe: Box<dyn DapProbe>, use_overrun_detect: bool) -> Self {
         let state = Uninitialized { use_overrun_detect };
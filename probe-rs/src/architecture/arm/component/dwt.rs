@@ -6,11 +6,28 @@
 //! See ARMv7-M architecture reference manual C1.8 for some additional
 //! info about this stuff.
 
+use anyhow::anyhow;
 use bitfield::bitfield;
 
 use super::super::memory::romtable::Component;
 use super::DebugRegister;
-use crate::{Core, Error};
+use crate::architecture::arm::core::armv7m::Demcr;
+use crate::{Core, CoreRegister, Error, MemoryInterface, Watchpoint, WatchpointKind};
+
+/// Base address of the DWT in the system control space of ARMv6-M, ARMv7-M and ARMv8-M cores.
+const DWT_BASE: u32 = 0xE000_1000;
+
+/// Offset of the program counter sample register `DWT_PCSR`.
+const PCSR_ADDRESS: u32 = 0x1C;
+
+/// The programmers model of the DWT comparators, which differs between architecture versions.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum DwtVersion {
+    /// The comparators of ARMv6-M and ARMv7-M, which use `DWT_MASKn` to match address ranges.
+    V7,
+    /// The comparators of ARMv8-M, which match on the access size given in `DWT_FUNCTIONn`.
+    V8,
+}
 
 /// A struct representing a DWT unit on target.
 pub struct Dwt<'probe: 'core, 'core> {
@@ -73,6 +90,26 @@ impl<'probe: 'core, 'core> Dwt<'probe, 'core> {
         function.store_unit(self.component, self.core, unit)
     }
 
+    /// Enables periodic sampling of the program counter.
+    ///
+    /// A sample is emitted every `(postpreset + 1) * 64` cycles, or every `(postpreset + 1) * 1024`
+    /// cycles with `cyctap` set. `postpreset` must be at most 15.
+    pub fn enable_pc_sampling(&mut self, cyctap: bool, postpreset: u8) -> Result<(), Error> {
+        let mut ctrl = Ctrl::load(self.component, self.core)?;
+        ctrl.set_cyccntena(true);
+        ctrl.set_cyctap(cyctap);
+        ctrl.set_postpreset(postpreset.min(15) as u32);
+        ctrl.set_pcsamplena(true);
+        ctrl.store(self.component, self.core)
+    }
+
+    /// Disables periodic sampling of the program counter.
+    pub fn disable_pc_sampling(&mut self) -> Result<(), Error> {
+        let mut ctrl = Ctrl::load(self.component, self.core)?;
+        ctrl.set_pcsamplena(false);
+        ctrl.store(self.component, self.core)
+    }
+
     /// Enable exception tracing.
     pub fn enable_exception_trace(&mut self) -> Result<(), Error> {
         let mut ctrl = Ctrl::load(self.component, self.core)?;
@@ -88,6 +125,226 @@ impl<'probe: 'core, 'core> Dwt<'probe, 'core> {
     }
 }
 
+/// Sets `DEMCR.TRCENA`, without which the DWT registers can't be accessed.
+fn enable_trcena(memory: &mut impl MemoryInterface) -> Result<(), Error> {
+    let mut demcr = Demcr(memory.read_word_32(Demcr::ADDRESS)?);
+    if !demcr.trcena() {
+        demcr.set_trcena(true);
+        memory.write_word_32(Demcr::ADDRESS, demcr.into())?;
+    }
+
+    Ok(())
+}
+
+/// Returns the number of DWT comparators of the core which can be used as data watchpoints.
+///
+/// A core without a DWT, or with a DWT without comparators, has no units.
+pub(crate) fn watchpoint_units(memory: &mut impl MemoryInterface) -> Result<u32, Error> {
+    enable_trcena(memory)?;
+
+    let ctrl = Ctrl(memory.read_word_32(DWT_BASE + Ctrl::ADDRESS)?);
+
+    Ok(ctrl.numcomp() as u32)
+}
+
+/// Reads the data watchpoints configured in the DWT comparators.
+///
+/// Comparators which are disabled or used for something else than a data
+/// address match are reported as `None`.
+pub(crate) fn watchpoints(
+    memory: &mut impl MemoryInterface,
+    version: DwtVersion,
+) -> Result<Vec<Option<Watchpoint>>, Error> {
+    let num_units = watchpoint_units(memory)? as usize;
+
+    (0..num_units)
+        .map(|unit| {
+            let function = memory.read_word_32(unit_address::<Function>(unit))?;
+            decode_watchpoint(memory, version, unit, function)
+        })
+        .collect()
+}
+
+/// Reads a sample of the program counter from `DWT_PCSR`, without halting the core.
+///
+/// Returns `None` if no sample is available, e.g. because the core is halted or the register is
+/// not implemented. Tracing must be enabled with [`enable_tracing`](super::enable_tracing).
+pub fn read_pc_sample(memory: &mut impl MemoryInterface) -> Result<Option<u32>, Error> {
+    let pcsr = memory.read_word_32(DWT_BASE + PCSR_ADDRESS)?;
+
+    Ok(if pcsr == 0xFFFF_FFFF {
+        None
+    } else {
+        Some(pcsr)
+    })
+}
+
+/// Returns the watchpoint whose comparator has the `MATCHED` flag set.
+///
+/// Reading `DWT_FUNCTIONn` clears the flag, so this only reports a watchpoint
+/// once after it has been hit.
+pub(crate) fn triggered_watchpoint(
+    memory: &mut impl MemoryInterface,
+    version: DwtVersion,
+) -> Result<Option<Watchpoint>, Error> {
+    let num_units = watchpoint_units(memory)? as usize;
+    let mut triggered = None;
+
+    // Read all units, so that no stale MATCHED flag is left behind.
+    for unit in 0..num_units {
+        let function = memory.read_word_32(unit_address::<Function>(unit))?;
+
+        if Function(function).matched() && triggered.is_none() {
+            triggered = decode_watchpoint(memory, version, unit, function)?;
+        }
+    }
+
+    Ok(triggered)
+}
+
+/// Configures DWT comparator `unit` to halt the core on an access to the memory described by `watchpoint`.
+pub(crate) fn set_watchpoint(
+    memory: &mut impl MemoryInterface,
+    version: DwtVersion,
+    unit: usize,
+    watchpoint: Watchpoint,
+) -> Result<(), Error> {
+    let Watchpoint {
+        address,
+        length,
+        kind,
+    } = watchpoint;
+
+    if !length.is_power_of_two() || address % length != 0 {
+        return Err(Error::Other(anyhow!(
+            "Unsupported watchpoint of {} bytes at {:#010x}. The length must be a power of two and the address must be aligned to it.",
+            length,
+            address
+        )));
+    }
+
+    // The DWT is only active if TRCENA is set.
+    enable_trcena(memory)?;
+
+    let size_exponent = length.trailing_zeros();
+
+    match version {
+        DwtVersion::V7 => {
+            let mut mask = Mask(0);
+            mask.set_mask(size_exponent);
+            memory.write_word_32(unit_address::<Mask>(unit), mask.into())?;
+
+            // Not all implementations support the full mask range, so check that the
+            // requested size is actually supported.
+            let readback = Mask(memory.read_word_32(unit_address::<Mask>(unit))?);
+            if readback.mask() != size_exponent {
+                return Err(Error::Other(anyhow!(
+                    "A watchpoint of {} bytes is not supported by this core",
+                    length
+                )));
+            }
+
+            let mut comp = Comp(0);
+            comp.set_comp(address);
+            memory.write_word_32(unit_address::<Comp>(unit), comp.into())?;
+
+            let mut function = Function(0);
+            function.set_function(match kind {
+                WatchpointKind::Read => 0b0101,
+                WatchpointKind::Write => 0b0110,
+                WatchpointKind::Access => 0b0111,
+            });
+            memory.write_word_32(unit_address::<Function>(unit), function.into())?;
+        }
+        DwtVersion::V8 => {
+            // ARMv8-M comparators only match single accesses of up to a word.
+            if length > 4 {
+                return Err(Error::Other(anyhow!(
+                    "A watchpoint of {} bytes is not supported by this core",
+                    length
+                )));
+            }
+
+            let mut comp = Comp(0);
+            comp.set_comp(address);
+            memory.write_word_32(unit_address::<Comp>(unit), comp.into())?;
+
+            let mut function = FunctionV8(0);
+            function.set_datavsize(size_exponent as u8);
+            // Generate a debug event on a match.
+            function.set_action(0b01);
+            function.set_match(match kind {
+                WatchpointKind::Access => 0b0100,
+                WatchpointKind::Write => 0b0101,
+                WatchpointKind::Read => 0b0110,
+            });
+            memory.write_word_32(unit_address::<FunctionV8>(unit), function.into())?;
+        }
+    }
+
+    memory.flush()
+}
+
+/// Disables DWT comparator `unit`.
+pub(crate) fn clear_watchpoint(
+    memory: &mut impl MemoryInterface,
+    unit: usize,
+) -> Result<(), Error> {
+    memory.write_word_32(unit_address::<Function>(unit), 0)?;
+    memory.write_word_32(unit_address::<Comp>(unit), 0)?;
+
+    memory.flush()
+}
+
+/// The address of register `R` of DWT comparator `unit`.
+fn unit_address<R: DebugRegister>(unit: usize) -> u32 {
+    DWT_BASE + R::ADDRESS + 16 * unit as u32
+}
+
+/// Decodes the watchpoint configured in comparator `unit`, given the value of its `DWT_FUNCTIONn` register.
+fn decode_watchpoint(
+    memory: &mut impl MemoryInterface,
+    version: DwtVersion,
+    unit: usize,
+    function: u32,
+) -> Result<Option<Watchpoint>, Error> {
+    let (kind, length) = match version {
+        DwtVersion::V7 => {
+            let kind = match Function(function).function() {
+                0b0101 => WatchpointKind::Read,
+                0b0110 => WatchpointKind::Write,
+                0b0111 => WatchpointKind::Access,
+                _ => return Ok(None),
+            };
+            let mask = Mask(memory.read_word_32(unit_address::<Mask>(unit))?);
+
+            (kind, 1 << mask.mask())
+        }
+        DwtVersion::V8 => {
+            let function = FunctionV8(function);
+            if function.action() != 0b01 {
+                return Ok(None);
+            }
+            let kind = match function.match_() {
+                0b0100 => WatchpointKind::Access,
+                0b0101 => WatchpointKind::Write,
+                0b0110 => WatchpointKind::Read,
+                _ => return Ok(None),
+            };
+
+            (kind, 1 << function.datavsize())
+        }
+    };
+
+    let address = Comp(memory.read_word_32(unit_address::<Comp>(unit))?).comp();
+
+    Ok(Some(Watchpoint {
+        address,
+        length,
+        kind,
+    }))
+}
+
 bitfield! {
     #[derive(Clone, Default)]
     pub struct Ctrl(u32);
@@ -285,3 +542,258 @@ impl DebugRegister for Function {
     const ADDRESS: u32 = 0x28;
     const NAME: &'static str = "DWT/FUNCTION";
 }
+
+bitfield! {
+    /// The `DWT_FUNCTIONn` register as defined by ARMv8-M (see armv8-M Architecture Reference Manual D1.2.62)
+    #[derive(Clone, Default)]
+    pub struct FunctionV8(u32);
+    impl Debug;
+    pub u8, id, _: 31, 27;
+    pub matched, _: 24;
+    /// 00 Byte.
+    /// 01 Halfword.
+    /// 10 Word.
+    pub u8, datavsize, set_datavsize: 11, 10;
+    /// 00 Trigger only.
+    /// 01 Generate debug event.
+    /// 10 Generate Data trace Match packet.
+    /// 11 Generate Data trace Data Value packet.
+    pub u8, action, set_action: 5, 4;
+    pub u8, match_, set_match: 3, 0;
+}
+
+impl From<u32> for FunctionV8 {
+    fn from(raw: u32) -> Self {
+        FunctionV8(raw)
+    }
+}
+
+impl From<FunctionV8> for u32 {
+    fn from(raw: FunctionV8) -> Self {
+        raw.0
+    }
+}
+
+impl DebugRegister for FunctionV8 {
+    const ADDRESS: u32 = 0x28;
+    const NAME: &'static str = "DWT/FUNCTION";
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    /// The DWT registers of a core with `numcomp` comparators, which supports masks of up to `max_mask` bits.
+    struct MockDwt {
+        registers: HashMap<u32, u32>,
+        max_mask: u32,
+    }
+
+    impl MockDwt {
+        fn new(numcomp: u32, max_mask: u32) -> Self {
+            let mut registers = HashMap::new();
+            registers.insert(DWT_BASE + Ctrl::ADDRESS, numcomp << 28);
+
+            Self {
+                registers,
+                max_mask,
+            }
+        }
+
+        fn register<R: DebugRegister>(&self, unit: usize) -> u32 {
+            self.registers
+                .get(&unit_address::<R>(unit))
+                .copied()
+                .unwrap_or_default()
+        }
+    }
+
+    impl MemoryInterface for MockDwt {
+        fn read_word_32(&mut self, address: u32) -> Result<u32, Error> {
+            Ok(self.registers.get(&address).copied().unwrap_or_default())
+        }
+
+        fn read_word_8(&mut self, _address: u32) -> Result<u8, Error> {
+            unimplemented!()
+        }
+
+        fn read_32(&mut self, address: u32, data: &mut [u32]) -> Result<(), Error> {
+            for (offset, word) in data.iter_mut().enumerate() {
+                *word = self.read_word_32(address + 4 * offset as u32)?;
+            }
+            Ok(())
+        }
+
+        fn read_8(&mut self, _address: u32, _data: &mut [u8]) -> Result<(), Error> {
+            unimplemented!()
+        }
+
+        fn write_word_32(&mut self, address: u32, data: u32) -> Result<(), Error> {
+            let is_mask = (0..16).any(|unit| unit_address::<Mask>(unit) == address);
+            let data = if is_mask {
+                data.min(self.max_mask)
+            } else {
+                data
+            };
+
+            self.registers.insert(address, data);
+            Ok(())
+        }
+
+        fn write_word_8(&mut self, _address: u32, _data: u8) -> Result<(), Error> {
+            unimplemented!()
+        }
+
+        fn write_32(&mut self, address: u32, data: &[u32]) -> Result<(), Error> {
+            for (offset, word) in data.iter().enumerate() {
+                self.write_word_32(address + 4 * offset as u32, *word)?;
+            }
+            Ok(())
+        }
+
+        fn write_8(&mut self, _address: u32, _data: &[u8]) -> Result<(), Error> {
+            unimplemented!()
+        }
+
+        fn flush(&mut self) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    fn watchpoint(address: u32, length: u32, kind: WatchpointKind) -> Watchpoint {
+        Watchpoint {
+            address,
+            length,
+            kind,
+        }
+    }
+
+    #[test]
+    fn no_comparators() {
+        let mut dwt = MockDwt::new(0, 31);
+
+        assert_eq!(watchpoint_units(&mut dwt).unwrap(), 0);
+        assert!(watchpoints(&mut dwt, DwtVersion::V7).unwrap().is_empty());
+    }
+
+    #[test]
+    fn watchpoint_units_enables_trcena() {
+        let mut dwt = MockDwt::new(4, 31);
+
+        assert_eq!(watchpoint_units(&mut dwt).unwrap(), 4);
+        assert!(Demcr(dwt.read_word_32(Demcr::ADDRESS).unwrap()).trcena());
+    }
+
+    #[test]
+    fn v7_encode_mask() {
+        let mut dwt = MockDwt::new(4, 31);
+
+        set_watchpoint(
+            &mut dwt,
+            DwtVersion::V7,
+            2,
+            watchpoint(0x2000_0010, 16, WatchpointKind::Write),
+        )
+        .unwrap();
+
+        assert_eq!(dwt.register::<Mask>(2), 4);
+        assert_eq!(dwt.register::<Comp>(2), 0x2000_0010);
+        assert_eq!(Function(dwt.register::<Function>(2)).function(), 0b0110);
+    }
+
+    #[test]
+    fn v7_round_trip() {
+        let mut dwt = MockDwt::new(2, 31);
+
+        let read = watchpoint(0x2000_0000, 1, WatchpointKind::Read);
+        let access = watchpoint(0x2000_0100, 256, WatchpointKind::Access);
+        set_watchpoint(&mut dwt, DwtVersion::V7, 0, read).unwrap();
+        set_watchpoint(&mut dwt, DwtVersion::V7, 1, access).unwrap();
+
+        assert_eq!(
+            watchpoints(&mut dwt, DwtVersion::V7).unwrap(),
+            vec![Some(read), Some(access)]
+        );
+
+        clear_watchpoint(&mut dwt, 0).unwrap();
+        assert_eq!(
+            watchpoints(&mut dwt, DwtVersion::V7).unwrap(),
+            vec![None, Some(access)]
+        );
+    }
+
+    #[test]
+    fn v7_unsupported_mask() {
+        let mut dwt = MockDwt::new(1, 4);
+
+        assert!(set_watchpoint(
+            &mut dwt,
+            DwtVersion::V7,
+            0,
+            watchpoint(0x2000_0000, 32, WatchpointKind::Write),
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn v8_encode_datavsize() {
+        let mut dwt = MockDwt::new(4, 0);
+
+        set_watchpoint(
+            &mut dwt,
+            DwtVersion::V8,
+            1,
+            watchpoint(0x2000_0002, 2, WatchpointKind::Read),
+        )
+        .unwrap();
+
+        let function = FunctionV8(dwt.register::<FunctionV8>(1));
+        assert_eq!(function.datavsize(), 1);
+        assert_eq!(function.action(), 0b01);
+        assert_eq!(function.match_(), 0b0110);
+        assert_eq!(dwt.register::<Comp>(1), 0x2000_0002);
+    }
+
+    #[test]
+    fn v8_round_trip() {
+        let mut dwt = MockDwt::new(3, 0);
+
+        let byte = watchpoint(0x2000_0001, 1, WatchpointKind::Access);
+        let word = watchpoint(0x2000_0004, 4, WatchpointKind::Write);
+        set_watchpoint(&mut dwt, DwtVersion::V8, 0, byte).unwrap();
+        set_watchpoint(&mut dwt, DwtVersion::V8, 2, word).unwrap();
+
+        assert_eq!(
+            watchpoints(&mut dwt, DwtVersion::V8).unwrap(),
+            vec![Some(byte), None, Some(word)]
+        );
+    }
+
+    #[test]
+    fn v8_rejects_ranges() {
+        let mut dwt = MockDwt::new(1, 0);
+
+        assert!(set_watchpoint(
+            &mut dwt,
+            DwtVersion::V8,
+            0,
+            watchpoint(0x2000_0000, 8, WatchpointKind::Write),
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn unaligned_watchpoint() {
+        let mut dwt = MockDwt::new(1, 31);
+
+        assert!(set_watchpoint(
+            &mut dwt,
+            DwtVersion::V7,
+            0,
+            watchpoint(0x2000_0002, 4, WatchpointKind::Write),
+        )
+        .is_err());
+    }
+}
@@ -8,7 +8,11 @@ use super::memory::romtable::{Component, PeripheralType, RomTableError};
 use crate::architecture::arm::core::armv6m::Demcr;
 use crate::architecture::arm::{SwoConfig, SwoMode};
 use crate::{Core, CoreRegister, Error, MemoryInterface};
-pub use dwt::Dwt;
+pub(crate) use dwt::{
+    clear_watchpoint, set_watchpoint, triggered_watchpoint, watchpoint_units, watchpoints,
+    DwtVersion,
+};
+pub use dwt::{read_pc_sample, Dwt};
 pub use itm::Itm;
 pub use tpiu::Tpiu;
 
@@ -189,6 +193,31 @@ pub fn remove_swv_data_trace(
     dwt.disable_data_trace(unit)
 }
 
+/// Configures the DWT to sample the program counter every `(postpreset + 1) * 64` cycles,
+/// or every `(postpreset + 1) * 1024` cycles with `cyctap` set.
+///
+/// Expects to be given a list of all ROM table `components` as the second argument.
+pub(crate) fn enable_swv_pc_sampling(
+    core: &mut Core,
+    components: &[Component],
+    cyctap: bool,
+    postpreset: u8,
+) -> Result<(), Error> {
+    let mut dwt = Dwt::new(core, find_component(components, PeripheralType::Dwt)?);
+    dwt.enable_pc_sampling(cyctap, postpreset)
+}
+
+/// Configures the DWT to stop sampling the program counter.
+///
+/// Expects to be given a list of all ROM table `components` as the second argument.
+pub(crate) fn disable_swv_pc_sampling(
+    core: &mut Core,
+    components: &[Component],
+) -> Result<(), Error> {
+    let mut dwt = Dwt::new(core, find_component(components, PeripheralType::Dwt)?);
+    dwt.disable_pc_sampling()
+}
+
 /// Sets TRCENA in DEMCR to begin trace generation.
 pub fn enable_tracing(core: &mut Core) -> Result<(), Error> {
     let mut demcr = Demcr(core.read_word_32(Demcr::ADDRESS)?);
@@ -1,16 +1,19 @@
 //! Register types and the core interface for armv7-a
 
+use crate::architecture::arm::ap::AccessPortError;
 use crate::architecture::arm::core::register;
 use crate::architecture::arm::sequences::ArmDebugSequence;
-use crate::core::RegisterFile;
+use crate::core::{RegisterFile, Watchpoint};
 use crate::error::Error;
 use crate::memory::Memory;
 use crate::CoreInterface;
 use crate::CoreRegisterAddress;
 use crate::CoreStatus;
 use crate::DebugProbeError;
+use crate::FaultInfo;
 use crate::HaltReason;
 use crate::MemoryInterface;
+use crate::VectorCatchCondition;
 use crate::{Architecture, CoreInformation, CoreType, InstructionSet};
 use anyhow::Result;
 
@@ -544,6 +547,51 @@ impl<'probe> CoreInterface for Armv7a<'probe> {
         true
     }
 
+    fn available_watchpoint_units(&mut self) -> Result<u32, Error> {
+        // Watchpoints are not yet supported on ARMv7-A
+        Ok(0)
+    }
+
+    fn hw_watchpoints(&mut self) -> Result<Vec<Option<Watchpoint>>, Error> {
+        Ok(vec![])
+    }
+
+    fn set_hw_watchpoint(
+        &mut self,
+        _unit_index: usize,
+        _watchpoint: Watchpoint,
+    ) -> Result<(), Error> {
+        Err(Error::Probe(DebugProbeError::CommandNotSupportedByProbe(
+            "set_hw_watchpoint",
+        )))
+    }
+
+    fn clear_hw_watchpoint(&mut self, _unit_index: usize) -> Result<(), Error> {
+        Err(Error::Probe(DebugProbeError::CommandNotSupportedByProbe(
+            "clear_hw_watchpoint",
+        )))
+    }
+
+    fn triggered_watchpoint(&mut self) -> Result<Option<Watchpoint>, Error> {
+        Ok(None)
+    }
+
+    fn fault_info(&mut self) -> Result<Option<FaultInfo>, Error> {
+        Ok(None)
+    }
+
+    fn enable_vector_catch(&mut self, _condition: VectorCatchCondition) -> Result<(), Error> {
+        Err(Error::Probe(DebugProbeError::CommandNotSupportedByProbe(
+            "enable_vector_catch",
+        )))
+    }
+
+    fn disable_vector_catch(&mut self, _condition: VectorCatchCondition) -> Result<(), Error> {
+        Err(Error::Probe(DebugProbeError::CommandNotSupportedByProbe(
+            "disable_vector_catch",
+        )))
+    }
+
     fn architecture(&self) -> Architecture {
         Architecture::Arm
     }
@@ -633,6 +681,21 @@ impl<'probe> MemoryInterface for Armv7a<'probe> {
         // Return the byte
         Ok(data.to_le_bytes()[byte_offset as usize])
     }
+    fn read_word_16(&mut self, address: u32) -> Result<u16, Error> {
+        if address % 2 != 0 {
+            return Err(AccessPortError::alignment_error(address, 2).into());
+        }
+
+        // Find the word this is in and its byte offset
+        let byte_offset = address % 4;
+        let word_start = address - byte_offset;
+
+        // Read the word
+        let data = self.read_word_32(word_start)?;
+
+        // Return the halfword
+        Ok((data >> (byte_offset * 8)) as u16)
+    }
     fn read_32(&mut self, address: u32, data: &mut [u32]) -> Result<(), Error> {
         for (i, word) in data.iter_mut().enumerate() {
             *word = self.read_word_32(address + ((i as u32) * 4))?;
@@ -640,6 +703,13 @@ impl<'probe> MemoryInterface for Armv7a<'probe> {
 
         Ok(())
     }
+    fn read_16(&mut self, address: u32, data: &mut [u16]) -> Result<(), Error> {
+        for (i, word) in data.iter_mut().enumerate() {
+            *word = self.read_word_16(address + ((i as u32) * 2))?;
+        }
+
+        Ok(())
+    }
     fn read_8(&mut self, address: u32, data: &mut [u8]) -> Result<(), Error> {
         for (i, byte) in data.iter_mut().enumerate() {
             *byte = self.read_word_8(address + (i as u32))?;
@@ -672,6 +742,22 @@ impl<'probe> MemoryInterface for Armv7a<'probe> {
 
         self.write_word_32(word_start, u32::from_le_bytes(word_bytes))
     }
+    fn write_word_16(&mut self, address: u32, data: u16) -> Result<(), Error> {
+        if address % 2 != 0 {
+            return Err(AccessPortError::alignment_error(address, 2).into());
+        }
+
+        // Find the word this is in and its byte offset
+        let byte_offset = address % 4;
+        let word_start = address - byte_offset;
+
+        // Get the current word value
+        let current_word = self.read_word_32(word_start)?;
+        let bit_offset = byte_offset * 8;
+        let word = current_word & !(0xFFFF << bit_offset) | (u32::from(data) << bit_offset);
+
+        self.write_word_32(word_start, word)
+    }
     fn write_32(&mut self, address: u32, data: &[u32]) -> Result<(), Error> {
         for (i, word) in data.iter().enumerate() {
             self.write_word_32(address + ((i as u32) * 4), *word)?;
@@ -679,6 +765,13 @@ impl<'probe> MemoryInterface for Armv7a<'probe> {
 
         Ok(())
     }
+    fn write_16(&mut self, address: u32, data: &[u16]) -> Result<(), Error> {
+        for (i, word) in data.iter().enumerate() {
+            self.write_word_16(address + ((i as u32) * 2), *word)?;
+        }
+
+        Ok(())
+    }
     fn write_8(&mut self, address: u32, data: &[u8]) -> Result<(), Error> {
         for (i, byte) in data.iter().enumerate() {
             self.write_word_8(address + ((i as u32) * 4), *byte)?;
@@ -1570,6 +1663,32 @@ mod test {
             todo!()
         }
 
+        fn read_16(&mut self, _ap: MemoryAp, address: u32, data: &mut [u16]) -> Result<(), Error> {
+            for (offset, halfword) in (0..).step_by(2).zip(data.iter_mut()) {
+                let address = address + offset;
+                if self.expected_ops.len() == 0 {
+                    panic!("Received unexpected read_16 op: address {:#010x}", address);
+                }
+
+                let expected_op = self.expected_ops.remove(0);
+
+                assert_eq!(
+                    expected_op.read, true,
+                    "R/W mismatch for address {:#010x}",
+                    address
+                );
+                assert_eq!(
+                    expected_op.address, address,
+                    "Read from unexpected address: Expected {:#010x} Actual: {:#010x}",
+                    expected_op.address, address
+                );
+
+                *halfword = expected_op.value as u16;
+            }
+
+            Ok(())
+        }
+
         fn read_32(&mut self, _ap: MemoryAp, address: u32, data: &mut [u32]) -> Result<(), Error> {
             if self.expected_ops.len() == 0 {
                 panic!(
@@ -1606,6 +1725,33 @@ mod test {
             todo!()
         }
 
+        fn write_16(&mut self, _ap: MemoryAp, address: u32, data: &[u16]) -> Result<(), Error> {
+            for (offset, halfword) in (0..).step_by(2).zip(data.iter()) {
+                let address = address + offset;
+                if self.expected_ops.len() == 0 {
+                    panic!("Received unexpected write_16 op: address {:#010x}", address);
+                }
+
+                let expected_op = self.expected_ops.remove(0);
+
+                assert_eq!(expected_op.read, false);
+                assert_eq!(
+                    expected_op.address, address,
+                    "Write to unexpected address: Expected {:#010x} Actual: {:#010x}",
+                    expected_op.address, address
+                );
+                assert_eq!(
+                    expected_op.value,
+                    u32::from(*halfword),
+                    "Write value mismatch Expected {:#X} Actual: {:#X}",
+                    expected_op.value,
+                    halfword
+                );
+            }
+
+            Ok(())
+        }
+
         fn write_32(&mut self, _ap: MemoryAp, address: u32, data: &[u32]) -> Result<(), Error> {
             if self.expected_ops.len() == 0 {
                 panic!(
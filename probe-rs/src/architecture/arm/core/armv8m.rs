@@ -1,19 +1,21 @@
 //! Register types and the core interface for armv8-M
 
+use crate::architecture::arm::component::{self, DwtVersion};
 use crate::architecture::arm::sequences::ArmDebugSequence;
-use crate::core::RegisterFile;
+use crate::core::{RegisterFile, Watchpoint};
 use crate::error::Error;
 use crate::memory::Memory;
 use crate::CoreRegisterAddress;
 use crate::{
     architecture::arm::core::register, CoreStatus, DebugProbeError, HaltReason, MemoryInterface,
 };
-use crate::{Architecture, CoreInformation};
+use crate::{Architecture, CoreInformation, FaultInfo, VectorCatchCondition};
 use crate::{CoreInterface, CoreRegister, CoreType, InstructionSet};
 use anyhow::Result;
 
 use bitfield::bitfield;
 
+use super::fault::{self, FaultModel};
 use super::{Dfsr, State, ARM_REGISTER_FILE};
 use std::sync::Arc;
 use std::{
@@ -147,6 +149,9 @@ impl<'probe> CoreInterface for Armv8m<'probe> {
         // Set the vc_corereset bit in the DEMCR register.
         // This will halt the core after reset.
 
+        // The reset catch is cleared again after the reset, so remember if it was enabled as a vector catch.
+        let reset_vector_catch = Demcr(self.memory.read_word_32(Demcr::ADDRESS)?).vc_corereset();
+
         self.sequence
             .reset_catch_set(&mut self.memory, crate::CoreType::Armv8m, None)?;
         self.sequence
@@ -163,6 +168,9 @@ impl<'probe> CoreInterface for Armv8m<'probe> {
 
         self.sequence
             .reset_catch_clear(&mut self.memory, crate::CoreType::Armv8m, None)?;
+        if reset_vector_catch {
+            self.enable_vector_catch(VectorCatchCondition::CoreReset)?;
+        }
 
         // try to read the program counter
         let pc_value = self.read_core_reg(register::PC.address)?;
@@ -270,6 +278,50 @@ impl<'probe> CoreInterface for Armv8m<'probe> {
         Ok(())
     }
 
+    fn available_watchpoint_units(&mut self) -> Result<u32, Error> {
+        component::watchpoint_units(self)
+    }
+
+    fn hw_watchpoints(&mut self) -> Result<Vec<Option<Watchpoint>>, Error> {
+        component::watchpoints(self, DwtVersion::V8)
+    }
+
+    fn set_hw_watchpoint(
+        &mut self,
+        unit_index: usize,
+        watchpoint: Watchpoint,
+    ) -> Result<(), Error> {
+        component::set_watchpoint(self, DwtVersion::V8, unit_index, watchpoint)
+    }
+
+    fn clear_hw_watchpoint(&mut self, unit_index: usize) -> Result<(), Error> {
+        component::clear_watchpoint(self, unit_index)
+    }
+
+    fn triggered_watchpoint(&mut self) -> Result<Option<Watchpoint>, Error> {
+        component::triggered_watchpoint(self, DwtVersion::V8)
+    }
+
+    fn enable_vector_catch(&mut self, condition: VectorCatchCondition) -> Result<(), Error> {
+        let mut demcr = Demcr(self.memory.read_word_32(Demcr::ADDRESS)?);
+        demcr.set_vector_catch(condition, true)?;
+        self.memory.write_word_32(Demcr::ADDRESS, demcr.into())?;
+
+        Ok(())
+    }
+
+    fn disable_vector_catch(&mut self, condition: VectorCatchCondition) -> Result<(), Error> {
+        let mut demcr = Demcr(self.memory.read_word_32(Demcr::ADDRESS)?);
+        demcr.set_vector_catch(condition, false)?;
+        self.memory.write_word_32(Demcr::ADDRESS, demcr.into())?;
+
+        Ok(())
+    }
+
+    fn fault_info(&mut self) -> Result<Option<FaultInfo>, Error> {
+        fault::read_fault_info(self, FaultModel::V8)
+    }
+
     fn hw_breakpoints_enabled(&self) -> bool {
         self.state.hw_breakpoints_enabled
     }
@@ -376,24 +428,36 @@ impl<'probe> MemoryInterface for Armv8m<'probe> {
     fn read_word_32(&mut self, address: u32) -> Result<u32, Error> {
         self.memory.read_word_32(address)
     }
+    fn read_word_16(&mut self, address: u32) -> Result<u16, Error> {
+        self.memory.read_word_16(address)
+    }
     fn read_word_8(&mut self, address: u32) -> Result<u8, Error> {
         self.memory.read_word_8(address)
     }
     fn read_32(&mut self, address: u32, data: &mut [u32]) -> Result<(), Error> {
         self.memory.read_32(address, data)
     }
+    fn read_16(&mut self, address: u32, data: &mut [u16]) -> Result<(), Error> {
+        self.memory.read_16(address, data)
+    }
     fn read_8(&mut self, address: u32, data: &mut [u8]) -> Result<(), Error> {
         self.memory.read_8(address, data)
     }
     fn write_word_32(&mut self, address: u32, data: u32) -> Result<(), Error> {
         self.memory.write_word_32(address, data)
     }
+    fn write_word_16(&mut self, address: u32, data: u16) -> Result<(), Error> {
+        self.memory.write_word_16(address, data)
+    }
     fn write_word_8(&mut self, address: u32, data: u8) -> Result<(), Error> {
         self.memory.write_word_8(address, data)
     }
     fn write_32(&mut self, address: u32, data: &[u32]) -> Result<(), Error> {
         self.memory.write_32(address, data)
     }
+    fn write_16(&mut self, address: u32, data: &[u16]) -> Result<(), Error> {
+        self.memory.write_16(address, data)
+    }
     fn write_8(&mut self, address: u32, data: &[u8]) -> Result<(), Error> {
         self.memory.write_8(address, data)
     }
@@ -861,6 +925,31 @@ impl CoreRegister for Demcr {
     const NAME: &'static str = "DEMCR";
 }
 
+impl Demcr {
+    /// Sets or clears the vector catch bits which belong to `condition`.
+    fn set_vector_catch(
+        &mut self,
+        condition: VectorCatchCondition,
+        enabled: bool,
+    ) -> Result<(), Error> {
+        match condition {
+            VectorCatchCondition::HardFault => self.set_vc_harderr(enabled),
+            VectorCatchCondition::CoreReset => self.set_vc_corereset(enabled),
+            VectorCatchCondition::MemManage => self.set_vc_mmerr(enabled),
+            VectorCatchCondition::BusFault => self.set_vc_buserr(enabled),
+            VectorCatchCondition::UsageFault => {
+                self.set_vc_staterr(enabled);
+                self.set_vc_chkerr(enabled);
+                self.set_vc_nocperr(enabled);
+            }
+            VectorCatchCondition::InterruptError => self.set_vc_interr(enabled),
+            VectorCatchCondition::SecureFault => self.set_vc_sferr(enabled),
+        }
+
+        Ok(())
+    }
+}
+
 bitfield! {
     /// Flash Patch Control Register, FP_CTRL (see armv8-M Architecture Reference Manual D1.2.108)
     #[derive(Copy,Clone)]
@@ -0,0 +1,202 @@
+//! Decoding of the exception frame, which Cortex-M cores push to the stack on exception entry.
+//!
+//! See the [ARMv7-M Architecture Reference Manual](https://developer.arm.com/documentation/ddi0403/ee),
+//! Section B1.5.6 and B1.5.8, and the ARMv8-M Architecture Reference Manual, Section B3.19.
+
+use crate::{Error, MemoryInterface};
+
+/// The `EXC_RETURN` value, which the core loads into LR on exception entry.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct ExcReturn(u32);
+
+impl ExcReturn {
+    /// Interprets the value of LR as `EXC_RETURN`, if it starts with `0xFF`.
+    ///
+    /// The `0xFFFF_FFFF` value of LR after reset is not an `EXC_RETURN` value.
+    pub(crate) fn from_lr(lr: u32) -> Option<Self> {
+        if lr >> 24 == 0xFF && lr != u32::MAX {
+            Some(ExcReturn(lr))
+        } else {
+            None
+        }
+    }
+
+    /// Bit 2 (`SPSEL`) is set if the frame was pushed to the process stack.
+    pub(crate) fn uses_process_stack(self) -> bool {
+        self.0 & (1 << 2) != 0
+    }
+
+    /// Bit 4 (`FType`) is cleared if the frame was extended with S0-S15 and FPSCR.
+    pub(crate) fn has_fpu_context(self) -> bool {
+        self.0 & (1 << 4) == 0
+    }
+
+    /// Bit 5 (`DCRS`) is cleared on ARMv8-M if the integrity signature and R4-R11 were stacked below
+    /// the basic frame. On ARMv7-M, the bit is always set.
+    pub(crate) fn has_additional_state(self) -> bool {
+        self.0 & (1 << 5) == 0
+    }
+}
+
+/// Size of the additional state context of ARMv8-M: the integrity signature, a reserved word and R4-R11.
+const ADDITIONAL_STATE_SIZE: u32 = 0x28;
+/// Size of the basic frame: R0-R3, R12, LR, PC and xPSR.
+const BASIC_FRAME_SIZE: u32 = 0x20;
+/// Size of the floating-point extension of the frame: S0-S15, FPSCR and a reserved word.
+const FPU_CONTEXT_SIZE: u32 = 0x48;
+
+/// The registers of the code which was interrupted by an exception, as they were stacked on exception entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ExceptionFrame {
+    /// R0-R3.
+    pub(crate) arguments: [u32; 4],
+    pub(crate) r12: u32,
+    pub(crate) lr: u32,
+    /// The address of the instruction which was interrupted, or which caused a fault.
+    pub(crate) pc: u32,
+    pub(crate) xpsr: u32,
+    /// R4-R11, if they were stacked in the additional state context of ARMv8-M.
+    pub(crate) callee_saved: Option<[u32; 8]>,
+    /// S0-S15, if the frame was extended with the floating-point context.
+    pub(crate) fpu_registers: Option<[u32; 16]>,
+    /// The value of the stack pointer before the frame was pushed.
+    pub(crate) stack_pointer: u32,
+}
+
+impl ExceptionFrame {
+    /// Reads the frame which starts at `stack_pointer`, which is the value of the stack pointer
+    /// selected by `exc_return` directly after exception entry.
+    pub(crate) fn read(
+        memory: &mut impl MemoryInterface,
+        exc_return: ExcReturn,
+        stack_pointer: u32,
+    ) -> Result<Self, Error> {
+        let mut frame_address = stack_pointer;
+
+        let callee_saved = if exc_return.has_additional_state() {
+            let mut additional_state = [0u32; 10];
+            memory.read_32(frame_address, &mut additional_state)?;
+            frame_address += ADDITIONAL_STATE_SIZE;
+
+            let mut callee_saved = [0u32; 8];
+            callee_saved.copy_from_slice(&additional_state[2..]);
+            Some(callee_saved)
+        } else {
+            None
+        };
+
+        let mut basic_frame = [0u32; 8];
+        memory.read_32(frame_address, &mut basic_frame)?;
+        let [r0, r1, r2, r3, r12, lr, pc, xpsr] = basic_frame;
+        frame_address += BASIC_FRAME_SIZE;
+
+        let fpu_registers = if exc_return.has_fpu_context() {
+            let mut fpu_registers = [0u32; 16];
+            memory.read_32(frame_address, &mut fpu_registers)?;
+            frame_address += FPU_CONTEXT_SIZE;
+            Some(fpu_registers)
+        } else {
+            None
+        };
+
+        // Bit 9 of the stacked xPSR is set if the stack was realigned to 8 bytes on exception entry.
+        if xpsr & (1 << 9) != 0 {
+            frame_address += 4;
+        }
+
+        Ok(ExceptionFrame {
+            arguments: [r0, r1, r2, r3],
+            r12,
+            lr,
+            pc,
+            xpsr,
+            callee_saved,
+            fpu_registers,
+            stack_pointer: frame_address,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ExcReturn, ExceptionFrame};
+    use crate::core::test::{MockCore, RAM_START};
+    use crate::MemoryInterface;
+
+    /// Writes the words `0x100..` to the stack at [`RAM_START`], and reads the frame.
+    fn read_frame(exc_return: u32, xpsr_offset: Option<usize>) -> ExceptionFrame {
+        let mut memory = MockCore::new();
+        let mut stack: Vec<u32> = (0x100..0x100 + 40).collect();
+        if let Some(offset) = xpsr_offset {
+            stack[offset] |= 1 << 9;
+        }
+        memory.write_32(RAM_START, &stack).unwrap();
+
+        ExceptionFrame::read(&mut memory, ExcReturn(exc_return), RAM_START).unwrap()
+    }
+
+    #[test]
+    fn exc_return() {
+        assert_eq!(
+            ExcReturn::from_lr(0xFFFF_FFF9),
+            Some(ExcReturn(0xFFFF_FFF9))
+        );
+        assert_eq!(ExcReturn::from_lr(0xFFFF_FFFF), None);
+        assert_eq!(ExcReturn::from_lr(0x0800_0401), None);
+
+        assert!(ExcReturn(0xFFFF_FFFD).uses_process_stack());
+        assert!(!ExcReturn(0xFFFF_FFF9).uses_process_stack());
+        assert!(ExcReturn(0xFFFF_FFE9).has_fpu_context());
+        assert!(!ExcReturn(0xFFFF_FFF9).has_fpu_context());
+        assert!(ExcReturn(0xFFFF_FF9D).has_additional_state());
+        assert!(!ExcReturn(0xFFFF_FFBC).has_additional_state());
+    }
+
+    #[test]
+    fn basic_frame() {
+        let frame = read_frame(0xFFFF_FFF9, None);
+
+        assert_eq!(frame.arguments, [0x100, 0x101, 0x102, 0x103]);
+        assert_eq!(frame.r12, 0x104);
+        assert_eq!(frame.lr, 0x105);
+        assert_eq!(frame.pc, 0x106);
+        assert_eq!(frame.xpsr, 0x107);
+        assert_eq!(frame.callee_saved, None);
+        assert_eq!(frame.fpu_registers, None);
+        assert_eq!(frame.stack_pointer, RAM_START + 0x20);
+    }
+
+    #[test]
+    fn alignment_padding() {
+        let frame = read_frame(0xFFFF_FFF9, Some(7));
+
+        assert_eq!(frame.xpsr, 0x107 | 1 << 9);
+        assert_eq!(frame.stack_pointer, RAM_START + 0x24);
+    }
+
+    #[test]
+    fn fpu_frame() {
+        let frame = read_frame(0xFFFF_FFE9, Some(7));
+
+        assert_eq!(frame.pc, 0x106);
+        let fpu_registers = frame.fpu_registers.unwrap();
+        assert_eq!(fpu_registers[0], 0x108);
+        assert_eq!(fpu_registers[15], 0x117);
+        assert_eq!(frame.stack_pointer, RAM_START + 0x6C);
+    }
+
+    #[test]
+    fn additional_state_context() {
+        // Secure exception returning to the Secure state, with DCRS cleared.
+        let frame = read_frame(0xFFFF_FF9D, Some(17));
+
+        assert_eq!(
+            frame.callee_saved,
+            Some([0x102, 0x103, 0x104, 0x105, 0x106, 0x107, 0x108, 0x109])
+        );
+        assert_eq!(frame.arguments, [0x10A, 0x10B, 0x10C, 0x10D]);
+        assert_eq!(frame.pc, 0x110);
+        assert_eq!(frame.xpsr, 0x111 | 1 << 9);
+        assert_eq!(frame.stack_pointer, RAM_START + 0x28 + 0x20 + 4);
+    }
+}
@@ -0,0 +1,799 @@
+//! Decoding of the fault status registers of ARMv6-M, ARMv7-M and ARMv8-M cores.
+//!
+//! When a Cortex-M core runs into a fault, the reason is recorded in the fault
+//! status registers of the System Control Block. The functions in this module
+//! read these registers, together with the exception frame which was stacked on
+//! fault entry, and turn them into a [`FaultInfo`].
+
+use std::fmt;
+
+use bitfield::bitfield;
+
+use super::exception_frame::{ExcReturn, ExceptionFrame};
+use super::register;
+use crate::{CoreInterface, CoreRegister, CoreType, Error, MemoryInterface};
+
+/// The fault status registers available on a core, which depend on the architecture version.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum FaultModel {
+    /// ARMv6-M only has a HardFault, without any fault status registers.
+    V6,
+    /// ARMv7-M has the configurable fault status registers.
+    V7,
+    /// ARMv8-M adds stack limit checking and, with the security extension, the SecureFault.
+    V8,
+}
+
+impl FaultModel {
+    /// Returns the fault model of a Cortex-M core, or `None` for other cores.
+    pub(crate) fn from_core_type(core_type: CoreType) -> Option<Self> {
+        match core_type {
+            CoreType::Armv6m => Some(FaultModel::V6),
+            CoreType::Armv7m | CoreType::Armv7em => Some(FaultModel::V7),
+            CoreType::Armv8m => Some(FaultModel::V8),
+            CoreType::Armv7a | CoreType::Riscv => None,
+        }
+    }
+
+    /// The addresses of the registers [`read_fault_info`] reads with this fault model.
+    pub(crate) fn registers(self) -> Vec<u32> {
+        let mut registers = vec![DHCSR, Icsr::ADDRESS, VTOR, DFSR];
+
+        if self != FaultModel::V6 {
+            registers.extend_from_slice(&[Cfsr::ADDRESS, Hfsr::ADDRESS, MMFAR, BFAR]);
+        }
+        if self == FaultModel::V8 {
+            registers.extend_from_slice(&[Sfsr::ADDRESS, SFAR]);
+        }
+
+        registers
+    }
+}
+
+bitfield! {
+    /// Interrupt Control and State Register, ICSR (see armv7-M Architecture Reference Manual B3.2.4)
+    #[derive(Copy, Clone)]
+    pub struct Icsr(u32);
+    impl Debug;
+    /// The exception number of the current executing exception. `0` means thread mode.
+    pub vectactive, _: 8, 0;
+}
+
+impl From<u32> for Icsr {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<Icsr> for u32 {
+    fn from(value: Icsr) -> Self {
+        value.0
+    }
+}
+
+impl CoreRegister for Icsr {
+    const ADDRESS: u32 = 0xE000_ED04;
+    const NAME: &'static str = "ICSR";
+}
+
+bitfield! {
+    /// Configurable Fault Status Register, CFSR (see armv7-M Architecture Reference Manual B3.2.15)
+    ///
+    /// This register combines the MemManage (MMFSR), BusFault (BFSR) and UsageFault (UFSR) status registers.
+    #[derive(Copy, Clone)]
+    pub struct Cfsr(u32);
+    impl Debug;
+    /// The processor attempted a division by zero.
+    pub divbyzero, _: 25;
+    /// The processor attempted an unaligned access which is not allowed.
+    pub unaligned, _: 24;
+    /// A stack overflow was detected by the stack limit registers (ARMv8-M only).
+    pub stkof, _: 20;
+    /// The processor attempted to access a coprocessor which is not present or not enabled.
+    pub nocp, _: 19;
+    /// The processor attempted an illegal load of EXC_RETURN to the PC.
+    pub invpc, _: 18;
+    /// The processor attempted to execute an instruction with an invalid EPSR value, e.g. without the Thumb bit set.
+    pub invstate, _: 17;
+    /// The processor attempted to execute an undefined instruction.
+    pub undefinstr, _: 16;
+    /// BFAR holds a valid fault address.
+    pub bfarvalid, _: 15;
+    /// A bus fault occurred during floating-point lazy state preservation.
+    pub lsperr, _: 13;
+    /// Stacking for an exception entry caused a bus fault.
+    pub stkerr, _: 12;
+    /// Unstacking for an exception return caused a bus fault.
+    pub unstkerr, _: 11;
+    /// An imprecise data bus error occurred.
+    pub impreciserr, _: 10;
+    /// A precise data bus error occurred, the faulting address is in BFAR.
+    pub preciserr, _: 9;
+    /// A bus fault occurred on an instruction prefetch.
+    pub ibuserr, _: 8;
+    /// MMFAR holds a valid fault address.
+    pub mmarvalid, _: 7;
+    /// A MemManage fault occurred during floating-point lazy state preservation.
+    pub mlsperr, _: 5;
+    /// Stacking for an exception entry caused a MemManage fault.
+    pub mstkerr, _: 4;
+    /// Unstacking for an exception return caused a MemManage fault.
+    pub munstkerr, _: 3;
+    /// A data access violated the MPU configuration, the faulting address is in MMFAR.
+    pub daccviol, _: 1;
+    /// An instruction fetch violated the MPU configuration, or was from an XN region.
+    pub iaccviol, _: 0;
+}
+
+impl From<u32> for Cfsr {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<Cfsr> for u32 {
+    fn from(value: Cfsr) -> Self {
+        value.0
+    }
+}
+
+impl CoreRegister for Cfsr {
+    const ADDRESS: u32 = 0xE000_ED28;
+    const NAME: &'static str = "CFSR";
+}
+
+bitfield! {
+    /// HardFault Status Register, HFSR (see armv7-M Architecture Reference Manual B3.2.16)
+    #[derive(Copy, Clone)]
+    pub struct Hfsr(u32);
+    impl Debug;
+    /// A debug event occurred while halting debug was disabled.
+    pub debugevt, _: 31;
+    /// A configurable fault was escalated to a HardFault.
+    pub forced, _: 30;
+    /// A bus fault occurred while reading the vector table.
+    pub vecttbl, _: 1;
+}
+
+impl From<u32> for Hfsr {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<Hfsr> for u32 {
+    fn from(value: Hfsr) -> Self {
+        value.0
+    }
+}
+
+impl CoreRegister for Hfsr {
+    const ADDRESS: u32 = 0xE000_ED2C;
+    const NAME: &'static str = "HFSR";
+}
+
+bitfield! {
+    /// Secure Fault Status Register, SFSR (see armv8-M Architecture Reference Manual D1.2.214)
+    #[derive(Copy, Clone)]
+    pub struct Sfsr(u32);
+    impl Debug;
+    /// A lazy state preservation error occurred.
+    pub lserr, _: 7;
+    /// SFAR holds a valid fault address.
+    pub sfarvalid, _: 6;
+    /// A SAU or IDAU violation occurred during floating-point lazy state preservation.
+    pub lsperr, _: 5;
+    /// A branch to the Non-secure state was attempted without clearing bit 0 of the target address.
+    pub invtran, _: 4;
+    /// An access violated the attribution given by the SAU or IDAU.
+    pub auviol, _: 3;
+    /// An exception return with an invalid EXC_RETURN value or integrity signature occurred.
+    pub inver, _: 2;
+    /// The integrity signature of an exception frame was invalid on unstacking.
+    pub invis, _: 1;
+    /// A Non-secure caller jumped to an invalid Secure entry point.
+    pub invep, _: 0;
+}
+
+impl From<u32> for Sfsr {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<Sfsr> for u32 {
+    fn from(value: Sfsr) -> Self {
+        value.0
+    }
+}
+
+impl CoreRegister for Sfsr {
+    const ADDRESS: u32 = 0xE000_EDE4;
+    const NAME: &'static str = "SFSR";
+}
+
+/// Vector Table Offset Register, VTOR
+const VTOR: u32 = 0xE000_ED08;
+/// Debug Fault Status Register, DFSR
+const DFSR: u32 = 0xE000_ED30;
+/// MemManage Fault Address Register, MMFAR
+const MMFAR: u32 = 0xE000_ED34;
+/// BusFault Address Register, BFAR
+const BFAR: u32 = 0xE000_ED38;
+/// Secure Fault Address Register, SFAR
+const SFAR: u32 = 0xE000_EDE8;
+/// Debug Halting Control and Status Register, DHCSR
+const DHCSR: u32 = 0xE000_EDF0;
+/// The `S_LOCKUP` bit in DHCSR.
+const DHCSR_S_LOCKUP: u32 = 1 << 19;
+
+/// The fault exception a Cortex-M core is handling.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FaultException {
+    /// A HardFault, either directly or escalated from a configurable fault.
+    HardFault,
+    /// A memory protection fault reported by the MPU.
+    MemManage,
+    /// A fault on a memory access over the bus.
+    BusFault,
+    /// A fault caused by the execution of an instruction.
+    UsageFault,
+    /// A violation of the security extension (ARMv8-M only).
+    SecureFault,
+}
+
+impl FaultException {
+    /// Returns the fault which belongs to the given exception number, if any.
+    fn from_exception_number(number: u32) -> Option<Self> {
+        match number {
+            3 => Some(FaultException::HardFault),
+            4 => Some(FaultException::MemManage),
+            5 => Some(FaultException::BusFault),
+            6 => Some(FaultException::UsageFault),
+            7 => Some(FaultException::SecureFault),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for FaultException {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            FaultException::HardFault => "HardFault",
+            FaultException::MemManage => "MemManage fault",
+            FaultException::BusFault => "BusFault",
+            FaultException::UsageFault => "UsageFault",
+            FaultException::SecureFault => "SecureFault",
+        };
+
+        f.write_str(name)
+    }
+}
+
+/// A single cause of a fault, decoded from one of the fault status registers.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FaultCause {
+    /// A configurable fault was escalated to a HardFault (HFSR.FORCED).
+    Forced,
+    /// A bus fault occurred while reading the vector table (HFSR.VECTTBL).
+    VectorTableRead,
+    /// A debug event occurred while halting debug was disabled (HFSR.DEBUGEVT).
+    DebugEvent,
+    /// An instruction fetch violated the MPU configuration (MMFSR.IACCVIOL).
+    InstructionAccessViolation,
+    /// A data access violated the MPU configuration (MMFSR.DACCVIOL).
+    DataAccessViolation {
+        /// The accessed address, if MMFAR is valid.
+        address: Option<u32>,
+    },
+    /// Unstacking for an exception return caused a MemManage fault (MMFSR.MUNSTKERR).
+    MemManageUnstacking,
+    /// Stacking for an exception entry caused a MemManage fault (MMFSR.MSTKERR).
+    MemManageStacking,
+    /// A MemManage fault occurred during lazy floating-point state preservation (MMFSR.MLSPERR).
+    MemManageLazyStatePreservation,
+    /// A bus fault occurred on an instruction prefetch (BFSR.IBUSERR).
+    InstructionBusError,
+    /// A precise data bus error occurred (BFSR.PRECISERR).
+    PreciseDataBusError {
+        /// The accessed address, if BFAR is valid.
+        address: Option<u32>,
+    },
+    /// An imprecise data bus error occurred, the stacked PC does not point at the faulting instruction (BFSR.IMPRECISERR).
+    ImpreciseDataBusError,
+    /// Unstacking for an exception return caused a bus fault (BFSR.UNSTKERR).
+    BusFaultUnstacking,
+    /// Stacking for an exception entry caused a bus fault (BFSR.STKERR).
+    BusFaultStacking,
+    /// A bus fault occurred during lazy floating-point state preservation (BFSR.LSPERR).
+    BusFaultLazyStatePreservation,
+    /// An undefined instruction was executed (UFSR.UNDEFINSTR).
+    UndefinedInstruction,
+    /// An instruction was executed with an invalid EPSR, usually a branch to an address without the Thumb bit (UFSR.INVSTATE).
+    InvalidState,
+    /// An invalid EXC_RETURN value was loaded into the PC (UFSR.INVPC).
+    InvalidExceptionReturn,
+    /// A disabled or absent coprocessor, e.g. the FPU, was accessed (UFSR.NOCP).
+    NoCoprocessor,
+    /// A stack limit check failed (UFSR.STKOF).
+    StackOverflow,
+    /// An unaligned memory access was trapped (UFSR.UNALIGNED).
+    UnalignedAccess,
+    /// An integer division by zero was trapped (UFSR.DIVBYZERO).
+    DivideByZero,
+    /// A Non-secure caller jumped to an invalid Secure entry point (SFSR.INVEP).
+    InvalidEntryPoint,
+    /// The integrity signature of an exception frame was invalid (SFSR.INVIS).
+    InvalidIntegritySignature,
+    /// An invalid exception return occurred in the security extension (SFSR.INVER).
+    InvalidSecureExceptionReturn,
+    /// An access violated the SAU or IDAU attribution (SFSR.AUVIOL).
+    AttributionUnitViolation {
+        /// The accessed address, if SFAR is valid.
+        address: Option<u32>,
+    },
+    /// A branch to Non-secure code did not clear bit 0 of the target address (SFSR.INVTRAN).
+    InvalidTransition,
+    /// A SecureFault occurred during lazy floating-point state preservation (SFSR.LSPERR or SFSR.LSERR).
+    SecureLazyStatePreservation,
+}
+
+impl fmt::Display for FaultCause {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FaultCause::Forced => f.write_str("configurable fault escalated to a HardFault"),
+            FaultCause::VectorTableRead => f.write_str("bus fault while reading the vector table"),
+            FaultCause::DebugEvent => f.write_str("debug event while halting debug was disabled"),
+            FaultCause::InstructionAccessViolation => {
+                f.write_str("instruction fetch from a protected or non-executable region")
+            }
+            FaultCause::DataAccessViolation { address } => {
+                with_address(f, "MPU data access violation", address)
+            }
+            FaultCause::MemManageUnstacking => {
+                f.write_str("MemManage fault while unstacking an exception frame")
+            }
+            FaultCause::MemManageStacking => {
+                f.write_str("MemManage fault while stacking an exception frame")
+            }
+            FaultCause::MemManageLazyStatePreservation => {
+                f.write_str("MemManage fault during lazy floating-point state preservation")
+            }
+            FaultCause::InstructionBusError => f.write_str("bus error on instruction fetch"),
+            FaultCause::PreciseDataBusError { address } => {
+                with_address(f, "precise data bus error", address)
+            }
+            FaultCause::ImpreciseDataBusError => f.write_str(
+                "imprecise data bus error, the faulting instruction precedes the stacked PC",
+            ),
+            FaultCause::BusFaultUnstacking => {
+                f.write_str("bus fault while unstacking an exception frame")
+            }
+            FaultCause::BusFaultStacking => {
+                f.write_str("bus fault while stacking an exception frame, the stack may be corrupt or overflowed")
+            }
+            FaultCause::BusFaultLazyStatePreservation => {
+                f.write_str("bus fault during lazy floating-point state preservation")
+            }
+            FaultCause::UndefinedInstruction => f.write_str("undefined instruction"),
+            FaultCause::InvalidState => f.write_str(
+                "invalid execution state, e.g. a branch to an address without the Thumb bit set",
+            ),
+            FaultCause::InvalidExceptionReturn => f.write_str("invalid EXC_RETURN value"),
+            FaultCause::NoCoprocessor => {
+                f.write_str("access to a disabled or absent coprocessor, e.g. the FPU")
+            }
+            FaultCause::StackOverflow => f.write_str("stack overflow detected by the stack limit"),
+            FaultCause::UnalignedAccess => f.write_str("unaligned memory access"),
+            FaultCause::DivideByZero => f.write_str("integer division by zero"),
+            FaultCause::InvalidEntryPoint => f.write_str("call to an invalid Secure entry point"),
+            FaultCause::InvalidIntegritySignature => {
+                f.write_str("invalid integrity signature in an exception frame")
+            }
+            FaultCause::InvalidSecureExceptionReturn => {
+                f.write_str("invalid exception return in the security extension")
+            }
+            FaultCause::AttributionUnitViolation { address } => {
+                with_address(f, "security attribution violation", address)
+            }
+            FaultCause::InvalidTransition => {
+                f.write_str("branch to Non-secure code without clearing bit 0 of the address")
+            }
+            FaultCause::SecureLazyStatePreservation => {
+                f.write_str("SecureFault during lazy floating-point state preservation")
+            }
+        }
+    }
+}
+
+/// Writes `text`, followed by the fault address if it is known.
+fn with_address(f: &mut fmt::Formatter<'_>, text: &str, address: &Option<u32>) -> fmt::Result {
+    match address {
+        Some(address) => write!(f, "{} at address {:#010x}", text, address),
+        None => f.write_str(text),
+    }
+}
+
+/// The decoded state of a Cortex-M core which is handling a fault or is locked up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FaultInfo {
+    /// The fault exception which is currently active, if the core is not locked up.
+    pub exception: Option<FaultException>,
+    /// `true` if the core is locked up, because it faulted while handling a HardFault or NMI.
+    pub locked_up: bool,
+    /// The raw value of the Configurable Fault Status Register, `0` on ARMv6-M.
+    pub cfsr: u32,
+    /// The raw value of the HardFault Status Register, `0` on ARMv6-M.
+    pub hfsr: u32,
+    /// The raw value of the Debug Fault Status Register.
+    ///
+    /// Note that [`Core::status`](crate::Core::status) clears this register when it reads the halt reason.
+    pub dfsr: u32,
+    /// The MemManage fault address, if MMFAR holds a valid address.
+    pub mmfar: Option<u32>,
+    /// The bus fault address, if BFAR holds a valid address.
+    pub bfar: Option<u32>,
+    /// The raw value of the Secure Fault Status Register, if the core implements the security extension.
+    pub sfsr: Option<u32>,
+    /// The SecureFault address, if SFAR holds a valid address.
+    pub sfar: Option<u32>,
+    /// The program counter at the time of the fault, taken from the stacked exception frame.
+    ///
+    /// It is only available if the core is halted at the entry of the fault handler, e.g. by a vector catch.
+    pub pc: Option<u32>,
+    /// The link register at the time of the fault, taken from the stacked exception frame.
+    pub lr: Option<u32>,
+}
+
+impl FaultInfo {
+    /// Returns all causes of the fault which are recorded in the fault status registers.
+    pub fn causes(&self) -> Vec<FaultCause> {
+        let hfsr = Hfsr(self.hfsr);
+        let cfsr = Cfsr(self.cfsr);
+
+        let mut causes = Vec::new();
+
+        let mut add = |condition: bool, cause: FaultCause| {
+            if condition {
+                causes.push(cause);
+            }
+        };
+
+        add(hfsr.forced(), FaultCause::Forced);
+        add(hfsr.vecttbl(), FaultCause::VectorTableRead);
+        add(hfsr.debugevt(), FaultCause::DebugEvent);
+
+        add(cfsr.iaccviol(), FaultCause::InstructionAccessViolation);
+        add(
+            cfsr.daccviol(),
+            FaultCause::DataAccessViolation {
+                address: self.mmfar,
+            },
+        );
+        add(cfsr.munstkerr(), FaultCause::MemManageUnstacking);
+        add(cfsr.mstkerr(), FaultCause::MemManageStacking);
+        add(cfsr.mlsperr(), FaultCause::MemManageLazyStatePreservation);
+
+        add(cfsr.ibuserr(), FaultCause::InstructionBusError);
+        add(
+            cfsr.preciserr(),
+            FaultCause::PreciseDataBusError { address: self.bfar },
+        );
+        add(cfsr.impreciserr(), FaultCause::ImpreciseDataBusError);
+        add(cfsr.unstkerr(), FaultCause::BusFaultUnstacking);
+        add(cfsr.stkerr(), FaultCause::BusFaultStacking);
+        add(cfsr.lsperr(), FaultCause::BusFaultLazyStatePreservation);
+
+        add(cfsr.undefinstr(), FaultCause::UndefinedInstruction);
+        add(cfsr.invstate(), FaultCause::InvalidState);
+        add(cfsr.invpc(), FaultCause::InvalidExceptionReturn);
+        add(cfsr.nocp(), FaultCause::NoCoprocessor);
+        add(cfsr.stkof(), FaultCause::StackOverflow);
+        add(cfsr.unaligned(), FaultCause::UnalignedAccess);
+        add(cfsr.divbyzero(), FaultCause::DivideByZero);
+
+        if let Some(sfsr) = self.sfsr.map(Sfsr) {
+            add(sfsr.invep(), FaultCause::InvalidEntryPoint);
+            add(sfsr.invis(), FaultCause::InvalidIntegritySignature);
+            add(sfsr.inver(), FaultCause::InvalidSecureExceptionReturn);
+            add(
+                sfsr.auviol(),
+                FaultCause::AttributionUnitViolation { address: self.sfar },
+            );
+            add(sfsr.invtran(), FaultCause::InvalidTransition);
+            add(
+                sfsr.lsperr() || sfsr.lserr(),
+                FaultCause::SecureLazyStatePreservation,
+            );
+        }
+
+        causes
+    }
+
+    /// Returns a one line description of the fault, e.g. for the status line of a debugger.
+    pub fn summary(&self) -> String {
+        let mut summary = match (self.locked_up, self.exception) {
+            (true, _) => "Core locked up".to_string(),
+            (false, Some(exception)) => exception.to_string(),
+            (false, None) => "Fault".to_string(),
+        };
+
+        // A forced HardFault is better described by the fault it was escalated from.
+        if let Some(cause) = self
+            .causes()
+            .into_iter()
+            .find(|cause| *cause != FaultCause::Forced)
+        {
+            summary.push_str(": ");
+            summary.push_str(&cause.to_string());
+        }
+
+        if let Some(pc) = self.pc {
+            summary.push_str(&format!(" (PC = {:#010x})", pc));
+        }
+
+        summary
+    }
+}
+
+impl fmt::Display for FaultInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.locked_up, self.exception) {
+            (true, Some(exception)) => {
+                writeln!(f, "Core locked up while handling a {}", exception)?
+            }
+            (true, None) => writeln!(f, "Core locked up")?,
+            (false, Some(exception)) => writeln!(f, "{}", exception)?,
+            (false, None) => writeln!(f, "Fault")?,
+        }
+
+        let causes = self.causes();
+        if causes.is_empty() {
+            writeln!(f, "  Cause: unknown, no fault status bits are set")?;
+        }
+        for cause in causes {
+            writeln!(f, "  Cause: {}", cause)?;
+        }
+
+        if let Some(pc) = self.pc {
+            writeln!(f, "  PC:    {:#010x}", pc)?;
+        }
+        if let Some(lr) = self.lr {
+            writeln!(f, "  LR:    {:#010x}", lr)?;
+        }
+
+        write!(
+            f,
+            "  CFSR:  {:#010x}  HFSR: {:#010x}  DFSR: {:#010x}",
+            self.cfsr, self.hfsr, self.dfsr
+        )?;
+
+        if let Some(sfsr) = self.sfsr {
+            write!(f, "  SFSR: {:#010x}", sfsr)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Reads the fault state of a halted Cortex-M core.
+///
+/// Returns `None` if the core is neither handling a fault exception nor locked up.
+pub(crate) fn read_fault_info(
+    core: &mut impl CoreInterface,
+    model: FaultModel,
+) -> Result<Option<FaultInfo>, Error> {
+    let locked_up = core.read_word_32(DHCSR)? & DHCSR_S_LOCKUP != 0;
+    let vectactive = Icsr(core.read_word_32(Icsr::ADDRESS)?).vectactive();
+    let exception = FaultException::from_exception_number(vectactive);
+
+    if exception.is_none() && !locked_up {
+        return Ok(None);
+    }
+
+    let mut info = FaultInfo {
+        exception,
+        locked_up,
+        cfsr: 0,
+        hfsr: 0,
+        dfsr: core.read_word_32(DFSR)?,
+        mmfar: None,
+        bfar: None,
+        sfsr: None,
+        sfar: None,
+        pc: None,
+        lr: None,
+    };
+
+    if model != FaultModel::V6 {
+        info.cfsr = core.read_word_32(Cfsr::ADDRESS)?;
+        info.hfsr = core.read_word_32(Hfsr::ADDRESS)?;
+
+        let cfsr = Cfsr(info.cfsr);
+        if cfsr.mmarvalid() {
+            info.mmfar = Some(core.read_word_32(MMFAR)?);
+        }
+        if cfsr.bfarvalid() {
+            info.bfar = Some(core.read_word_32(BFAR)?);
+        }
+    }
+
+    if model == FaultModel::V8 {
+        // SFSR is only implemented with the security extension, and may not be
+        // accessible at all from the Non-secure side.
+        match core.read_word_32(Sfsr::ADDRESS) {
+            Ok(sfsr) => {
+                info.sfsr = Some(sfsr);
+                if Sfsr(sfsr).sfarvalid() {
+                    info.sfar = Some(core.read_word_32(SFAR)?);
+                }
+            }
+            Err(error) => log::debug!("Unable to read SFSR: {}", error),
+        }
+    }
+
+    // The core registers can only be read while the core is halted, which is not the case when it is locked up.
+    if exception.is_some() {
+        match read_stacked_frame(core, vectactive) {
+            Ok(Some(frame)) => {
+                info.pc = Some(frame.pc);
+                info.lr = Some(frame.lr);
+            }
+            Ok(None) => {}
+            Err(error) => log::debug!("Unable to read the stacked exception frame: {}", error),
+        }
+    }
+
+    Ok(Some(info))
+}
+
+/// Reads the exception frame the core stacked when it entered the current exception.
+fn read_stacked_frame(
+    core: &mut impl CoreInterface,
+    exception_number: u32,
+) -> Result<Option<ExceptionFrame>, Error> {
+    let state = HandlerState {
+        vtor: core.read_word_32(VTOR)?,
+        pc: core.read_core_reg(register::PC.address)?,
+        lr: core.read_core_reg(register::LR.address)?,
+        msp: core.read_core_reg(register::MSP.address)?,
+        psp: core.read_core_reg(register::PSP.address)?,
+    };
+
+    decode_stacked_frame(core, exception_number, &state)
+}
+
+/// The registers needed to locate the exception frame.
+struct HandlerState {
+    vtor: u32,
+    pc: u32,
+    lr: u32,
+    msp: u32,
+    psp: u32,
+}
+
+/// Decodes the exception frame of the exception `exception_number`.
+///
+/// Without debug information, the frame can only be located at the entry of the handler,
+/// e.g. when the core was halted by a vector catch, because the prologue of the handler
+/// changes LR and SP. Elsewhere, `None` is returned.
+fn decode_stacked_frame(
+    memory: &mut impl MemoryInterface,
+    exception_number: u32,
+    state: &HandlerState,
+) -> Result<Option<ExceptionFrame>, Error> {
+    let handler = memory.read_word_32(state.vtor + 4 * exception_number)?;
+    if handler & !1 != state.pc {
+        log::debug!(
+            "The core is not at the entry of the handler of exception {} (PC = {:#010x}), unable to locate the exception frame",
+            exception_number,
+            state.pc
+        );
+        return Ok(None);
+    }
+
+    let exc_return = match ExcReturn::from_lr(state.lr) {
+        Some(exc_return) => exc_return,
+        None => {
+            log::debug!(
+                "LR does not contain an EXC_RETURN value ({:#010x}), unable to locate the exception frame",
+                state.lr
+            );
+            return Ok(None);
+        }
+    };
+
+    let stack_pointer = if exc_return.uses_process_stack() {
+        state.psp
+    } else {
+        state.msp
+    };
+
+    ExceptionFrame::read(memory, exc_return, stack_pointer).map(Some)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{decode_stacked_frame, HandlerState};
+    use crate::core::test::{MockCore, RAM_START};
+    use crate::MemoryInterface;
+
+    /// Exception number of the HardFault.
+    const HARD_FAULT: u32 = 3;
+    const HANDLER: u32 = 0x0800_0400;
+
+    /// A vector table at [`RAM_START`] with the HardFault handler at [`HANDLER`], and a frame
+    /// which interrupted `0x0800_0200` on the main stack at `RAM_START + 0x40` and on the
+    /// process stack at `RAM_START + 0x80`.
+    fn memory() -> MockCore {
+        let mut memory = MockCore::new();
+        memory
+            .write_word_32(RAM_START + 4 * HARD_FAULT, HANDLER | 1)
+            .unwrap();
+        memory
+            .write_32(
+                RAM_START + 0x40,
+                &[0, 1, 2, 3, 12, 0x0800_0101, 0x0800_0200, 0x0100_0000],
+            )
+            .unwrap();
+        memory
+            .write_32(
+                RAM_START + 0x80,
+                &[0, 1, 2, 3, 12, 0x0800_0301, 0x0800_0300, 0x0100_0000],
+            )
+            .unwrap();
+        memory
+    }
+
+    fn handler_entry(lr: u32) -> HandlerState {
+        HandlerState {
+            vtor: RAM_START,
+            pc: HANDLER,
+            lr,
+            msp: RAM_START + 0x40,
+            psp: RAM_START + 0x80,
+        }
+    }
+
+    #[test]
+    fn main_stack_frame() {
+        let frame = decode_stacked_frame(&mut memory(), HARD_FAULT, &handler_entry(0xFFFF_FFF9))
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(frame.pc, 0x0800_0200);
+        assert_eq!(frame.lr, 0x0800_0101);
+        assert_eq!(frame.stack_pointer, RAM_START + 0x60);
+    }
+
+    #[test]
+    fn process_stack_frame() {
+        let frame = decode_stacked_frame(&mut memory(), HARD_FAULT, &handler_entry(0xFFFF_FFFD))
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(frame.pc, 0x0800_0300);
+        assert_eq!(frame.lr, 0x0800_0301);
+        assert_eq!(frame.stack_pointer, RAM_START + 0xA0);
+    }
+
+    #[test]
+    fn frame_is_not_located_after_handler_entry() {
+        // The prologue of the handler already ran, so SP no longer points to the frame.
+        let mut state = handler_entry(0xFFFF_FFF9);
+        state.pc = HANDLER + 4;
+
+        assert_eq!(
+            decode_stacked_frame(&mut memory(), HARD_FAULT, &state).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn frame_is_not_located_without_exc_return() {
+        assert_eq!(
+            decode_stacked_frame(&mut memory(), HARD_FAULT, &handler_entry(0xFFFF_FFFF)).unwrap(),
+            None
+        );
+    }
+}
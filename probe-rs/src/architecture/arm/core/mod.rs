@@ -9,26 +9,8 @@ pub mod armv6m;
 pub mod armv7a;
 pub mod armv7m;
 pub mod armv8m;
-
-/// Core information data which is downloaded from the target, represents its state and can be used for debugging.
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Dump {
-    /// The register values at the time of the dump.
-    pub regs: [u32; 16],
-    stack_addr: u32,
-    stack: Vec<u8>,
-}
-
-impl Dump {
-    /// Create a new dump from a SP and a stack dump with zeroed out registers.
-    pub fn new(stack_addr: u32, stack: Vec<u8>) -> Dump {
-        Dump {
-            regs: [0u32; 16],
-            stack_addr,
-            stack,
-        }
-    }
-}
+pub(crate) mod exception_frame;
+pub(crate) mod fault;
 
 pub(crate) mod register {
     use crate::{
@@ -80,6 +62,12 @@ pub(crate) mod register {
         address: CoreRegisterAddress(0b10100),
     };
 
+    pub const FPSCR: RegisterDescription = RegisterDescription {
+        name: "FPSCR",
+        _kind: RegisterKind::General,
+        address: CoreRegisterAddress(0b010_0001),
+    };
+
     // TODO: Floating point support
     pub const FP: RegisterDescription = RegisterDescription {
         name: "FP",
@@ -88,7 +76,7 @@ pub(crate) mod register {
     };
 }
 
-static ARM_REGISTER_FILE: RegisterFile = RegisterFile {
+pub(crate) static ARM_REGISTER_FILE: RegisterFile = RegisterFile {
     platform_registers: &[
         RegisterDescription {
             name: "R0",
@@ -216,7 +204,171 @@ static ARM_REGISTER_FILE: RegisterFile = RegisterFile {
     msp: Some(&register::MSP),
     psp: Some(&register::PSP),
     extra: Some(&register::EXTRA),
-    // TODO: Floating point support
+
+    fpscr: Some(&register::FPSCR),
+
+    fpu_registers: Some(&[
+        RegisterDescription {
+            name: "S0",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(0b1000000),
+        },
+        RegisterDescription {
+            name: "S1",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(0b1000001),
+        },
+        RegisterDescription {
+            name: "S2",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(0b1000010),
+        },
+        RegisterDescription {
+            name: "S3",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(0b1000011),
+        },
+        RegisterDescription {
+            name: "S4",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(0b1000100),
+        },
+        RegisterDescription {
+            name: "S5",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(0b1000101),
+        },
+        RegisterDescription {
+            name: "S6",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(0b1000110),
+        },
+        RegisterDescription {
+            name: "S7",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(0b1000111),
+        },
+        RegisterDescription {
+            name: "S8",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(0b1001000),
+        },
+        RegisterDescription {
+            name: "S9",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(0b1001001),
+        },
+        RegisterDescription {
+            name: "S10",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(0b1001010),
+        },
+        RegisterDescription {
+            name: "S11",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(0b1001011),
+        },
+        RegisterDescription {
+            name: "S12",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(0b1001100),
+        },
+        RegisterDescription {
+            name: "S13",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(0b1001101),
+        },
+        RegisterDescription {
+            name: "S14",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(0b1001110),
+        },
+        RegisterDescription {
+            name: "S15",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(0b1001111),
+        },
+        RegisterDescription {
+            name: "S16",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(0b1010000),
+        },
+        RegisterDescription {
+            name: "S17",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(0b1010001),
+        },
+        RegisterDescription {
+            name: "S18",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(0b1010010),
+        },
+        RegisterDescription {
+            name: "S19",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(0b1010011),
+        },
+        RegisterDescription {
+            name: "S20",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(0b1010100),
+        },
+        RegisterDescription {
+            name: "S21",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(0b1010101),
+        },
+        RegisterDescription {
+            name: "S22",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(0b1010110),
+        },
+        RegisterDescription {
+            name: "S23",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(0b1010111),
+        },
+        RegisterDescription {
+            name: "S24",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(0b1011000),
+        },
+        RegisterDescription {
+            name: "S25",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(0b1011001),
+        },
+        RegisterDescription {
+            name: "S26",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(0b1011010),
+        },
+        RegisterDescription {
+            name: "S27",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(0b1011011),
+        },
+        RegisterDescription {
+            name: "S28",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(0b1011100),
+        },
+        RegisterDescription {
+            name: "S29",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(0b1011101),
+        },
+        RegisterDescription {
+            name: "S30",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(0b1011110),
+        },
+        RegisterDescription {
+            name: "S31",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(0b1011111),
+        },
+    ]),
 };
 
 bitfield! {
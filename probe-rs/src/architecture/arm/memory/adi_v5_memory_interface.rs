@@ -26,9 +26,11 @@ pub trait ArmProbe: SwdSequence {
     ) -> Result<(), Error>;
 
     fn read_8(&mut self, ap: MemoryAp, address: u32, data: &mut [u8]) -> Result<(), Error>;
+    fn read_16(&mut self, ap: MemoryAp, address: u32, data: &mut [u16]) -> Result<(), Error>;
     fn read_32(&mut self, ap: MemoryAp, address: u32, data: &mut [u32]) -> Result<(), Error>;
 
     fn write_8(&mut self, ap: MemoryAp, address: u32, data: &[u8]) -> Result<(), Error>;
+    fn write_16(&mut self, ap: MemoryAp, address: u32, data: &[u16]) -> Result<(), Error>;
     fn write_32(&mut self, ap: MemoryAp, address: u32, data: &[u32]) -> Result<(), Error>;
 
     fn flush(&mut self) -> Result<(), Error>;
@@ -262,6 +264,42 @@ where
         Ok(result)
     }
 
+    /// Read a 16bit word at `addr`.
+    ///
+    /// The address where the read should be performed at has to be halfword aligned.
+    /// Returns `AccessPortError::MemoryNotAligned` if this does not hold true.
+    pub fn read_word_16(
+        &mut self,
+        access_port: MemoryAp,
+        address: u32,
+    ) -> Result<u16, AccessPortError> {
+        if (address % 2) != 0 {
+            return Err(AccessPortError::alignment_error(address, 2));
+        }
+
+        let aligned = aligned_range(address, 2)?;
+
+        // Offset of halfword in word (little endian)
+        let bit_offset = (address - aligned.start) * 8;
+
+        let result = if self.only_32bit_data_size {
+            // Read 32-bit word and extract the correct halfword
+            ((self.read_word_32(access_port, aligned.start)? >> bit_offset) & 0xFFFF) as u16
+        } else {
+            let csw = self.build_csw_register(DataSize::U16);
+            let tar = TAR { address };
+            self.write_csw_register(access_port, csw)?;
+            self.write_ap_register(access_port, tar)?;
+            let result: DRW = self.read_ap_register(access_port)?;
+
+            // Extract the correct halfword
+            // See "Arm Debug Interface Architecture Specification ADIv5.0 to ADIv5.2", C2.2.6
+            ((result.data >> bit_offset) & 0xFFFF) as u16
+        };
+
+        Ok(result)
+    }
+
     /// Read a block of words of the size defined by S at `addr`.
     ///
     /// The number of words read is `data.len()`.
@@ -385,6 +423,24 @@ where
         Ok(())
     }
 
+    /// Read a block of 16bit words at `addr`.
+    ///
+    /// The number of words read is `data.len()`.
+    /// Every word is read with a separate 16bit access, so this can be used
+    /// for peripherals which only support halfword accesses.
+    pub fn read_16(
+        &mut self,
+        access_port: MemoryAp,
+        address: u32,
+        data: &mut [u16],
+    ) -> Result<(), AccessPortError> {
+        for (offset, word) in (0..).step_by(2).zip(data.iter_mut()) {
+            *word = self.read_word_16(access_port, address + offset)?;
+        }
+
+        Ok(())
+    }
+
     /// Write a 32bit word at `addr`.
     ///
     /// The address where the write should be performed at has to be word aligned.
@@ -444,6 +500,47 @@ where
         Ok(())
     }
 
+    /// Write a 16bit word at `addr`.
+    ///
+    /// The address where the write should be performed at has to be halfword aligned.
+    /// Returns `AccessPortError::MemoryNotAligned` if this does not hold true.
+    pub fn write_word_16(
+        &mut self,
+        access_port: MemoryAp,
+        address: u32,
+        data: u16,
+    ) -> Result<(), AccessPortError> {
+        if (address % 2) != 0 {
+            return Err(AccessPortError::alignment_error(address, 2));
+        }
+
+        let aligned = aligned_range(address, 2)?;
+
+        // Offset of halfword in word (little endian)
+        let bit_offset = (address - aligned.start) * 8;
+
+        if self.only_32bit_data_size {
+            // Read the existing 32-bit word and insert the halfword at the correct bit offset
+            // See "Arm Debug Interface Architecture Specification ADIv5.0 to ADIv5.2", C2.2.6
+            let word = self.read_word_32(access_port, aligned.start)?;
+            let word = word & !(0xFFFF << bit_offset) | (u32::from(data) << bit_offset);
+
+            self.write_word_32(access_port, aligned.start, word)?;
+        } else {
+            let csw = self.build_csw_register(DataSize::U16);
+            let drw = DRW {
+                data: u32::from(data) << bit_offset,
+            };
+            let tar = TAR { address };
+            self.write_csw_register(access_port, csw)?;
+
+            self.write_ap_register(access_port, tar)?;
+            self.write_ap_register(access_port, drw)?;
+        }
+
+        Ok(())
+    }
+
     /// Write a block of 32bit words at `addr`.
     ///
     /// The number of words written is `data.len()`.
@@ -543,6 +640,23 @@ where
         Ok(())
     }
 
+    /// Write a block of 16bit words at `addr`.
+    ///
+    /// The number of words written is `data.len()`.
+    /// Every word is written with a separate 16bit access.
+    pub fn write_16(
+        &mut self,
+        access_port: MemoryAp,
+        address: u32,
+        data: &[u16],
+    ) -> Result<(), AccessPortError> {
+        for (offset, word) in (0..).step_by(2).zip(data.iter()) {
+            self.write_word_16(access_port, address + offset, *word)?;
+        }
+
+        Ok(())
+    }
+
     /// Write a block of 8bit words at `addr`.
     ///
     /// The number of words written is `data.len()`.
@@ -658,6 +772,12 @@ where
         Ok(())
     }
 
+    fn read_16(&mut self, ap: MemoryAp, address: u32, data: &mut [u16]) -> Result<(), Error> {
+        self.read_16(ap, address, data)?;
+
+        Ok(())
+    }
+
     fn read_32(&mut self, ap: MemoryAp, address: u32, data: &mut [u32]) -> Result<(), Error> {
         if data.len() == 1 {
             data[0] = self.read_word_32(ap, address)?;
@@ -678,6 +798,12 @@ where
         Ok(())
     }
 
+    fn write_16(&mut self, ap: MemoryAp, address: u32, data: &[u16]) -> Result<(), Error> {
+        self.write_16(ap, address, data)?;
+
+        Ok(())
+    }
+
     fn write_32(&mut self, ap: MemoryAp, address: u32, data: &[u32]) -> Result<(), Error> {
         if data.len() == 1 {
             self.write_word_32(ap, address, data[0])?;
@@ -752,7 +878,7 @@ bitfield! {
     pub struct Dcrsr(u32);
     impl Debug;
     pub _, set_regwnr: 16;
-    pub _, set_regsel: 4,0;
+    pub _, set_regsel: 6,0;
 }
 
 impl From<u32> for Dcrsr {
@@ -920,6 +1046,44 @@ mod tests {
         }
     }
 
+    #[test]
+    fn read_word_16() {
+        let mut mock = MockMemoryAp::with_pattern();
+        mock.memory[..8].copy_from_slice(&DATA8[..8]);
+        let mut mi = ADIMemoryInterface::new_mock(&mut mock);
+
+        for address in (0..8).step_by(2) {
+            let value = mi
+                .read_word_16(DUMMY_AP, address)
+                .unwrap_or_else(|_| panic!("read_word_16 failed, address = {}", address));
+            let expected =
+                u16::from_le_bytes([DATA8[address as usize], DATA8[address as usize + 1]]);
+            assert_eq!(value, expected, "address = {}", address);
+        }
+
+        assert!(mi.read_word_16(DUMMY_AP, 1).is_err());
+    }
+
+    #[test]
+    fn write_word_16() {
+        for address in (0..8).step_by(2) {
+            let mut mock = MockMemoryAp::with_pattern();
+            let mut mi = ADIMemoryInterface::new_mock(&mut mock);
+
+            let mut expected = Vec::from(mi.mock_memory());
+            expected[address..address + 2].copy_from_slice(&DATA8[..2]);
+
+            mi.write_word_16(DUMMY_AP, address as u32, 0x8180)
+                .unwrap_or_else(|_| panic!("write_word_16 failed, address = {}", address));
+            assert_eq!(
+                mi.mock_memory(),
+                expected.as_slice(),
+                "address = {}",
+                address
+            );
+        }
+    }
+
     #[test]
     fn read_32() {
         let mut mock = MockMemoryAp::with_pattern();
@@ -20,6 +20,5 @@ pub use self::core::armv6m;
 pub use self::core::armv7a;
 pub use self::core::armv7m;
 pub use self::core::armv8m;
-pub use self::core::Dump;
 
 pub use communication_interface::ArmProbeInterface;
@@ -0,0 +1,747 @@
+//! Parser and evaluator for the statements and expressions of the CMSIS-Pack debug sequence language.
+//!
+//! The language is a small subset of C: all values are 64-bit unsigned integers,
+//! variables are declared with `__var`, and the debug access functions like `Read32` are
+//! provided by an [`Environment`].
+
+use std::fmt;
+
+use super::SequenceError;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(u64),
+    String(String),
+    Identifier(String),
+    Symbol(&'static str),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Number(value) => value.fmt(f),
+            Token::String(value) => write!(f, "\"{}\"", value),
+            Token::Identifier(identifier) => identifier.fmt(f),
+            Token::Symbol(symbol) => symbol.fmt(f),
+        }
+    }
+}
+
+/// Symbols, with the longer ones first so they take precedence.
+const SYMBOLS: [&str; 37] = [
+    "<<=", ">>=", "||", "&&", "==", "!=", "<=", ">=", "<<", ">>", "+=", "-=", "*=", "/=", "%=",
+    "&=", "|=", "^=", "(", ")", ",", ";", "?", ":", "+", "-", "*", "/", "%", "&", "|", "^", "!",
+    "~", "<", ">", "=",
+];
+
+/// A binary operator, in the order of the C operator precedence.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum BinaryOperator {
+    Or,
+    And,
+    BitOr,
+    BitXor,
+    BitAnd,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    ShiftLeft,
+    ShiftRight,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+}
+
+/// The binary operators with their precedence. Operators with a higher precedence bind stronger.
+const BINARY_OPERATORS: [(&str, BinaryOperator, u8); 18] = [
+    ("||", BinaryOperator::Or, 1),
+    ("&&", BinaryOperator::And, 2),
+    ("|", BinaryOperator::BitOr, 3),
+    ("^", BinaryOperator::BitXor, 4),
+    ("&", BinaryOperator::BitAnd, 5),
+    ("==", BinaryOperator::Equal, 6),
+    ("!=", BinaryOperator::NotEqual, 6),
+    ("<", BinaryOperator::Less, 7),
+    ("<=", BinaryOperator::LessEqual, 7),
+    (">", BinaryOperator::Greater, 7),
+    (">=", BinaryOperator::GreaterEqual, 7),
+    ("<<", BinaryOperator::ShiftLeft, 8),
+    (">>", BinaryOperator::ShiftRight, 8),
+    ("+", BinaryOperator::Add, 9),
+    ("-", BinaryOperator::Subtract, 9),
+    ("*", BinaryOperator::Multiply, 10),
+    ("/", BinaryOperator::Divide, 10),
+    ("%", BinaryOperator::Remainder, 10),
+];
+
+/// The assignment operators, and the binary operator they apply before assigning.
+const ASSIGNMENT_OPERATORS: [(&str, Option<BinaryOperator>); 11] = [
+    ("=", None),
+    ("+=", Some(BinaryOperator::Add)),
+    ("-=", Some(BinaryOperator::Subtract)),
+    ("*=", Some(BinaryOperator::Multiply)),
+    ("/=", Some(BinaryOperator::Divide)),
+    ("%=", Some(BinaryOperator::Remainder)),
+    ("&=", Some(BinaryOperator::BitAnd)),
+    ("|=", Some(BinaryOperator::BitOr)),
+    ("^=", Some(BinaryOperator::BitXor)),
+    ("<<=", Some(BinaryOperator::ShiftLeft)),
+    (">>=", Some(BinaryOperator::ShiftRight)),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum UnaryOperator {
+    /// Logical not, `!`.
+    Not,
+    /// Bitwise complement, `~`.
+    Complement,
+    /// Two's complement negation, `-`.
+    Negate,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Expression {
+    Number(u64),
+    /// String literals are only valid as function arguments.
+    String(String),
+    Variable(String),
+    Unary(UnaryOperator, Box<Expression>),
+    Binary(BinaryOperator, Box<Expression>, Box<Expression>),
+    /// `condition ? then : otherwise`
+    Conditional(Box<Expression>, Box<Expression>, Box<Expression>),
+    Call(String, Vec<Expression>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Statement {
+    /// `__var name = value;`
+    Declaration {
+        name: String,
+        value: Expression,
+    },
+    /// `name = value;`, or a compound assignment like `name |= value;`.
+    Assignment {
+        name: String,
+        operator: Option<BinaryOperator>,
+        value: Expression,
+    },
+    Expression(Expression),
+}
+
+/// An evaluated function argument.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Argument {
+    Number(u64),
+    String(String),
+}
+
+impl Argument {
+    pub(crate) fn number(&self) -> Result<u64, SequenceError> {
+        match self {
+            Argument::Number(value) => Ok(*value),
+            Argument::String(value) => Err(SequenceError::Syntax(format!(
+                "Expected a number, found the string \"{}\"",
+                value
+            ))),
+        }
+    }
+
+    pub(crate) fn string(&self) -> Result<&str, SequenceError> {
+        match self {
+            Argument::String(value) => Ok(value),
+            Argument::Number(value) => Err(SequenceError::Syntax(format!(
+                "Expected a string, found the number {}",
+                value
+            ))),
+        }
+    }
+}
+
+/// The variables and functions which are available to a sequence.
+pub(crate) trait Environment {
+    /// Returns the value of a variable, or `None` if it is not declared.
+    fn variable(&self, name: &str) -> Option<u64>;
+
+    /// Declares a variable in the current scope.
+    fn declare(&mut self, name: &str, value: u64);
+
+    /// Assigns a value to a declared variable.
+    fn assign(&mut self, name: &str, value: u64) -> Result<(), SequenceError>;
+
+    /// Calls a built-in function.
+    fn call(&mut self, name: &str, arguments: &[Argument]) -> Result<u64, SequenceError>;
+}
+
+/// Parses the statements of a `<block>`.
+pub(crate) fn parse_statements(code: &str) -> Result<Vec<Statement>, SequenceError> {
+    let mut parser = Parser {
+        tokens: tokenize(code)?,
+        position: 0,
+    };
+
+    let mut statements = Vec::new();
+    while parser.peek().is_some() {
+        if !parser.consume(";") {
+            statements.push(parser.statement()?);
+            if parser.peek().is_some() {
+                parser.expect(";")?;
+            }
+        }
+    }
+
+    Ok(statements)
+}
+
+/// Parses a single expression, like the condition of a `<control>` element.
+pub(crate) fn parse_expression(code: &str) -> Result<Expression, SequenceError> {
+    let mut parser = Parser {
+        tokens: tokenize(code)?,
+        position: 0,
+    };
+
+    let expression = parser.expression()?;
+    if let Some(token) = parser.peek() {
+        return Err(SequenceError::Syntax(format!(
+            "Unexpected '{}' in expression '{}'",
+            token, code
+        )));
+    }
+
+    Ok(expression)
+}
+
+/// Executes statements in the given environment.
+pub(crate) fn execute(
+    statements: &[Statement],
+    environment: &mut dyn Environment,
+) -> Result<(), SequenceError> {
+    for statement in statements {
+        match statement {
+            Statement::Declaration { name, value } => {
+                let value = evaluate(value, environment)?;
+                environment.declare(name, value);
+            }
+            Statement::Assignment {
+                name,
+                operator,
+                value,
+            } => {
+                let mut value = evaluate(value, environment)?;
+                if let Some(operator) = operator {
+                    let current = environment
+                        .variable(name)
+                        .ok_or_else(|| SequenceError::UnknownVariable(name.clone()))?;
+                    value = apply(*operator, current, value)?;
+                }
+                environment.assign(name, value)?;
+            }
+            Statement::Expression(expression) => {
+                evaluate(expression, environment)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Evaluates an expression in the given environment.
+pub(crate) fn evaluate(
+    expression: &Expression,
+    environment: &mut dyn Environment,
+) -> Result<u64, SequenceError> {
+    Ok(match expression {
+        Expression::Number(value) => *value,
+        Expression::String(value) => {
+            return Err(SequenceError::Syntax(format!(
+                "The string \"{}\" can only be used as a function argument",
+                value
+            )))
+        }
+        Expression::Variable(name) => environment
+            .variable(name)
+            .ok_or_else(|| SequenceError::UnknownVariable(name.clone()))?,
+        Expression::Unary(operator, operand) => {
+            let operand = evaluate(operand, environment)?;
+            match operator {
+                UnaryOperator::Not => (operand == 0) as u64,
+                UnaryOperator::Complement => !operand,
+                UnaryOperator::Negate => operand.wrapping_neg(),
+            }
+        }
+        // The logical operators short-circuit, so function calls on the right side are only made if needed.
+        Expression::Binary(BinaryOperator::And, left, right) => {
+            (evaluate(left, environment)? != 0 && evaluate(right, environment)? != 0) as u64
+        }
+        Expression::Binary(BinaryOperator::Or, left, right) => {
+            (evaluate(left, environment)? != 0 || evaluate(right, environment)? != 0) as u64
+        }
+        Expression::Binary(operator, left, right) => {
+            let left = evaluate(left, environment)?;
+            let right = evaluate(right, environment)?;
+            apply(*operator, left, right)?
+        }
+        Expression::Conditional(condition, then, otherwise) => {
+            if evaluate(condition, environment)? != 0 {
+                evaluate(then, environment)?
+            } else {
+                evaluate(otherwise, environment)?
+            }
+        }
+        Expression::Call(name, arguments) => {
+            let arguments = arguments
+                .iter()
+                .map(|argument| match argument {
+                    Expression::String(value) => Ok(Argument::String(value.clone())),
+                    argument => evaluate(argument, environment).map(Argument::Number),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            environment.call(name, &arguments)?
+        }
+    })
+}
+
+fn apply(operator: BinaryOperator, left: u64, right: u64) -> Result<u64, SequenceError> {
+    let shift = u32::try_from(right).unwrap_or(u32::MAX);
+
+    Ok(match operator {
+        BinaryOperator::Or => (left != 0 || right != 0) as u64,
+        BinaryOperator::And => (left != 0 && right != 0) as u64,
+        BinaryOperator::BitOr => left | right,
+        BinaryOperator::BitXor => left ^ right,
+        BinaryOperator::BitAnd => left & right,
+        BinaryOperator::Equal => (left == right) as u64,
+        BinaryOperator::NotEqual => (left != right) as u64,
+        BinaryOperator::Less => (left < right) as u64,
+        BinaryOperator::LessEqual => (left <= right) as u64,
+        BinaryOperator::Greater => (left > right) as u64,
+        BinaryOperator::GreaterEqual => (left >= right) as u64,
+        BinaryOperator::ShiftLeft => left.checked_shl(shift).unwrap_or(0),
+        BinaryOperator::ShiftRight => left.checked_shr(shift).unwrap_or(0),
+        BinaryOperator::Add => left.wrapping_add(right),
+        BinaryOperator::Subtract => left.wrapping_sub(right),
+        BinaryOperator::Multiply => left.wrapping_mul(right),
+        BinaryOperator::Divide => left
+            .checked_div(right)
+            .ok_or(SequenceError::DivisionByZero)?,
+        BinaryOperator::Remainder => left
+            .checked_rem(right)
+            .ok_or(SequenceError::DivisionByZero)?,
+    })
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn peek_symbol(&self) -> Option<&'static str> {
+        match self.peek() {
+            Some(Token::Symbol(symbol)) => Some(*symbol),
+            _ => None,
+        }
+    }
+
+    fn next(&mut self) -> Result<Token, SequenceError> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .cloned()
+            .ok_or_else(|| SequenceError::Syntax("Unexpected end of code".to_string()))?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    fn consume(&mut self, symbol: &str) -> bool {
+        if self.peek_symbol() == Some(symbol) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, symbol: &str) -> Result<(), SequenceError> {
+        match self.next()? {
+            Token::Symbol(found) if found == symbol => Ok(()),
+            other => Err(SequenceError::Syntax(format!(
+                "Expected '{}', found '{}'",
+                symbol, other
+            ))),
+        }
+    }
+
+    fn identifier(&mut self) -> Result<String, SequenceError> {
+        match self.next()? {
+            Token::Identifier(name) => Ok(name),
+            other => Err(SequenceError::Syntax(format!(
+                "Expected an identifier, found '{}'",
+                other
+            ))),
+        }
+    }
+
+    fn statement(&mut self) -> Result<Statement, SequenceError> {
+        if self.peek() == Some(&Token::Identifier("__var".to_string())) {
+            self.position += 1;
+            let name = self.identifier()?;
+            let value = if self.consume("=") {
+                self.expression()?
+            } else {
+                Expression::Number(0)
+            };
+            return Ok(Statement::Declaration { name, value });
+        }
+
+        if let (Some(Token::Identifier(name)), Some(Token::Symbol(symbol))) = (
+            self.tokens.get(self.position),
+            self.tokens.get(self.position + 1),
+        ) {
+            if let Some((_, operator)) = ASSIGNMENT_OPERATORS
+                .iter()
+                .find(|(assignment, _)| assignment == symbol)
+            {
+                let name = name.clone();
+                let operator = *operator;
+                self.position += 2;
+                let value = self.expression()?;
+                return Ok(Statement::Assignment {
+                    name,
+                    operator,
+                    value,
+                });
+            }
+        }
+
+        Ok(Statement::Expression(self.expression()?))
+    }
+
+    fn expression(&mut self) -> Result<Expression, SequenceError> {
+        let condition = self.binary(1)?;
+
+        if self.consume("?") {
+            let then = self.expression()?;
+            self.expect(":")?;
+            let otherwise = self.expression()?;
+            Ok(Expression::Conditional(
+                Box::new(condition),
+                Box::new(then),
+                Box::new(otherwise),
+            ))
+        } else {
+            Ok(condition)
+        }
+    }
+
+    /// Parses binary operators with at least the given precedence.
+    fn binary(&mut self, min_precedence: u8) -> Result<Expression, SequenceError> {
+        let mut left = self.unary()?;
+
+        while let Some((_, operator, precedence)) = self.peek_symbol().and_then(|symbol| {
+            BINARY_OPERATORS
+                .iter()
+                .find(|(operator, _, _)| *operator == symbol)
+        }) {
+            if *precedence < min_precedence {
+                break;
+            }
+            self.position += 1;
+            let right = self.binary(precedence + 1)?;
+            left = Expression::Binary(*operator, Box::new(left), Box::new(right));
+        }
+
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expression, SequenceError> {
+        let operator = match self.peek_symbol() {
+            Some("!") => UnaryOperator::Not,
+            Some("~") => UnaryOperator::Complement,
+            Some("-") => UnaryOperator::Negate,
+            Some("+") => {
+                self.position += 1;
+                return self.unary();
+            }
+            _ => return self.primary(),
+        };
+        self.position += 1;
+
+        Ok(Expression::Unary(operator, Box::new(self.unary()?)))
+    }
+
+    fn primary(&mut self) -> Result<Expression, SequenceError> {
+        match self.next()? {
+            Token::Number(value) => Ok(Expression::Number(value)),
+            Token::String(value) => Ok(Expression::String(value)),
+            Token::Identifier(name) => {
+                if !self.consume("(") {
+                    return Ok(Expression::Variable(name));
+                }
+
+                let mut arguments = Vec::new();
+                if !self.consume(")") {
+                    loop {
+                        arguments.push(self.expression()?);
+                        if self.consume(")") {
+                            break;
+                        }
+                        self.expect(",")?;
+                    }
+                }
+                Ok(Expression::Call(name, arguments))
+            }
+            Token::Symbol("(") => {
+                let expression = self.expression()?;
+                self.expect(")")?;
+                Ok(expression)
+            }
+            other => Err(SequenceError::Syntax(format!("Unexpected '{}'", other))),
+        }
+    }
+}
+
+fn tokenize(code: &str) -> Result<Vec<Token>, SequenceError> {
+    let mut tokens = Vec::new();
+    let mut rest = code.trim_start();
+
+    while let Some(c) = rest.chars().next() {
+        let length = if rest.starts_with("//") {
+            rest.find('\n').unwrap_or(rest.len())
+        } else if rest.starts_with("/*") {
+            rest.find("*/").map(|end| end + 2).ok_or_else(|| {
+                SequenceError::Syntax(format!("Unterminated comment in '{}'", code))
+            })?
+        } else if c.is_ascii_digit() {
+            let length = rest
+                .find(|c: char| !c.is_ascii_alphanumeric())
+                .unwrap_or(rest.len());
+            tokens.push(Token::Number(parse_number(&rest[..length]).ok_or_else(
+                || SequenceError::Syntax(format!("Invalid number '{}'", &rest[..length])),
+            )?));
+            length
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let length = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            tokens.push(Token::Identifier(rest[..length].to_string()));
+            length
+        } else if c == '"' {
+            let mut value = String::new();
+            let mut characters = rest.char_indices().skip(1);
+            let length = loop {
+                match characters.next() {
+                    Some((index, '"')) => break index + 1,
+                    Some((_, '\\')) => match characters.next() {
+                        Some((_, 'n')) => value.push('\n'),
+                        Some((_, 't')) => value.push('\t'),
+                        Some((_, escaped)) => value.push(escaped),
+                        None => break rest.len() + 1,
+                    },
+                    Some((_, character)) => value.push(character),
+                    None => break rest.len() + 1,
+                }
+            };
+            if length > rest.len() {
+                return Err(SequenceError::Syntax(format!(
+                    "Unterminated string in '{}'",
+                    code
+                )));
+            }
+            tokens.push(Token::String(value));
+            length
+        } else if let Some(symbol) = SYMBOLS.iter().find(|symbol| rest.starts_with(**symbol)) {
+            tokens.push(Token::Symbol(symbol));
+            symbol.len()
+        } else {
+            return Err(SequenceError::Syntax(format!(
+                "Unexpected character '{}' in '{}'",
+                c, code
+            )));
+        };
+
+        rest = rest[length..].trim_start();
+    }
+
+    Ok(tokens)
+}
+
+/// Parses a decimal or `0x` prefixed hexadecimal number, with an optional `U`, `L` or `UL` suffix.
+fn parse_number(number: &str) -> Option<u64> {
+    let number = number.trim_end_matches(['u', 'U', 'l', 'L']);
+
+    if let Some(hex) = number
+        .strip_prefix("0x")
+        .or_else(|| number.strip_prefix("0X"))
+    {
+        u64::from_str_radix(hex, 16).ok()
+    } else if let Some(binary) = number
+        .strip_prefix("0b")
+        .or_else(|| number.strip_prefix("0B"))
+    {
+        u64::from_str_radix(binary, 2).ok()
+    } else {
+        number.parse().ok()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[derive(Default)]
+    struct TestEnvironment {
+        variables: HashMap<String, u64>,
+        calls: Vec<(String, Vec<Argument>)>,
+    }
+
+    impl Environment for TestEnvironment {
+        fn variable(&self, name: &str) -> Option<u64> {
+            self.variables.get(name).copied()
+        }
+
+        fn declare(&mut self, name: &str, value: u64) {
+            self.variables.insert(name.to_string(), value);
+        }
+
+        fn assign(&mut self, name: &str, value: u64) -> Result<(), SequenceError> {
+            match self.variables.get_mut(name) {
+                Some(variable) => {
+                    *variable = value;
+                    Ok(())
+                }
+                None => Err(SequenceError::UnknownVariable(name.to_string())),
+            }
+        }
+
+        fn call(&mut self, name: &str, arguments: &[Argument]) -> Result<u64, SequenceError> {
+            self.calls.push((name.to_string(), arguments.to_vec()));
+            // Pretend every read returns the address.
+            Ok(arguments
+                .first()
+                .and_then(|argument| argument.number().ok())
+                .unwrap_or(0))
+        }
+    }
+
+    fn evaluate_str(code: &str) -> u64 {
+        evaluate(
+            &parse_expression(code).unwrap(),
+            &mut TestEnvironment::default(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn tokenize_code() {
+        assert_eq!(
+            tokenize("__var x = 0x10UL; // comment\n x <<= 2; /* more */ Message(0, \"a\\\"b\")")
+                .unwrap(),
+            vec![
+                Token::Identifier("__var".to_string()),
+                Token::Identifier("x".to_string()),
+                Token::Symbol("="),
+                Token::Number(0x10),
+                Token::Symbol(";"),
+                Token::Identifier("x".to_string()),
+                Token::Symbol("<<="),
+                Token::Number(2),
+                Token::Symbol(";"),
+                Token::Identifier("Message".to_string()),
+                Token::Symbol("("),
+                Token::Number(0),
+                Token::Symbol(","),
+                Token::String("a\"b".to_string()),
+                Token::Symbol(")"),
+            ]
+        );
+    }
+
+    #[test]
+    fn tokenize_invalid() {
+        assert!(tokenize("a # b").is_err());
+        assert!(tokenize("0xZZ").is_err());
+        assert!(tokenize("\"open").is_err());
+        assert!(tokenize("/* open").is_err());
+    }
+
+    #[test]
+    fn operator_precedence() {
+        assert_eq!(evaluate_str("1 + 2 * 3"), 7);
+        assert_eq!(evaluate_str("(1 + 2) * 3"), 9);
+        assert_eq!(evaluate_str("1 << 4 | 1"), 0x11);
+        assert_eq!(evaluate_str("0x12345678 & 0xFF == 0x78"), 0);
+        assert_eq!(evaluate_str("(0x12345678 & 0xFF) == 0x78"), 1);
+        assert_eq!(evaluate_str("10 - 4 - 3"), 3);
+        assert_eq!(evaluate_str("!0 && ~0 != 0"), 1);
+        assert_eq!(evaluate_str("-1"), u64::MAX);
+        assert_eq!(evaluate_str("1 ? 2 : 3"), 2);
+        assert_eq!(evaluate_str("0 ? 2 : 3"), 3);
+        assert_eq!(evaluate_str("1 << 64"), 0);
+    }
+
+    #[test]
+    fn division_by_zero() {
+        let expression = parse_expression("1 / 0").unwrap();
+        assert!(matches!(
+            evaluate(&expression, &mut TestEnvironment::default()),
+            Err(SequenceError::DivisionByZero)
+        ));
+    }
+
+    #[test]
+    fn execute_statements() {
+        let statements = parse_statements(
+            "__var value = Read32(0x40000000);\n value |= 0x3;\n value += 1;\n Write32(0x40000004, value);",
+        )
+        .unwrap();
+
+        let mut environment = TestEnvironment::default();
+        execute(&statements, &mut environment).unwrap();
+
+        assert_eq!(environment.variables["value"], 0x4000_0004);
+        assert_eq!(
+            environment.calls,
+            vec![
+                ("Read32".to_string(), vec![Argument::Number(0x4000_0000)]),
+                (
+                    "Write32".to_string(),
+                    vec![Argument::Number(0x4000_0004), Argument::Number(0x4000_0004)]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn short_circuit() {
+        let statements =
+            parse_statements("0 && Read32(1); 1 || Read32(2); 1 && Read32(3)").unwrap();
+
+        let mut environment = TestEnvironment::default();
+        execute(&statements, &mut environment).unwrap();
+
+        assert_eq!(
+            environment.calls,
+            vec![("Read32".to_string(), vec![Argument::Number(3)])]
+        );
+    }
+
+    #[test]
+    fn assign_undeclared_variable() {
+        let statements = parse_statements("missing = 1;").unwrap();
+
+        assert!(matches!(
+            execute(&statements, &mut TestEnvironment::default()),
+            Err(SequenceError::UnknownVariable(name)) if name == "missing"
+        ));
+    }
+}
@@ -0,0 +1,1153 @@
+//! An interpreter for the debug sequences of CMSIS-Packs.
+//!
+//! Target descriptions generated from a CMSIS-Pack contain the `<sequences>` of the pack.
+//! The [`CmsisDebugSequence`] executes them instead of the built-in implementations of
+//! the [`ArmDebugSequence`] functions, so chips with vendor specific sequences work without
+//! any chip specific code in probe-rs.
+//!
+//! See the [CMSIS Debug Description] for the sequence language.
+//!
+//! [CMSIS Debug Description]: https://open-cmsis-pack.github.io/Open-CMSIS-Pack-Spec/main/html/debug_description.html
+
+mod language;
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use probe_rs_target::{CoreType, SequenceDescription, SequenceElement};
+
+use self::language::{Argument, Environment};
+use super::{ArmDebugSequence, DefaultArmSequence};
+use crate::{
+    architecture::arm::{
+        ap::MemoryAp,
+        communication_interface::{DapProbe, Initialized, SwdSequence},
+        ApAddress, ArmCommunicationInterface, DapAccess, DpAddress, PortType,
+    },
+    DebugProbeError, Memory, WireProtocol,
+};
+
+/// An error which occurred while executing a CMSIS-Pack debug sequence.
+#[derive(thiserror::Error, Debug)]
+pub enum SequenceError {
+    /// The code of a sequence could not be parsed, or a function was called with invalid arguments.
+    #[error("Invalid debug sequence code: {0}")]
+    Syntax(String),
+    /// A variable was used before it was declared with `__var`.
+    #[error("The variable '{0}' is not declared")]
+    UnknownVariable(String),
+    /// A function of the sequence language is not supported by probe-rs.
+    #[error("The debug sequence function '{0}' is not supported")]
+    UnsupportedFunction(String),
+    /// A sequence called with `Sequence("name")` does not exist.
+    #[error("The debug sequence '{0}' is not defined")]
+    UnknownSequence(String),
+    /// A division or remainder by zero.
+    #[error("Division by zero in debug sequence")]
+    DivisionByZero,
+    /// The timeout of a `while` loop expired.
+    #[error("A loop in the debug sequence '{0}' timed out")]
+    Timeout(String),
+    /// The sequence reported an error with `Message(2, ...)`.
+    #[error("The debug sequence reported an error: {0}")]
+    Message(String),
+    /// A debug access of the sequence failed.
+    #[error("A debug access of the debug sequence failed")]
+    Target(#[source] crate::Error),
+}
+
+impl From<SequenceError> for crate::Error {
+    fn from(error: SequenceError) -> Self {
+        match error {
+            SequenceError::Target(error) => error,
+            error => crate::Error::architecture_specific(error),
+        }
+    }
+}
+
+impl From<SequenceError> for DebugProbeError {
+    fn from(error: SequenceError) -> Self {
+        match error {
+            SequenceError::Target(crate::Error::Probe(error)) => error,
+            error => DebugProbeError::ArchitectureSpecific(Box::new(error)),
+        }
+    }
+}
+
+/// An [`ArmDebugSequence`] which executes the debug sequences of a CMSIS-Pack.
+///
+/// Functions without a matching sequence use the implementation of the [`DefaultArmSequence`].
+/// Disabled sequences are skipped, without falling back to the default implementation.
+pub struct CmsisDebugSequence {
+    sequences: Vec<SequenceDescription>,
+    debug_vars: Option<String>,
+    /// The global variables, which keep their values between sequences.
+    /// They are initialized when the first sequence is executed.
+    globals: Mutex<Option<HashMap<String, u64>>>,
+}
+
+impl CmsisDebugSequence {
+    /// Creates a debug sequence which executes the given CMSIS-Pack sequences.
+    ///
+    /// `debug_vars` are the `__var` declarations from the `<debugvars>` element of the chip,
+    /// which are evaluated before the first sequence is executed.
+    pub fn create(
+        sequences: Vec<SequenceDescription>,
+        debug_vars: Option<String>,
+    ) -> Arc<dyn ArmDebugSequence> {
+        Arc::new(Self {
+            sequences,
+            debug_vars,
+            globals: Mutex::new(None),
+        })
+    }
+
+    /// Executes the sequence `name`.
+    ///
+    /// Returns `false` if the sequence is not defined, and the default implementation should be used instead.
+    fn run(
+        &self,
+        name: &str,
+        interface: &mut dyn SequenceInterface,
+    ) -> Result<bool, SequenceError> {
+        let sequence = match find_sequence(&self.sequences, name) {
+            Some(sequence) => sequence,
+            None => return Ok(false),
+        };
+
+        if sequence.disabled {
+            log::debug!("Skipping disabled debug sequence {}", name);
+            return Ok(true);
+        }
+
+        log::debug!("Executing debug sequence {}", name);
+
+        let mut globals = self
+            .globals
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let initialize = globals.is_none();
+        let globals = globals.get_or_insert_with(predefined_variables);
+
+        // The protocol and the AP of the core can differ between calls.
+        globals.insert("__protocol".to_string(), protocol(interface.protocol()));
+        globals.insert("__ap".to_string(), u64::from(interface.default_ap()));
+
+        let mut executor = Executor {
+            sequences: &self.sequences,
+            interface,
+            globals,
+            scopes: Vec::new(),
+        };
+
+        if initialize {
+            if let Some(debug_vars) = &self.debug_vars {
+                language::execute(&language::parse_statements(debug_vars)?, &mut executor)?;
+            }
+        }
+
+        executor.run_sequence(sequence)?;
+
+        Ok(true)
+    }
+}
+
+impl ArmDebugSequence for CmsisDebugSequence {
+    fn reset_hardware_assert(&self, interface: &mut dyn DapProbe) -> Result<(), crate::Error> {
+        if !self.run("ResetHardwareAssert", &mut ProbeInterface(interface))? {
+            DefaultArmSequence(()).reset_hardware_assert(interface)?;
+        }
+        Ok(())
+    }
+
+    fn reset_hardware_deassert(&self, memory: &mut Memory) -> Result<(), crate::Error> {
+        if !self.run("ResetHardwareDeassert", memory)? {
+            DefaultArmSequence(()).reset_hardware_deassert(memory)?;
+        }
+        Ok(())
+    }
+
+    fn debug_port_setup(&self, interface: &mut Box<dyn DapProbe>) -> Result<(), crate::Error> {
+        if !self.run("DebugPortSetup", &mut ProbeInterface(interface.as_mut()))? {
+            DefaultArmSequence(()).debug_port_setup(interface)?;
+        }
+        Ok(())
+    }
+
+    fn debug_port_start(
+        &self,
+        interface: &mut ArmCommunicationInterface<Initialized>,
+        dp: DpAddress,
+    ) -> Result<(), DebugProbeError> {
+        if !self.run("DebugPortStart", &mut DebugPortInterface { interface, dp })? {
+            DefaultArmSequence(()).debug_port_start(interface, dp)?;
+        }
+        Ok(())
+    }
+
+    fn debug_core_start(
+        &self,
+        core: &mut Memory,
+        core_type: CoreType,
+        debug_base: Option<u32>,
+    ) -> Result<(), crate::Error> {
+        if !self.run("DebugCoreStart", core)? {
+            DefaultArmSequence(()).debug_core_start(core, core_type, debug_base)?;
+        }
+        Ok(())
+    }
+
+    fn reset_catch_set(
+        &self,
+        core: &mut Memory,
+        core_type: CoreType,
+        debug_base: Option<u32>,
+    ) -> Result<(), crate::Error> {
+        if !self.run("ResetCatchSet", core)? {
+            DefaultArmSequence(()).reset_catch_set(core, core_type, debug_base)?;
+        }
+        Ok(())
+    }
+
+    fn reset_catch_clear(
+        &self,
+        core: &mut Memory,
+        core_type: CoreType,
+        debug_base: Option<u32>,
+    ) -> Result<(), crate::Error> {
+        if !self.run("ResetCatchClear", core)? {
+            DefaultArmSequence(()).reset_catch_clear(core, core_type, debug_base)?;
+        }
+        Ok(())
+    }
+
+    fn reset_system(
+        &self,
+        interface: &mut Memory,
+        core_type: CoreType,
+        debug_base: Option<u32>,
+    ) -> Result<(), crate::Error> {
+        if !self.run("ResetSystem", interface)? {
+            DefaultArmSequence(()).reset_system(interface, core_type, debug_base)?;
+        }
+        Ok(())
+    }
+
+    fn debug_device_unlock(
+        &self,
+        interface: &mut Memory,
+        permissions: &crate::Permissions,
+    ) -> Result<(), crate::Error> {
+        if !self.run("DebugDeviceUnlock", interface)? {
+            DefaultArmSequence(()).debug_device_unlock(interface, permissions)?;
+        }
+        Ok(())
+    }
+
+    fn recover_support_start(&self, interface: &mut Memory) -> Result<(), crate::Error> {
+        if !self.run("RecoverSupportStart", interface)? {
+            DefaultArmSequence(()).recover_support_start(interface)?;
+        }
+        Ok(())
+    }
+}
+
+/// Finds the sequence with the given name.
+///
+/// Sequences for all processors are preferred over processor specific ones,
+/// because the sequence functions are not called for a specific processor.
+fn find_sequence<'a>(
+    sequences: &'a [SequenceDescription],
+    name: &str,
+) -> Option<&'a SequenceDescription> {
+    let mut matching = sequences.iter().filter(|sequence| sequence.name == name);
+
+    matching
+        .clone()
+        .find(|sequence| sequence.processor.is_none())
+        .or_else(|| matching.next())
+}
+
+/// The predefined variables of the sequence language.
+fn predefined_variables() -> HashMap<String, u64> {
+    [
+        ("__protocol", 0),
+        // The connection is used for debugging.
+        ("__connection", 0x1),
+        ("__dp", 0),
+        ("__ap", 0),
+        ("__traceout", 0),
+        ("__errorcontrol", 0),
+        ("__FlashOp", 0),
+        ("__FlashAddr", 0),
+        ("__FlashLen", 0),
+        ("__FlashArg", 0),
+    ]
+    .iter()
+    .map(|(name, value)| (name.to_string(), *value))
+    .collect()
+}
+
+/// The value of the `__protocol` variable: the protocol in bits 0..15, and
+/// bit 16 set because probe-rs can send SWJ-DP switching sequences.
+fn protocol(protocol: Option<WireProtocol>) -> u64 {
+    let protocol = match protocol {
+        Some(WireProtocol::Jtag) => 1,
+        Some(WireProtocol::Swd) => 2,
+        None => 0,
+    };
+
+    protocol | 1 << 16
+}
+
+/// The size of a memory access.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum AccessSize {
+    U8,
+    U16,
+    U32,
+    U64,
+}
+
+/// The debug access which is available to a sequence.
+///
+/// This depends on when the sequence is executed: before the debug port is started, only the
+/// debug port and the SWJ pins can be accessed.
+trait SequenceInterface {
+    /// The wire protocol used by the probe.
+    fn protocol(&mut self) -> Option<WireProtocol>;
+
+    /// The access port used for memory accesses, unless the sequence selects another one with `__ap`.
+    fn default_ap(&mut self) -> u8;
+
+    fn read_dp(&mut self, address: u8) -> Result<u32, crate::Error>;
+
+    fn write_dp(&mut self, address: u8, value: u32) -> Result<(), crate::Error>;
+
+    fn read_ap(&mut self, ap: u8, address: u8) -> Result<u32, crate::Error>;
+
+    fn write_ap(&mut self, ap: u8, address: u8, value: u32) -> Result<(), crate::Error>;
+
+    fn read_memory(&mut self, ap: u8, address: u32, size: AccessSize) -> Result<u64, crate::Error>;
+
+    fn write_memory(
+        &mut self,
+        ap: u8,
+        address: u32,
+        size: AccessSize,
+        value: u64,
+    ) -> Result<(), crate::Error>;
+
+    fn swj_pins(
+        &mut self,
+        pin_out: u32,
+        pin_select: u32,
+        pin_wait: u32,
+    ) -> Result<u32, crate::Error>;
+
+    fn swj_sequence(&mut self, bit_len: u8, bits: u64) -> Result<(), crate::Error>;
+}
+
+fn read_memory(memory: &mut Memory, address: u32, size: AccessSize) -> Result<u64, crate::Error> {
+    Ok(match size {
+        AccessSize::U8 => u64::from(memory.read_word_8(address)?),
+        AccessSize::U16 => u64::from(memory.read_word_16(address)?),
+        AccessSize::U32 => u64::from(memory.read_word_32(address)?),
+        AccessSize::U64 => {
+            let mut words = [0; 2];
+            memory.read_32(address, &mut words)?;
+            u64::from(words[0]) | u64::from(words[1]) << 32
+        }
+    })
+}
+
+fn write_memory(
+    memory: &mut Memory,
+    address: u32,
+    size: AccessSize,
+    value: u64,
+) -> Result<(), crate::Error> {
+    match size {
+        AccessSize::U8 => memory.write_word_8(address, value as u8),
+        AccessSize::U16 => memory.write_word_16(address, value as u16),
+        AccessSize::U32 => memory.write_word_32(address, value as u32),
+        AccessSize::U64 => memory.write_32(address, &[value as u32, (value >> 32) as u32]),
+    }
+}
+
+/// Sequences which are executed with a core, like `ResetSystem`, have full access to the target.
+impl SequenceInterface for Memory<'_> {
+    fn protocol(&mut self) -> Option<WireProtocol> {
+        self.get_arm_interface()
+            .ok()
+            .and_then(|interface| interface.active_protocol())
+    }
+
+    fn default_ap(&mut self) -> u8 {
+        self.get_ap().ap
+    }
+
+    fn read_dp(&mut self, address: u8) -> Result<u32, crate::Error> {
+        let dp = self.get_ap().dp;
+        Ok(self
+            .get_arm_interface()?
+            .read_raw_dp_register(dp, address)?)
+    }
+
+    fn write_dp(&mut self, address: u8, value: u32) -> Result<(), crate::Error> {
+        let dp = self.get_ap().dp;
+        Ok(self
+            .get_arm_interface()?
+            .write_raw_dp_register(dp, address, value)?)
+    }
+
+    fn read_ap(&mut self, ap: u8, address: u8) -> Result<u32, crate::Error> {
+        let dp = self.get_ap().dp;
+        Ok(self
+            .get_arm_interface()?
+            .read_raw_ap_register(ApAddress { dp, ap }, address)?)
+    }
+
+    fn write_ap(&mut self, ap: u8, address: u8, value: u32) -> Result<(), crate::Error> {
+        let dp = self.get_ap().dp;
+        Ok(self
+            .get_arm_interface()?
+            .write_raw_ap_register(ApAddress { dp, ap }, address, value)?)
+    }
+
+    fn read_memory(&mut self, ap: u8, address: u32, size: AccessSize) -> Result<u64, crate::Error> {
+        let current = self.get_ap();
+        if current.ap == ap {
+            return read_memory(self, address, size);
+        }
+
+        let mut memory = self
+            .get_arm_interface()?
+            .memory_interface(MemoryAp::new(ApAddress { dp: current.dp, ap }))?;
+        read_memory(&mut memory, address, size)
+    }
+
+    fn write_memory(
+        &mut self,
+        ap: u8,
+        address: u32,
+        size: AccessSize,
+        value: u64,
+    ) -> Result<(), crate::Error> {
+        let current = self.get_ap();
+        if current.ap == ap {
+            return write_memory(self, address, size, value);
+        }
+
+        let mut memory = self
+            .get_arm_interface()?
+            .memory_interface(MemoryAp::new(ApAddress { dp: current.dp, ap }))?;
+        write_memory(&mut memory, address, size, value)
+    }
+
+    fn swj_pins(
+        &mut self,
+        pin_out: u32,
+        pin_select: u32,
+        pin_wait: u32,
+    ) -> Result<u32, crate::Error> {
+        self.get_arm_probe().swj_pins(pin_out, pin_select, pin_wait)
+    }
+
+    fn swj_sequence(&mut self, bit_len: u8, bits: u64) -> Result<(), crate::Error> {
+        self.get_arm_probe().swj_sequence(bit_len, bits)
+    }
+}
+
+/// `DebugPortStart` is executed with the communication interface, before any core is attached.
+struct DebugPortInterface<'a> {
+    interface: &'a mut ArmCommunicationInterface<Initialized>,
+    dp: DpAddress,
+}
+
+impl SequenceInterface for DebugPortInterface<'_> {
+    fn protocol(&mut self) -> Option<WireProtocol> {
+        self.interface.active_protocol()
+    }
+
+    fn default_ap(&mut self) -> u8 {
+        0
+    }
+
+    fn read_dp(&mut self, address: u8) -> Result<u32, crate::Error> {
+        Ok(self.interface.read_raw_dp_register(self.dp, address)?)
+    }
+
+    fn write_dp(&mut self, address: u8, value: u32) -> Result<(), crate::Error> {
+        Ok(self
+            .interface
+            .write_raw_dp_register(self.dp, address, value)?)
+    }
+
+    fn read_ap(&mut self, ap: u8, address: u8) -> Result<u32, crate::Error> {
+        Ok(self
+            .interface
+            .read_raw_ap_register(ApAddress { dp: self.dp, ap }, address)?)
+    }
+
+    fn write_ap(&mut self, ap: u8, address: u8, value: u32) -> Result<(), crate::Error> {
+        Ok(self
+            .interface
+            .write_raw_ap_register(ApAddress { dp: self.dp, ap }, address, value)?)
+    }
+
+    fn read_memory(&mut self, ap: u8, address: u32, size: AccessSize) -> Result<u64, crate::Error> {
+        let mut memory = self
+            .interface
+            .memory_interface(MemoryAp::new(ApAddress { dp: self.dp, ap }))?;
+        read_memory(&mut memory, address, size)
+    }
+
+    fn write_memory(
+        &mut self,
+        ap: u8,
+        address: u32,
+        size: AccessSize,
+        value: u64,
+    ) -> Result<(), crate::Error> {
+        let mut memory = self
+            .interface
+            .memory_interface(MemoryAp::new(ApAddress { dp: self.dp, ap }))?;
+        write_memory(&mut memory, address, size, value)
+    }
+
+    fn swj_pins(
+        &mut self,
+        pin_out: u32,
+        pin_select: u32,
+        pin_wait: u32,
+    ) -> Result<u32, crate::Error> {
+        self.interface.swj_pins(pin_out, pin_select, pin_wait)
+    }
+
+    fn swj_sequence(&mut self, bit_len: u8, bits: u64) -> Result<(), crate::Error> {
+        self.interface.swj_sequence(bit_len, bits)
+    }
+}
+
+/// `DebugPortSetup` and `ResetHardwareAssert` are executed with the raw probe,
+/// so only the registers in the first bank of the debug port can be accessed.
+struct ProbeInterface<'a>(&'a mut dyn DapProbe);
+
+impl ProbeInterface<'_> {
+    fn not_available(access: &'static str) -> crate::Error {
+        crate::Error::Probe(DebugProbeError::NotImplemented(access))
+    }
+}
+
+impl SequenceInterface for ProbeInterface<'_> {
+    fn protocol(&mut self) -> Option<WireProtocol> {
+        self.0.active_protocol()
+    }
+
+    fn default_ap(&mut self) -> u8 {
+        0
+    }
+
+    fn read_dp(&mut self, address: u8) -> Result<u32, crate::Error> {
+        if address & 0xf0 != 0 {
+            return Err(Self::not_available(
+                "banked DP registers before the debug port is started",
+            ));
+        }
+        Ok(self.0.raw_read_register(PortType::DebugPort, address)?)
+    }
+
+    fn write_dp(&mut self, address: u8, value: u32) -> Result<(), crate::Error> {
+        if address & 0xf0 != 0 {
+            return Err(Self::not_available(
+                "banked DP registers before the debug port is started",
+            ));
+        }
+        Ok(self
+            .0
+            .raw_write_register(PortType::DebugPort, address, value)?)
+    }
+
+    fn read_ap(&mut self, _ap: u8, _address: u8) -> Result<u32, crate::Error> {
+        Err(Self::not_available(
+            "AP accesses before the debug port is started",
+        ))
+    }
+
+    fn write_ap(&mut self, _ap: u8, _address: u8, _value: u32) -> Result<(), crate::Error> {
+        Err(Self::not_available(
+            "AP accesses before the debug port is started",
+        ))
+    }
+
+    fn read_memory(
+        &mut self,
+        _ap: u8,
+        _address: u32,
+        _size: AccessSize,
+    ) -> Result<u64, crate::Error> {
+        Err(Self::not_available(
+            "memory accesses before the debug port is started",
+        ))
+    }
+
+    fn write_memory(
+        &mut self,
+        _ap: u8,
+        _address: u32,
+        _size: AccessSize,
+        _value: u64,
+    ) -> Result<(), crate::Error> {
+        Err(Self::not_available(
+            "memory accesses before the debug port is started",
+        ))
+    }
+
+    fn swj_pins(
+        &mut self,
+        pin_out: u32,
+        pin_select: u32,
+        pin_wait: u32,
+    ) -> Result<u32, crate::Error> {
+        Ok(self.0.swj_pins(pin_out, pin_select, pin_wait)?)
+    }
+
+    fn swj_sequence(&mut self, bit_len: u8, bits: u64) -> Result<(), crate::Error> {
+        Ok(self.0.swj_sequence(bit_len, bits)?)
+    }
+}
+
+/// Executes sequences, and provides the variables and functions of the sequence language.
+struct Executor<'a> {
+    sequences: &'a [SequenceDescription],
+    interface: &'a mut dyn SequenceInterface,
+    globals: &'a mut HashMap<String, u64>,
+    /// The local variables of each sequence which is currently executed.
+    scopes: Vec<HashMap<String, u64>>,
+}
+
+impl Executor<'_> {
+    /// Executes a sequence, and returns the value of its `__Result` variable.
+    fn run_sequence(&mut self, sequence: &SequenceDescription) -> Result<u64, SequenceError> {
+        let mut scope = HashMap::new();
+        scope.insert("__Result".to_string(), 0);
+        self.scopes.push(scope);
+
+        let result = self.run_elements(&sequence.name, &sequence.body);
+        let scope = self.scopes.pop().unwrap_or_default();
+        result?;
+
+        Ok(scope.get("__Result").copied().unwrap_or(0))
+    }
+
+    fn run_elements(
+        &mut self,
+        sequence: &str,
+        elements: &[SequenceElement],
+    ) -> Result<(), SequenceError> {
+        for element in elements {
+            match element {
+                SequenceElement::Block(block) => {
+                    let statements = language::parse_statements(&block.code)?;
+                    language::execute(&statements, self)?;
+                }
+                SequenceElement::Control(control) => {
+                    if let Some(condition) = &control.if_condition {
+                        let condition = language::parse_expression(condition)?;
+                        if language::evaluate(&condition, self)? == 0 {
+                            continue;
+                        }
+                    }
+
+                    let condition = match &control.while_condition {
+                        Some(condition) => language::parse_expression(condition)?,
+                        None => {
+                            self.run_elements(sequence, &control.body)?;
+                            continue;
+                        }
+                    };
+
+                    let timeout = control
+                        .timeout
+                        .filter(|timeout| *timeout > 0)
+                        .map(Duration::from_micros);
+                    let start = Instant::now();
+
+                    while language::evaluate(&condition, self)? != 0 {
+                        if timeout.map_or(false, |timeout| start.elapsed() > timeout) {
+                            return Err(SequenceError::Timeout(sequence.to_string()));
+                        }
+                        self.run_elements(sequence, &control.body)?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Runs a debug access. Errors are ignored if bit 0 of `__errorcontrol` is set.
+    fn access<T: Default>(
+        &mut self,
+        access: impl FnOnce(&mut dyn SequenceInterface) -> Result<T, crate::Error>,
+    ) -> Result<T, SequenceError> {
+        match access(&mut *self.interface) {
+            Ok(value) => Ok(value),
+            Err(error) if self.variable("__errorcontrol").unwrap_or(0) & 1 != 0 => {
+                log::debug!("Ignoring failed debug access in sequence: {}", error);
+                Ok(T::default())
+            }
+            Err(error) => Err(SequenceError::Target(error)),
+        }
+    }
+
+    fn current_ap(&self) -> Result<u8, SequenceError> {
+        let ap = self.variable("__ap").unwrap_or(0);
+        u8::try_from(ap).map_err(|_| SequenceError::Syntax(format!("Invalid AP {}", ap)))
+    }
+}
+
+impl Environment for Executor<'_> {
+    fn variable(&self, name: &str) -> Option<u64> {
+        self.scopes
+            .last()
+            .and_then(|scope| scope.get(name))
+            .or_else(|| self.globals.get(name))
+            .copied()
+    }
+
+    fn declare(&mut self, name: &str, value: u64) {
+        // Declarations outside of a sequence are debug variables, which are global.
+        match self.scopes.last_mut() {
+            Some(scope) => scope.insert(name.to_string(), value),
+            None => self.globals.insert(name.to_string(), value),
+        };
+    }
+
+    fn assign(&mut self, name: &str, value: u64) -> Result<(), SequenceError> {
+        let variable = match self.scopes.last_mut().and_then(|scope| scope.get_mut(name)) {
+            Some(variable) => variable,
+            None => self
+                .globals
+                .get_mut(name)
+                .ok_or_else(|| SequenceError::UnknownVariable(name.to_string()))?,
+        };
+        *variable = value;
+
+        Ok(())
+    }
+
+    fn call(&mut self, name: &str, arguments: &[Argument]) -> Result<u64, SequenceError> {
+        let expected = match name {
+            "LoadDebugInfo" | "Sequence" | "DAP_Delay" | "DAP_WriteABORT" | "DAP_SWJ_Clock"
+            | "ReadDP" | "ReadAP" | "Read8" | "Read16" | "Read32" | "Read64" => Some(1),
+            "QueryValue" | "DAP_SWJ_Sequence" | "WriteDP" | "WriteAP" | "Write8" | "Write16"
+            | "Write32" | "Write64" => Some(2),
+            "Query" | "DAP_SWJ_Pins" => Some(3),
+            // Message has a variable number of arguments.
+            _ => None,
+        };
+        if let Some(expected) = expected {
+            if arguments.len() != expected {
+                return Err(SequenceError::Syntax(format!(
+                    "{} expects {} arguments, but got {}",
+                    name,
+                    expected,
+                    arguments.len()
+                )));
+            }
+        }
+
+        let size = match name {
+            "Read8" | "Write8" => AccessSize::U8,
+            "Read16" | "Write16" => AccessSize::U16,
+            "Read64" | "Write64" => AccessSize::U64,
+            _ => AccessSize::U32,
+        };
+
+        Ok(match name {
+            "Sequence" => {
+                let sequence = arguments[0].string()?;
+                let sequence = find_sequence(self.sequences, sequence)
+                    .ok_or_else(|| SequenceError::UnknownSequence(sequence.to_string()))?;
+                if sequence.disabled {
+                    0
+                } else {
+                    self.run_sequence(sequence)?
+                }
+            }
+            "Read8" | "Read16" | "Read32" | "Read64" => {
+                let ap = self.current_ap()?;
+                let address = address(&arguments[0])?;
+                self.access(|interface| interface.read_memory(ap, address, size))?
+            }
+            "Write8" | "Write16" | "Write32" | "Write64" => {
+                let ap = self.current_ap()?;
+                let address = address(&arguments[0])?;
+                let value = arguments[1].number()?;
+                self.access(|interface| interface.write_memory(ap, address, size, value))?;
+                0
+            }
+            "ReadDP" => {
+                let register = register(&arguments[0])?;
+                u64::from(self.access(|interface| interface.read_dp(register))?)
+            }
+            "WriteDP" => {
+                let register = register(&arguments[0])?;
+                let value = arguments[1].number()? as u32;
+                self.access(|interface| interface.write_dp(register, value))?;
+                0
+            }
+            "ReadAP" => {
+                let ap = self.current_ap()?;
+                let register = register(&arguments[0])?;
+                u64::from(self.access(|interface| interface.read_ap(ap, register))?)
+            }
+            "WriteAP" => {
+                let ap = self.current_ap()?;
+                let register = register(&arguments[0])?;
+                let value = arguments[1].number()? as u32;
+                self.access(|interface| interface.write_ap(ap, register, value))?;
+                0
+            }
+            "DAP_WriteABORT" => {
+                // ABORT is written at address 0 of the debug port.
+                let value = arguments[0].number()? as u32;
+                self.access(|interface| interface.write_dp(0x0, value))?;
+                0
+            }
+            "DAP_Delay" => {
+                thread::sleep(Duration::from_micros(arguments[0].number()?));
+                0
+            }
+            "DAP_SWJ_Pins" => {
+                let pin_out = arguments[0].number()? as u32;
+                let pin_select = arguments[1].number()? as u32;
+                let pin_wait = arguments[2].number()? as u32;
+                u64::from(
+                    self.access(|interface| interface.swj_pins(pin_out, pin_select, pin_wait))?,
+                )
+            }
+            "DAP_SWJ_Sequence" => {
+                let bit_len = arguments[0].number()?;
+                if !(1..=64).contains(&bit_len) {
+                    return Err(SequenceError::Syntax(format!(
+                        "DAP_SWJ_Sequence supports 1 to 64 bits, not {}",
+                        bit_len
+                    )));
+                }
+                let bits = arguments[1].number()?;
+                self.access(|interface| interface.swj_sequence(bit_len as u8, bits))?;
+                0
+            }
+            "DAP_SWJ_Clock" => {
+                log::debug!(
+                    "Ignoring DAP_SWJ_Clock({}) in debug sequence, the configured speed is kept",
+                    arguments[0].number()?
+                );
+                0
+            }
+            "Query" => {
+                // There is no user interaction, so the default answer is used.
+                log::info!(
+                    "{} Using the default answer {}.",
+                    arguments[1].string()?,
+                    arguments[2].number()?
+                );
+                arguments[2].number()?
+            }
+            "QueryValue" => {
+                log::info!(
+                    "{} Using the default value {}.",
+                    arguments[0].string()?,
+                    arguments[1].number()?
+                );
+                arguments[1].number()?
+            }
+            "LoadDebugInfo" => 0,
+            "Message" => {
+                let (message_type, format) = match arguments {
+                    [message_type, format, ..] => (message_type.number()?, format.string()?),
+                    _ => {
+                        return Err(SequenceError::Syntax(
+                            "Message expects a type and a format string".to_string(),
+                        ))
+                    }
+                };
+                let message = format_message(format, &arguments[2..])?;
+                match message_type {
+                    0 => log::info!("{}", message),
+                    1 => log::warn!("{}", message),
+                    _ => return Err(SequenceError::Message(message)),
+                }
+                0
+            }
+            name => return Err(SequenceError::UnsupportedFunction(name.to_string())),
+        })
+    }
+}
+
+fn address(argument: &Argument) -> Result<u32, SequenceError> {
+    let address = argument.number()?;
+    u32::try_from(address)
+        .map_err(|_| SequenceError::Syntax(format!("Address {:#x} is out of range", address)))
+}
+
+fn register(argument: &Argument) -> Result<u8, SequenceError> {
+    let register = argument.number()?;
+    u8::try_from(register)
+        .map_err(|_| SequenceError::Syntax(format!("Register {:#x} is out of range", register)))
+}
+
+/// Formats a message with `printf` like placeholders: `%d`, `%u`, `%x`, `%X` and `%s`,
+/// with an optional width, which is zero padded if it starts with `0`.
+fn format_message(format: &str, arguments: &[Argument]) -> Result<String, SequenceError> {
+    let mut message = String::new();
+    let mut arguments = arguments.iter();
+    let mut rest = format;
+
+    while let Some(index) = rest.find('%') {
+        message.push_str(&rest[..index]);
+        rest = &rest[index + 1..];
+
+        let length = rest
+            .find(|c: char| c.is_ascii_alphabetic() || c == '%')
+            .map_or(rest.len(), |index| index + 1);
+        let (width, conversion) = rest[..length].split_at(length.saturating_sub(1));
+        rest = &rest[length..];
+
+        if conversion == "%" {
+            message.push('%');
+            continue;
+        }
+
+        let argument = arguments.next().ok_or_else(|| {
+            SequenceError::Syntax(format!("Missing argument for message '{}'", format))
+        })?;
+        let zero_padded = width.starts_with('0');
+        let width = width.parse::<usize>().unwrap_or(0);
+
+        let formatted = match (conversion, argument) {
+            (_, Argument::String(value)) => value.clone(),
+            ("x", Argument::Number(value)) => format!("{:x}", value),
+            ("X", Argument::Number(value)) => format!("{:X}", value),
+            (_, Argument::Number(value)) => value.to_string(),
+        };
+        let padding = if zero_padded { '0' } else { ' ' };
+        for _ in formatted.len()..width {
+            message.push(padding);
+        }
+        message.push_str(&formatted);
+    }
+    message.push_str(rest);
+
+    Ok(message)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use probe_rs_target::{SequenceBlock, SequenceControl};
+
+    /// Records debug accesses, and simulates a register at 0x1000 which counts down on each read.
+    #[derive(Default)]
+    struct TestInterface {
+        writes: Vec<(u32, u64)>,
+        counter: u64,
+    }
+
+    impl SequenceInterface for TestInterface {
+        fn protocol(&mut self) -> Option<WireProtocol> {
+            Some(WireProtocol::Swd)
+        }
+
+        fn default_ap(&mut self) -> u8 {
+            0
+        }
+
+        fn read_dp(&mut self, _address: u8) -> Result<u32, crate::Error> {
+            Ok(0)
+        }
+
+        fn write_dp(&mut self, _address: u8, _value: u32) -> Result<(), crate::Error> {
+            Ok(())
+        }
+
+        fn read_ap(&mut self, _ap: u8, _address: u8) -> Result<u32, crate::Error> {
+            Ok(0)
+        }
+
+        fn write_ap(&mut self, _ap: u8, _address: u8, _value: u32) -> Result<(), crate::Error> {
+            Ok(())
+        }
+
+        fn read_memory(
+            &mut self,
+            _ap: u8,
+            address: u32,
+            _size: AccessSize,
+        ) -> Result<u64, crate::Error> {
+            match address {
+                0x1000 => {
+                    self.counter = self.counter.saturating_sub(1);
+                    Ok(self.counter)
+                }
+                _ => Err(crate::Error::Probe(DebugProbeError::Timeout)),
+            }
+        }
+
+        fn write_memory(
+            &mut self,
+            _ap: u8,
+            address: u32,
+            _size: AccessSize,
+            value: u64,
+        ) -> Result<(), crate::Error> {
+            self.writes.push((address, value));
+            Ok(())
+        }
+
+        fn swj_pins(
+            &mut self,
+            _pin_out: u32,
+            _pin_select: u32,
+            _pin_wait: u32,
+        ) -> Result<u32, crate::Error> {
+            Ok(0)
+        }
+
+        fn swj_sequence(&mut self, _bit_len: u8, _bits: u64) -> Result<(), crate::Error> {
+            Ok(())
+        }
+    }
+
+    fn block(code: &str) -> SequenceElement {
+        SequenceElement::Block(SequenceBlock {
+            atomic: false,
+            code: code.to_string(),
+        })
+    }
+
+    fn sequence(name: &str, body: Vec<SequenceElement>) -> SequenceDescription {
+        SequenceDescription {
+            name: name.to_string(),
+            processor: None,
+            disabled: false,
+            body,
+        }
+    }
+
+    fn run(
+        sequences: Vec<SequenceDescription>,
+        debug_vars: Option<&str>,
+        interface: &mut TestInterface,
+    ) -> Result<bool, SequenceError> {
+        let sequence = CmsisDebugSequence {
+            sequences,
+            debug_vars: debug_vars.map(str::to_string),
+            globals: Mutex::new(None),
+        };
+        sequence.run("ResetSystem", interface)
+    }
+
+    #[test]
+    fn missing_sequence_uses_default() {
+        let mut interface = TestInterface::default();
+        assert!(!run(vec![], None, &mut interface).unwrap());
+    }
+
+    #[test]
+    fn debug_vars_and_nested_sequences() {
+        let sequences = vec![
+            sequence(
+                "ResetSystem",
+                vec![block(
+                    "__var result = Sequence(\"Helper\");\n Write32(0x2000, result + Offset);",
+                )],
+            ),
+            sequence("Helper", vec![block("__Result = __protocol & 0xFFFF;")]),
+        ];
+
+        let mut interface = TestInterface::default();
+        assert!(run(sequences, Some("__var Offset = 0x10;"), &mut interface).unwrap());
+        assert_eq!(interface.writes, vec![(0x2000, 0x12)]);
+    }
+
+    #[test]
+    fn control_elements() {
+        let sequences = vec![sequence(
+            "ResetSystem",
+            vec![
+                SequenceElement::Control(SequenceControl {
+                    if_condition: Some("0".to_string()),
+                    while_condition: None,
+                    timeout: None,
+                    body: vec![block("Write32(0x3000, 1);")],
+                }),
+                SequenceElement::Control(SequenceControl {
+                    if_condition: None,
+                    while_condition: Some("Read32(0x1000) != 0".to_string()),
+                    timeout: Some(1_000_000),
+                    body: vec![block("Write32(0x4000, 2);")],
+                }),
+            ],
+        )];
+
+        let mut interface = TestInterface {
+            counter: 3,
+            ..Default::default()
+        };
+        assert!(run(sequences, None, &mut interface).unwrap());
+        // The counter reads 2 and 1 before it reaches 0.
+        assert_eq!(interface.writes, vec![(0x4000, 2), (0x4000, 2)]);
+    }
+
+    #[test]
+    fn error_control() {
+        let failing = vec![sequence("ResetSystem", vec![block("Read32(0x5000);")])];
+        let mut interface = TestInterface::default();
+        assert!(matches!(
+            run(failing, None, &mut interface),
+            Err(SequenceError::Target(_))
+        ));
+
+        let ignored = vec![sequence(
+            "ResetSystem",
+            vec![block("__errorcontrol = 1; Read32(0x5000);")],
+        )];
+        assert!(run(ignored, None, &mut interface).unwrap());
+    }
+
+    #[test]
+    fn disabled_sequence_is_skipped() {
+        let mut disabled = sequence("ResetSystem", vec![block("Write32(0x2000, 1);")]);
+        disabled.disabled = true;
+
+        let mut interface = TestInterface::default();
+        assert!(run(vec![disabled], None, &mut interface).unwrap());
+        assert!(interface.writes.is_empty());
+    }
+
+    #[test]
+    fn format_messages() {
+        assert_eq!(
+            format_message(
+                "Value %08X at %x is %d%%, %s",
+                &[
+                    Argument::Number(0xAB),
+                    Argument::Number(0x10),
+                    Argument::Number(42),
+                    Argument::String("ok".to_string()),
+                ]
+            )
+            .unwrap(),
+            "Value 000000AB at 10 is 42%, ok"
+        );
+        assert!(format_message("%d", &[]).is_err());
+    }
+}
@@ -1,7 +1,9 @@
 //! Debug sequences to operate special requirements ARM targets.
 
+pub mod cmsis;
 pub mod nrf53;
 pub mod nxp;
+pub mod registry;
 
 use std::{
     sync::Arc,
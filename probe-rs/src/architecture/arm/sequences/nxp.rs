@@ -20,12 +20,23 @@ use crate::{
 use super::ArmDebugSequence;
 
 /// The sequence handle for the LPC55S69.
-pub struct LPC55S69(());
+pub struct LPC55S69 {
+    /// The access port of the debug mailbox.
+    mailbox_ap: u8,
+}
 
 impl LPC55S69 {
+    /// The access port of the debug mailbox on the LPC55S69.
+    pub const DEFAULT_MAILBOX_AP: u8 = 2;
+
     /// Create a sequence handle for the LPC55S69.
     pub fn create() -> Arc<dyn ArmDebugSequence> {
-        Arc::new(Self(()))
+        Self::create_with_mailbox_ap(Self::DEFAULT_MAILBOX_AP)
+    }
+
+    /// Create a sequence handle for a chip of the LPC55 series with the debug mailbox on the given access port.
+    pub fn create_with_mailbox_ap(mailbox_ap: u8) -> Arc<dyn ArmDebugSequence> {
+        Arc::new(Self { mailbox_ap })
     }
 }
 
@@ -90,7 +101,7 @@ impl ArmDebugSequence for LPC55S69 {
 
             interface.write_dp_register(dp, abort)?;
 
-            enable_debug_mailbox(interface, dp)?;
+            enable_debug_mailbox(interface, dp, self.mailbox_ap)?;
         }
 
         Ok(())
@@ -218,11 +229,14 @@ impl ArmDebugSequence for LPC55S69 {
         log::info!("Waiting after reset");
         thread::sleep(Duration::from_millis(10));
 
-        wait_for_stop_after_reset(interface)
+        wait_for_stop_after_reset(interface, self.mailbox_ap)
     }
 }
 
-fn wait_for_stop_after_reset(memory: &mut crate::Memory) -> Result<(), crate::Error> {
+fn wait_for_stop_after_reset(
+    memory: &mut crate::Memory,
+    mailbox_ap: u8,
+) -> Result<(), crate::Error> {
     use crate::architecture::arm::core::armv7m::Dhcsr;
     log::info!("Wait for stop after reset");
 
@@ -231,7 +245,7 @@ fn wait_for_stop_after_reset(memory: &mut crate::Memory) -> Result<(), crate::Er
     let dp = memory.get_ap().dp;
     let interface = memory.get_arm_interface()?;
 
-    enable_debug_mailbox(interface, dp)?;
+    enable_debug_mailbox(interface, dp, mailbox_ap)?;
 
     let mut timeout = true;
 
@@ -269,10 +283,11 @@ fn wait_for_stop_after_reset(memory: &mut crate::Memory) -> Result<(), crate::Er
 fn enable_debug_mailbox(
     interface: &mut ArmCommunicationInterface<Initialized>,
     dp: DpAddress,
+    mailbox_ap: u8,
 ) -> Result<(), DebugProbeError> {
     log::info!("LPC55xx connect srcipt start");
 
-    let ap = ApAddress { dp, ap: 2 };
+    let ap = ApAddress { dp, ap: mailbox_ap };
 
     let status: IDR = interface.read_ap_register(GenericAp::new(ap))?;
 
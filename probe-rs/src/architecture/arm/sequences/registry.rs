@@ -0,0 +1,154 @@
+//! Registry of debug sequences which can be selected by name from a target description.
+//!
+//! The `debug_sequence` field of a [`Chip`](crate::config::Chip) names one of the sequences in
+//! this registry. The sequences shipped with probe-rs are always registered, and library users can
+//! register their own [`ArmDebugSequence`] or [`RiscvDebugSequence`] implementations at runtime,
+//! before the target using them is loaded:
+//!
+//! ```no_run
+//! use probe_rs::architecture::arm::sequences::{registry, DefaultArmSequence};
+//!
+//! registry::register_arm_sequence("MyChip", |_parameters| DefaultArmSequence::create());
+//! ```
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use once_cell::sync::Lazy;
+use probe_rs_target::DebugSequenceParameters;
+
+use super::{nrf53::Nrf5340, nxp::LPC55S69, ArmDebugSequence};
+use crate::architecture::riscv::sequences::{esp32c3::ESP32C3, RiscvDebugSequence};
+use crate::config::DebugSequence;
+
+/// Creates a debug sequence from the parameters given in the target description.
+type SequenceFactory = Arc<dyn Fn(&DebugSequenceParameters) -> DebugSequence + Send + Sync>;
+
+static SEQUENCES: Lazy<RwLock<HashMap<String, SequenceFactory>>> =
+    Lazy::new(|| RwLock::new(builtin_sequences()));
+
+fn builtin_sequences() -> HashMap<String, SequenceFactory> {
+    let mut sequences: HashMap<String, SequenceFactory> = HashMap::new();
+
+    sequences.insert(
+        "LPC55S69".to_owned(),
+        Arc::new(|parameters: &DebugSequenceParameters| {
+            DebugSequence::Arm(LPC55S69::create_with_mailbox_ap(
+                parameters
+                    .mailbox_ap
+                    .unwrap_or(LPC55S69::DEFAULT_MAILBOX_AP),
+            ))
+        }),
+    );
+    sequences.insert(
+        "nRF5340".to_owned(),
+        Arc::new(|_: &DebugSequenceParameters| DebugSequence::Arm(Nrf5340::create())),
+    );
+    sequences.insert(
+        "esp32c3".to_owned(),
+        Arc::new(|_: &DebugSequenceParameters| DebugSequence::Riscv(ESP32C3::create())),
+    );
+
+    sequences
+}
+
+fn register(name: &str, factory: SequenceFactory) {
+    // A poisoned lock can only be caused by a panic while inserting, which leaves the map intact.
+    let mut sequences = SEQUENCES.write().unwrap_or_else(|e| e.into_inner());
+
+    if sequences.insert(name.to_owned(), factory).is_some() {
+        log::debug!("Replacing the registered debug sequence '{}'", name);
+    }
+}
+
+/// Registers an ARM debug sequence under the given name.
+///
+/// The factory is called with the parameters from the target description
+/// each time a target using the sequence is loaded.
+/// A sequence which is already registered under the same name, including a built-in one, is replaced.
+pub fn register_arm_sequence(
+    name: &str,
+    factory: impl Fn(&DebugSequenceParameters) -> Arc<dyn ArmDebugSequence> + Send + Sync + 'static,
+) {
+    register(
+        name,
+        Arc::new(move |parameters: &DebugSequenceParameters| {
+            DebugSequence::Arm(factory(parameters))
+        }),
+    );
+}
+
+/// Registers a RISC-V debug sequence under the given name.
+///
+/// See [`register_arm_sequence`] for details.
+pub fn register_riscv_sequence(
+    name: &str,
+    factory: impl Fn(&DebugSequenceParameters) -> Arc<dyn RiscvDebugSequence> + Send + Sync + 'static,
+) {
+    register(
+        name,
+        Arc::new(move |parameters: &DebugSequenceParameters| {
+            DebugSequence::Riscv(factory(parameters))
+        }),
+    );
+}
+
+/// Returns the names of all registered debug sequences, in alphabetical order.
+pub fn registered_sequences() -> Vec<String> {
+    let sequences = SEQUENCES.read().unwrap_or_else(|e| e.into_inner());
+
+    let mut names: Vec<_> = sequences.keys().cloned().collect();
+    names.sort();
+    names
+}
+
+/// Creates the debug sequence registered under the given name,
+/// or returns `None` if no such sequence is registered.
+pub(crate) fn create_sequence(
+    name: &str,
+    parameters: &DebugSequenceParameters,
+) -> Option<DebugSequence> {
+    // Clone the factory, so it is not called with the lock held.
+    let factory = SEQUENCES
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(name)
+        .cloned()?;
+
+    Some(factory(parameters))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::architecture::arm::sequences::DefaultArmSequence;
+
+    #[test]
+    fn builtin_sequences_are_registered() {
+        let names = registered_sequences();
+
+        for name in ["LPC55S69", "nRF5340", "esp32c3"] {
+            assert!(names.iter().any(|n| n == name), "{} is missing", name);
+        }
+
+        assert!(matches!(
+            create_sequence("esp32c3", &DebugSequenceParameters::default()),
+            Some(DebugSequence::Riscv(_))
+        ));
+    }
+
+    #[test]
+    fn register_user_sequence() {
+        register_arm_sequence("test-user-sequence", |_| DefaultArmSequence::create());
+
+        assert!(matches!(
+            create_sequence("test-user-sequence", &DebugSequenceParameters::default()),
+            Some(DebugSequence::Arm(_))
+        ));
+        assert!(
+            create_sequence("test-unknown-sequence", &DebugSequenceParameters::default()).is_none()
+        );
+    }
+}
@@ -1,5 +1,7 @@
 //! SWO tracing related functions.
 
+pub mod trace;
+
 use crate::architecture::arm::communication_interface::ArmProbeInterface;
 use crate::Error;
 
@@ -0,0 +1,567 @@
+//! Decoding of the ITM and DWT packets in the SWO stream.
+//!
+//! The packet protocol is described in the ARMv7-M Architecture Reference Manual, appendix D4.
+//! The decoder expects the raw packet stream, so TPIU continuous formatting must be disabled
+//! in the [`SwoConfig`](super::SwoConfig).
+//!
+//! ```no_run
+//! # use probe_rs::{Permissions, Probe};
+//! use probe_rs::architecture::arm::swo::{trace::TraceDecoder, SwoConfig};
+//!
+//! # let probe = Probe::list_all()[0].open()?;
+//! # let mut session = probe.attach("stm32f407", Permissions::default())?;
+//! session.setup_swv(0, &SwoConfig::new(16_000_000))?;
+//!
+//! let mut decoder = TraceDecoder::new();
+//! loop {
+//!     decoder.push(&session.read_swo()?);
+//!     while let Some(event) = decoder.pull() {
+//!         println!("{:?}", event);
+//!     }
+//! }
+//! # Ok::<(), probe_rs::Error>(())
+//! ```
+
+use serde::Serialize;
+
+/// The relation of a local timestamp to the packet it belongs to.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimestampRelation {
+    /// The timestamp is synchronous to the corresponding data.
+    Sync,
+    /// The timestamp was delayed relative to the data.
+    TimestampDelayed,
+    /// The packet was delayed relative to the event which generated it.
+    DataDelayed,
+    /// Both the timestamp and the packet were delayed.
+    BothDelayed,
+}
+
+/// What happened to an exception in an exception trace packet.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExceptionAction {
+    /// The exception was entered.
+    Entered,
+    /// The exception was exited.
+    Exited,
+    /// Execution returned to the exception, e.g. to the interrupted handler.
+    Returned,
+}
+
+/// The kind of access which matched a DWT comparator.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DataAccess {
+    /// The data was read.
+    Read,
+    /// The data was written.
+    Write,
+}
+
+/// An event decoded from the SWO stream.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TraceEvent {
+    /// The ITM synchronization packet.
+    Sync,
+    /// The ITM could not output all packets, and some were lost.
+    Overflow,
+    /// Software wrote to an ITM stimulus port.
+    Instrumentation {
+        /// The number of the stimulus port.
+        port: u8,
+        /// The 1, 2 or 4 bytes written to the port.
+        payload: Vec<u8>,
+    },
+    /// One or more DWT event counters wrapped around.
+    EventCounter {
+        /// The bits of the counters which wrapped: `CPI` (bit 0), `EXC` (1), `SLEEP` (2),
+        /// `LSU` (3), `FOLD` (4) and `POSTCNT` (5).
+        counters: u8,
+    },
+    /// An exception was entered, exited or returned to.
+    Exception {
+        /// The exception number, with 16 and above being external interrupts.
+        number: u16,
+        /// What happened to the exception.
+        action: ExceptionAction,
+    },
+    /// A periodic sample of the program counter.
+    PcSample {
+        /// The sampled program counter, or `None` if the core was sleeping.
+        pc: Option<u32>,
+    },
+    /// The program counter of an access which matched a DWT comparator.
+    DataTracePc {
+        /// The number of the comparator.
+        comparator: u8,
+        /// The program counter of the instruction which made the access.
+        pc: u32,
+    },
+    /// The lower 16 bits of the address of an access which matched a DWT comparator.
+    DataTraceAddress {
+        /// The number of the comparator.
+        comparator: u8,
+        /// The lower 16 bits of the data address.
+        address: u16,
+    },
+    /// The value of an access which matched a DWT comparator.
+    DataTraceValue {
+        /// The number of the comparator.
+        comparator: u8,
+        /// Whether the value was read or written.
+        access: DataAccess,
+        /// The value, zero-extended from 1, 2 or 4 bytes.
+        value: u32,
+    },
+    /// The time since the last local timestamp.
+    LocalTimestamp {
+        /// The number of timestamp clock cycles since the last local timestamp.
+        delta: u32,
+        /// The relation of the timestamp to the packet it belongs to.
+        relation: TimestampRelation,
+    },
+    /// The value of the global timestamp counter.
+    GlobalTimestamp {
+        /// The value of the counter, as far as it is known from the packets received so far.
+        timestamp: u64,
+        /// The higher bits of the counter changed since they were last sent.
+        wrapped: bool,
+        /// The clock of the counter changed.
+        clock_changed: bool,
+    },
+    /// An extension packet, which e.g. selects the page of the stimulus ports.
+    Extension {
+        /// Whether the extension was generated by the hardware or the software source.
+        hardware: bool,
+        /// The information bits of the extension.
+        value: u32,
+    },
+    /// A header byte which does not start a known packet, or a packet which is malformed.
+    Unknown {
+        /// The bytes which were skipped.
+        bytes: Vec<u8>,
+    },
+}
+
+/// A decoder for the ITM and DWT packets in the SWO stream.
+///
+/// Data is added with [`TraceDecoder::push`] as it is read from the probe,
+/// and decoded events are taken out with [`TraceDecoder::pull`].
+/// Packets which are split between two reads are kept until the rest of the packet arrives.
+#[derive(Debug, Default)]
+pub struct TraceDecoder {
+    buffer: Vec<u8>,
+    /// The bits of the global timestamp which were received so far.
+    global_timestamp: u64,
+}
+
+/// The result of decoding the packet at the start of the buffer.
+enum Decoded {
+    /// A packet of the given length was decoded.
+    Event(TraceEvent, usize),
+    /// A packet of the given length was decoded, which does not result in an event.
+    Skip(usize),
+    /// More data is needed to decode the packet.
+    Incomplete,
+}
+
+impl TraceDecoder {
+    /// Creates a decoder for a new SWO stream.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds data read from the SWO stream.
+    pub fn push(&mut self, data: &[u8]) {
+        self.buffer.extend_from_slice(data);
+    }
+
+    /// Returns the next event, or `None` if more data is needed.
+    pub fn pull(&mut self) -> Option<TraceEvent> {
+        loop {
+            match self.decode() {
+                Decoded::Event(event, length) => {
+                    self.buffer.drain(..length);
+                    return Some(event);
+                }
+                Decoded::Skip(length) => {
+                    self.buffer.drain(..length);
+                }
+                Decoded::Incomplete => return None,
+            }
+        }
+    }
+
+    fn decode(&mut self) -> Decoded {
+        let header = match self.buffer.first() {
+            Some(header) => *header,
+            None => return Decoded::Incomplete,
+        };
+
+        match header {
+            0x00 => self.decode_sync(),
+            0x70 => Decoded::Event(TraceEvent::Overflow, 1),
+            // Local timestamp format 2, with a delta of 1 to 6 cycles and no payload.
+            _ if header & 0b1000_1111 == 0 => Decoded::Event(
+                TraceEvent::LocalTimestamp {
+                    delta: (header >> 4) as u32,
+                    relation: TimestampRelation::Sync,
+                },
+                1,
+            ),
+            // Local timestamp format 1, with the delta in the payload.
+            _ if header & 0b1100_1111 == 0b1100_0000 => {
+                let relation = match (header >> 4) & 0b11 {
+                    0 => TimestampRelation::Sync,
+                    1 => TimestampRelation::TimestampDelayed,
+                    2 => TimestampRelation::DataDelayed,
+                    _ => TimestampRelation::BothDelayed,
+                };
+
+                self.decode_continued(4, |value, _payload| TraceEvent::LocalTimestamp {
+                    delta: value as u32,
+                    relation,
+                })
+            }
+            0b1001_0100 => self.decode_global_timestamp_low(),
+            0b1011_0100 => self.decode_global_timestamp_high(),
+            // Extension
+            _ if header & 0b0000_1011 == 0b0000_1000 => {
+                let hardware = header & 0b0000_0100 != 0;
+                let low = ((header >> 4) & 0b111) as u64;
+
+                if header & 0x80 == 0 {
+                    Decoded::Event(
+                        TraceEvent::Extension {
+                            hardware,
+                            value: low as u32,
+                        },
+                        1,
+                    )
+                } else {
+                    self.decode_continued(4, |value, _payload| TraceEvent::Extension {
+                        hardware,
+                        value: (low | value << 3) as u32,
+                    })
+                }
+            }
+            // Source packets, from the ITM stimulus ports or the DWT.
+            _ if header & 0b11 != 0 => {
+                let size = match header & 0b11 {
+                    1 => 1,
+                    2 => 2,
+                    _ => 4,
+                };
+
+                if self.buffer.len() < 1 + size {
+                    return Decoded::Incomplete;
+                }
+
+                let payload = &self.buffer[1..1 + size];
+                let id = header >> 3;
+
+                let event = if header & 0b100 == 0 {
+                    TraceEvent::Instrumentation {
+                        port: id,
+                        payload: payload.to_vec(),
+                    }
+                } else {
+                    decode_hardware_source(id, payload)
+                };
+
+                Decoded::Event(event, 1 + size)
+            }
+            _ => Decoded::Event(
+                TraceEvent::Unknown {
+                    bytes: vec![header],
+                },
+                1,
+            ),
+        }
+    }
+
+    /// Decodes a synchronization packet, which consists of at least 47 zero bits followed by a one.
+    fn decode_sync(&self) -> Decoded {
+        let zeros = self.buffer.iter().take_while(|byte| **byte == 0).count();
+
+        match self.buffer.get(zeros) {
+            None => Decoded::Incomplete,
+            Some(0x80) if zeros >= 5 => Decoded::Event(TraceEvent::Sync, zeros + 1),
+            // Zero bytes which are not followed by the end of a synchronization packet are padding.
+            Some(_) => Decoded::Skip(zeros),
+        }
+    }
+
+    /// Decodes a packet with a payload of up to `max_length` bytes, which is continued as long as bit 7 is set.
+    ///
+    /// `event` is called with the 7 bit groups of the payload combined, and the payload bytes.
+    fn decode_continued(
+        &self,
+        max_length: usize,
+        event: impl FnOnce(u64, &[u8]) -> TraceEvent,
+    ) -> Decoded {
+        let mut value = 0u64;
+
+        for (index, byte) in self.buffer[1..].iter().take(max_length).enumerate() {
+            value |= ((byte & 0x7f) as u64) << (7 * index);
+
+            if byte & 0x80 == 0 {
+                return Decoded::Event(event(value, &self.buffer[1..index + 2]), index + 2);
+            }
+        }
+
+        if self.buffer.len() > max_length {
+            // The last payload byte must not have the continuation bit set.
+            Decoded::Event(
+                TraceEvent::Unknown {
+                    bytes: self.buffer[..=max_length].to_vec(),
+                },
+                max_length + 1,
+            )
+        } else {
+            Decoded::Incomplete
+        }
+    }
+
+    /// Decodes the GTS1 packet, which contains bits 25:0 of the global timestamp.
+    fn decode_global_timestamp_low(&mut self) -> Decoded {
+        let decoded = self.decode_continued(4, |value, payload| {
+            // In the fourth byte, bits 5 and 6 are flags instead of timestamp bits.
+            let (value, wrapped, clock_changed) = match payload.get(3) {
+                Some(last) => (value & 0x3ff_ffff, last & 0x40 != 0, last & 0x20 != 0),
+                None => (value, false, false),
+            };
+
+            TraceEvent::GlobalTimestamp {
+                timestamp: value,
+                wrapped,
+                clock_changed,
+            }
+        });
+
+        match decoded {
+            Decoded::Event(
+                TraceEvent::GlobalTimestamp {
+                    timestamp,
+                    wrapped,
+                    clock_changed,
+                },
+                length,
+            ) => {
+                // Only the bits which were sent are updated.
+                let mask = (1u64 << (7 * (length as u64 - 1)).min(26)) - 1;
+                self.global_timestamp = (self.global_timestamp & !mask) | (timestamp & mask);
+
+                Decoded::Event(
+                    TraceEvent::GlobalTimestamp {
+                        timestamp: self.global_timestamp,
+                        wrapped,
+                        clock_changed,
+                    },
+                    length,
+                )
+            }
+            other => other,
+        }
+    }
+
+    /// Decodes the GTS2 packet, which contains the higher bits of the global timestamp.
+    fn decode_global_timestamp_high(&mut self) -> Decoded {
+        match self.decode_continued(6, |value, _payload| TraceEvent::GlobalTimestamp {
+            timestamp: value,
+            wrapped: false,
+            clock_changed: false,
+        }) {
+            Decoded::Event(TraceEvent::GlobalTimestamp { timestamp, .. }, length) => {
+                self.global_timestamp = (self.global_timestamp & 0x3ff_ffff) | (timestamp << 26);
+                Decoded::Skip(length)
+            }
+            other => other,
+        }
+    }
+}
+
+/// Decodes a packet of the DWT, identified by its discriminator `id`.
+fn decode_hardware_source(id: u8, payload: &[u8]) -> TraceEvent {
+    let value = payload
+        .iter()
+        .rev()
+        .fold(0u32, |value, byte| value << 8 | *byte as u32);
+    let comparator = (id >> 1) & 0b11;
+
+    match (id, payload.len()) {
+        (0, 1) => TraceEvent::EventCounter {
+            counters: payload[0] & 0x3f,
+        },
+        (1, 2) => {
+            let action = match (payload[1] >> 4) & 0b11 {
+                1 => Some(ExceptionAction::Entered),
+                2 => Some(ExceptionAction::Exited),
+                3 => Some(ExceptionAction::Returned),
+                _ => None,
+            };
+
+            match action {
+                Some(action) => TraceEvent::Exception {
+                    number: (value & 0x1ff) as u16,
+                    action,
+                },
+                None => unknown_hardware_source(id, payload),
+            }
+        }
+        (2, 4) => TraceEvent::PcSample { pc: Some(value) },
+        (2, 1) if value == 0 => TraceEvent::PcSample { pc: None },
+        (8..=15, 4) if id & 1 == 0 => TraceEvent::DataTracePc {
+            comparator,
+            pc: value,
+        },
+        (8..=15, 2) if id & 1 == 1 => TraceEvent::DataTraceAddress {
+            comparator,
+            address: value as u16,
+        },
+        (16..=23, _) => TraceEvent::DataTraceValue {
+            comparator,
+            access: if id & 1 == 1 {
+                DataAccess::Write
+            } else {
+                DataAccess::Read
+            },
+            value,
+        },
+        _ => unknown_hardware_source(id, payload),
+    }
+}
+
+fn unknown_hardware_source(id: u8, payload: &[u8]) -> TraceEvent {
+    let size = match payload.len() {
+        1 => 1,
+        2 => 2,
+        _ => 3,
+    };
+
+    let mut bytes = vec![id << 3 | 0b100 | size];
+    bytes.extend_from_slice(payload);
+
+    TraceEvent::Unknown { bytes }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn decode(data: &[u8]) -> Vec<TraceEvent> {
+        let mut decoder = TraceDecoder::new();
+        decoder.push(data);
+
+        std::iter::from_fn(|| decoder.pull()).collect()
+    }
+
+    #[test]
+    fn decode_instrumentation() {
+        assert_eq!(
+            decode(&[0x00, 0x00, 0x00, 0x00, 0x00, 0x80, 0x01, b'h', 0x0a, b'i', b'!']),
+            vec![
+                TraceEvent::Sync,
+                TraceEvent::Instrumentation {
+                    port: 0,
+                    payload: b"h".to_vec(),
+                },
+                TraceEvent::Instrumentation {
+                    port: 1,
+                    payload: b"i!".to_vec(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn decode_hardware_packets() {
+        assert_eq!(
+            decode(&[
+                // Exception 16 (IRQ 0) entered
+                0x0e, 0x10, 0x10, //
+                // PC sample
+                0x17, 0x78, 0x56, 0x34, 0x12, //
+                // PC sample while sleeping
+                0x15, 0x00, //
+                // Write of 0x2a, matched by comparator 1
+                0x9d, 0x2a, //
+                // Local timestamp of 300 cycles, delayed data
+                0xe0, 0xac, 0x02, //
+                // Local timestamp format 2
+                0x30,
+            ]),
+            vec![
+                TraceEvent::Exception {
+                    number: 16,
+                    action: ExceptionAction::Entered,
+                },
+                TraceEvent::PcSample {
+                    pc: Some(0x1234_5678),
+                },
+                TraceEvent::PcSample { pc: None },
+                TraceEvent::DataTraceValue {
+                    comparator: 1,
+                    access: DataAccess::Write,
+                    value: 0x2a,
+                },
+                TraceEvent::LocalTimestamp {
+                    delta: 300,
+                    relation: TimestampRelation::DataDelayed,
+                },
+                TraceEvent::LocalTimestamp {
+                    delta: 3,
+                    relation: TimestampRelation::Sync,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn decode_split_packets() {
+        let mut decoder = TraceDecoder::new();
+
+        decoder.push(&[0x03, 0x01, 0x02]);
+        assert_eq!(decoder.pull(), None);
+
+        decoder.push(&[0x03, 0x04, 0x70]);
+        assert_eq!(
+            decoder.pull(),
+            Some(TraceEvent::Instrumentation {
+                port: 0,
+                payload: vec![1, 2, 3, 4],
+            })
+        );
+        assert_eq!(decoder.pull(), Some(TraceEvent::Overflow));
+        assert_eq!(decoder.pull(), None);
+    }
+
+    #[test]
+    fn decode_global_timestamp() {
+        assert_eq!(
+            decode(&[
+                // GTS2 with bits 47:26
+                0xb4, 0x01, //
+                // GTS1 with all bits 25:0, and the wrap flag
+                0x94, 0x85, 0x80, 0x80, 0x40, //
+                // GTS1 with bits 6:0 only
+                0x94, 0x06,
+            ]),
+            vec![
+                TraceEvent::GlobalTimestamp {
+                    timestamp: (1 << 26) | 5,
+                    wrapped: true,
+                    clock_changed: false,
+                },
+                TraceEvent::GlobalTimestamp {
+                    timestamp: (1 << 26) | 6,
+                    wrapped: false,
+                    clock_changed: false,
+                },
+            ]
+        );
+    }
+}
@@ -1703,6 +1703,11 @@ impl MemoryInterface for RiscvCommunicationInterface {
         self.read_word(address)
     }
 
+    fn read_word_16(&mut self, address: u32) -> Result<u16, crate::Error> {
+        log::debug!("read_word_16 from {:#08x}", address);
+        self.read_word(address)
+    }
+
     fn read_word_8(&mut self, address: u32) -> Result<u8, crate::Error> {
         log::debug!("read_word_8 from {:#08x}", address);
         self.read_word(address)
@@ -1713,6 +1718,11 @@ impl MemoryInterface for RiscvCommunicationInterface {
         self.read_multiple(address, data)
     }
 
+    fn read_16(&mut self, address: u32, data: &mut [u16]) -> Result<(), crate::Error> {
+        log::debug!("read_16 from {:#08x}", address);
+        self.read_multiple(address, data)
+    }
+
     /// Read 8-bit values from target memory.
     fn read_8(&mut self, address: u32, data: &mut [u8]) -> Result<(), crate::Error> {
         log::debug!("read_8 from {:#08x}", address);
@@ -1724,6 +1734,10 @@ impl MemoryInterface for RiscvCommunicationInterface {
         self.write_word(address, data)
     }
 
+    fn write_word_16(&mut self, address: u32, data: u16) -> Result<(), crate::Error> {
+        self.write_word(address, data)
+    }
+
     fn write_word_8(&mut self, address: u32, data: u8) -> Result<(), crate::Error> {
         self.write_word(address, data)
     }
@@ -1734,6 +1748,12 @@ impl MemoryInterface for RiscvCommunicationInterface {
         self.write_multiple(address, data)
     }
 
+    fn write_16(&mut self, address: u32, data: &[u16]) -> Result<(), crate::Error> {
+        log::debug!("write_16 to {:#08x}", address);
+
+        self.write_multiple(address, data)
+    }
+
     fn write_8(&mut self, address: u32, data: &[u8]) -> Result<(), crate::Error> {
         log::debug!("write_8 to {:#08x}", address);
 
@@ -10,13 +10,16 @@ use communication_interface::{
 };
 
 use crate::core::{CoreInformation, RegisterFile};
-use crate::{CoreRegisterAddress, CoreStatus, Error, HaltReason, MemoryInterface};
+use crate::{
+    CoreRegisterAddress, CoreStatus, Error, FaultInfo, HaltReason, MemoryInterface,
+    VectorCatchCondition, Watchpoint, WatchpointKind,
+};
 use bitfield::bitfield;
 use register::RISCV_REGISTERS;
 use std::time::{Duration, Instant};
 
 #[macro_use]
-mod register;
+pub(crate) mod register;
 pub(crate) mod assembly;
 mod dtm;
 
@@ -65,6 +68,175 @@ impl<'probe> Riscv32<'probe> {
             other => other,
         }
     }
+
+    /// Decodes the data watchpoint configured in the currently selected trigger.
+    ///
+    /// Returns `None` if the trigger is not an `mcontrol` trigger which halts on a load or store.
+    fn selected_watchpoint(&mut self, tdata_value: &Mcontrol) -> Result<Option<Watchpoint>, Error> {
+        let tdata2 = 0x7a2;
+
+        let trigger_any_mode_active = tdata_value.m() || tdata_value.s() || tdata_value.u();
+
+        if tdata_value.type_() != 0b10
+            || tdata_value.action() != 1
+            || tdata_value.execute()
+            || !trigger_any_mode_active
+        {
+            return Ok(None);
+        }
+
+        let kind = match (tdata_value.load(), tdata_value.store()) {
+            (true, true) => WatchpointKind::Access,
+            (true, false) => WatchpointKind::Read,
+            (false, true) => WatchpointKind::Write,
+            (false, false) => return Ok(None),
+        };
+
+        let value = self.read_csr(tdata2)?;
+
+        let (address, length) = match tdata_value.match_() {
+            // Exact match on a single address
+            0 => (value, 1),
+            1 => decode_napot(value),
+            _ => return Ok(None),
+        };
+
+        Ok(Some(Watchpoint {
+            address,
+            length,
+            kind,
+        }))
+    }
+
+    /// Selects the exception trigger (`etrigger`) used for vector catches.
+    ///
+    /// If no trigger is configured as exception trigger yet and `allocate` is set, the last
+    /// unused trigger which supports it is configured, so that the triggers used for
+    /// breakpoints, which are allocated from the first one, are not taken.
+    /// Returns `false` if there is no exception trigger.
+    fn select_exception_trigger(&mut self, allocate: bool) -> Result<bool, Error> {
+        let tselect = 0x7a0;
+        let tdata1 = 0x7a1;
+        let tdata2 = 0x7a2;
+
+        let num_triggers = self.available_breakpoint_units()?;
+        for unit_index in (0..num_triggers).rev() {
+            self.write_csr(tselect, unit_index)?;
+            if Etrigger(self.read_csr(tdata1)?).type_() == 5 {
+                return Ok(true);
+            }
+        }
+
+        if !allocate {
+            return Ok(false);
+        }
+
+        for unit_index in (0..num_triggers).rev() {
+            self.write_csr(tselect, unit_index)?;
+
+            let tdata_value = Mcontrol(self.read_csr(tdata1)?);
+            let unused = tdata_value.type_() == 0
+                || (tdata_value.type_() == 0b10
+                    && !(tdata_value.m() || tdata_value.s() || tdata_value.u()));
+            if !unused {
+                continue;
+            }
+
+            let mut exception_trigger = Etrigger(0);
+            exception_trigger.set_type(5);
+            exception_trigger.set_dmode(true);
+            exception_trigger.set_m(true);
+            exception_trigger.set_s(true);
+            exception_trigger.set_u(true);
+            // Enter debug mode
+            exception_trigger.set_action(1);
+
+            // No exceptions are enabled until the mask in tdata2 is written.
+            self.write_csr(tdata2, 0)?;
+            self.write_csr(tdata1, exception_trigger.0)?;
+
+            // Triggers which don't support the type keep their previous type.
+            if Etrigger(self.read_csr(tdata1)?).type_() == 5 {
+                return Ok(true);
+            }
+            self.write_csr(tdata1, tdata_value.0)?;
+        }
+
+        Ok(false)
+    }
+
+    /// Sets or clears the request to halt the hart when it comes out of reset.
+    fn set_reset_halt_request(&mut self, enabled: bool) -> Result<(), Error> {
+        let dmstatus: Dmstatus = self.interface.read_dm_register()?;
+        if !dmstatus.hasresethaltreq() {
+            return Err(Error::Other(anyhow!(
+                "The debug module does not support halting the core on reset"
+            )));
+        }
+
+        let mut dmcontrol = Dmcontrol(0);
+        dmcontrol.set_dmactive(true);
+        if enabled {
+            dmcontrol.set_resethaltreq(true);
+        } else {
+            dmcontrol.set_clrresethaltreq(true);
+        }
+
+        self.interface.write_dm_register(dmcontrol)?;
+
+        Ok(())
+    }
+}
+
+/// The mask of the exception causes (`mcause`) which correspond to `condition`, for the `tdata2`
+/// register of an exception trigger.
+///
+/// RISC-V has no HardFault, so it catches all the faults the other conditions catch.
+fn exception_causes(condition: VectorCatchCondition) -> Result<u32, Error> {
+    const INSTRUCTION_ADDRESS_MISALIGNED: u32 = 1 << 0;
+    const INSTRUCTION_ACCESS_FAULT: u32 = 1 << 1;
+    const ILLEGAL_INSTRUCTION: u32 = 1 << 2;
+    const LOAD_ADDRESS_MISALIGNED: u32 = 1 << 4;
+    const LOAD_ACCESS_FAULT: u32 = 1 << 5;
+    const STORE_ADDRESS_MISALIGNED: u32 = 1 << 6;
+    const STORE_ACCESS_FAULT: u32 = 1 << 7;
+    const INSTRUCTION_PAGE_FAULT: u32 = 1 << 12;
+    const LOAD_PAGE_FAULT: u32 = 1 << 13;
+    const STORE_PAGE_FAULT: u32 = 1 << 15;
+
+    const BUS_FAULT: u32 = INSTRUCTION_ACCESS_FAULT | LOAD_ACCESS_FAULT | STORE_ACCESS_FAULT;
+    const USAGE_FAULT: u32 = INSTRUCTION_ADDRESS_MISALIGNED
+        | ILLEGAL_INSTRUCTION
+        | LOAD_ADDRESS_MISALIGNED
+        | STORE_ADDRESS_MISALIGNED;
+    const MEM_MANAGE: u32 = INSTRUCTION_PAGE_FAULT | LOAD_PAGE_FAULT | STORE_PAGE_FAULT;
+
+    match condition {
+        VectorCatchCondition::HardFault => Ok(BUS_FAULT | USAGE_FAULT | MEM_MANAGE),
+        VectorCatchCondition::BusFault => Ok(BUS_FAULT),
+        VectorCatchCondition::UsageFault => Ok(USAGE_FAULT),
+        VectorCatchCondition::MemManage => Ok(MEM_MANAGE),
+        VectorCatchCondition::CoreReset
+        | VectorCatchCondition::InterruptError
+        | VectorCatchCondition::SecureFault => Err(Error::Other(anyhow!(
+            "Vector catch {:?} is not supported on RISC-V cores",
+            condition
+        ))),
+    }
+}
+
+/// Encodes the naturally aligned power-of-two range of `length` bytes at `address` for `tdata2`.
+///
+/// The size of the range is encoded in the number of trailing ones, a range of
+/// `2^(n + 1)` bytes has `n` trailing ones.
+fn encode_napot(address: u32, length: u32) -> u32 {
+    address | ((length >> 1) - 1)
+}
+
+/// Decodes the address and length of a range encoded with [`encode_napot`].
+fn decode_napot(value: u32) -> (u32, u32) {
+    let trailing_ones = value.trailing_ones();
+    (value & !((1 << trailing_ones) - 1), 2 << trailing_ones)
 }
 
 impl<'probe> CoreInterface for Riscv32<'probe> {
@@ -369,8 +541,17 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
         Ok(tselect_index)
     }
 
-    fn enable_breakpoints(&mut self, _state: bool) -> Result<(), crate::Error> {
-        // seems not needed on RISCV
+    fn enable_breakpoints(&mut self, state: bool) -> Result<(), crate::Error> {
+        // Triggers are always active, but an `ebreak` instruction only enters
+        // debug mode if this is enabled for the current privilege mode.
+        let mut dcsr = Dcsr(self.read_core_reg(CoreRegisterAddress(0x7b0))?);
+
+        dcsr.set_ebreakm(state);
+        dcsr.set_ebreaks(state);
+        dcsr.set_ebreaku(state);
+
+        self.write_csr(0x7b0, dcsr.0)?;
+
         Ok(())
     }
 
@@ -434,6 +615,189 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
         Ok(())
     }
 
+    fn available_watchpoint_units(&mut self) -> Result<u32, crate::Error> {
+        // Watchpoints use the same triggers as breakpoints.
+        self.available_breakpoint_units()
+    }
+
+    fn hw_watchpoints(&mut self) -> Result<Vec<Option<Watchpoint>>, Error> {
+        let tselect = 0x7a0;
+        let tdata1 = 0x7a1;
+
+        let mut watchpoints = vec![];
+        let num_triggers = self.available_watchpoint_units()? as usize;
+        for unit_index in 0..num_triggers {
+            self.write_csr(tselect, unit_index as u32)?;
+
+            let tdata_value = Mcontrol(self.read_csr(tdata1)?);
+
+            watchpoints.push(self.selected_watchpoint(&tdata_value)?);
+        }
+
+        Ok(watchpoints)
+    }
+
+    fn set_hw_watchpoint(
+        &mut self,
+        unit_index: usize,
+        watchpoint: Watchpoint,
+    ) -> Result<(), crate::Error> {
+        let tselect = 0x7a0;
+        let tdata1 = 0x7a1;
+        let tdata2 = 0x7a2;
+
+        let Watchpoint {
+            address,
+            length,
+            kind,
+        } = watchpoint;
+
+        if !length.is_power_of_two() || address % length != 0 {
+            return Err(anyhow!(
+                "Unsupported watchpoint of {} bytes at {:#010x}. The length must be a power of two and the address must be aligned to it.",
+                length,
+                address
+            )
+            .into());
+        }
+
+        self.write_csr(tselect, unit_index as u32)?;
+
+        let tdata_value = Mcontrol(self.read_csr(tdata1)?);
+
+        let trigger_type = tdata_value.type_();
+        if trigger_type != 0b10 {
+            return Err(RiscvError::UnexpectedTriggerType(trigger_type).into());
+        }
+
+        // Ranges are matched using NAPOT encoding, which is limited to 2^maskmax bytes.
+        if length > 1 && length.trailing_zeros() > tdata_value.maskmax() {
+            return Err(anyhow!(
+                "A watchpoint of {} bytes is not supported by this trigger",
+                length
+            )
+            .into());
+        }
+
+        let mut data_watchpoint = Mcontrol(0);
+
+        // Enter debug mode
+        data_watchpoint.set_action(1);
+
+        data_watchpoint.set_m(true);
+        data_watchpoint.set_s(true);
+        data_watchpoint.set_u(true);
+
+        data_watchpoint.set_load(matches!(
+            kind,
+            WatchpointKind::Read | WatchpointKind::Access
+        ));
+        data_watchpoint.set_store(matches!(
+            kind,
+            WatchpointKind::Write | WatchpointKind::Access
+        ));
+
+        data_watchpoint.set_dmode(true);
+
+        // Match address
+        data_watchpoint.set_select(false);
+
+        let tdata2_value = if length == 1 {
+            // Match exactly the value in tdata2
+            data_watchpoint.set_match(0);
+            address
+        } else {
+            // Match the naturally aligned power-of-two range in tdata2
+            data_watchpoint.set_match(1);
+            encode_napot(address, length)
+        };
+
+        self.write_csr(tdata1, data_watchpoint.0)?;
+        self.write_csr(tdata2, tdata2_value)?;
+
+        Ok(())
+    }
+
+    fn clear_hw_watchpoint(&mut self, unit_index: usize) -> Result<(), crate::Error> {
+        self.clear_hw_breakpoint(unit_index)
+    }
+
+    fn triggered_watchpoint(&mut self) -> Result<Option<Watchpoint>, Error> {
+        let tselect = 0x7a0;
+        let tdata1 = 0x7a1;
+
+        let num_triggers = self.available_watchpoint_units()? as usize;
+        for unit_index in 0..num_triggers {
+            self.write_csr(tselect, unit_index as u32)?;
+
+            let mut tdata_value = Mcontrol(self.read_csr(tdata1)?);
+
+            // Implementing the hit bit is optional, so this might never be set.
+            if !tdata_value.hit() {
+                continue;
+            }
+
+            let watchpoint = self.selected_watchpoint(&tdata_value)?;
+
+            tdata_value.set_hit(false);
+            self.write_csr(tdata1, tdata_value.0)?;
+
+            if watchpoint.is_some() {
+                return Ok(watchpoint);
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn fault_info(&mut self) -> Result<Option<FaultInfo>, Error> {
+        Ok(None)
+    }
+
+    fn enable_vector_catch(&mut self, condition: VectorCatchCondition) -> Result<(), Error> {
+        if condition == VectorCatchCondition::CoreReset {
+            return self.set_reset_halt_request(true);
+        }
+
+        let causes = exception_causes(condition)?;
+
+        // Exception triggers are optional, and there might be no free trigger.
+        if !self.select_exception_trigger(true)? {
+            return Err(Error::Other(anyhow!(
+                "The core has no free trigger which can halt on exceptions"
+            )));
+        }
+
+        let tdata2 = 0x7a2;
+        let enabled_causes = self.read_csr(tdata2)?;
+        self.write_csr(tdata2, enabled_causes | causes)?;
+
+        Ok(())
+    }
+
+    fn disable_vector_catch(&mut self, condition: VectorCatchCondition) -> Result<(), Error> {
+        if condition == VectorCatchCondition::CoreReset {
+            return self.set_reset_halt_request(false);
+        }
+
+        let causes = exception_causes(condition)?;
+
+        if !self.select_exception_trigger(false)? {
+            return Ok(());
+        }
+
+        let tdata1 = 0x7a1;
+        let tdata2 = 0x7a2;
+        let enabled_causes = self.read_csr(tdata2)? & !causes;
+        if enabled_causes == 0 {
+            // Free the trigger for breakpoints.
+            self.write_csr(tdata1, 0)?;
+        }
+        self.write_csr(tdata2, enabled_causes)?;
+
+        Ok(())
+    }
+
     fn registers(&self) -> &'static RegisterFile {
         &RISCV_REGISTERS
     }
@@ -493,7 +857,7 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
     }
 
     /// See docs on the [`CoreInterface::hw_breakpoints`] trait
-    /// NOTE: For riscv, this assumes that only execution breakpoints are used.
+    /// NOTE: For riscv, only execution triggers are reported, data watchpoints are returned by [`CoreInterface::hw_watchpoints`].
     fn hw_breakpoints(&mut self) -> Result<Vec<Option<u32>>, Error> {
         let tselect = 0x7a0;
         let tdata1 = 0x7a1;
@@ -513,15 +877,12 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
             // The trigger must be active in at least a single mode
             let trigger_any_mode_active = tdata_value.m() || tdata_value.s() || tdata_value.u();
 
-            let trigger_any_action_enabled =
-                tdata_value.execute() || tdata_value.store() || tdata_value.load();
-
             // Only return if the trigger if it is for an execution debug action in all modes.
             if tdata_value.type_() == 0b10
                 && tdata_value.action() == 1
                 && tdata_value.match_() == 0
                 && trigger_any_mode_active
-                && trigger_any_action_enabled
+                && tdata_value.execute()
             {
                 let breakpoint = self.read_csr(tdata2)?;
                 breakpoints.push(Some(breakpoint));
@@ -538,24 +899,36 @@ impl<'probe> MemoryInterface for Riscv32<'probe> {
     fn read_word_32(&mut self, address: u32) -> Result<u32, Error> {
         self.interface.read_word_32(address)
     }
+    fn read_word_16(&mut self, address: u32) -> Result<u16, Error> {
+        self.interface.read_word_16(address)
+    }
     fn read_word_8(&mut self, address: u32) -> Result<u8, Error> {
         self.interface.read_word_8(address)
     }
     fn read_32(&mut self, address: u32, data: &mut [u32]) -> Result<(), Error> {
         self.interface.read_32(address, data)
     }
+    fn read_16(&mut self, address: u32, data: &mut [u16]) -> Result<(), Error> {
+        self.interface.read_16(address, data)
+    }
     fn read_8(&mut self, address: u32, data: &mut [u8]) -> Result<(), Error> {
         self.interface.read_8(address, data)
     }
     fn write_word_32(&mut self, address: u32, data: u32) -> Result<(), Error> {
         self.interface.write_word_32(address, data)
     }
+    fn write_word_16(&mut self, address: u32, data: u16) -> Result<(), Error> {
+        self.interface.write_word_16(address, data)
+    }
     fn write_word_8(&mut self, address: u32, data: u8) -> Result<(), Error> {
         self.interface.write_word_8(address, data)
     }
     fn write_32(&mut self, address: u32, data: &[u32]) -> Result<(), Error> {
         self.interface.write_32(address, data)
     }
+    fn write_16(&mut self, address: u32, data: &[u16]) -> Result<(), Error> {
+        self.interface.write_16(address, data)
+    }
     fn write_8(&mut self, address: u32, data: &[u8]) -> Result<(), Error> {
         self.interface.write_8(address, data)
     }
@@ -790,3 +1163,68 @@ bitfield! {
     store, set_store: 1;
     load, set_load: 0;
 }
+
+bitfield! {
+    /// An exception trigger, selected in `tdata1` with type 5.
+    struct Etrigger(u32);
+    impl Debug;
+
+    type_, set_type: 31, 28;
+    dmode, set_dmode: 27;
+    hit, set_hit: 26;
+    m, set_m: 9;
+    s, set_s: 7;
+    u, set_u: 6;
+    action, set_action: 5, 0;
+}
+
+#[cfg(test)]
+mod test {
+    use super::{decode_napot, encode_napot, exception_causes};
+    use crate::VectorCatchCondition;
+
+    #[test]
+    fn napot_encoding() {
+        assert_eq!(encode_napot(0x2000_0000, 2), 0x2000_0000);
+        assert_eq!(encode_napot(0x2000_0000, 4), 0x2000_0001);
+        assert_eq!(encode_napot(0x2000_0010, 16), 0x2000_0017);
+    }
+
+    #[test]
+    fn napot_round_trip() {
+        for &(address, length) in &[(0x2000_0000, 2), (0x2000_0004, 4), (0x8000_0100, 256)] {
+            assert_eq!(
+                decode_napot(encode_napot(address, length)),
+                (address, length)
+            );
+        }
+    }
+
+    #[test]
+    fn exception_causes_of_faults() {
+        assert_eq!(
+            exception_causes(VectorCatchCondition::BusFault).unwrap(),
+            0b1010_0010
+        );
+        assert_eq!(
+            exception_causes(VectorCatchCondition::UsageFault).unwrap(),
+            0b0101_0101
+        );
+
+        let hard_fault = exception_causes(VectorCatchCondition::HardFault).unwrap();
+        for condition in [
+            VectorCatchCondition::BusFault,
+            VectorCatchCondition::UsageFault,
+            VectorCatchCondition::MemManage,
+        ] {
+            assert_eq!(
+                hard_fault & exception_causes(condition).unwrap(),
+                exception_causes(condition).unwrap()
+            );
+        }
+        // Breakpoints and environment calls are not faults.
+        assert_eq!(hard_fault & ((1 << 3) | (1 << 8) | (1 << 11)), 0);
+
+        assert!(exception_causes(VectorCatchCondition::SecureFault).is_err());
+    }
+}
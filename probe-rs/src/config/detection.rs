@@ -0,0 +1,112 @@
+//! Automatic identification of the connected chip.
+
+use std::collections::HashMap;
+
+use probe_rs_target::{Architecture, CoreAccessOptions, DetectionRule};
+
+use super::registry::get_target_by_detection_rules;
+use super::{get_target_by_chip_info, Chip, ChipInfo, RegistryError, Target};
+use crate::architecture::arm::{ap::MemoryAp, ApAddress, ArmProbeInterface, DpAddress};
+use crate::architecture::riscv::communication_interface::RiscvCommunicationInterface;
+use crate::MemoryInterface;
+
+/// Identifies the ARM chip connected to the given debug port.
+///
+/// The detection rules of the target descriptions are checked first.
+/// If no rule matches, the chip is identified by the part number in its ROM table.
+pub fn identify_arm_target(
+    interface: &mut dyn ArmProbeInterface,
+    dp: DpAddress,
+) -> Result<Target, RegistryError> {
+    let chip_info = interface
+        .read_chip_info_from_rom_table(dp)
+        .unwrap_or_else(|e| {
+            log::info!("Error during auto-detection of ARM chips: {}", e);
+            None
+        });
+
+    // Many chips share the same address, e.g. the DBGMCU_IDCODE register of an STM32 family.
+    let mut words = HashMap::new();
+
+    let target = get_target_by_detection_rules(
+        Architecture::Arm,
+        chip_info.as_ref().map(|info| info.manufacturer),
+        |chip, rule| match rule {
+            DetectionRule::Memory(rule) => {
+                let (psel, core_ap) = core_access_port(chip);
+                let ap = rule.ap.unwrap_or(core_ap);
+                // Chips on a multidrop bus, e.g. the RP2040, are only reachable with their own DP address.
+                let dp = match psel {
+                    0 => dp,
+                    x => DpAddress::Multidrop(x),
+                };
+
+                let word = *words.entry((dp, ap, rule.address)).or_insert_with(|| {
+                    read_arm_word(interface, ApAddress { dp, ap }, rule.address)
+                });
+
+                word.map_or(false, |word| rule.matches(word))
+            }
+            DetectionRule::JtagIdcode(_) => false,
+        },
+    )?;
+
+    match (target, chip_info) {
+        (Some(target), _) => Ok(target),
+        (None, Some(chip_info)) => get_target_by_chip_info(ChipInfo::from(chip_info)),
+        (None, None) => Err(RegistryError::ChipAutodetectFailed),
+    }
+}
+
+/// Identifies the connected RISC-V chip by its JTAG `IDCODE` and the detection rules of the target descriptions.
+pub fn identify_riscv_target(
+    interface: &mut RiscvCommunicationInterface,
+) -> Result<Target, RegistryError> {
+    let idcode = interface.read_idcode();
+
+    log::debug!("ID Code read over JTAG: {:x?}", idcode);
+
+    let idcode = idcode.ok();
+    let mut words = HashMap::new();
+
+    get_target_by_detection_rules(Architecture::Riscv, None, |_chip, rule| match rule {
+        DetectionRule::JtagIdcode(rule) => idcode.map_or(false, |idcode| rule.matches(idcode)),
+        DetectionRule::Memory(rule) => {
+            let word = *words.entry(rule.address).or_insert_with(|| {
+                u32::try_from(rule.address)
+                    .ok()
+                    .and_then(|address| interface.read_word_32(address).ok())
+            });
+
+            word.map_or(false, |word| rule.matches(word))
+        }
+    })?
+    .ok_or(RegistryError::ChipAutodetectFailed)
+}
+
+/// The port select number and the access port of the first core of the chip.
+fn core_access_port(chip: &Chip) -> (u32, u8) {
+    match &chip.cores[0].core_access_options {
+        CoreAccessOptions::Arm(options) => (options.psel, options.ap),
+        CoreAccessOptions::Riscv(_) => (0, 0),
+    }
+}
+
+/// Reads a word for a detection rule. Errors are not fatal, the rule just does not match.
+fn read_arm_word(
+    interface: &mut dyn ArmProbeInterface,
+    ap: ApAddress,
+    address: u64,
+) -> Option<u32> {
+    let address = u32::try_from(address).ok()?;
+
+    let mut memory = interface.memory_interface(MemoryAp::new(ap)).ok()?;
+
+    match memory.read_word_32(address) {
+        Ok(word) => Some(word),
+        Err(e) => {
+            log::debug!("Failed to read {:#010x} from AP {:?}: {}", address, ap, e);
+            None
+        }
+    }
+}
@@ -23,15 +23,17 @@
 //!
 
 mod chip_info;
+mod detection;
 mod registry;
 mod target;
 
 pub use probe_rs_target::{
-    Chip, ChipFamily, Core, CoreType, FlashProperties, InstructionSet, MemoryRange, MemoryRegion,
-    NvmRegion, PageInfo, RamRegion, RawFlashAlgorithm, SectorDescription, SectorInfo,
-    TargetDescriptionSource,
+    Chip, ChipDebugSequence, ChipFamily, Core, CoreType, DebugSequenceParameters, FlashProperties,
+    InstructionSet, MemoryRange, MemoryRegion, NvmRegion, PageInfo, RamRegion, RawFlashAlgorithm,
+    SectorDescription, SectorInfo, TargetDescriptionSource,
 };
 
+pub use detection::{identify_arm_target, identify_riscv_target};
 pub use registry::{
     add_target_from_yaml, families, get_target_by_name, search_chips, RegistryError,
 };
@@ -2,8 +2,9 @@
 
 use super::{Chip, ChipFamily, ChipInfo, Core, Target, TargetDescriptionSource};
 use crate::config::CoreType;
+use jep106::JEP106Code;
 use once_cell::sync::Lazy;
-use probe_rs_target::{CoreAccessOptions, RiscvCoreAccessOptions};
+use probe_rs_target::{Architecture, CoreAccessOptions, DetectionRule, RiscvCoreAccessOptions};
 use std::fs::File;
 use std::path::Path;
 use std::sync::{Arc, Mutex};
@@ -38,6 +39,9 @@ pub enum RegistryError {
     /// An invalid [`ChipFamily`] was encountered.
     #[error("Invalid chip family definition ({})", .0.name)]
     InvalidChipFamilyDefinition(ChipFamily, String),
+    /// The debug sequence named in a target description is not registered.
+    #[error("The debug sequence '{0}' is not registered.")]
+    UnknownDebugSequence(String),
 }
 
 fn add_generic_targets(vec: &mut Vec<ChipFamily>) {
@@ -52,6 +56,7 @@ fn add_generic_targets(vec: &mut Vec<ChipFamily>) {
             ],
 
             flash_algorithms: vec![],
+            debug_sequences: vec![],
             source: TargetDescriptionSource::Generic,
         },
         ChipFamily {
@@ -59,6 +64,7 @@ fn add_generic_targets(vec: &mut Vec<ChipFamily>) {
             manufacturer: None,
             variants: vec![Chip::generic_arm("Cortex-M3", CoreType::Armv7m)],
             flash_algorithms: vec![],
+            debug_sequences: vec![],
             source: TargetDescriptionSource::Generic,
         },
         ChipFamily {
@@ -69,6 +75,7 @@ fn add_generic_targets(vec: &mut Vec<ChipFamily>) {
                 Chip::generic_arm("Cortex-M7", CoreType::Armv7em),
             ],
             flash_algorithms: vec![],
+            debug_sequences: vec![],
             source: TargetDescriptionSource::Generic,
         },
         ChipFamily {
@@ -81,6 +88,7 @@ fn add_generic_targets(vec: &mut Vec<ChipFamily>) {
                 Chip::generic_arm("Cortex-M55", CoreType::Armv8m),
             ],
             flash_algorithms: vec![],
+            debug_sequences: vec![],
             source: TargetDescriptionSource::Generic,
         },
         ChipFamily {
@@ -96,8 +104,12 @@ fn add_generic_targets(vec: &mut Vec<ChipFamily>) {
                 }],
                 memory_map: vec![],
                 flash_algorithms: vec![],
+                debug_vars: None,
+                debug_sequence: None,
+                detection: vec![],
             }],
             flash_algorithms: vec![],
+            debug_sequences: vec![],
             source: TargetDescriptionSource::Generic,
         },
     ]);
@@ -261,6 +273,55 @@ impl Registry {
         self.get_target(family, chip)
     }
 
+    fn get_target_by_detection_rules(
+        &self,
+        architecture: Architecture,
+        manufacturer: Option<JEP106Code>,
+        mut check: impl FnMut(&Chip, &DetectionRule) -> bool,
+    ) -> Result<Option<Target>, RegistryError> {
+        let mut identified_chips = Vec::new();
+
+        for family in &self.families {
+            // Chips of other manufacturers are skipped, so we do not read random memory addresses.
+            if let (Some(manufacturer), Some(family_manufacturer)) =
+                (manufacturer, family.manufacturer)
+            {
+                if manufacturer != family_manufacturer {
+                    continue;
+                }
+            }
+
+            for chip in family.variants() {
+                if chip.detection.is_empty()
+                    || chip.cores[0].core_type.architecture() != architecture
+                {
+                    continue;
+                }
+
+                log::debug!("Checking detection rules of chip {}", chip.name);
+
+                if chip.detection.iter().all(|rule| check(chip, rule)) {
+                    identified_chips.push((family, chip));
+                }
+            }
+        }
+
+        match identified_chips.as_slice() {
+            [] => Ok(None),
+            [(family, chip)] => self.get_target(family, chip).map(Some),
+            _ => {
+                log::warn!(
+                    "The detection rules of multiple chips match: {:?}",
+                    identified_chips
+                        .iter()
+                        .map(|(_, chip)| &chip.name)
+                        .collect::<Vec<_>>()
+                );
+                Ok(None)
+            }
+        }
+    }
+
     fn get_target(&self, family: &ChipFamily, chip: &Chip) -> Result<Target, RegistryError> {
         // The validity of the given `ChipFamily` is checked in the constructor.
         Target::new(family, &chip.name)
@@ -302,6 +363,21 @@ pub(crate) fn get_target_by_chip_info(chip_info: ChipInfo) -> Result<Target, Reg
     REGISTRY.lock().unwrap().get_target_by_chip_info(chip_info)
 }
 
+/// Finds the single chip of the given architecture whose detection rules all match.
+///
+/// `check` evaluates one rule of a chip. If a manufacturer is given,
+/// only families of this manufacturer or without a manufacturer are checked.
+pub(crate) fn get_target_by_detection_rules(
+    architecture: Architecture,
+    manufacturer: Option<JEP106Code>,
+    check: impl FnMut(&Chip, &DetectionRule) -> bool,
+) -> Result<Option<Target>, RegistryError> {
+    REGISTRY
+        .lock()
+        .unwrap()
+        .get_target_by_detection_rules(architecture, manufacturer, check)
+}
+
 /// Parse a target description file and add the contained targets
 /// to the internal target registry.
 pub fn add_target_from_yaml(path_to_yaml: &Path) -> Result<(), RegistryError> {
@@ -395,4 +471,56 @@ mod tests {
             .collect::<Result<Vec<_>, _>>()
             .unwrap();
     }
+
+    #[test]
+    fn detect_chip_by_rules() {
+        use probe_rs_target::MemoryDetectionRule;
+
+        let rule = |value| {
+            DetectionRule::Memory(MemoryDetectionRule {
+                ap: None,
+                address: 0xE004_2000,
+                mask: 0x0000_0fff,
+                value,
+            })
+        };
+
+        let mut first = Chip::generic_arm("Detect-1", CoreType::Armv7em);
+        first.detection = vec![rule(0x413)];
+        let mut second = Chip::generic_arm("Detect-2", CoreType::Armv7em);
+        second.detection = vec![rule(0x419)];
+
+        let registry = Registry {
+            families: vec![ChipFamily {
+                name: "Detection test".to_owned(),
+                manufacturer: None,
+                variants: vec![first, second],
+                flash_algorithms: vec![],
+                debug_sequences: vec![],
+                source: TargetDescriptionSource::Generic,
+            }],
+        };
+
+        let read_word = |word: u32| {
+            move |_: &Chip, rule: &DetectionRule| match rule {
+                DetectionRule::Memory(rule) => rule.matches(word),
+                DetectionRule::JtagIdcode(_) => false,
+            }
+        };
+
+        let target = registry
+            .get_target_by_detection_rules(Architecture::Arm, None, read_word(0x1000_6419))
+            .unwrap()
+            .unwrap();
+        assert_eq!(target.name, "Detect-2");
+
+        assert!(registry
+            .get_target_by_detection_rules(Architecture::Arm, None, read_word(0x1000_6000))
+            .unwrap()
+            .is_none());
+        assert!(registry
+            .get_target_by_detection_rules(Architecture::Riscv, None, read_word(0x1000_6419))
+            .unwrap()
+            .is_none());
+    }
 }
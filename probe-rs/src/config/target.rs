@@ -2,10 +2,9 @@ use probe_rs_target::{Architecture, ChipFamily};
 
 use super::{Core, MemoryRegion, RawFlashAlgorithm, RegistryError, TargetDescriptionSource};
 
-use crate::architecture::arm::sequences::nrf53::Nrf5340;
-use crate::architecture::arm::sequences::nxp::LPC55S69;
+use crate::architecture::arm::sequences::cmsis::CmsisDebugSequence;
+use crate::architecture::arm::sequences::registry;
 use crate::architecture::arm::sequences::ArmDebugSequence;
-use crate::architecture::riscv::sequences::esp32c3::ESP32C3;
 use crate::architecture::riscv::sequences::{DefaultRiscvSequence, RiscvDebugSequence};
 use crate::flashing::FlashLoader;
 use std::sync::Arc;
@@ -29,6 +28,9 @@ pub struct Target {
 
     /// Debug sequences for the given target.
     pub debug_sequence: DebugSequence,
+
+    /// The address of the ROM table, overriding the base address read from the access port of the first core.
+    pub(crate) rom_table_base: Option<u64>,
 }
 
 impl std::fmt::Debug for Target {
@@ -90,15 +92,32 @@ impl Target {
             Architecture::Riscv => DebugSequence::Riscv(DefaultRiscvSequence::create()),
         };
 
-        if chip.name.starts_with("LPC55S16") || chip.name.starts_with("LPC55S69") {
-            log::warn!("Using custom sequence for LPC55S16/LPC55S69");
-            debug_sequence = DebugSequence::Arm(LPC55S69::create());
-        } else if chip.name.starts_with("esp32c3") {
-            log::warn!("Using custom sequence for ESP32c3");
-            debug_sequence = DebugSequence::Riscv(ESP32C3::create());
-        } else if chip.name.starts_with("nRF5340") {
-            log::warn!("Using custom sequence for nRF5340");
-            debug_sequence = DebugSequence::Arm(Nrf5340::create());
+        if let Some(sequence) = &chip.debug_sequence {
+            log::debug!("Using the debug sequence '{}'", sequence.name);
+            debug_sequence = registry::create_sequence(&sequence.name, &sequence.parameters)
+                .ok_or_else(|| RegistryError::UnknownDebugSequence(sequence.name.clone()))?;
+
+            let sequence_architecture = match debug_sequence {
+                DebugSequence::Arm(_) => Architecture::Arm,
+                DebugSequence::Riscv(_) => Architecture::Riscv,
+            };
+            if sequence_architecture != chip.cores[0].core_type.architecture() {
+                return Err(RegistryError::InvalidChipFamilyDefinition(
+                    family.clone(),
+                    format!(
+                        "The debug sequence '{}' of chip '{}' is for a different architecture",
+                        sequence.name, chip.name
+                    ),
+                ));
+            }
+        } else if !family.debug_sequences.is_empty()
+            && chip.cores[0].core_type.architecture() == Architecture::Arm
+        {
+            log::debug!("Using the debug sequences from the target description");
+            debug_sequence = DebugSequence::Arm(CmsisDebugSequence::create(
+                family.debug_sequences.clone(),
+                chip.debug_vars.clone(),
+            ));
         }
 
         Ok(Target {
@@ -108,6 +127,10 @@ impl Target {
             source: family.source.clone(),
             memory_map: chip.memory_map.clone(),
             debug_sequence,
+            rom_table_base: chip
+                .debug_sequence
+                .as_ref()
+                .and_then(|sequence| sequence.parameters.rom_table_base),
         })
     }
 
@@ -0,0 +1,740 @@
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+use std::time::Duration;
+
+use anyhow::anyhow;
+use probe_rs_target::{MemoryRegion, RamRegion};
+
+use crate::architecture::arm::core::fault::{self, FaultModel};
+use crate::architecture::arm::core::ARM_REGISTER_FILE;
+use crate::architecture::riscv::register::RISCV_REGISTERS;
+use crate::core::{
+    Architecture, CoreInformation, CoreInterface, CoreRegisterAddress, CoreState, CoreStatus,
+    FaultInfo, HaltReason, RegisterFile, VectorCatchCondition, Watchpoint,
+};
+use crate::{Core, CoreType, Error, InstructionSet, MemoryInterface, Session, Target};
+
+/// Identifies a file as a core dump.
+const MAGIC: [u8; 8] = *b"PRSCDUMP";
+
+/// The version of the core dump format. It has to be increased with every incompatible change.
+const FORMAT_VERSION: u32 = 1;
+
+/// How long to wait for a running core to halt before it is dumped.
+const HALT_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Media and VFP Feature Register 0, MVFR0. It reads as zero if the core does not implement the floating-point extension.
+const MVFR0: u32 = 0xE000_EF40;
+
+/// An error while saving, loading or replaying a [`CoreDump`].
+#[derive(thiserror::Error, Debug)]
+pub enum CoreDumpError {
+    /// The core dump could not be read or written.
+    #[error("Failed to read or write the core dump")]
+    Io(#[from] std::io::Error),
+    /// The core dump could not be encoded or decoded.
+    #[error("Failed to encode or decode the core dump")]
+    Encoding(#[from] bincode::Error),
+    /// The data does not start with the core dump header.
+    #[error("The file is not a core dump")]
+    NotACoreDump,
+    /// The core dump was written in a format this version of probe-rs cannot read.
+    #[error(
+        "The core dump has version {0}, but only version {} is supported",
+        FORMAT_VERSION
+    )]
+    UnsupportedVersion(u32),
+    /// The memory was not read from the target when the dump was captured.
+    #[error("The memory at {address:#010x} ({length} bytes) is not contained in the core dump")]
+    MemoryNotDumped {
+        /// The start address of the access.
+        address: u32,
+        /// The length of the access in bytes.
+        length: usize,
+    },
+    /// The register was not read from the core when the dump was captured.
+    #[error("The register {0:#x} is not contained in the core dump")]
+    RegisterNotDumped(u16),
+    /// The operation would change the state of the target, which is not possible with a core dump.
+    #[error("A core dump cannot be modified or run: {0} is not supported")]
+    ReadOnly(&'static str),
+}
+
+impl From<CoreDumpError> for Error {
+    fn from(error: CoreDumpError) -> Self {
+        Error::Other(error.into())
+    }
+}
+
+/// A contiguous block of memory saved in a [`CoreDump`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DumpedMemory {
+    /// The address of the first byte of the block.
+    pub address: u32,
+    /// The contents of the memory.
+    pub data: Vec<u8>,
+}
+
+impl DumpedMemory {
+    /// Returns the part of the block at `address`, if the block contains all `length` bytes.
+    fn get(&self, address: u32, length: usize) -> Option<&[u8]> {
+        let offset = address.checked_sub(self.address)? as usize;
+        self.data.get(offset..offset.checked_add(length)?)
+    }
+}
+
+/// The state of a single core saved in a [`CoreDump`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DumpedCore {
+    /// The name of the core in the target description.
+    pub name: String,
+    /// The type of the core.
+    pub core_type: CoreType,
+    /// The values of the core registers, by their [`CoreRegisterAddress`].
+    ///
+    /// This includes the floating-point registers, if the core implements the floating-point extension.
+    pub registers: BTreeMap<u16, u32>,
+    /// Memory which is only visible to this core, like the fault status registers of a Cortex-M core.
+    pub memory: Vec<DumpedMemory>,
+}
+
+/// The state of all cores and the contents of the RAM of a target.
+///
+/// A core dump is captured from a halted target with [`CoreDump::capture`], and can be analysed
+/// later without any hardware: [`CoreDump::core`] returns a [`Core`] which replays the saved
+/// registers and memory, so the debug information can be used to unwind the stack and inspect
+/// variables just like on the target.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CoreDump {
+    /// The name of the target the dump was captured from.
+    pub target: String,
+    /// The cores of the target, in the order of their indices.
+    pub cores: Vec<DumpedCore>,
+    /// The contents of the RAM regions of the target, sorted by address.
+    pub memory: Vec<DumpedMemory>,
+}
+
+impl CoreDump {
+    /// Captures the registers of all cores of the target and the contents of all its RAM regions.
+    ///
+    /// Running cores are halted while the dump is captured, and resumed afterwards.
+    pub fn capture(session: &mut Session) -> Result<CoreDump, Error> {
+        let target = session.target().clone();
+
+        let mut halted = Vec::new();
+        let result = Self::capture_halted(session, &target, &mut halted);
+
+        // Every core which was halted is resumed, even if resuming another one failed.
+        for index in halted {
+            if let Err(error) = session.core(index).and_then(|mut core| core.run()) {
+                log::warn!(
+                    "Failed to resume core {} after the core dump: {}",
+                    index,
+                    error
+                );
+            }
+        }
+
+        result
+    }
+
+    fn capture_halted(
+        session: &mut Session,
+        target: &Target,
+        halted: &mut Vec<usize>,
+    ) -> Result<CoreDump, Error> {
+        let mut cores = Vec::new();
+        for (index, _) in session.list_cores() {
+            let mut core = session.core(index)?;
+            if !core.core_halted()? {
+                core.halt(HALT_TIMEOUT)?;
+                halted.push(index);
+            }
+
+            cores.push(dump_core(&mut core, target)?);
+        }
+
+        let mut memory = Vec::new();
+        for region in ram_regions(target) {
+            let index = region
+                .cores
+                .first()
+                .and_then(|name| target.core_index_by_name(name))
+                .unwrap_or(0);
+            let mut core = session.core(index)?;
+            memory.extend(dump_region(&mut core, region));
+        }
+        memory.sort_by_key(|block| block.address);
+
+        Ok(CoreDump {
+            target: target.name.clone(),
+            cores,
+            memory,
+        })
+    }
+
+    /// Captures the registers of a single core, and the contents of the RAM regions it can access.
+    ///
+    /// This is useful if the session is not available, because the core is borrowed from it.
+    /// The core is halted while the dump is captured if it is running, and resumed afterwards.
+    pub fn capture_core(core: &mut Core, target: &Target) -> Result<CoreDump, Error> {
+        let was_running = !core.core_halted()?;
+        if was_running {
+            core.halt(HALT_TIMEOUT)?;
+        }
+
+        let result = dump_core(core, target).map(|dumped_core| {
+            let mut memory: Vec<DumpedMemory> = ram_regions(target)
+                .filter(|region| region.cores.contains(&dumped_core.name))
+                .filter_map(|region| dump_region(core, region))
+                .collect();
+            memory.sort_by_key(|block| block.address);
+
+            CoreDump {
+                target: target.name.clone(),
+                cores: vec![dumped_core],
+                memory,
+            }
+        });
+
+        // A failure to resume the core must not discard the dump.
+        if was_running {
+            if let Err(error) = core.run() {
+                log::warn!("Failed to resume the core after the core dump: {}", error);
+            }
+        }
+
+        result
+    }
+
+    /// Writes the core dump in the binary core dump format.
+    pub fn save(&self, mut writer: impl Write) -> Result<(), CoreDumpError> {
+        writer.write_all(&MAGIC)?;
+        bincode::serialize_into(&mut writer, &FORMAT_VERSION)?;
+        bincode::serialize_into(&mut writer, self)?;
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    /// Reads a core dump which was written with [`CoreDump::save`].
+    pub fn load(mut reader: impl Read) -> Result<CoreDump, CoreDumpError> {
+        let mut magic = [0; MAGIC.len()];
+        match reader.read_exact(&mut magic) {
+            Ok(()) if magic == MAGIC => {}
+            Ok(()) => return Err(CoreDumpError::NotACoreDump),
+            Err(error) if error.kind() == std::io::ErrorKind::UnexpectedEof => {
+                return Err(CoreDumpError::NotACoreDump)
+            }
+            Err(error) => return Err(error.into()),
+        }
+
+        let version: u32 = bincode::deserialize_from(&mut reader)?;
+        if version != FORMAT_VERSION {
+            return Err(CoreDumpError::UnsupportedVersion(version));
+        }
+
+        Ok(bincode::deserialize_from(&mut reader)?)
+    }
+
+    /// Returns a [`Core`] which replays the state of the core with the given index.
+    ///
+    /// All registers and memory contained in the dump can be read, but the core cannot be
+    /// run, and neither registers nor memory can be written.
+    pub fn core<'dump>(
+        &'dump self,
+        index: usize,
+        state: &'dump mut CoreState,
+    ) -> Result<Core<'dump>, Error> {
+        if index >= self.cores.len() {
+            return Err(Error::CoreNotFound(index));
+        }
+
+        Ok(Core::new(DumpedCoreInterface { dump: self, index }, state))
+    }
+
+    /// Reads memory of the core with the given index, from the memory only visible to the core or the RAM.
+    fn read_memory(
+        &self,
+        core_index: usize,
+        address: u32,
+        data: &mut [u8],
+    ) -> Result<(), CoreDumpError> {
+        self.cores[core_index]
+            .memory
+            .iter()
+            .chain(&self.memory)
+            .find_map(|block| block.get(address, data.len()))
+            .map(|contents| data.copy_from_slice(contents))
+            .ok_or(CoreDumpError::MemoryNotDumped {
+                address,
+                length: data.len(),
+            })
+    }
+}
+
+fn ram_regions(target: &Target) -> impl Iterator<Item = &RamRegion> {
+    target.memory_map.iter().filter_map(|region| match region {
+        MemoryRegion::Ram(region) => Some(region),
+        _ => None,
+    })
+}
+
+/// Reads the contents of a RAM region. Regions which cannot be read are left out of the dump.
+fn dump_region(core: &mut Core, region: &RamRegion) -> Option<DumpedMemory> {
+    let mut data = vec![0; (region.range.end - region.range.start) as usize];
+
+    match core.read(region.range.start, &mut data) {
+        Ok(()) => Some(DumpedMemory {
+            address: region.range.start,
+            data,
+        }),
+        Err(error) => {
+            log::warn!(
+                "Failed to read the RAM region {:#010x}..{:#010x}, it is missing in the core dump: {}",
+                region.range.start,
+                region.range.end,
+                error
+            );
+            None
+        }
+    }
+}
+
+/// Reads all registers of a halted core, and the fault status registers of Cortex-M cores.
+fn dump_core(core: &mut Core, target: &Target) -> Result<DumpedCore, Error> {
+    let (name, core_type) = match target.cores.get(core.id()) {
+        Some(description) => (description.name.clone(), description.core_type),
+        None => (format!("core{}", core.id()), core.core_type()),
+    };
+
+    let register_file = core.registers();
+    let mut descriptions: Vec<_> = register_file
+        .registers()
+        .chain([
+            register_file.program_counter(),
+            register_file.stack_pointer(),
+            register_file.return_address(),
+            register_file.frame_pointer(),
+        ])
+        .collect();
+
+    // MSP, PSP and the special purpose registers can only be selected on Cortex-M cores.
+    if core_type.is_cortex_m() {
+        descriptions.extend(register_file.msp());
+        descriptions.extend(register_file.psp());
+        descriptions.extend(register_file.extra());
+
+        if has_fpu(core, core_type) {
+            descriptions.extend(register_file.fpscr());
+            descriptions.extend(register_file.fpu_registers());
+        }
+    }
+
+    let mut registers = BTreeMap::new();
+    for description in descriptions {
+        let address = CoreRegisterAddress::from(description);
+        registers.insert(address.0, core.read_core_reg(address)?);
+    }
+
+    let mut memory = Vec::new();
+    if let Some(model) = FaultModel::from_core_type(core_type) {
+        for address in model.registers() {
+            match core.read_word_32(address) {
+                Ok(value) => memory.push(DumpedMemory {
+                    address,
+                    data: value.to_le_bytes().to_vec(),
+                }),
+                Err(error) => log::debug!(
+                    "Failed to read the fault status register at {:#010x}: {}",
+                    address,
+                    error
+                ),
+            }
+        }
+    }
+
+    Ok(DumpedCore {
+        name,
+        core_type,
+        registers,
+        memory,
+    })
+}
+
+/// Checks if a Cortex-M core implements the floating-point extension.
+fn has_fpu(core: &mut Core, core_type: CoreType) -> bool {
+    if !matches!(
+        core_type,
+        CoreType::Armv7m | CoreType::Armv7em | CoreType::Armv8m
+    ) {
+        return false;
+    }
+
+    match core.read_word_32(MVFR0) {
+        Ok(mvfr0) => mvfr0 != 0,
+        Err(error) => {
+            log::debug!("Failed to read MVFR0, assuming there is no FPU: {}", error);
+            false
+        }
+    }
+}
+
+/// Replays the registers and memory of a core in a [`CoreDump`].
+struct DumpedCoreInterface<'dump> {
+    dump: &'dump CoreDump,
+    index: usize,
+}
+
+impl<'dump> DumpedCoreInterface<'dump> {
+    fn core(&self) -> &'dump DumpedCore {
+        &self.dump.cores[self.index]
+    }
+
+    fn read_bytes<const N: usize>(&self, address: u32) -> Result<[u8; N], Error> {
+        let mut bytes = [0; N];
+        self.dump.read_memory(self.index, address, &mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+impl<'dump> MemoryInterface for DumpedCoreInterface<'dump> {
+    fn read_word_32(&mut self, address: u32) -> Result<u32, Error> {
+        Ok(u32::from_le_bytes(self.read_bytes(address)?))
+    }
+
+    fn read_word_16(&mut self, address: u32) -> Result<u16, Error> {
+        Ok(u16::from_le_bytes(self.read_bytes(address)?))
+    }
+
+    fn read_word_8(&mut self, address: u32) -> Result<u8, Error> {
+        Ok(self.read_bytes::<1>(address)?[0])
+    }
+
+    fn read_32(&mut self, address: u32, data: &mut [u32]) -> Result<(), Error> {
+        let mut bytes = vec![0; data.len() * 4];
+        self.dump.read_memory(self.index, address, &mut bytes)?;
+
+        for (word, bytes) in data.iter_mut().zip(bytes.chunks_exact(4)) {
+            *word = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+
+        Ok(())
+    }
+
+    fn read_16(&mut self, address: u32, data: &mut [u16]) -> Result<(), Error> {
+        let mut bytes = vec![0; data.len() * 2];
+        self.dump.read_memory(self.index, address, &mut bytes)?;
+
+        for (half_word, bytes) in data.iter_mut().zip(bytes.chunks_exact(2)) {
+            *half_word = u16::from_le_bytes([bytes[0], bytes[1]]);
+        }
+
+        Ok(())
+    }
+
+    fn read_8(&mut self, address: u32, data: &mut [u8]) -> Result<(), Error> {
+        Ok(self.dump.read_memory(self.index, address, data)?)
+    }
+
+    fn write_word_32(&mut self, _address: u32, _data: u32) -> Result<(), Error> {
+        Err(CoreDumpError::ReadOnly("writing memory").into())
+    }
+
+    fn write_word_16(&mut self, _address: u32, _data: u16) -> Result<(), Error> {
+        Err(CoreDumpError::ReadOnly("writing memory").into())
+    }
+
+    fn write_word_8(&mut self, _address: u32, _data: u8) -> Result<(), Error> {
+        Err(CoreDumpError::ReadOnly("writing memory").into())
+    }
+
+    fn write_32(&mut self, _address: u32, _data: &[u32]) -> Result<(), Error> {
+        Err(CoreDumpError::ReadOnly("writing memory").into())
+    }
+
+    fn write_16(&mut self, _address: u32, _data: &[u16]) -> Result<(), Error> {
+        Err(CoreDumpError::ReadOnly("writing memory").into())
+    }
+
+    fn write_8(&mut self, _address: u32, _data: &[u8]) -> Result<(), Error> {
+        Err(CoreDumpError::ReadOnly("writing memory").into())
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<'dump> CoreInterface for DumpedCoreInterface<'dump> {
+    fn wait_for_core_halted(&mut self, _timeout: Duration) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn core_halted(&mut self) -> Result<bool, Error> {
+        Ok(true)
+    }
+
+    fn status(&mut self) -> Result<CoreStatus, Error> {
+        Ok(CoreStatus::Halted(HaltReason::Request))
+    }
+
+    fn halt(&mut self, _timeout: Duration) -> Result<CoreInformation, Error> {
+        let pc = self.read_core_reg(self.registers().program_counter().into())?;
+        Ok(CoreInformation { pc })
+    }
+
+    fn run(&mut self) -> Result<(), Error> {
+        Err(CoreDumpError::ReadOnly("running the core").into())
+    }
+
+    fn reset(&mut self) -> Result<(), Error> {
+        Err(CoreDumpError::ReadOnly("resetting the core").into())
+    }
+
+    fn reset_and_halt(&mut self, _timeout: Duration) -> Result<CoreInformation, Error> {
+        Err(CoreDumpError::ReadOnly("resetting the core").into())
+    }
+
+    fn step(&mut self) -> Result<CoreInformation, Error> {
+        Err(CoreDumpError::ReadOnly("stepping the core").into())
+    }
+
+    fn read_core_reg(&mut self, address: CoreRegisterAddress) -> Result<u32, Error> {
+        self.core()
+            .registers
+            .get(&address.0)
+            .copied()
+            .ok_or_else(|| CoreDumpError::RegisterNotDumped(address.0).into())
+    }
+
+    fn write_core_reg(&mut self, _address: CoreRegisterAddress, _value: u32) -> anyhow::Result<()> {
+        Err(anyhow!(CoreDumpError::ReadOnly("writing registers")))
+    }
+
+    fn available_breakpoint_units(&mut self) -> Result<u32, Error> {
+        Ok(0)
+    }
+
+    fn hw_breakpoints(&mut self) -> Result<Vec<Option<u32>>, Error> {
+        Ok(Vec::new())
+    }
+
+    fn enable_breakpoints(&mut self, _state: bool) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn set_hw_breakpoint(&mut self, _unit_index: usize, _addr: u32) -> Result<(), Error> {
+        Err(CoreDumpError::ReadOnly("setting breakpoints").into())
+    }
+
+    fn clear_hw_breakpoint(&mut self, _unit_index: usize) -> Result<(), Error> {
+        Err(CoreDumpError::ReadOnly("clearing breakpoints").into())
+    }
+
+    fn available_watchpoint_units(&mut self) -> Result<u32, Error> {
+        Ok(0)
+    }
+
+    fn hw_watchpoints(&mut self) -> Result<Vec<Option<Watchpoint>>, Error> {
+        Ok(Vec::new())
+    }
+
+    fn set_hw_watchpoint(
+        &mut self,
+        _unit_index: usize,
+        _watchpoint: Watchpoint,
+    ) -> Result<(), Error> {
+        Err(CoreDumpError::ReadOnly("setting watchpoints").into())
+    }
+
+    fn clear_hw_watchpoint(&mut self, _unit_index: usize) -> Result<(), Error> {
+        Err(CoreDumpError::ReadOnly("clearing watchpoints").into())
+    }
+
+    fn triggered_watchpoint(&mut self) -> Result<Option<Watchpoint>, Error> {
+        Ok(None)
+    }
+
+    fn enable_vector_catch(&mut self, _condition: VectorCatchCondition) -> Result<(), Error> {
+        Err(CoreDumpError::ReadOnly("vector catch").into())
+    }
+
+    fn disable_vector_catch(&mut self, _condition: VectorCatchCondition) -> Result<(), Error> {
+        Err(CoreDumpError::ReadOnly("vector catch").into())
+    }
+
+    fn fault_info(&mut self) -> Result<Option<FaultInfo>, Error> {
+        match FaultModel::from_core_type(self.core().core_type) {
+            Some(model) => fault::read_fault_info(self, model),
+            None => Ok(None),
+        }
+    }
+
+    fn registers(&self) -> &'static RegisterFile {
+        match self.architecture() {
+            Architecture::Arm => &ARM_REGISTER_FILE,
+            Architecture::Riscv => &RISCV_REGISTERS,
+        }
+    }
+
+    fn hw_breakpoints_enabled(&self) -> bool {
+        false
+    }
+
+    fn architecture(&self) -> Architecture {
+        self.core().core_type.architecture()
+    }
+
+    fn core_type(&self) -> CoreType {
+        self.core().core_type
+    }
+
+    fn instruction_set(&mut self) -> Result<InstructionSet, Error> {
+        match self.core().core_type {
+            CoreType::Armv7a => {
+                // CPSR bit 5 - T - Thumb mode
+                let cpsr = self.read_core_reg(CoreRegisterAddress(16))?;
+                match (cpsr >> 5) & 1 {
+                    1 => Ok(InstructionSet::Thumb2),
+                    _ => Ok(InstructionSet::A32),
+                }
+            }
+            CoreType::Riscv => Ok(InstructionSet::RV32),
+            _ => Ok(InstructionSet::Thumb2),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::core::{FaultCause, FaultException};
+
+    const STACK_POINTER: u32 = 0x2000_0ff0;
+
+    /// A Cortex-M4 which took a HardFault, with the exception frame at the top of the main stack.
+    fn hard_fault_dump() -> CoreDump {
+        let mut registers = BTreeMap::new();
+        registers.insert(13, STACK_POINTER);
+        registers.insert(14, 0xffff_fff9);
+        registers.insert(15, 0x0800_0400);
+        registers.insert(0b10001, STACK_POINTER);
+        registers.insert(0b100_0000, 1.5f32.to_bits());
+
+        let icsr = DumpedMemory {
+            address: 0xE000_ED04,
+            data: 3u32.to_le_bytes().to_vec(),
+        };
+        let hfsr = DumpedMemory {
+            address: 0xE000_ED2C,
+            data: (1u32 << 30).to_le_bytes().to_vec(),
+        };
+        let mut fault_registers = vec![icsr, hfsr];
+        for address in [0xE000_EDF0, 0xE000_ED30, 0xE000_ED28] {
+            fault_registers.push(DumpedMemory {
+                address,
+                data: vec![0; 4],
+            });
+        }
+
+        // R0-R3, R12, LR, PC and xPSR of the faulting code.
+        let frame: Vec<u8> = [0, 1, 2, 3, 12, 0x0800_0123, 0x0800_0200, 0x0100_0000u32]
+            .iter()
+            .flat_map(|word| word.to_le_bytes())
+            .collect();
+        let mut ram = vec![0; 0x1000];
+        ram[0xff0..].copy_from_slice(&frame[..0x10]);
+
+        CoreDump {
+            target: "nRF52840_xxAA".to_string(),
+            cores: vec![DumpedCore {
+                name: "main".to_string(),
+                core_type: CoreType::Armv7em,
+                registers,
+                memory: fault_registers,
+            }],
+            memory: vec![
+                DumpedMemory {
+                    address: 0x2000_0000,
+                    data: ram,
+                },
+                DumpedMemory {
+                    address: 0x2000_1000,
+                    data: frame[0x10..].to_vec(),
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn replay_registers_and_memory() {
+        let dump = hard_fault_dump();
+        let mut state = CoreState::new(0);
+        let mut core = dump.core(0, &mut state).unwrap();
+
+        assert_eq!(core.halt(HALT_TIMEOUT).unwrap().pc, 0x0800_0400);
+        assert_eq!(
+            core.read_core_reg(ARM_REGISTER_FILE.stack_pointer())
+                .unwrap(),
+            STACK_POINTER
+        );
+        assert_eq!(
+            core.read_core_reg(CoreRegisterAddress(0b100_0000)).unwrap(),
+            1.5f32.to_bits()
+        );
+        assert!(core.read_core_reg(CoreRegisterAddress(0)).is_err());
+
+        assert_eq!(core.read_word_32(STACK_POINTER + 4).unwrap(), 1);
+        assert_eq!(core.read_word_16(STACK_POINTER + 0x14).unwrap(), 0x0123);
+
+        // The access spans the two memory blocks, which are not merged.
+        let mut frame = [0u32; 8];
+        assert!(core.read_32(STACK_POINTER, &mut frame).is_err());
+        core.read_32(STACK_POINTER, &mut frame[..4]).unwrap();
+        assert_eq!(frame[..4], [0, 1, 2, 3]);
+
+        assert!(core.read_word_32(0x1fff_fffe).is_err());
+        assert!(core.write_word_32(STACK_POINTER, 0).is_err());
+        assert!(core.run().is_err());
+        assert_eq!(core.instruction_set().unwrap(), InstructionSet::Thumb2);
+    }
+
+    #[test]
+    fn replay_fault_info() {
+        let mut dump = hard_fault_dump();
+        let frame = dump.memory.pop().unwrap();
+        dump.memory[0].data.extend_from_slice(&frame.data);
+
+        let mut state = CoreState::new(0);
+        let mut core = dump.core(0, &mut state).unwrap();
+        let info = core.fault_info().unwrap().unwrap();
+
+        assert_eq!(info.exception, Some(FaultException::HardFault));
+        assert_eq!(info.causes(), [FaultCause::Forced]);
+        assert_eq!(info.pc, Some(0x0800_0200));
+        assert_eq!(info.lr, Some(0x0800_0123));
+    }
+
+    #[test]
+    fn save_and_load() {
+        let dump = hard_fault_dump();
+
+        let mut file = Vec::new();
+        dump.save(&mut file).unwrap();
+        assert_eq!(CoreDump::load(&file[..]).unwrap(), dump);
+
+        let mut other_version = file.clone();
+        other_version[MAGIC.len()] = 2;
+        assert!(matches!(
+            CoreDump::load(&other_version[..]),
+            Err(CoreDumpError::UnsupportedVersion(2))
+        ));
+
+        assert!(matches!(
+            CoreDump::load(&b"ELF"[..]),
+            Err(CoreDumpError::NotACoreDump)
+        ));
+        assert!(CoreDump::load(&file[..file.len() - 1]).is_err());
+    }
+}
@@ -1,9 +1,12 @@
 pub(crate) mod communication_interface;
+mod dump;
 
 use crate::{CoreType, InstructionSet};
 pub use communication_interface::CommunicationInterface;
+pub use dump::{CoreDump, CoreDumpError, DumpedCore, DumpedMemory};
 pub use probe_rs_target::Architecture;
 
+pub use crate::architecture::arm::core::fault::{FaultCause, FaultException, FaultInfo};
 use crate::architecture::arm::sequences::ArmDebugSequenceError;
 use crate::architecture::{
     arm::core::State, riscv::communication_interface::RiscvCommunicationInterface,
@@ -12,6 +15,9 @@ use crate::error;
 use crate::Target;
 use crate::{Error, Memory, MemoryInterface};
 use anyhow::{anyhow, Result};
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::sync::{Arc, Mutex, MutexGuard};
 use std::time::Duration;
 
 /// A core register (e.g. Stack Pointer).
@@ -100,7 +106,10 @@ pub struct RegisterFile {
     pub(crate) psp: Option<&'static RegisterDescription>,
 
     pub(crate) extra: Option<&'static RegisterDescription>,
-    // TODO: floating point support
+
+    pub(crate) fpscr: Option<&'static RegisterDescription>,
+
+    pub(crate) fpu_registers: Option<&'static [RegisterDescription]>,
 }
 
 impl RegisterFile {
@@ -188,18 +197,30 @@ impl RegisterFile {
     // Bits[15:8]  BASEPRI.
     // Bits[7:0]   PRIMASK.
     // In each field, the valid bits are packed with leading zeros. For example,
-    // // FAULTMASK is always a single bit, DCRDR[16], and DCRDR[23:17] is 0b0000000.
-    // pub fn extra(&self) -> Option<&RegisterDescription> {
-    //     self.extra
-    // }
+    // FAULTMASK is always a single bit, DCRDR[16], and DCRDR[23:17] is 0b0000000.
+    /// The CONTROL, FAULTMASK, BASEPRI and PRIMASK registers, packed into a single register.
+    pub fn extra(&self) -> Option<&RegisterDescription> {
+        self.extra
+    }
 
-    // TODO: support for floating point registers
+    // C1.6.3 Debug Core Register Selector Register, DCRSR
     // 0b0100001            Floating-point Status and Control Register, FPSCR.
     // 0b1000000-0b1011111  FP registers S0-S31.
-    // For example, 0b1000000 specifies S0, and 0b1000101 specifies S5.
-    // All other values are Reserved.
     // If the processor does not implement the FP extension the REGSEL field is bits[4:0], and
     // bits[6:5] are Reserved, SBZ.
+    /// The floating-point status and control register.
+    ///
+    /// It can only be read if the core implements the floating-point extension.
+    pub fn fpscr(&self) -> Option<&RegisterDescription> {
+        self.fpscr
+    }
+
+    /// Returns an iterator over the single-precision floating-point registers S0-S31.
+    ///
+    /// They can only be read if the core implements the floating-point extension.
+    pub fn fpu_registers(&self) -> impl Iterator<Item = &RegisterDescription> {
+        self.fpu_registers.unwrap_or(&[]).iter()
+    }
 }
 
 /// A generic interface to control a MCU core.
@@ -260,6 +281,41 @@ pub trait CoreInterface: MemoryInterface {
     /// Clears the breakpoint configured in unit `unit_index`.
     fn clear_hw_breakpoint(&mut self, unit_index: usize) -> Result<(), error::Error>;
 
+    /// Returns the number of comparator units which can be used as data watchpoints.
+    fn available_watchpoint_units(&mut self) -> Result<u32, error::Error>;
+
+    /// Read the data watchpoints from the comparator registers, and adds them to the Result Vector.
+    /// A value of None in any position of the Vector indicates that the position is unset/available.
+    fn hw_watchpoints(&mut self) -> Result<Vec<Option<Watchpoint>>, error::Error>;
+
+    /// Configures unit `unit_index` as a data watchpoint.
+    fn set_hw_watchpoint(
+        &mut self,
+        unit_index: usize,
+        watchpoint: Watchpoint,
+    ) -> Result<(), error::Error>;
+
+    /// Clears the watchpoint configured in unit `unit_index`.
+    fn clear_hw_watchpoint(&mut self, unit_index: usize) -> Result<(), error::Error>;
+
+    /// Returns the watchpoint which caused the last halt, if the hardware is able to tell.
+    ///
+    /// On ARM cores the matched flag is cleared when it is read, so this should be called
+    /// once, directly after the core halted due to a watchpoint.
+    fn triggered_watchpoint(&mut self) -> Result<Option<Watchpoint>, error::Error>;
+
+    /// Configures the core to halt when the exception described by `condition` occurs.
+    fn enable_vector_catch(&mut self, condition: VectorCatchCondition) -> Result<(), error::Error>;
+
+    /// Stops the core from halting when the exception described by `condition` occurs.
+    fn disable_vector_catch(&mut self, condition: VectorCatchCondition)
+        -> Result<(), error::Error>;
+
+    /// Decodes why the core faulted, if it is currently handling a fault exception or is locked up.
+    ///
+    /// This is only supported on ARMv6-M, ARMv7-M and ARMv8-M cores, all other cores return `None`.
+    fn fault_info(&mut self) -> Result<Option<FaultInfo>, error::Error>;
+
     /// Returns a list of all the registers of this core.
     fn registers(&self) -> &'static RegisterFile;
 
@@ -283,6 +339,10 @@ impl<'probe> MemoryInterface for Core<'probe> {
         self.inner.read_word_32(address)
     }
 
+    fn read_word_16(&mut self, address: u32) -> Result<u16, Error> {
+        self.inner.read_word_16(address)
+    }
+
     fn read_word_8(&mut self, address: u32) -> Result<u8, Error> {
         self.inner.read_word_8(address)
     }
@@ -291,6 +351,10 @@ impl<'probe> MemoryInterface for Core<'probe> {
         self.inner.read_32(address, data)
     }
 
+    fn read_16(&mut self, address: u32, data: &mut [u16]) -> Result<(), Error> {
+        self.inner.read_16(address, data)
+    }
+
     fn read_8(&mut self, address: u32, data: &mut [u8]) -> Result<(), Error> {
         self.inner.read_8(address, data)
     }
@@ -299,6 +363,10 @@ impl<'probe> MemoryInterface for Core<'probe> {
         self.inner.write_word_32(addr, data)
     }
 
+    fn write_word_16(&mut self, addr: u32, data: u16) -> Result<(), Error> {
+        self.inner.write_word_16(addr, data)
+    }
+
     fn write_word_8(&mut self, addr: u32, data: u8) -> Result<(), Error> {
         self.inner.write_word_8(addr, data)
     }
@@ -307,6 +375,10 @@ impl<'probe> MemoryInterface for Core<'probe> {
         self.inner.write_32(addr, data)
     }
 
+    fn write_16(&mut self, addr: u32, data: &[u16]) -> Result<(), Error> {
+        self.inner.write_16(addr, data)
+    }
+
     fn write_8(&mut self, addr: u32, data: &[u8]) -> Result<(), Error> {
         self.inner.write_8(addr, data)
     }
@@ -320,12 +392,30 @@ impl<'probe> MemoryInterface for Core<'probe> {
 #[derive(Debug)]
 pub struct CoreState {
     id: usize,
+
+    /// The software breakpoints which are inserted into the target, by address.
+    ///
+    /// The cores of a session share their memory, so the breakpoints are tracked
+    /// once for all cores of the session, see [`SharedSoftwareBreakpoints`].
+    pub(crate) software_breakpoints: SharedSoftwareBreakpoints,
+
+    /// The address ranges of non-volatile memory, in which software breakpoints can only be
+    /// inserted by reprogramming the flash, see [`Session::set_breakpoint`](crate::Session::set_breakpoint).
+    pub(crate) nvm_ranges: Vec<Range<u32>>,
+
+    /// The address ranges of all memory regions of the target.
+    pub(crate) memory_ranges: Vec<Range<u32>>,
 }
 
 impl CoreState {
     /// Creates a new core state from the core ID.
     pub fn new(id: usize) -> Self {
-        Self { id }
+        Self {
+            id,
+            software_breakpoints: SharedSoftwareBreakpoints::default(),
+            nvm_ranges: Vec::new(),
+            memory_ranges: Vec::new(),
+        }
     }
 
     /// Returns the core ID.
@@ -491,6 +581,7 @@ impl<'probe> Core<'probe> {
 
     /// Continue to execute instructions.
     pub fn run(&mut self) -> Result<(), error::Error> {
+        self.step_over_sw_breakpoint()?;
         self.inner.run()
     }
 
@@ -512,7 +603,10 @@ impl<'probe> Core<'probe> {
 
     /// Steps one instruction and then enters halted state again.
     pub fn step(&mut self) -> Result<CoreInformation, error::Error> {
-        self.inner.step()
+        match self.step_over_sw_breakpoint()? {
+            Some(core_information) => Ok(core_information),
+            None => self.inner.step(),
+        }
     }
 
     /// Returns the current status of the core.
@@ -520,6 +614,14 @@ impl<'probe> Core<'probe> {
         self.inner.status()
     }
 
+    /// Returns a decoded description of the fault the core is handling, if any.
+    ///
+    /// This should be called while the core is halted. On Cortex-M cores it decodes
+    /// the fault status registers and reads the faulting PC from the stacked exception frame.
+    pub fn fault_info(&mut self) -> Result<Option<FaultInfo>, error::Error> {
+        self.inner.fault_info()
+    }
+
     /// Read the value of a core register.
     pub fn read_core_reg(
         &mut self,
@@ -542,8 +644,15 @@ impl<'probe> Core<'probe> {
         self.inner.available_breakpoint_units()
     }
 
+    /// Returns all hardware breakpoints which are currently configured on the core.
+    ///
+    /// A value of None in any position of the Vector indicates that the unit is available.
+    pub fn hw_breakpoints(&mut self) -> Result<Vec<Option<u32>>, error::Error> {
+        self.inner.hw_breakpoints()
+    }
+
     /// Enables breakpoints on this core. If a breakpoint is set, it will halt as soon as it is hit.
-    fn enable_breakpoints(&mut self, state: bool) -> Result<(), error::Error> {
+    pub(crate) fn enable_breakpoints(&mut self, state: bool) -> Result<(), error::Error> {
         self.inner.enable_breakpoints(state)
     }
 
@@ -552,18 +661,88 @@ impl<'probe> Core<'probe> {
         self.inner.registers()
     }
 
+    /// Returns the comparator units which are in use by a watchpoint, if the core allocates
+    /// breakpoints and watchpoints from the same pool of units.
+    ///
+    /// This is the case for the RISC-V trigger module, while ARM cores use the FPB for
+    /// breakpoints and the DWT for watchpoints.
+    fn units_shared_with_watchpoints(&mut self) -> Result<Vec<bool>, error::Error> {
+        match self.architecture() {
+            Architecture::Riscv => Ok(self
+                .inner
+                .hw_watchpoints()?
+                .iter()
+                .map(Option::is_some)
+                .collect()),
+            Architecture::Arm => Ok(vec![]),
+        }
+    }
+
+    /// Returns the comparator units which are in use by a breakpoint, if the core allocates
+    /// breakpoints and watchpoints from the same pool of units.
+    fn units_shared_with_breakpoints(&mut self) -> Result<Vec<bool>, error::Error> {
+        match self.architecture() {
+            Architecture::Riscv => Ok(self
+                .inner
+                .hw_breakpoints()?
+                .iter()
+                .map(Option::is_some)
+                .collect()),
+            Architecture::Arm => Ok(vec![]),
+        }
+    }
+
+    /// Find the index of the next available HW breakpoint comparator, if there is one.
+    fn free_breakpoint_comparator_index(&mut self) -> Result<Option<usize>, error::Error> {
+        let used_by_watchpoints = self.units_shared_with_watchpoints()?;
+
+        let free_index = self
+            .inner
+            .hw_breakpoints()?
+            .iter()
+            .enumerate()
+            .find(|(index, breakpoint)| {
+                breakpoint.is_none() && !used_by_watchpoints.get(*index).copied().unwrap_or(false)
+            })
+            .map(|(index, _)| index);
+
+        Ok(free_index)
+    }
+
     /// Find the index of the next available HW breakpoint comparator.
     fn find_free_breakpoint_comparator_index(&mut self) -> Result<usize, error::Error> {
-        let mut next_available_hw_breakpoint = 0;
-        for breakpoint in self.inner.hw_breakpoints()? {
-            if breakpoint.is_none() {
-                return Ok(next_available_hw_breakpoint);
-            } else {
-                next_available_hw_breakpoint += 1;
+        self.free_breakpoint_comparator_index()?
+            .ok_or_else(|| error::Error::Other(anyhow!("No available hardware breakpoints")))
+    }
+
+    /// Checks if a hardware breakpoint can be set at `address`, either because a
+    /// comparator is already configured for `address`, or because a comparator is free.
+    pub(crate) fn hw_breakpoint_available(&mut self, address: u32) -> Result<bool, error::Error> {
+        if self.inner.hw_breakpoints()?.contains(&Some(address)) {
+            return Ok(true);
+        }
+
+        Ok(self.free_breakpoint_comparator_index()?.is_some())
+    }
+
+    /// Find the index of the next available HW watchpoint comparator.
+    fn find_free_watchpoint_comparator_index(&mut self) -> Result<usize, error::Error> {
+        let used_by_breakpoints = self.units_shared_with_breakpoints()?;
+        let watchpoints = self.inner.hw_watchpoints()?;
+
+        if watchpoints.is_empty() {
+            return Err(error::Error::Other(anyhow!(
+                "The core has no hardware watchpoint units"
+            )));
+        }
+
+        for (index, watchpoint) in watchpoints.iter().enumerate() {
+            if watchpoint.is_none() && !used_by_breakpoints.get(index).copied().unwrap_or(false) {
+                return Ok(index);
             }
         }
         Err(error::Error::Other(anyhow!(
-            "No available hardware breakpoints"
+            "No available hardware watchpoints"
         )))
     }
 
@@ -641,6 +820,372 @@ impl<'probe> Core<'probe> {
         Ok(())
     }
 
+    /// Set a breakpoint
+    ///
+    /// This function sets a hardware breakpoint at `address` if a comparator unit is available,
+    /// and falls back to a software breakpoint once all of them are in use.
+    ///
+    /// See [`Core::set_sw_breakpoint`] for the limitations of software breakpoints.
+    pub fn set_breakpoint(&mut self, address: u32) -> Result<(), error::Error> {
+        if self.software_breakpoints().contains_key(&address) {
+            // The breakpoint instruction was inserted by another core, this core only has to halt on it.
+            return self.enable_breakpoints(true);
+        }
+
+        if self.hw_breakpoint_available(address)? {
+            self.set_hw_breakpoint(address)
+        } else {
+            log::debug!(
+                "No hardware breakpoint available for {:#010x}, using a software breakpoint",
+                address
+            );
+            self.set_sw_breakpoint(address)
+        }
+    }
+
+    /// Clear a breakpoint
+    ///
+    /// This function clears the breakpoint at `address`, regardless of it being a hardware or a software breakpoint.
+    /// Software breakpoints are shared by all cores of a session, so this also clears them for the other cores.
+    pub fn clear_breakpoint(&mut self, address: u32) -> Result<(), error::Error> {
+        let hw_breakpoint = self.inner.hw_breakpoints()?.contains(&Some(address));
+
+        if hw_breakpoint {
+            self.clear_hw_breakpoint(address)?;
+        }
+
+        if self.software_breakpoints().contains_key(&address) {
+            self.clear_sw_breakpoint(address)
+        } else if hw_breakpoint {
+            Ok(())
+        } else {
+            Err(error::Error::Other(anyhow!(
+                "No breakpoint found at address {:#010x}",
+                address
+            )))
+        }
+    }
+
+    /// Set a software breakpoint
+    ///
+    /// This function replaces the instruction at `address` with a breakpoint instruction (`BKPT` on ARM, `ebreak` on RISC-V).
+    /// The original instruction is restored when the breakpoint is cleared, and while stepping or resuming
+    /// the core from the breakpoint.
+    ///
+    /// This only works for memory which can be written directly, such as RAM.
+    /// Breakpoints in flash can be set using [`Session::set_breakpoint`](crate::Session::set_breakpoint),
+    /// which reprograms the flash.
+    pub fn set_sw_breakpoint(&mut self, address: u32) -> Result<(), error::Error> {
+        if self.software_breakpoints().contains_key(&address) {
+            return self.enable_breakpoints(true);
+        }
+
+        if self.is_nvm_address(address) {
+            return Err(error::Error::Other(anyhow!(
+                "The software breakpoint at {:#010x} is located in flash, it has to be set using `Session::set_breakpoint`",
+                address
+            )));
+        }
+
+        let (original_instruction, breakpoint_instruction) =
+            self.read_sw_breakpoint_instruction(address)?;
+
+        self.enable_breakpoints(true)?;
+
+        self.write_8(address, breakpoint_instruction)?;
+
+        // Writes to flash or ROM are silently ignored on a lot of targets,
+        // so make sure the breakpoint instruction actually ended up in memory.
+        let mut readback = vec![0; breakpoint_instruction.len()];
+        self.read_8(address, &mut readback)?;
+
+        if readback != breakpoint_instruction {
+            return Err(error::Error::Other(anyhow!(
+                "Unable to set a software breakpoint at {:#010x}, the memory is not writable",
+                address
+            )));
+        }
+
+        log::debug!("Set SW breakpoint at {:#010x}", address);
+
+        self.software_breakpoints().insert(
+            address,
+            SoftwareBreakpoint {
+                original_instruction,
+                breakpoint_instruction,
+                in_flash: false,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Clear a software breakpoint
+    ///
+    /// This function restores the original instruction at `address`, if a software breakpoint was set there.
+    pub fn clear_sw_breakpoint(&mut self, address: u32) -> Result<(), error::Error> {
+        let breakpoint = match self.software_breakpoints().get(&address).cloned() {
+            Some(breakpoint) if breakpoint.in_flash => {
+                return Err(error::Error::Other(anyhow!(
+                    "The software breakpoint at {:#010x} is located in flash, it has to be cleared using `Session::clear_breakpoint`",
+                    address
+                )))
+            }
+            Some(breakpoint) => breakpoint,
+            None => {
+                return Err(error::Error::Other(anyhow!(
+                    "No software breakpoint found at address {:#010x}",
+                    address
+                )))
+            }
+        };
+
+        self.write_8(address, &breakpoint.original_instruction)?;
+        self.software_breakpoints().remove(&address);
+
+        log::debug!("Cleared SW breakpoint at {:#010x}", address);
+
+        Ok(())
+    }
+
+    /// Clear all software breakpoints
+    ///
+    /// This function restores the original instructions of all software breakpoints set in RAM.
+    /// Software breakpoints in flash are left in place, they are cleared by [`Session::clear_all_sw_breakpoints`](crate::Session::clear_all_sw_breakpoints).
+    pub fn clear_all_sw_breakpoints(&mut self) -> Result<(), error::Error> {
+        let addresses: Vec<u32> = self
+            .software_breakpoints()
+            .iter()
+            .filter(|(_, breakpoint)| !breakpoint.in_flash)
+            .map(|(&address, _)| address)
+            .collect();
+
+        for address in addresses {
+            self.clear_sw_breakpoint(address)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the addresses of all software breakpoints which are set on the cores of this session.
+    pub fn sw_breakpoints(&self) -> Vec<u32> {
+        self.software_breakpoints().keys().copied().collect()
+    }
+
+    /// Locks the software breakpoints, which are shared with the other cores of the session.
+    pub(crate) fn software_breakpoints(&self) -> MutexGuard<'_, BTreeMap<u32, SoftwareBreakpoint>> {
+        self.state.software_breakpoints.lock().unwrap()
+    }
+
+    /// Checks if `address` is located in non-volatile memory, where software breakpoints can't be written directly.
+    pub(crate) fn is_nvm_address(&self, address: u32) -> bool {
+        self.state
+            .nvm_ranges
+            .iter()
+            .any(|range| range.contains(&address))
+    }
+
+    /// Returns the address range of the memory region which contains `address`,
+    /// or `None` if the memory map of the target does not describe it.
+    pub(crate) fn memory_range(&self, address: u32) -> Option<Range<u32>> {
+        self.state
+            .memory_ranges
+            .iter()
+            .find(|range| range.contains(&address))
+            .cloned()
+    }
+
+    /// Reads the instruction at `address`, and returns it together with the breakpoint instruction which replaces it.
+    pub(crate) fn read_sw_breakpoint_instruction(
+        &mut self,
+        address: u32,
+    ) -> Result<(Vec<u8>, &'static [u8]), error::Error> {
+        // All supported instruction sets have instructions of at least two bytes,
+        // which is enough to determine the size of the instruction.
+        let mut first_halfword = [0u8; 2];
+        self.read_8(address, &mut first_halfword)?;
+
+        let breakpoint_instruction: &'static [u8] = match self.instruction_set()? {
+            InstructionSet::Thumb2 => &THUMB_BKPT,
+            InstructionSet::A32 => &A32_BKPT,
+            // Only the lowest two bits of uncompressed instructions are both set.
+            InstructionSet::RV32 if first_halfword[0] & 0b11 != 0b11 => &RISCV_C_EBREAK,
+            InstructionSet::RV32 => &RISCV_EBREAK,
+        };
+
+        let mut original_instruction = vec![0; breakpoint_instruction.len()];
+        self.read_8(address, &mut original_instruction)?;
+
+        // The breakpoint instruction might have been left behind by another debugger.
+        // Saving it would make it permanent.
+        if original_instruction == breakpoint_instruction {
+            return Err(error::Error::Other(anyhow!(
+                "The instruction at {:#010x} already is a breakpoint instruction",
+                address
+            )));
+        }
+
+        Ok((original_instruction, breakpoint_instruction))
+    }
+
+    /// Steps over the instruction at the program counter, if it was replaced by a software breakpoint.
+    ///
+    /// The original instruction is restored for the duration of the step, and the breakpoint is
+    /// inserted again afterwards. Returns `None` if the core is not halted on a software breakpoint.
+    fn step_over_sw_breakpoint(&mut self) -> Result<Option<CoreInformation>, error::Error> {
+        let no_breakpoints = self.software_breakpoints().is_empty();
+
+        if no_breakpoints || !self.inner.core_halted()? {
+            return Ok(None);
+        }
+
+        let pc_register = self.registers().program_counter().into();
+        let pc = self.inner.read_core_reg(pc_register)?;
+
+        let breakpoint = match self.software_breakpoints().get(&pc).cloned() {
+            Some(breakpoint) => breakpoint,
+            None => return Ok(None),
+        };
+
+        if breakpoint.in_flash {
+            return Err(error::Error::Other(anyhow!(
+                "Unable to step over the software breakpoint at {:#010x}, because it is located in flash. The breakpoint has to be cleared first.",
+                pc
+            )));
+        }
+
+        self.write_8(pc, &breakpoint.original_instruction)?;
+
+        let step_result = self.inner.step();
+
+        // Insert the breakpoint again even if stepping failed, so the target matches the tracked state.
+        self.write_8(pc, breakpoint.breakpoint_instruction)?;
+
+        step_result.map(Some)
+    }
+
+    /// Returns the number of comparator units which can be used as data watchpoints.
+    pub fn available_watchpoint_units(&mut self) -> Result<u32, error::Error> {
+        self.inner.available_watchpoint_units()
+    }
+
+    /// Returns all data watchpoints which are currently configured on the core.
+    ///
+    /// A value of None in any position of the Vector indicates that the unit is available.
+    pub fn hw_watchpoints(&mut self) -> Result<Vec<Option<Watchpoint>>, error::Error> {
+        self.inner.hw_watchpoints()
+    }
+
+    /// Set a hardware watchpoint
+    ///
+    /// This function will try to set a data watchpoint which halts the core when
+    /// the `length` bytes starting at `address` are accessed as given by `kind`.
+    ///
+    /// Depending on the architecture, `length` has to be a power of two and
+    /// `address` has to be aligned to `length`.
+    pub fn set_hw_watchpoint(
+        &mut self,
+        address: u32,
+        length: u32,
+        kind: WatchpointKind,
+    ) -> Result<(), error::Error> {
+        let watchpoint = Watchpoint {
+            address,
+            length,
+            kind,
+        };
+
+        // If there is a watchpoint of the same kind set already, reuse its unit, else find the next free index.
+        let watchpoint_comparator_index = match self
+            .inner
+            .hw_watchpoints()?
+            .iter()
+            .position(|wp| wp.map(|wp| (wp.address, wp.kind)) == Some((address, kind)))
+        {
+            Some(watchpoint_comparator_index) => watchpoint_comparator_index,
+            None => self.find_free_watchpoint_comparator_index()?,
+        };
+
+        log::debug!(
+            "Trying to set HW watchpoint #{} for {:?}",
+            watchpoint_comparator_index,
+            watchpoint
+        );
+
+        self.inner
+            .set_hw_watchpoint(watchpoint_comparator_index, watchpoint)
+    }
+
+    /// Clear a hardware watchpoint
+    ///
+    /// This function will try to clear the watchpoint of the given `kind` at `address`, if there exists one.
+    pub fn clear_hw_watchpoint(
+        &mut self,
+        address: u32,
+        kind: WatchpointKind,
+    ) -> Result<(), error::Error> {
+        let wp_position = self
+            .inner
+            .hw_watchpoints()?
+            .iter()
+            .position(|wp| wp.map(|wp| (wp.address, wp.kind)) == Some((address, kind)));
+
+        log::debug!(
+            "Will clear HW watchpoint    #{} with comparator address    {:#08x}",
+            wp_position.unwrap_or(usize::MAX),
+            address
+        );
+
+        match wp_position {
+            Some(wp_position) => self.inner.clear_hw_watchpoint(wp_position),
+            None => Err(error::Error::Other(anyhow!(
+                "No {:?} watchpoint found at address {:#010x}",
+                kind,
+                address
+            ))),
+        }
+    }
+
+    /// Clear all hardware watchpoints
+    ///
+    /// This function will clear all data watchpoints which are configured on the target,
+    /// regardless if they are set by probe-rs.
+    pub fn clear_all_hw_watchpoints(&mut self) -> Result<(), error::Error> {
+        let watchpoints = self.inner.hw_watchpoints()?;
+        for (unit_index, _) in watchpoints
+            .iter()
+            .enumerate()
+            .filter(|(_, wp)| wp.is_some())
+        {
+            self.inner.clear_hw_watchpoint(unit_index)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the watchpoint which caused the core to halt, if the hardware reports it.
+    ///
+    /// See [`CoreInterface::triggered_watchpoint`] for the limitations of this function.
+    pub fn triggered_watchpoint(&mut self) -> Result<Option<Watchpoint>, error::Error> {
+        self.inner.triggered_watchpoint()
+    }
+
+    /// Halts the core when the exception described by `condition` occurs, before its handler is executed.
+    ///
+    /// The core then reports [`HaltReason::Exception`] as the halt reason.
+    pub fn enable_vector_catch(
+        &mut self,
+        condition: VectorCatchCondition,
+    ) -> Result<(), error::Error> {
+        self.inner.enable_vector_catch(condition)
+    }
+
+    /// Disables halting the core when the exception described by `condition` occurs.
+    pub fn disable_vector_catch(
+        &mut self,
+        condition: VectorCatchCondition,
+    ) -> Result<(), error::Error> {
+        self.inner.disable_vector_catch(condition)
+    }
+
     /// Returns the architecture of the core.
     pub fn architecture(&self) -> Architecture {
         self.inner.architecture()
@@ -670,6 +1215,77 @@ impl BreakpointId {
     }
 }
 
+/// `BKPT #0` in the Thumb instruction set.
+const THUMB_BKPT: [u8; 2] = [0x00, 0xbe];
+
+/// `BKPT #0` in the A32 instruction set.
+const A32_BKPT: [u8; 4] = [0x70, 0x00, 0x20, 0xe1];
+
+/// `ebreak` in the RISC-V base instruction set.
+const RISCV_EBREAK: [u8; 4] = [0x73, 0x00, 0x10, 0x00];
+
+/// `c.ebreak` in the RISC-V compressed instruction set extension.
+const RISCV_C_EBREAK: [u8; 2] = [0x02, 0x90];
+
+/// The software breakpoints of a session by address, which are shared by all of its cores.
+pub(crate) type SharedSoftwareBreakpoints = Arc<Mutex<BTreeMap<u32, SoftwareBreakpoint>>>;
+
+/// A software breakpoint, which replaces an instruction in the target memory with a breakpoint instruction.
+#[derive(Debug, Clone)]
+pub(crate) struct SoftwareBreakpoint {
+    /// The instruction which was replaced by the breakpoint.
+    pub(crate) original_instruction: Vec<u8>,
+    /// The breakpoint instruction which was written to the target.
+    pub(crate) breakpoint_instruction: &'static [u8],
+    /// Whether the breakpoint was inserted by reprogramming flash.
+    pub(crate) in_flash: bool,
+}
+
+/// The type of memory access which triggers a data watchpoint.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum WatchpointKind {
+    /// Halt when the watched memory is written.
+    Write,
+    /// Halt when the watched memory is read.
+    Read,
+    /// Halt on any read or write of the watched memory.
+    Access,
+}
+
+/// A data watchpoint, as configured in one of the comparator units of a core.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Watchpoint {
+    /// The start address of the watched memory range.
+    pub address: u32,
+    /// The number of watched bytes.
+    pub length: u32,
+    /// The type of access which triggers the watchpoint.
+    pub kind: WatchpointKind,
+}
+
+/// An exception on which the core can be halted, before the exception handler is executed.
+///
+/// On ARM Cortex-M cores, this is implemented with the vector catch bits of the `DEMCR` register.
+/// On RISC-V cores, the faults are caught with an exception trigger, which reports
+/// [`HaltReason::Breakpoint`], and the reset with the reset halt request of the debug module.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum VectorCatchCondition {
+    /// Halt on a HardFault. On RISC-V, this halts on all the faults below.
+    HardFault,
+    /// Halt at the reset vector, after a local reset of the core.
+    CoreReset,
+    /// Halt on a MemManage fault. Not available on ARMv6-M.
+    MemManage,
+    /// Halt on a BusFault. Not available on ARMv6-M.
+    BusFault,
+    /// Halt on a UsageFault, e.g. an undefined instruction or a division by zero. Not available on ARMv6-M.
+    UsageFault,
+    /// Halt on a fault during exception entry or return. Not available on ARMv6-M and RISC-V.
+    InterruptError,
+    /// Halt on a SecureFault. Only available on ARMv8-M cores with the security extension.
+    SecureFault,
+}
+
 /// The status of the core.
 #[derive(Debug, PartialEq, Copy, Clone)]
 pub enum CoreStatus {
@@ -720,3 +1336,411 @@ pub enum HaltReason {
     /// This can happen for example when the core is already halted when we connect.
     Unknown,
 }
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+    use crate::architecture::arm::core::ARM_REGISTER_FILE;
+    use crate::architecture::riscv::register::RISCV_REGISTERS;
+    use std::collections::HashMap;
+
+    pub(crate) const RAM_START: u32 = 0x2000_0000;
+    const FLASH: Range<u32> = 0x0..0x1000;
+
+    /// A Thumb core with a single hardware breakpoint unit and 256 bytes of RAM at [`RAM_START`],
+    /// which initially hold the values `0..=255`.
+    pub(crate) struct MockCore {
+        ram: Vec<u8>,
+        pc: u32,
+        registers: HashMap<CoreRegisterAddress, u32>,
+        hw_breakpoints: Vec<Option<u32>>,
+        architecture: Architecture,
+        running: bool,
+        /// The reason reported by [`CoreInterface::status`] while the core is halted.
+        pub(crate) halt_reason: HaltReason,
+    }
+
+    impl MockCore {
+        pub(crate) fn new() -> Self {
+            Self {
+                ram: (0..=255).collect(),
+                pc: RAM_START,
+                registers: HashMap::new(),
+                hw_breakpoints: vec![None],
+                architecture: Architecture::Arm,
+                running: false,
+                halt_reason: HaltReason::Request,
+            }
+        }
+
+        /// A RISC-V core with the same memory as [`MockCore::new`].
+        pub(crate) fn riscv() -> Self {
+            Self {
+                architecture: Architecture::Riscv,
+                ..Self::new()
+            }
+        }
+
+        fn ram_offset(&self, address: u32) -> Result<usize, Error> {
+            match address.checked_sub(RAM_START) {
+                Some(offset) if (offset as usize) < self.ram.len() => Ok(offset as usize),
+                _ => Err(Error::Other(anyhow!("No RAM at {:#010x}", address))),
+            }
+        }
+    }
+
+    impl MemoryInterface for MockCore {
+        fn read_word_32(&mut self, address: u32) -> Result<u32, Error> {
+            let mut bytes = [0; 4];
+            self.read_8(address, &mut bytes)?;
+            Ok(u32::from_le_bytes(bytes))
+        }
+
+        fn read_word_16(&mut self, address: u32) -> Result<u16, Error> {
+            let mut bytes = [0; 2];
+            self.read_8(address, &mut bytes)?;
+            Ok(u16::from_le_bytes(bytes))
+        }
+
+        fn read_word_8(&mut self, address: u32) -> Result<u8, Error> {
+            let offset = self.ram_offset(address)?;
+            Ok(self.ram[offset])
+        }
+
+        fn read_32(&mut self, address: u32, data: &mut [u32]) -> Result<(), Error> {
+            for (i, word) in data.iter_mut().enumerate() {
+                *word = self.read_word_32(address + 4 * i as u32)?;
+            }
+            Ok(())
+        }
+
+        fn read_16(&mut self, address: u32, data: &mut [u16]) -> Result<(), Error> {
+            for (i, word) in data.iter_mut().enumerate() {
+                *word = self.read_word_16(address + 2 * i as u32)?;
+            }
+            Ok(())
+        }
+
+        fn read_8(&mut self, address: u32, data: &mut [u8]) -> Result<(), Error> {
+            for (i, byte) in data.iter_mut().enumerate() {
+                *byte = self.read_word_8(address + i as u32)?;
+            }
+            Ok(())
+        }
+
+        fn write_word_32(&mut self, address: u32, data: u32) -> Result<(), Error> {
+            self.write_8(address, &data.to_le_bytes())
+        }
+
+        fn write_word_16(&mut self, address: u32, data: u16) -> Result<(), Error> {
+            self.write_8(address, &data.to_le_bytes())
+        }
+
+        fn write_word_8(&mut self, address: u32, data: u8) -> Result<(), Error> {
+            let offset = self.ram_offset(address)?;
+            self.ram[offset] = data;
+            Ok(())
+        }
+
+        fn write_32(&mut self, address: u32, data: &[u32]) -> Result<(), Error> {
+            for (i, word) in data.iter().enumerate() {
+                self.write_word_32(address + 4 * i as u32, *word)?;
+            }
+            Ok(())
+        }
+
+        fn write_16(&mut self, address: u32, data: &[u16]) -> Result<(), Error> {
+            for (i, word) in data.iter().enumerate() {
+                self.write_word_16(address + 2 * i as u32, *word)?;
+            }
+            Ok(())
+        }
+
+        fn write_8(&mut self, address: u32, data: &[u8]) -> Result<(), Error> {
+            for (i, byte) in data.iter().enumerate() {
+                self.write_word_8(address + i as u32, *byte)?;
+            }
+            Ok(())
+        }
+
+        fn flush(&mut self) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    impl CoreInterface for MockCore {
+        fn wait_for_core_halted(&mut self, _timeout: Duration) -> Result<(), Error> {
+            Ok(())
+        }
+
+        fn core_halted(&mut self) -> Result<bool, Error> {
+            Ok(true)
+        }
+
+        fn status(&mut self) -> Result<CoreStatus, Error> {
+            if self.running {
+                Ok(CoreStatus::Running)
+            } else {
+                Ok(CoreStatus::Halted(self.halt_reason))
+            }
+        }
+
+        fn halt(&mut self, _timeout: Duration) -> Result<CoreInformation, Error> {
+            self.running = false;
+            Ok(CoreInformation { pc: self.pc })
+        }
+
+        fn run(&mut self) -> Result<(), Error> {
+            self.running = true;
+            Ok(())
+        }
+
+        fn reset(&mut self) -> Result<(), Error> {
+            Ok(())
+        }
+
+        fn reset_and_halt(&mut self, _timeout: Duration) -> Result<CoreInformation, Error> {
+            Ok(CoreInformation { pc: self.pc })
+        }
+
+        fn step(&mut self) -> Result<CoreInformation, Error> {
+            let mut instruction = [0; 2];
+            self.read_8(self.pc, &mut instruction)?;
+
+            if instruction == THUMB_BKPT {
+                return Err(Error::Other(anyhow!(
+                    "Stepped onto a breakpoint instruction"
+                )));
+            }
+
+            self.pc += 2;
+            Ok(CoreInformation { pc: self.pc })
+        }
+
+        fn read_core_reg(&mut self, address: CoreRegisterAddress) -> Result<u32, Error> {
+            if address == self.registers().program_counter().into() {
+                Ok(self.pc)
+            } else {
+                Ok(self.registers.get(&address).copied().unwrap_or(0))
+            }
+        }
+
+        fn write_core_reg(&mut self, address: CoreRegisterAddress, value: u32) -> Result<()> {
+            if address == self.registers().program_counter().into() {
+                self.pc = value;
+            } else {
+                self.registers.insert(address, value);
+            }
+            Ok(())
+        }
+
+        fn available_breakpoint_units(&mut self) -> Result<u32, Error> {
+            Ok(self.hw_breakpoints.len() as u32)
+        }
+
+        fn hw_breakpoints(&mut self) -> Result<Vec<Option<u32>>, Error> {
+            Ok(self.hw_breakpoints.clone())
+        }
+
+        fn enable_breakpoints(&mut self, _state: bool) -> Result<(), Error> {
+            Ok(())
+        }
+
+        fn set_hw_breakpoint(&mut self, unit_index: usize, addr: u32) -> Result<(), Error> {
+            self.hw_breakpoints[unit_index] = Some(addr);
+            Ok(())
+        }
+
+        fn clear_hw_breakpoint(&mut self, unit_index: usize) -> Result<(), Error> {
+            self.hw_breakpoints[unit_index] = None;
+            Ok(())
+        }
+
+        fn available_watchpoint_units(&mut self) -> Result<u32, Error> {
+            Ok(0)
+        }
+
+        fn hw_watchpoints(&mut self) -> Result<Vec<Option<Watchpoint>>, Error> {
+            Ok(Vec::new())
+        }
+
+        fn set_hw_watchpoint(
+            &mut self,
+            _unit_index: usize,
+            _watchpoint: Watchpoint,
+        ) -> Result<(), Error> {
+            unimplemented!()
+        }
+
+        fn clear_hw_watchpoint(&mut self, _unit_index: usize) -> Result<(), Error> {
+            unimplemented!()
+        }
+
+        fn triggered_watchpoint(&mut self) -> Result<Option<Watchpoint>, Error> {
+            Ok(None)
+        }
+
+        fn enable_vector_catch(&mut self, _condition: VectorCatchCondition) -> Result<(), Error> {
+            unimplemented!()
+        }
+
+        fn disable_vector_catch(&mut self, _condition: VectorCatchCondition) -> Result<(), Error> {
+            unimplemented!()
+        }
+
+        fn fault_info(&mut self) -> Result<Option<FaultInfo>, Error> {
+            Ok(None)
+        }
+
+        fn registers(&self) -> &'static RegisterFile {
+            match self.architecture {
+                Architecture::Arm => &ARM_REGISTER_FILE,
+                Architecture::Riscv => &RISCV_REGISTERS,
+            }
+        }
+
+        fn hw_breakpoints_enabled(&self) -> bool {
+            true
+        }
+
+        fn architecture(&self) -> Architecture {
+            self.architecture
+        }
+
+        fn core_type(&self) -> CoreType {
+            match self.architecture {
+                Architecture::Arm => CoreType::Armv7m,
+                Architecture::Riscv => CoreType::Riscv,
+            }
+        }
+
+        fn instruction_set(&mut self) -> Result<InstructionSet, Error> {
+            match self.architecture {
+                Architecture::Arm => Ok(InstructionSet::Thumb2),
+                Architecture::Riscv => Ok(InstructionSet::RV32),
+            }
+        }
+    }
+
+    fn core_state() -> CoreState {
+        let mut state = CoreState::new(0);
+        state.nvm_ranges = vec![FLASH];
+        state
+    }
+
+    #[test]
+    fn breakpoint_falls_back_to_software_breakpoint() {
+        let mut state = core_state();
+        let mut core = Core::new(MockCore::new(), &mut state);
+
+        core.set_breakpoint(RAM_START + 0x10).unwrap();
+        core.set_breakpoint(RAM_START + 0x20).unwrap();
+
+        assert_eq!(
+            core.inner.hw_breakpoints().unwrap(),
+            vec![Some(RAM_START + 0x10)]
+        );
+        assert_eq!(core.sw_breakpoints(), vec![RAM_START + 0x20]);
+
+        let mut instruction = [0; 2];
+        core.read_8(RAM_START + 0x20, &mut instruction).unwrap();
+        assert_eq!(instruction, THUMB_BKPT);
+    }
+
+    #[test]
+    fn clearing_software_breakpoint_restores_instruction() {
+        let mut state = core_state();
+        let mut core = Core::new(MockCore::new(), &mut state);
+
+        core.set_sw_breakpoint(RAM_START + 0x20).unwrap();
+        core.clear_breakpoint(RAM_START + 0x20).unwrap();
+
+        assert!(core.sw_breakpoints().is_empty());
+
+        let mut instruction = [0; 2];
+        core.read_8(RAM_START + 0x20, &mut instruction).unwrap();
+        assert_eq!(instruction, [0x20, 0x21]);
+    }
+
+    #[test]
+    fn software_breakpoint_in_flash_is_rejected() {
+        let mut state = core_state();
+        let mut core = Core::new(MockCore::new(), &mut state);
+
+        core.set_hw_breakpoint(RAM_START).unwrap();
+
+        assert!(core.set_breakpoint(0x100).is_err());
+        assert!(core.sw_breakpoints().is_empty());
+    }
+
+    #[test]
+    fn software_breakpoint_in_flash_is_cleared_by_the_session() {
+        let mut state = core_state();
+        state.software_breakpoints.lock().unwrap().insert(
+            0x100,
+            SoftwareBreakpoint {
+                original_instruction: vec![0x00, 0xbf],
+                breakpoint_instruction: &THUMB_BKPT,
+                in_flash: true,
+            },
+        );
+        let mut core = Core::new(MockCore::new(), &mut state);
+
+        core.set_sw_breakpoint(RAM_START + 0x20).unwrap();
+        core.clear_all_sw_breakpoints().unwrap();
+
+        assert_eq!(core.sw_breakpoints(), vec![0x100]);
+        assert!(core.clear_breakpoint(0x100).is_err());
+    }
+
+    #[test]
+    fn existing_breakpoint_instruction_is_not_saved() {
+        let mut mock = MockCore::new();
+        mock.ram[0x20..0x22].copy_from_slice(&THUMB_BKPT);
+
+        let mut state = core_state();
+        let mut core = Core::new(mock, &mut state);
+
+        assert!(core.set_sw_breakpoint(RAM_START + 0x20).is_err());
+        assert!(core.sw_breakpoints().is_empty());
+    }
+
+    #[test]
+    fn software_breakpoints_are_shared_between_cores() {
+        let mut first_state = core_state();
+        let mut second_state = core_state();
+        second_state.software_breakpoints = first_state.software_breakpoints.clone();
+
+        let mut first_core = Core::new(MockCore::new(), &mut first_state);
+        first_core.set_sw_breakpoint(RAM_START + 0x20).unwrap();
+        drop(first_core);
+
+        // The second core must not insert the breakpoint again, nor use a hardware breakpoint for it.
+        let mut second_core = Core::new(MockCore::new(), &mut second_state);
+        second_core.set_breakpoint(RAM_START + 0x20).unwrap();
+        assert_eq!(second_core.inner.hw_breakpoints().unwrap(), vec![None]);
+
+        second_core.clear_breakpoint(RAM_START + 0x20).unwrap();
+        drop(second_core);
+
+        let first_core = Core::new(MockCore::new(), &mut first_state);
+        assert!(first_core.sw_breakpoints().is_empty());
+    }
+
+    #[test]
+    fn step_over_software_breakpoint() {
+        let mut state = core_state();
+        let mut core = Core::new(MockCore::new(), &mut state);
+
+        core.set_sw_breakpoint(RAM_START).unwrap();
+
+        // The mock core fails to step a breakpoint instruction, so this only
+        // succeeds if the original instruction is restored for the step.
+        let info = core.step().unwrap();
+        assert_eq!(info.pc, RAM_START + 2);
+
+        let mut instruction = [0; 2];
+        core.read_8(RAM_START, &mut instruction).unwrap();
+        assert_eq!(instruction, THUMB_BKPT);
+    }
+}
@@ -2,10 +2,16 @@ use super::{
     function_die::FunctionDie, get_sequential_key, unit_info::UnitInfo, unit_info::UnitIter,
     variable::*, DebugError, Registers, SourceLocation, StackFrame, VariableCache,
 };
-use crate::{core::Core, debug::registers, MemoryInterface};
+use crate::{
+    architecture::arm::core::exception_frame::{ExcReturn, ExceptionFrame},
+    core::Core,
+    debug::registers,
+    MemoryInterface,
+};
 use ::gimli::{FileEntry, LineProgramHeader, UnwindContext};
 use num_traits::Zero;
 use object::read::{Object, ObjectSection};
+use probe_rs_target::Architecture;
 use std::{
     borrow,
     num::NonZeroU64,
@@ -520,10 +526,70 @@ impl DebugInfo {
         );
         function_root_variable.variable_node_type = VariableNodeType::DirectLookup;
         function_root_variable.name = VariableName::LocalScopeRoot;
-        function_variable_cache.cache_variable(None, function_root_variable, core)?;
+        function_variable_cache.cache_variable(None, function_root_variable.clone(), core)?;
+
+        // The arguments are children of the same function DIE, but get their own root.
+        let mut argument_root_variable = function_root_variable;
+        argument_root_variable.name = VariableName::ArgumentScopeRoot;
+        function_variable_cache.cache_variable(None, argument_root_variable, core)?;
         Ok(function_variable_cache)
     }
 
+    /// Appends the arguments of the function to its name, e.g. `foo(x = 3, buf = &[..])`, similar to what gdb shows for a stack frame.
+    /// Compound values are abbreviated, and can be inspected in the `Arguments` of the stack frame.
+    fn function_name_with_arguments(
+        &self,
+        core: &mut Core<'_>,
+        function_name: String,
+        local_variables: Option<&mut VariableCache>,
+        stack_frame_registers: &Registers,
+    ) -> String {
+        let cache = match local_variables {
+            Some(cache) => cache,
+            None => return function_name,
+        };
+        let mut argument_root_variable =
+            match cache.get_variable_by_name_and_parent(&VariableName::ArgumentScopeRoot, None) {
+                Some(argument_root_variable) => argument_root_variable,
+                None => return function_name,
+            };
+
+        let arguments = self
+            .cache_deferred_variables(
+                cache,
+                core,
+                &mut argument_root_variable,
+                stack_frame_registers,
+            )
+            .and_then(|()| {
+                cache
+                    .get_children(Some(argument_root_variable.variable_key))
+                    .map_err(DebugError::from)
+            });
+        match arguments {
+            Ok(arguments) => format!(
+                "{}({})",
+                function_name,
+                arguments
+                    .iter()
+                    .map(|argument| format!(
+                        "{} = {}",
+                        argument.name,
+                        argument_summary(argument, cache)
+                    ))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Err(error) => {
+                log::error!(
+                    "Could not resolve function arguments. {}. Continuing...",
+                    error
+                );
+                function_name
+            }
+        }
+    }
+
     /// This effects the on-demand expansion of lazy/deferred load of all the 'child' `Variable`s for a given 'parent'.
     pub fn cache_deferred_variables(
         &self,
@@ -709,6 +775,114 @@ impl DebugInfo {
         Ok(())
     }
 
+    /// Creates a `Variable` of the named type (a base type, structure, union, enumeration or typedef) at `address`,
+    /// as used by casts like `*(MyStruct*)0x2000_0000` in expressions.
+    /// The variable is cached as the child of an artificial root variable in `cache`.
+    /// Returns `None` if there is no type with the given name.
+    pub(crate) fn cache_variable_of_type(
+        &self,
+        cache: &mut VariableCache,
+        core: &mut Core<'_>,
+        type_name: &str,
+        variable_name: String,
+        address: u32,
+        stack_frame_registers: &Registers,
+    ) -> Result<Option<Variable>, DebugError> {
+        let (header_offset, type_offset) = match self.find_type(type_name)? {
+            Some(offsets) => offsets,
+            None => return Ok(None),
+        };
+
+        let unit_header = self.dwarf.debug_info.header_from_offset(header_offset)?;
+        let unit_info = UnitInfo {
+            debug_info: self,
+            unit: gimli::Unit::new(&self.dwarf, unit_header)?,
+        };
+
+        let root_variable =
+            match cache.get_variable_by_name_and_parent(&VariableName::Artifical, None) {
+                Some(root_variable) => root_variable,
+                None => {
+                    let mut root_variable = Variable::new(None, None);
+                    root_variable.name = VariableName::Artifical;
+                    root_variable.variable_node_type = VariableNodeType::DoNotRecurse;
+                    cache.cache_variable(None, root_variable, core)?
+                }
+            };
+
+        let mut type_tree = unit_info
+            .unit
+            .header
+            .entries_tree(&unit_info.unit.abbreviations, Some(type_offset))?;
+        let type_node = type_tree.root()?;
+
+        let mut variable = Variable::new(Some(header_offset), Some(type_offset));
+        variable.name = VariableName::Named(variable_name);
+        variable.memory_location = VariableLocation::Address(address);
+        let variable = cache.cache_variable(Some(root_variable.variable_key), variable, core)?;
+
+        unit_info
+            .extract_type(
+                type_node,
+                &root_variable,
+                variable,
+                core,
+                stack_frame_registers,
+                cache,
+            )
+            .map(Some)
+    }
+
+    /// Find the DIE of the type with the given name, resolving typedefs to the type they refer to.
+    fn find_type(
+        &self,
+        type_name: &str,
+    ) -> Result<Option<(gimli::DebugInfoOffset, gimli::UnitOffset)>, DebugError> {
+        let mut units = self.get_units();
+        while let Some(unit_info) = self.get_next_unit_info(&mut units) {
+            let mut entries_cursor = unit_info.unit.entries();
+            while let Ok(Some((_depth, current))) = entries_cursor.next_dfs() {
+                if !matches!(
+                    current.tag(),
+                    gimli::DW_TAG_base_type
+                        | gimli::DW_TAG_structure_type
+                        | gimli::DW_TAG_union_type
+                        | gimli::DW_TAG_enumeration_type
+                        | gimli::DW_TAG_typedef
+                ) {
+                    continue;
+                }
+                let name = match current.attr_value(gimli::DW_AT_name)? {
+                    Some(name) => super::extract_name(self, name),
+                    None => continue,
+                };
+                if name != type_name {
+                    continue;
+                }
+
+                let header_offset = match unit_info.unit.header.offset().as_debug_info_offset() {
+                    Some(header_offset) => header_offset,
+                    None => continue,
+                };
+
+                // Follow typedefs to the type they refer to.
+                let mut type_offset = current.offset();
+                loop {
+                    let entry = unit_info.unit.entry(type_offset)?;
+                    if entry.tag() != gimli::DW_TAG_typedef {
+                        break;
+                    }
+                    match entry.attr_value(gimli::DW_AT_type)? {
+                        Some(gimli::AttributeValue::UnitRef(offset)) => type_offset = offset,
+                        _ => break,
+                    }
+                }
+                return Ok(Some((header_offset, type_offset)));
+            }
+        }
+        Ok(None)
+    }
+
     /// Returns a populated (resolved) [`StackFrame`] struct.
     /// This function will also populate the `DebugInfo::VariableCache` with in scope `Variable`s for each `StackFrame`
     pub(crate) fn get_stackframe_info(
@@ -783,7 +957,7 @@ impl DebugInfo {
                         );
 
                     // Next, resolve and cache the function variables.
-                    let local_variables = self
+                    let mut local_variables = self
                         .create_function_scope_cache(core, function_die, &unit_info)
                         .map_or_else(
                             |error| {
@@ -796,6 +970,13 @@ impl DebugInfo {
                             Some,
                         );
 
+                    let function_name = self.function_name_with_arguments(
+                        core,
+                        function_name,
+                        local_variables.as_mut(),
+                        &stack_frame_registers,
+                    );
+
                     frames.push(StackFrame {
                         // MS DAP Specification requires the id to be unique accross all threads, so using  so using unique `Variable::variable_key` of the `stackframe_root_variable` as the id.
                         id: get_sequential_key(),
@@ -842,7 +1023,7 @@ impl DebugInfo {
                 );
 
             // Next, resolve and cache the function variables.
-            let local_variables = self
+            let mut local_variables = self
                 .create_function_scope_cache(core, last_function, &unit_info)
                 .map_or_else(
                     |error| {
@@ -855,6 +1036,13 @@ impl DebugInfo {
                     Some,
                 );
 
+            let function_name = self.function_name_with_arguments(
+                core,
+                function_name,
+                local_variables.as_mut(),
+                &stack_frame_registers,
+            );
+
             frames.push(StackFrame {
                 // MS DAP Specification requires the id to be unique accross all threads, so using  so using unique `Variable::variable_key` of the `stackframe_root_variable` as the id.
                 id: get_sequential_key(),
@@ -955,7 +1143,10 @@ impl DebugInfo {
             };
 
             // Part 1-b: Check LR values to determine if we can continue unwinding.
-            // TODO: ARM has special ranges of LR addresses to indicate fault conditions. We should check those also.
+            // On ARM Cortex-M, exception handlers return to an `EXC_RETURN` value, which is handled in PART 2-d.
+            let exception_return = unwind_registers
+                .get_return_address()
+                .filter(|return_address| is_exception_return(&unwind_registers, *return_address));
             if let Some(check_return_address) = unwind_registers.get_return_address() {
                 if check_return_address == u32::MAX {
                     // When we encounter the starting (after reset) return address, we've reached the bottom of the stack, so no more unwinding after this.
@@ -1182,6 +1373,41 @@ impl DebugInfo {
                 }
             };
 
+            // PART 2-d: If this function is an exception handler, the registers of the interrupted code were stacked by the hardware on exception entry.
+            let exception_entry_frame = if let Some(exception_return) = exception_return {
+                if let Err(error) =
+                    unwind_exception_frame(core, exception_return, &mut unwind_registers)
+                {
+                    log::error!(
+                        "UNWIND: Unable to read the exception frame for EXC_RETURN={:#010x}: {}",
+                        exception_return,
+                        error
+                    );
+                    stack_frames.push(return_frame);
+                    break;
+                }
+                log::trace!(
+                    "UNWIND: Unwound exception frame for EXC_RETURN={:#010x}, interrupted PC={:#010x}",
+                    exception_return,
+                    unwind_registers.get_program_counter().unwrap_or_default()
+                );
+                Some(StackFrame {
+                    id: get_sequential_key(),
+                    function_name: "<exception entry>".to_string(),
+                    source_location: None,
+                    registers: unwind_registers.clone(),
+                    pc: exception_return,
+                    is_inlined: false,
+                    static_variables: None,
+                    local_variables: None,
+                })
+            } else {
+                None
+            };
+
+            stack_frames.push(return_frame);
+            stack_frames.extend(exception_entry_frame);
+
             // PART 3: In order to set the correct value of the previous frame we need to peek one frame deeper in the stack.
             // NOTE: ARM Specific.
             // TODO: Investigate and document why and under which circumstances this extra step is necessary. It was added during PR#895.
@@ -1200,7 +1426,6 @@ impl DebugInfo {
                         previous_frame_pc,
                         error
                     );
-                        stack_frames.push(return_frame);
                         break;
                     }
                 };
@@ -1231,7 +1456,6 @@ impl DebugInfo {
                                                         "UNWIND: `StackFrameIterator` unable to determine the previous frame unwind CFA: Missing value of register {}",
                                                         register.0
                                                     );
-                                        stack_frames.push(return_frame);
                                         break;
                                     }
                                 }
@@ -1272,14 +1496,12 @@ impl DebugInfo {
                                             address_offset,
                                             unwind_cfa
                                         );
-                                        stack_frames.push(return_frame);
                                         break;
                                     }
                                     let previous_frame_register_value = u32::from_le_bytes(buff);
                                     Some(previous_frame_register_value as u32)
                                 } else {
                                     log::error!("UNWIND: Tried to unwind `RegisterRule` at CFA = None. Please report this as a bug.");
-                                    stack_frames.push(return_frame);
                                     break;
                                 }
                             }
@@ -1300,16 +1522,13 @@ impl DebugInfo {
                     }
                     Err(error) => {
                         log::trace!("UNWIND: Stack unwind complete. No available debug info for program counter {:#x}: {}",frame_pc, error);
-                        stack_frames.push(return_frame);
                         break;
                     }
                 };
             } else {
                 log::error!("UNWIND: Cannot read previous FrameDescriptorEntry without a valid PC");
-                stack_frames.push(return_frame);
                 break;
             }
-            stack_frames.push(return_frame);
         }
 
         Ok(stack_frames)
@@ -1436,3 +1655,185 @@ impl DebugInfo {
         Some((file_name, directory))
     }
 }
+
+/// A short representation of the value of a function argument, for use in the name of a stack frame.
+fn argument_summary(argument: &Variable, cache: &VariableCache) -> String {
+    match &argument.type_name {
+        VariableType::Struct(name) if name.starts_with("&str") => argument.get_value(cache),
+        VariableType::Struct(name) if name.starts_with("&[") || name.starts_with("&mut [") => {
+            "&[..]".to_string()
+        }
+        VariableType::Struct(_) => "{..}".to_string(),
+        VariableType::Array { .. } => "[..]".to_string(),
+        VariableType::Pointer(_) => "&..".to_string(),
+        _ => argument.get_value(cache),
+    }
+}
+
+/// On ARM Cortex-M, the LR register of an exception handler holds an `EXC_RETURN` value, which starts with `0xFF`.
+/// The `u32::MAX` value after reset is excluded.
+fn is_exception_return(registers: &Registers, return_address: u32) -> bool {
+    registers.architecture == Architecture::Arm && ExcReturn::from_lr(return_address).is_some()
+}
+
+/// Restore the registers of the code which was interrupted by an exception, from the frame that the hardware stacked on exception entry.
+///
+/// `unwind_registers` are the registers of the exception handler, unwound to the point where it was entered.
+fn unwind_exception_frame(
+    memory: &mut impl MemoryInterface,
+    exception_return: u32,
+    unwind_registers: &mut Registers,
+) -> Result<(), crate::Error> {
+    let exc_return = ExcReturn::from_lr(exception_return)
+        .ok_or_else(|| anyhow::anyhow!("{:#010x} is not an EXC_RETURN value", exception_return))?;
+
+    // The stack pointer was unwound to the CFA of the handler, which runs on the main stack.
+    let main_stack_pointer = unwind_registers
+        .get_stack_pointer()
+        .ok_or_else(|| anyhow::anyhow!("The main stack pointer could not be unwound"))?;
+    let frame_address = if exc_return.uses_process_stack() {
+        // The handler doesn't use the process stack, so it still points to the frame.
+        unwind_registers
+            .get_process_stack_pointer()
+            .ok_or_else(|| anyhow::anyhow!("The process stack pointer is not available"))?
+    } else {
+        main_stack_pointer
+    };
+
+    let frame = ExceptionFrame::read(memory, exc_return, frame_address)?;
+
+    for (register_number, value) in (0..).zip(frame.arguments) {
+        unwind_registers.set_by_dwarf_register_number(register_number, Some(value));
+    }
+    unwind_registers.set_by_dwarf_register_number(12, Some(frame.r12));
+    if let Some(callee_saved) = frame.callee_saved {
+        for (register_number, value) in (4..).zip(callee_saved) {
+            unwind_registers.set_by_dwarf_register_number(register_number, Some(value));
+        }
+    }
+    if let Some(fpu_registers) = frame.fpu_registers {
+        // S0-S15 have the DWARF register numbers 64-79.
+        for (register_number, value) in (64..).zip(fpu_registers) {
+            unwind_registers.set_by_dwarf_register_number(register_number, Some(value));
+        }
+    }
+
+    unwind_registers.set_return_address(Some(frame.lr));
+    // The stacked PC is the address of the interrupted instruction, not a return address.
+    unwind_registers.set_program_counter(Some(frame.pc & !0b1));
+
+    unwind_registers.set_stack_pointer(Some(frame.stack_pointer));
+    if exc_return.uses_process_stack() {
+        unwind_registers.set_main_stack_pointer(Some(main_stack_pointer));
+        unwind_registers.set_process_stack_pointer(Some(frame.stack_pointer));
+    } else {
+        unwind_registers.set_main_stack_pointer(Some(frame.stack_pointer));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::unwind_exception_frame;
+    use crate::architecture::arm::core::ARM_REGISTER_FILE;
+    use crate::core::test::{MockCore, RAM_START};
+    use crate::debug::Registers;
+    use crate::MemoryInterface;
+    use probe_rs_target::Architecture;
+    use std::collections::HashMap;
+
+    /// The registers of a handler, unwound to its entry, with the main stack at `RAM_START + 0x40`
+    /// and the process stack at `RAM_START + 0x80`.
+    fn handler_registers() -> Registers {
+        let mut registers = Registers {
+            register_description: &ARM_REGISTER_FILE,
+            values: HashMap::new(),
+            architecture: Architecture::Arm,
+        };
+        registers.set_stack_pointer(Some(RAM_START + 0x40));
+        registers.set_main_stack_pointer(Some(RAM_START + 0x40));
+        registers.set_process_stack_pointer(Some(RAM_START + 0x80));
+        registers
+    }
+
+    /// Stacks a basic frame with the PC `pc` at `address`.
+    fn stack_frame(memory: &mut MockCore, address: u32, pc: u32, xpsr: u32) {
+        memory
+            .write_32(address, &[0, 1, 2, 3, 12, 0x0800_0101, pc, xpsr])
+            .unwrap();
+    }
+
+    #[test]
+    fn main_stack_frame() {
+        let mut memory = MockCore::new();
+        stack_frame(&mut memory, RAM_START + 0x40, 0x0800_0201, 0x0100_0000);
+        let mut registers = handler_registers();
+
+        unwind_exception_frame(&mut memory, 0xFFFF_FFF9, &mut registers).unwrap();
+
+        assert_eq!(registers.get_program_counter(), Some(0x0800_0200));
+        assert_eq!(registers.get_return_address(), Some(0x0800_0101));
+        assert_eq!(registers.get_value_by_dwarf_register_number(12), Some(12));
+        assert_eq!(registers.get_stack_pointer(), Some(RAM_START + 0x60));
+        assert_eq!(registers.get_main_stack_pointer(), Some(RAM_START + 0x60));
+        assert_eq!(
+            registers.get_process_stack_pointer(),
+            Some(RAM_START + 0x80)
+        );
+    }
+
+    #[test]
+    fn process_stack_frame() {
+        let mut memory = MockCore::new();
+        stack_frame(&mut memory, RAM_START + 0x80, 0x0800_0301, 0x0100_0000);
+        let mut registers = handler_registers();
+
+        unwind_exception_frame(&mut memory, 0xFFFF_FFFD, &mut registers).unwrap();
+
+        assert_eq!(registers.get_program_counter(), Some(0x0800_0300));
+        assert_eq!(registers.get_stack_pointer(), Some(RAM_START + 0xA0));
+        assert_eq!(
+            registers.get_process_stack_pointer(),
+            Some(RAM_START + 0xA0)
+        );
+        // The main stack pointer is back at the CFA of the handler.
+        assert_eq!(registers.get_main_stack_pointer(), Some(RAM_START + 0x40));
+    }
+
+    #[test]
+    fn aligned_fpu_frame() {
+        let mut memory = MockCore::new();
+        stack_frame(&mut memory, RAM_START + 0x40, 0x0800_0201, 0x0100_0200);
+        memory
+            .write_32(RAM_START + 0x60, &[0x3F80_0000; 16])
+            .unwrap();
+        let mut registers = handler_registers();
+
+        unwind_exception_frame(&mut memory, 0xFFFF_FFE9, &mut registers).unwrap();
+
+        assert_eq!(registers.get_program_counter(), Some(0x0800_0200));
+        assert_eq!(
+            registers.get_value_by_dwarf_register_number(64),
+            Some(0x3F80_0000)
+        );
+        assert_eq!(
+            registers.get_value_by_dwarf_register_number(79),
+            Some(0x3F80_0000)
+        );
+        assert_eq!(
+            registers.get_name_by_dwarf_register_number(64).as_deref(),
+            Some("S0")
+        );
+        // 0x20 bytes basic frame, 0x48 bytes FPU context and 4 bytes alignment padding.
+        assert_eq!(registers.get_stack_pointer(), Some(RAM_START + 0x40 + 0x6C));
+    }
+
+    #[test]
+    fn not_an_exception_return() {
+        let mut memory = MockCore::new();
+        let mut registers = handler_registers();
+
+        assert!(unwind_exception_frame(&mut memory, 0x0800_0101, &mut registers).is_err());
+    }
+}
@@ -0,0 +1,1622 @@
+use super::{
+    DebugError, DebugInfo, Registers, StackFrame, Variable, VariableCache, VariableName,
+    VariableType,
+};
+use crate::{core::Core, MemoryInterface};
+use anyhow::anyhow;
+use std::fmt;
+
+/// The target state which an expression is evaluated against.
+pub struct ExpressionContext<'a, 'probe> {
+    /// The core which is used to read registers and memory.
+    pub core: &'a mut Core<'probe>,
+    /// The debug information, which is used to resolve variables and the types used in casts.
+    /// Without it, only registers, literals and raw memory expressions like `*(u32*)0x2000_0000` can be evaluated.
+    pub debug_info: Option<&'a DebugInfo>,
+    /// The stack frame whose registers, local and static variables are in scope.
+    /// Without it, the current register values of the core are used, and no variables are available.
+    pub stack_frame: Option<&'a mut StackFrame>,
+}
+
+/// The result of an expression evaluated with [`evaluate_expression`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExpressionResult {
+    /// The formatted value.
+    pub value: String,
+    /// The name of the type of the value.
+    pub type_name: String,
+    /// The address of the value in target memory, if it has one.
+    pub memory_address: Option<u32>,
+    /// If the value is a variable, a copy of it.
+    ///
+    /// The variable is stored in either the `local_variables` or the `static_variables` cache of the
+    /// stack frame, so its children can be looked up there. Variables created by casts to DWARF types
+    /// are stored in the `local_variables` cache.
+    pub variable: Option<Variable>,
+}
+
+/// Evaluates an expression, like `counter + 1`, `state.buffer[3]`, `*ptr`,
+/// `(MyStruct*)0x2000_0400`, `*(u32*)0x2000_0000` or `pc & ~1`.
+///
+/// Identifiers are resolved to registers (or `$register`), then local and then static variables of the stack frame.
+/// The supported operators are field access (`a.b`, `a->b`, `a.0`), indexing (`a[3]`), dereference (`*a`),
+/// address-of (`&a`), casts (`(type)a` or `a as type`), arithmetic, bitwise and logical operators, and comparisons.
+/// Casts accept the Rust and C names of primitive types, pointers to them (`*const u32`, `u32*`),
+/// and pointers to types from the debug information (`MyStruct*`).
+pub fn evaluate_expression(
+    expression: &str,
+    context: &mut ExpressionContext,
+) -> Result<ExpressionResult, DebugError> {
+    let (mut evaluator, value) = evaluate(expression, context)?;
+
+    evaluator.result(value)
+}
+
+/// Evaluates an expression with [`evaluate_expression`], and interprets its value as a condition,
+/// like the condition of a breakpoint. Integers and pointers are `true` if they are not zero.
+pub fn evaluate_condition(
+    expression: &str,
+    context: &mut ExpressionContext,
+) -> Result<bool, DebugError> {
+    let (mut evaluator, value) = evaluate(expression, context)?;
+    let (value, _) = evaluator.scalar_value(&value)?;
+
+    is_true(value)
+}
+
+/// Parses and evaluates the whole expression.
+fn evaluate<'c, 'a, 'probe>(
+    expression: &str,
+    context: &'c mut ExpressionContext<'a, 'probe>,
+) -> Result<(Evaluator<'c, 'a, 'probe>, Value), DebugError> {
+    let tokens = tokenize(expression)?;
+    let mut evaluator = Evaluator {
+        tokens,
+        position: 0,
+        context,
+    };
+
+    let value = evaluator.expression()?;
+    if let Some(token) = evaluator.tokens.get(evaluator.position) {
+        return Err(anyhow!("Unexpected '{}' in expression '{}'", token, expression).into());
+    }
+
+    Ok((evaluator, value))
+}
+
+/// A primitive type, which can be used in casts and to read raw memory.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Scalar {
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    I8,
+    I16,
+    I32,
+    I64,
+    I128,
+    F32,
+    F64,
+    Bool,
+    Char,
+}
+
+impl Scalar {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "u8" | "uint8_t" => Scalar::U8,
+            "u16" | "uint16_t" => Scalar::U16,
+            // All supported targets are 32 bit.
+            "u32" | "usize" | "uint32_t" | "unsigned" => Scalar::U32,
+            "u64" | "uint64_t" => Scalar::U64,
+            "u128" => Scalar::U128,
+            "i8" | "int8_t" => Scalar::I8,
+            "i16" | "int16_t" | "short" => Scalar::I16,
+            "i32" | "isize" | "int32_t" | "int" => Scalar::I32,
+            "i64" | "int64_t" => Scalar::I64,
+            "i128" => Scalar::I128,
+            "f32" | "float" => Scalar::F32,
+            "f64" | "double" => Scalar::F64,
+            "bool" => Scalar::Bool,
+            "char" => Scalar::Char,
+            _ => return None,
+        })
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Scalar::U8 => "u8",
+            Scalar::U16 => "u16",
+            Scalar::U32 => "u32",
+            Scalar::U64 => "u64",
+            Scalar::U128 => "u128",
+            Scalar::I8 => "i8",
+            Scalar::I16 => "i16",
+            Scalar::I32 => "i32",
+            Scalar::I64 => "i64",
+            Scalar::I128 => "i128",
+            Scalar::F32 => "f32",
+            Scalar::F64 => "f64",
+            Scalar::Bool => "bool",
+            Scalar::Char => "char",
+        }
+    }
+
+    fn size(self) -> u32 {
+        match self {
+            Scalar::U8 | Scalar::I8 | Scalar::Bool => 1,
+            Scalar::U16 | Scalar::I16 => 2,
+            Scalar::U32 | Scalar::I32 | Scalar::F32 | Scalar::Char => 4,
+            Scalar::U64 | Scalar::I64 | Scalar::F64 => 8,
+            Scalar::U128 | Scalar::I128 => 16,
+        }
+    }
+
+    fn is_signed(self) -> bool {
+        matches!(
+            self,
+            Scalar::I8 | Scalar::I16 | Scalar::I32 | Scalar::I64 | Scalar::I128
+        )
+    }
+
+    fn is_float(self) -> bool {
+        matches!(self, Scalar::F32 | Scalar::F64)
+    }
+
+    /// Truncates an integer to the size of this type, and sign extends it for signed types.
+    fn wrap(self, value: i128) -> i128 {
+        let bits = self.size() * 8;
+        if bits >= 128 {
+            return value;
+        }
+        let value = value & ((1i128 << bits) - 1);
+        if self.is_signed() && (value >> (bits - 1)) & 1 == 1 {
+            value - (1i128 << bits)
+        } else {
+            value
+        }
+    }
+
+    /// Decodes a little endian value of this type.
+    fn decode(self, bytes: &[u8]) -> Result<ScalarValue, DebugError> {
+        let mut buffer = [0u8; 16];
+        buffer[..bytes.len()].copy_from_slice(bytes);
+        let raw = u128::from_le_bytes(buffer);
+
+        Ok(match self {
+            Scalar::F32 => ScalarValue::Float(f32::from_bits(raw as u32) as f64),
+            Scalar::F64 => ScalarValue::Float(f64::from_bits(raw as u64)),
+            Scalar::Bool => ScalarValue::Bool(raw != 0),
+            Scalar::Char => ScalarValue::Char(
+                char::from_u32(raw as u32)
+                    .ok_or_else(|| anyhow!("Invalid char value {:#x}", raw))?,
+            ),
+            integer => ScalarValue::Integer(integer.wrap(raw as i128)),
+        })
+    }
+}
+
+/// The type in a cast.
+#[derive(Debug, Clone, PartialEq)]
+enum Type {
+    Scalar(Scalar),
+    Pointer(Box<Type>),
+    /// A type from the debug information.
+    Named(String),
+}
+
+impl Type {
+    fn size(&self) -> Option<u32> {
+        match self {
+            Type::Scalar(scalar) => Some(scalar.size()),
+            Type::Pointer(_) => Some(4),
+            Type::Named(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Scalar(scalar) => scalar.name().fmt(f),
+            Type::Pointer(target) => write!(f, "*const {}", target),
+            Type::Named(name) => name.fmt(f),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ScalarValue {
+    Integer(i128),
+    Float(f64),
+    Bool(bool),
+    Char(char),
+}
+
+impl fmt::Display for ScalarValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScalarValue::Integer(value) => value.fmt(f),
+            ScalarValue::Float(value) => value.fmt(f),
+            ScalarValue::Bool(value) => value.fmt(f),
+            ScalarValue::Char(value) => value.fmt(f),
+        }
+    }
+}
+
+/// The variable cache of the stack frame which holds a variable.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Scope {
+    Local,
+    Static,
+}
+
+#[derive(Debug, Clone)]
+enum Value {
+    Variable(Variable, Scope),
+    Scalar {
+        value: ScalarValue,
+        /// The type of the value, or `None` for literals.
+        scalar: Option<Scalar>,
+        /// The location of the value in target memory, if it was read from there.
+        address: Option<u32>,
+    },
+    Pointer {
+        address: u32,
+        target: Type,
+    },
+}
+
+impl Value {
+    fn scalar(value: ScalarValue, scalar: Option<Scalar>) -> Self {
+        Value::Scalar {
+            value,
+            scalar,
+            address: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Integer(i128),
+    Float(f64),
+    Char(char),
+    Identifier(String),
+    Symbol(&'static str),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Integer(value) => value.fmt(f),
+            Token::Float(value) => value.fmt(f),
+            Token::Char(value) => write!(f, "'{}'", value),
+            Token::Identifier(identifier) => identifier.fmt(f),
+            Token::Symbol(symbol) => symbol.fmt(f),
+        }
+    }
+}
+
+/// Symbols, with the longer ones first so they take precedence.
+const SYMBOLS: [&str; 26] = [
+    "||", "&&", "==", "!=", "<=", ">=", "<<", ">>", "->", "(", ")", "[", "]", ".", "+", "-", "*",
+    "/", "%", "&", "|", "^", "!", "~", "<", ">",
+];
+
+fn tokenize(expression: &str) -> Result<Vec<Token>, DebugError> {
+    let mut tokens = Vec::new();
+    let mut rest = expression.trim_start();
+
+    while let Some(c) = rest.chars().next() {
+        let length = if c.is_ascii_digit() {
+            let length = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            let mut number = rest[..length].replace('_', "");
+            let mut length = length;
+
+            // A fractional part, unless this is a tuple field like `a.0.1`.
+            let is_field = tokens.last() == Some(&Token::Symbol("."));
+            if !is_field
+                && rest[length..].starts_with('.')
+                && rest[length + 1..].starts_with(|c: char| c.is_ascii_digit())
+            {
+                let fraction_length = rest[length + 1..]
+                    .find(|c: char| !(c.is_ascii_digit() || c == '_'))
+                    .unwrap_or(rest.len() - length - 1);
+                number = rest[..length + 1 + fraction_length].replace('_', "");
+                length += 1 + fraction_length;
+                tokens.push(Token::Float(number.parse().map_err(|_| {
+                    anyhow!("Invalid number '{}' in expression '{}'", number, expression)
+                })?));
+            } else {
+                tokens.push(Token::Integer(parse_integer(&number).ok_or_else(|| {
+                    anyhow!("Invalid number '{}' in expression '{}'", number, expression)
+                })?));
+            }
+            length
+        } else if c.is_alphabetic() || c == '_' || c == '$' {
+            let mut length = 0;
+            loop {
+                length += rest[length..]
+                    .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$'))
+                    .unwrap_or(rest.len() - length);
+                // Paths like `module::STATIC` are a single identifier.
+                if rest[length..].starts_with("::") {
+                    length += 2;
+                } else {
+                    break;
+                }
+            }
+            tokens.push(Token::Identifier(rest[..length].to_string()));
+            length
+        } else if c == '\'' {
+            let (value, length) = match rest[1..].chars().next() {
+                Some('\\') => {
+                    let value = match rest[2..].chars().next() {
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('0') => '\0',
+                        Some(other) => other,
+                        None => {
+                            return Err(anyhow!("Invalid char literal in '{}'", expression).into())
+                        }
+                    };
+                    (value, 3 + value.len_utf8())
+                }
+                Some(value) => (value, 2 + value.len_utf8()),
+                None => return Err(anyhow!("Invalid char literal in '{}'", expression).into()),
+            };
+            if !rest
+                .get(length - 1..)
+                .map_or(false, |rest| rest.starts_with('\''))
+            {
+                return Err(anyhow!("Invalid char literal in '{}'", expression).into());
+            }
+            tokens.push(Token::Char(value));
+            length
+        } else if let Some(symbol) = SYMBOLS.iter().find(|symbol| rest.starts_with(**symbol)) {
+            tokens.push(Token::Symbol(*symbol));
+            symbol.len()
+        } else {
+            return Err(anyhow!(
+                "Unexpected character '{}' in expression '{}'",
+                c,
+                expression
+            )
+            .into());
+        };
+
+        rest = rest[length..].trim_start();
+    }
+
+    Ok(tokens)
+}
+
+/// Parses decimal, `0x` hexadecimal, `0o` octal and `0b` binary integers.
+fn parse_integer(number: &str) -> Option<i128> {
+    let (digits, radix) = match number.get(..2) {
+        Some("0x") | Some("0X") => (&number[2..], 16),
+        Some("0o") | Some("0O") => (&number[2..], 8),
+        Some("0b") | Some("0B") => (&number[2..], 2),
+        _ => (number, 10),
+    };
+    i128::from_str_radix(digits, radix).ok()
+}
+
+/// A recursive descent parser, which evaluates the expression while parsing it.
+struct Evaluator<'c, 'a, 'probe> {
+    tokens: Vec<Token>,
+    position: usize,
+    context: &'c mut ExpressionContext<'a, 'probe>,
+}
+
+/// The binary operators, from the lowest to the highest precedence.
+const BINARY_OPERATORS: [&[&str]; 10] = [
+    &["||"],
+    &["&&"],
+    &["|"],
+    &["^"],
+    &["&"],
+    &["==", "!="],
+    &["<", "<=", ">", ">="],
+    &["<<", ">>"],
+    &["+", "-"],
+    &["*", "/", "%"],
+];
+
+impl<'c, 'a, 'probe> Evaluator<'c, 'a, 'probe> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn peek_symbol(&self, offset: usize) -> Option<&'static str> {
+        match self.tokens.get(self.position + offset) {
+            Some(Token::Symbol(symbol)) => Some(*symbol),
+            _ => None,
+        }
+    }
+
+    fn next_symbol(&mut self, symbols: &[&str]) -> Option<&'static str> {
+        match self.peek_symbol(0) {
+            Some(symbol) if symbols.contains(&symbol) => {
+                self.position += 1;
+                Some(symbol)
+            }
+            _ => None,
+        }
+    }
+
+    fn expect_symbol(&mut self, symbol: &str) -> Result<(), DebugError> {
+        match self.next_symbol(&[symbol]) {
+            Some(_) => Ok(()),
+            None => Err(match self.peek() {
+                Some(token) => anyhow!("Expected '{}', found '{}'", symbol, token),
+                None => anyhow!("Expected '{}' at the end of the expression", symbol),
+            }
+            .into()),
+        }
+    }
+
+    fn expression(&mut self) -> Result<Value, DebugError> {
+        self.binary(0)
+    }
+
+    fn binary(&mut self, level: usize) -> Result<Value, DebugError> {
+        if level == BINARY_OPERATORS.len() {
+            return self.cast();
+        }
+
+        let mut left = self.binary(level + 1)?;
+        while let Some(operator) = self.next_symbol(BINARY_OPERATORS[level]) {
+            // The right operand of `||` and `&&` is only evaluated if it decides the result.
+            if matches!(operator, "||" | "&&") {
+                let (value, _) = self.scalar_value(&left)?;
+                let value = is_true(value)?;
+                if value == (operator == "||") {
+                    self.skip_operand(operator, level)?;
+                    left = Value::scalar(ScalarValue::Bool(value), Some(Scalar::Bool));
+                    continue;
+                }
+            }
+
+            let right = self.binary(level + 1)?;
+            left = self.apply_binary(operator, left, right)?;
+        }
+        Ok(left)
+    }
+
+    /// Skips the right operand of the binary `operator` at `level`, without evaluating it.
+    fn skip_operand(&mut self, operator: &str, level: usize) -> Result<(), DebugError> {
+        let start = self.position;
+        let mut depth = 0usize;
+
+        while let Some(token) = self.peek() {
+            match token {
+                Token::Symbol("(" | "[") => depth += 1,
+                Token::Symbol(")" | "]") if depth == 0 => break,
+                Token::Symbol(")" | "]") => depth -= 1,
+                Token::Symbol(symbol)
+                    if depth == 0
+                        && BINARY_OPERATORS[..=level]
+                            .iter()
+                            .any(|operators| operators.contains(symbol)) =>
+                {
+                    break
+                }
+                _ => {}
+            }
+            self.position += 1;
+        }
+
+        if self.position == start {
+            return Err(anyhow!("Expected an operand after '{}'", operator).into());
+        }
+        Ok(())
+    }
+
+    /// A postfix `as` cast.
+    fn cast(&mut self) -> Result<Value, DebugError> {
+        let mut value = self.unary()?;
+        while self.peek() == Some(&Token::Identifier("as".to_string())) {
+            self.position += 1;
+            let type_ = self
+                .parse_type()
+                .ok_or_else(|| anyhow!("Expected a type after 'as'"))?;
+            value = self.cast_to(value, &type_)?;
+        }
+        Ok(value)
+    }
+
+    fn unary(&mut self) -> Result<Value, DebugError> {
+        if let Some(operator) = self.next_symbol(&["-", "!", "~", "*", "&"]) {
+            let value = self.unary()?;
+            return match operator {
+                "*" => self.dereference(value),
+                "&" => self.address_of(value),
+                "!" => match self.scalar_value(&value)? {
+                    (ScalarValue::Bool(value), _) => {
+                        Ok(Value::scalar(ScalarValue::Bool(!value), Some(Scalar::Bool)))
+                    }
+                    (ScalarValue::Integer(value), scalar) => Ok(Value::scalar(
+                        ScalarValue::Integer(wrap(!value, scalar)),
+                        scalar,
+                    )),
+                    (other, _) => Err(anyhow!("Can not apply '!' to {}", other).into()),
+                },
+                "~" => match self.scalar_value(&value)? {
+                    (ScalarValue::Integer(value), scalar) => Ok(Value::scalar(
+                        ScalarValue::Integer(wrap(!value, scalar)),
+                        scalar,
+                    )),
+                    (other, _) => Err(anyhow!("Can not apply '~' to {}", other).into()),
+                },
+                _ => match self.scalar_value(&value)? {
+                    (ScalarValue::Integer(value), scalar) => Ok(Value::scalar(
+                        ScalarValue::Integer(wrap(value.wrapping_neg(), scalar)),
+                        scalar,
+                    )),
+                    (ScalarValue::Float(value), scalar) => {
+                        Ok(Value::scalar(ScalarValue::Float(-value), scalar))
+                    }
+                    (other, _) => Err(anyhow!("Can not negate {}", other).into()),
+                },
+            };
+        }
+
+        // A C style cast, e.g. `(u32*)0x2000_0000`.
+        if self.peek_symbol(0) == Some("(") {
+            let start = self.position;
+            self.position += 1;
+            match self.parse_type() {
+                Some(type_ @ (Type::Scalar(_) | Type::Pointer(_)))
+                    if self.next_symbol(&[")"]).is_some() =>
+                {
+                    let value = self.unary()?;
+                    return self.cast_to(value, &type_);
+                }
+                _ => self.position = start,
+            }
+        }
+
+        self.postfix()
+    }
+
+    fn postfix(&mut self) -> Result<Value, DebugError> {
+        let mut value = self.primary()?;
+
+        while let Some(symbol) = self.next_symbol(&[".", "->", "["]) {
+            if symbol == "[" {
+                let index = self.expression()?;
+                self.expect_symbol("]")?;
+                value = match self.scalar_value(&index)? {
+                    (ScalarValue::Integer(index), _) => self.index(value, index)?,
+                    (other, _) => return Err(anyhow!("Can not use {} as an index", other).into()),
+                };
+            } else {
+                let field = match self.peek().cloned() {
+                    Some(Token::Identifier(field)) => field,
+                    // Tuple fields are named `__0`, `__1`, ... in the debug information.
+                    Some(Token::Integer(index)) => format!("__{}", index),
+                    _ => return Err(anyhow!("Expected a field name after '{}'", symbol).into()),
+                };
+                self.position += 1;
+                if symbol == "->" {
+                    value = self.dereference(value)?;
+                }
+                value = self.field(value, &field)?;
+            }
+        }
+
+        Ok(value)
+    }
+
+    fn primary(&mut self) -> Result<Value, DebugError> {
+        let token = self
+            .peek()
+            .cloned()
+            .ok_or_else(|| anyhow!("Unexpected end of the expression"))?;
+        self.position += 1;
+
+        match token {
+            Token::Integer(value) => Ok(Value::scalar(ScalarValue::Integer(value), None)),
+            Token::Float(value) => Ok(Value::scalar(ScalarValue::Float(value), None)),
+            Token::Char(value) => Ok(Value::scalar(ScalarValue::Char(value), Some(Scalar::Char))),
+            Token::Identifier(identifier) if identifier == "true" || identifier == "false" => Ok(
+                Value::scalar(ScalarValue::Bool(identifier == "true"), Some(Scalar::Bool)),
+            ),
+            Token::Identifier(identifier) => self.identifier(&identifier),
+            Token::Symbol("(") => {
+                let value = self.expression()?;
+                self.expect_symbol(")")?;
+                Ok(value)
+            }
+            Token::Symbol(symbol) => Err(anyhow!("Unexpected '{}'", symbol).into()),
+        }
+    }
+
+    /// Parses a type in a cast, e.g. `u32`, `*const u8`, `&MyStruct` or `MyStruct*`.
+    /// Returns `None` (and may leave the position anywhere) if the tokens are not a type.
+    fn parse_type(&mut self) -> Option<Type> {
+        if self.next_symbol(&["*"]).is_some() {
+            match self.peek() {
+                Some(Token::Identifier(qualifier))
+                    if qualifier == "const" || qualifier == "mut" =>
+                {
+                    self.position += 1;
+                }
+                _ => return None,
+            }
+            return Some(Type::Pointer(Box::new(self.parse_type()?)));
+        }
+
+        if self.next_symbol(&["&"]).is_some() {
+            if self.peek() == Some(&Token::Identifier("mut".to_string())) {
+                self.position += 1;
+            }
+            return Some(Type::Pointer(Box::new(self.parse_type()?)));
+        }
+
+        let mut type_ = match self.peek() {
+            Some(Token::Identifier(name)) if name == "const" => {
+                self.position += 1;
+                return self.parse_type();
+            }
+            Some(Token::Identifier(name)) => match Scalar::from_name(name) {
+                Some(scalar) => Type::Scalar(scalar),
+                None => Type::Named(name.clone()),
+            },
+            _ => return None,
+        };
+        self.position += 1;
+
+        // C style pointers, e.g. `u32*`.
+        while self.peek_symbol(0) == Some("*") && matches!(self.peek_symbol(1), Some(")" | "*")) {
+            self.position += 1;
+            type_ = Type::Pointer(Box::new(type_));
+        }
+
+        Some(type_)
+    }
+
+    fn identifier(&mut self, name: &str) -> Result<Value, DebugError> {
+        let (register_name, register_only) = match name.strip_prefix('$') {
+            Some(register_name) => (register_name, true),
+            None => (name, false),
+        };
+
+        if let Some(value) = self.register(register_name) {
+            return Ok(Value::scalar(
+                ScalarValue::Integer(value as i128),
+                Some(Scalar::U32),
+            ));
+        }
+        if register_only {
+            return Err(anyhow!("Register '{}' not found", register_name).into());
+        }
+
+        for scope in [Scope::Local, Scope::Static] {
+            if let Some(variable) = self.find_variable(name, scope)? {
+                return Ok(Value::Variable(variable, scope));
+            }
+        }
+
+        Err(anyhow!("Variable '{}' not found", name).into())
+    }
+
+    fn register(&mut self, name: &str) -> Option<u32> {
+        let registers_from_core;
+        let registers = match self.context.stack_frame.as_deref() {
+            Some(stack_frame) => &stack_frame.registers,
+            None => {
+                registers_from_core = Registers::from_core(self.context.core);
+                &registers_from_core
+            }
+        };
+
+        let value = registers
+            .registers()
+            .find(|(register_number, _)| {
+                registers
+                    .get_name_by_dwarf_register_number(**register_number)
+                    .map_or(false, |register_name| {
+                        register_name.eq_ignore_ascii_case(name)
+                    })
+            })
+            .map(|(_, value)| *value);
+        value
+    }
+
+    /// Find a top level variable of the local or static scope. For statics, the name can be a path like `module::STATIC`.
+    fn find_variable(&mut self, name: &str, scope: Scope) -> Result<Option<Variable>, DebugError> {
+        // Local variables shadow the arguments of the function.
+        let root_names: &[VariableName] = match scope {
+            Scope::Local => &[
+                VariableName::LocalScopeRoot,
+                VariableName::ArgumentScopeRoot,
+            ],
+            Scope::Static => &[VariableName::StaticScopeRoot],
+        };
+        let short_name = name.rsplit("::").next().unwrap_or(name);
+
+        for root_name in root_names {
+            let mut root = match self
+                .cache(scope)
+                .and_then(|cache| cache.get_variable_by_name_and_parent(root_name, None))
+            {
+                Some(root) => root,
+                None => continue,
+            };
+            if let Some(variable) = self
+                .children(&mut root, scope)?
+                .into_iter()
+                .find(|variable| variable.name.to_string() == short_name)
+            {
+                return Ok(Some(variable));
+            }
+        }
+
+        // Statics are nested in the namespaces of their modules.
+        Ok(match (scope, self.cache(scope)) {
+            (Scope::Static, Some(cache)) => {
+                cache.get_variable_by_name(&VariableName::Named(short_name.to_string()))
+            }
+            _ => None,
+        })
+    }
+
+    fn cache(&mut self, scope: Scope) -> Option<&mut VariableCache> {
+        let stack_frame = self.context.stack_frame.as_deref_mut()?;
+        match scope {
+            Scope::Local => stack_frame.local_variables.as_mut(),
+            Scope::Static => stack_frame.static_variables.as_mut(),
+        }
+    }
+
+    /// Caches the children of a variable, if they are loaded lazily and not yet available.
+    fn expand(&mut self, variable: &mut Variable, scope: Scope) -> Result<(), DebugError> {
+        let debug_info = match self.context.debug_info {
+            Some(debug_info) => debug_info,
+            None => return Ok(()),
+        };
+        let stack_frame = match self.context.stack_frame.as_deref_mut() {
+            Some(stack_frame) => stack_frame,
+            None => return Ok(()),
+        };
+        let cache = match scope {
+            Scope::Local => stack_frame.local_variables.as_mut(),
+            Scope::Static => stack_frame.static_variables.as_mut(),
+        };
+
+        if let Some(cache) = cache {
+            if variable.variable_node_type.is_deferred() && !cache.has_children(variable)? {
+                debug_info.cache_deferred_variables(
+                    cache,
+                    self.context.core,
+                    variable,
+                    &stack_frame.registers,
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    fn children(
+        &mut self,
+        variable: &mut Variable,
+        scope: Scope,
+    ) -> Result<Vec<Variable>, DebugError> {
+        self.expand(variable, scope)?;
+        match self.cache(scope) {
+            Some(cache) => Ok(cache.get_children(Some(variable.variable_key))?),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    fn field(&mut self, value: Value, field: &str) -> Result<Value, DebugError> {
+        match value {
+            Value::Variable(variable, scope)
+                if matches!(variable.type_name, VariableType::Pointer(_)) =>
+            {
+                // Pointers are dereferenced automatically, like in Rust.
+                let value = self.dereference(Value::Variable(variable, scope))?;
+                self.field(value, field)
+            }
+            Value::Variable(mut variable, scope) => self
+                .children(&mut variable, scope)?
+                .into_iter()
+                .find(|child| child.name.to_string() == field)
+                .map(|child| Value::Variable(child, scope))
+                .ok_or_else(|| anyhow!("{} has no field '{}'", variable.name, field).into()),
+            Value::Pointer { .. } => {
+                let value = self.dereference(value)?;
+                self.field(value, field)
+            }
+            Value::Scalar { value, .. } => {
+                Err(anyhow!("Can not access field '{}' of {}", field, value).into())
+            }
+        }
+    }
+
+    fn index(&mut self, value: Value, index: i128) -> Result<Value, DebugError> {
+        match value {
+            Value::Variable(mut variable, scope) if variable.type_name.is_array() => {
+                let name = format!("__{}", index);
+                self.children(&mut variable, scope)?
+                    .into_iter()
+                    .find(|child| child.name.to_string() == name)
+                    .map(|child| Value::Variable(child, scope))
+                    .ok_or_else(|| {
+                        anyhow!("Index {} is out of bounds for {}", index, variable.name).into()
+                    })
+            }
+            Value::Pointer { address, target } => {
+                let size = target
+                    .size()
+                    .ok_or_else(|| anyhow!("Can not index a pointer to {}", target))?;
+                let address = offset_address(address, index, size)?;
+                self.read(address, &target)
+            }
+            Value::Variable(variable, _) => Err(anyhow!(
+                "Can not index {} of type {}",
+                variable.name,
+                variable.type_name
+            )
+            .into()),
+            Value::Scalar { value, .. } => Err(anyhow!("Can not index {}", value).into()),
+        }
+    }
+
+    fn dereference(&mut self, value: Value) -> Result<Value, DebugError> {
+        match value {
+            Value::Pointer { address, target } => self.read(address, &target),
+            Value::Variable(mut variable, scope)
+                if matches!(variable.type_name, VariableType::Pointer(_)) =>
+            {
+                match self.children(&mut variable, scope)?.into_iter().next() {
+                    Some(referenced_variable) => Ok(Value::Variable(referenced_variable, scope)),
+                    None => Err(anyhow!("Can not dereference {}", variable.name).into()),
+                }
+            }
+            Value::Variable(variable, _) => Err(anyhow!(
+                "Can not dereference {} of type {}",
+                variable.name,
+                variable.type_name
+            )
+            .into()),
+            Value::Scalar { value, .. } => Err(anyhow!(
+                "Can not dereference {}, cast it to a pointer first, e.g. `*(u32*){}`",
+                value,
+                value
+            )
+            .into()),
+        }
+    }
+
+    fn address_of(&mut self, value: Value) -> Result<Value, DebugError> {
+        match value {
+            Value::Variable(variable, _) => {
+                let address = variable.memory_location.memory_address()?;
+                let target = match &variable.type_name {
+                    VariableType::Base(name) => Scalar::from_name(name)
+                        .map(Type::Scalar)
+                        .unwrap_or_else(|| Type::Named(name.clone())),
+                    other => Type::Named(other.to_string()),
+                };
+                Ok(Value::Pointer { address, target })
+            }
+            Value::Scalar {
+                address: Some(address),
+                scalar,
+                ..
+            } => Ok(Value::Pointer {
+                address,
+                target: Type::Scalar(scalar.unwrap_or(Scalar::U8)),
+            }),
+            _ => Err(anyhow!("Can not take the address of a value which is not in memory").into()),
+        }
+    }
+
+    /// Reads a value of type `type_` from target memory.
+    fn read(&mut self, address: u32, type_: &Type) -> Result<Value, DebugError> {
+        match type_ {
+            Type::Scalar(scalar) => {
+                let mut bytes = vec![0u8; scalar.size() as usize];
+                self.context.core.read_8(address, &mut bytes)?;
+                Ok(Value::Scalar {
+                    value: scalar.decode(&bytes)?,
+                    scalar: Some(*scalar),
+                    address: Some(address),
+                })
+            }
+            Type::Pointer(target) => {
+                let pointer = self.context.core.read_word_32(address)?;
+                Ok(Value::Pointer {
+                    address: pointer,
+                    target: *target.clone(),
+                })
+            }
+            Type::Named(type_name) => {
+                let debug_info = self.context.debug_info.ok_or_else(|| {
+                    anyhow!("Debug information is required for the type {}", type_name)
+                })?;
+                let stack_frame = self.context.stack_frame.as_deref_mut().ok_or_else(|| {
+                    anyhow!("A stack frame is required for the type {}", type_name)
+                })?;
+                let cache = stack_frame
+                    .local_variables
+                    .get_or_insert_with(VariableCache::new);
+                let name = format!("*({}*){:#010x}", type_name, address);
+
+                match debug_info.cache_variable_of_type(
+                    cache,
+                    self.context.core,
+                    type_name,
+                    name,
+                    address,
+                    &stack_frame.registers,
+                )? {
+                    Some(variable) => Ok(Value::Variable(variable, Scope::Local)),
+                    None => Err(anyhow!("Type {} not found", type_name).into()),
+                }
+            }
+        }
+    }
+
+    fn cast_to(&mut self, value: Value, type_: &Type) -> Result<Value, DebugError> {
+        let (scalar_value, _) = self.scalar_value(&value)?;
+        match type_ {
+            Type::Scalar(scalar) => {
+                let cast_value = match (scalar_value, scalar) {
+                    (ScalarValue::Integer(value), Scalar::Bool) => ScalarValue::Bool(value != 0),
+                    (ScalarValue::Integer(value), Scalar::Char) => ScalarValue::Char(
+                        u32::try_from(value)
+                            .ok()
+                            .and_then(char::from_u32)
+                            .ok_or_else(|| anyhow!("{} is not a valid char", value))?,
+                    ),
+                    (ScalarValue::Integer(value), scalar) if scalar.is_float() => {
+                        ScalarValue::Float(round_float(value as f64, *scalar))
+                    }
+                    (ScalarValue::Integer(value), scalar) => {
+                        ScalarValue::Integer(scalar.wrap(value))
+                    }
+                    (ScalarValue::Float(value), scalar) if scalar.is_float() => {
+                        ScalarValue::Float(round_float(value, *scalar))
+                    }
+                    (ScalarValue::Float(value), Scalar::Bool | Scalar::Char) => {
+                        return Err(anyhow!("Can not cast {} to {}", value, scalar.name()).into())
+                    }
+                    (ScalarValue::Float(value), scalar) => {
+                        ScalarValue::Integer(scalar.wrap(value as i128))
+                    }
+                    (ScalarValue::Bool(value), _) => {
+                        return self.cast_to(
+                            Value::scalar(ScalarValue::Integer(value as i128), None),
+                            type_,
+                        )
+                    }
+                    (ScalarValue::Char(value), _) => {
+                        return self.cast_to(
+                            Value::scalar(ScalarValue::Integer(value as i128), None),
+                            type_,
+                        )
+                    }
+                };
+                Ok(Value::scalar(cast_value, Some(*scalar)))
+            }
+            Type::Pointer(target) => match scalar_value {
+                ScalarValue::Integer(address) => Ok(Value::Pointer {
+                    address: address as u32,
+                    target: *target.clone(),
+                }),
+                other => Err(anyhow!("Can not cast {} to a pointer", other).into()),
+            },
+            Type::Named(name) => {
+                Err(anyhow!("Can only cast to primitive types, or pointers to {}", name).into())
+            }
+        }
+    }
+
+    /// The scalar value of a value, for arithmetic and comparisons.
+    fn scalar_value(&mut self, value: &Value) -> Result<(ScalarValue, Option<Scalar>), DebugError> {
+        match value {
+            Value::Scalar { value, scalar, .. } => Ok((*value, *scalar)),
+            Value::Pointer { address, .. } => {
+                Ok((ScalarValue::Integer(*address as i128), Some(Scalar::U32)))
+            }
+            Value::Variable(variable, scope) => {
+                if let VariableType::Pointer(_) = variable.type_name {
+                    // The value of a pointer is the address it points to.
+                    let address = variable.memory_location.memory_address()?;
+                    let pointer = self.context.core.read_word_32(address)?;
+                    return Ok((ScalarValue::Integer(pointer as i128), Some(Scalar::U32)));
+                }
+
+                let scalar = match &variable.type_name {
+                    VariableType::Base(name) => Scalar::from_name(name),
+                    _ => None,
+                };
+                let formatted_value = match self.cache(*scope) {
+                    Some(cache) => variable.get_value(cache),
+                    None => String::new(),
+                };
+
+                let scalar_value = match scalar {
+                    Some(Scalar::Bool) => formatted_value.parse().ok().map(ScalarValue::Bool),
+                    Some(Scalar::Char) => formatted_value.chars().next().map(ScalarValue::Char),
+                    Some(scalar) if scalar.is_float() => {
+                        formatted_value.parse().ok().map(ScalarValue::Float)
+                    }
+                    Some(_) => formatted_value.parse().ok().map(ScalarValue::Integer),
+                    None => None,
+                };
+
+                match scalar_value {
+                    Some(scalar_value) => Ok((scalar_value, scalar)),
+                    None => Err(anyhow!(
+                        "{} = {} of type {} is not a number",
+                        variable.name,
+                        formatted_value,
+                        variable.type_name
+                    )
+                    .into()),
+                }
+            }
+        }
+    }
+
+    fn apply_binary(
+        &mut self,
+        operator: &str,
+        left: Value,
+        right: Value,
+    ) -> Result<Value, DebugError> {
+        // Pointer arithmetic is scaled by the size of the target type.
+        if let (Value::Pointer { address, target }, "+" | "-") = (&left, operator) {
+            if let (ScalarValue::Integer(offset), _) = self.scalar_value(&right)? {
+                let offset = if operator == "-" { -offset } else { offset };
+                return Ok(Value::Pointer {
+                    address: offset_address(*address, offset, target.size().unwrap_or(1))?,
+                    target: target.clone(),
+                });
+            }
+        }
+
+        if matches!(operator, "==" | "!=") {
+            if let Some(equal) = self.compare_compound(&left, &right) {
+                return Ok(Value::scalar(
+                    ScalarValue::Bool(equal == (operator == "==")),
+                    Some(Scalar::Bool),
+                ));
+            }
+        }
+
+        let (left, left_scalar) = self.scalar_value(&left)?;
+        let (right, right_scalar) = self.scalar_value(&right)?;
+        let scalar = left_scalar.or(right_scalar);
+
+        match operator {
+            "||" | "&&" => {
+                let (left, right) = (is_true(left)?, is_true(right)?);
+                let value = if operator == "||" {
+                    left || right
+                } else {
+                    left && right
+                };
+                Ok(Value::scalar(ScalarValue::Bool(value), Some(Scalar::Bool)))
+            }
+            "==" | "!=" | "<" | "<=" | ">" | ">=" => {
+                let ordering = match (left, right) {
+                    (ScalarValue::Bool(left), ScalarValue::Bool(right)) => left.partial_cmp(&right),
+                    (ScalarValue::Char(left), ScalarValue::Char(right)) => left.partial_cmp(&right),
+                    (left, right) => match (as_number(left), as_number(right)) {
+                        (Some(Number::Integer(left)), Some(Number::Integer(right))) => {
+                            left.partial_cmp(&right)
+                        }
+                        (Some(left), Some(right)) => left.to_float().partial_cmp(&right.to_float()),
+                        _ => None,
+                    },
+                }
+                .ok_or_else(|| anyhow!("Can not compare {} and {}", left, right))?;
+
+                let value = match operator {
+                    "==" => ordering.is_eq(),
+                    "!=" => ordering.is_ne(),
+                    "<" => ordering.is_lt(),
+                    "<=" => ordering.is_le(),
+                    ">" => ordering.is_gt(),
+                    _ => ordering.is_ge(),
+                };
+                Ok(Value::scalar(ScalarValue::Bool(value), Some(Scalar::Bool)))
+            }
+            _ => {
+                let (left_number, right_number) = match (as_number(left), as_number(right)) {
+                    (Some(left), Some(right)) => (left, right),
+                    _ => {
+                        return Err(anyhow!(
+                            "Can not apply '{}' to {} and {}",
+                            operator,
+                            left,
+                            right
+                        )
+                        .into())
+                    }
+                };
+
+                match (left_number, right_number) {
+                    (Number::Integer(left), Number::Integer(right)) => {
+                        let value = match operator {
+                            "+" => left.checked_add(right),
+                            "-" => left.checked_sub(right),
+                            "*" => left.checked_mul(right),
+                            "/" => left.checked_div(right),
+                            "%" => left.checked_rem(right),
+                            "&" => Some(left & right),
+                            "|" => Some(left | right),
+                            "^" => Some(left ^ right),
+                            "<<" => u32::try_from(right)
+                                .ok()
+                                .and_then(|right| left.checked_shl(right)),
+                            ">>" => u32::try_from(right)
+                                .ok()
+                                .and_then(|right| left.checked_shr(right)),
+                            _ => None,
+                        }
+                        .ok_or_else(|| {
+                            anyhow!(
+                                "Overflow or division by zero in {} {} {}",
+                                left,
+                                operator,
+                                right
+                            )
+                        })?;
+                        Ok(Value::scalar(
+                            ScalarValue::Integer(wrap(value, scalar)),
+                            scalar,
+                        ))
+                    }
+                    (left, right) => {
+                        let (left, right) = (left.to_float(), right.to_float());
+                        let value = match operator {
+                            "+" => left + right,
+                            "-" => left - right,
+                            "*" => left * right,
+                            "/" => left / right,
+                            "%" => left % right,
+                            _ => {
+                                return Err(anyhow!(
+                                    "Can not apply '{}' to floating point values",
+                                    operator
+                                )
+                                .into())
+                            }
+                        };
+                        let scalar = match (left_scalar, right_scalar) {
+                            (Some(Scalar::F32), Some(Scalar::F32) | None)
+                            | (None, Some(Scalar::F32)) => Some(Scalar::F32),
+                            _ => Some(Scalar::F64),
+                        };
+                        Ok(Value::scalar(ScalarValue::Float(value), scalar))
+                    }
+                }
+            }
+        }
+    }
+
+    /// Compares two values by their formatted value, if either of them is a variable which is not a number, like an enum.
+    fn compare_compound(&mut self, left: &Value, right: &Value) -> Option<bool> {
+        let is_compound = |value: &Value| match value {
+            Value::Variable(variable, _) => matches!(
+                variable.type_name,
+                VariableType::Enum(_) | VariableType::Struct(_) | VariableType::Array { .. }
+            ),
+            _ => false,
+        };
+        if !is_compound(left) && !is_compound(right) {
+            return None;
+        }
+
+        let left = self.result(left.clone()).ok()?.value;
+        let right = self.result(right.clone()).ok()?.value;
+        Some(left == right)
+    }
+
+    /// Converts the final value of the expression into an [`ExpressionResult`].
+    fn result(&mut self, value: Value) -> Result<ExpressionResult, DebugError> {
+        match value {
+            Value::Variable(mut variable, scope) => {
+                // Cache the children, so that compound values can be formatted.
+                self.expand(&mut variable, scope)?;
+                let value = match self.cache(scope) {
+                    Some(cache) => {
+                        let variable = cache
+                            .get_variable_by_key(variable.variable_key)
+                            .unwrap_or_else(|| variable.clone());
+                        variable.get_value(cache)
+                    }
+                    None => String::new(),
+                };
+                Ok(ExpressionResult {
+                    value,
+                    type_name: variable.type_name.to_string(),
+                    memory_address: variable.memory_location.memory_address().ok(),
+                    variable: Some(variable),
+                })
+            }
+            Value::Scalar {
+                value,
+                scalar,
+                address,
+            } => Ok(ExpressionResult {
+                value: value.to_string(),
+                type_name: match (scalar, value) {
+                    (Some(scalar), _) => scalar.name().to_string(),
+                    (None, ScalarValue::Float(_)) => "{float}".to_string(),
+                    (None, _) => "{integer}".to_string(),
+                },
+                memory_address: address,
+                variable: None,
+            }),
+            Value::Pointer { address, target } => Ok(ExpressionResult {
+                value: format!("{:#010x}", address),
+                type_name: Type::Pointer(Box::new(target)).to_string(),
+                memory_address: None,
+                variable: None,
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Number {
+    Integer(i128),
+    Float(f64),
+}
+
+impl Number {
+    fn to_float(self) -> f64 {
+        match self {
+            Number::Integer(value) => value as f64,
+            Number::Float(value) => value,
+        }
+    }
+}
+
+fn as_number(value: ScalarValue) -> Option<Number> {
+    match value {
+        ScalarValue::Integer(value) => Some(Number::Integer(value)),
+        ScalarValue::Float(value) => Some(Number::Float(value)),
+        ScalarValue::Bool(_) | ScalarValue::Char(_) => None,
+    }
+}
+
+fn is_true(value: ScalarValue) -> Result<bool, DebugError> {
+    match value {
+        ScalarValue::Bool(value) => Ok(value),
+        ScalarValue::Integer(value) => Ok(value != 0),
+        other => Err(anyhow!("{} can not be used as a boolean", other).into()),
+    }
+}
+
+/// Wraps an integer to its type, if it has one.
+fn wrap(value: i128, scalar: Option<Scalar>) -> i128 {
+    match scalar {
+        Some(scalar) if !scalar.is_float() => scalar.wrap(value),
+        _ => value,
+    }
+}
+
+fn round_float(value: f64, scalar: Scalar) -> f64 {
+    if scalar == Scalar::F32 {
+        value as f32 as f64
+    } else {
+        value
+    }
+}
+
+fn offset_address(address: u32, index: i128, size: u32) -> Result<u32, DebugError> {
+    index
+        .checked_mul(size as i128)
+        .and_then(|offset| offset.checked_add(address as i128))
+        .and_then(|address| u32::try_from(address).ok())
+        .ok_or_else(|| anyhow!("Address overflow at index {}", index).into())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+    use crate::architecture::arm::core::ARM_REGISTER_FILE;
+    use crate::core::test::{MockCore, RAM_START};
+    use crate::core::CoreState;
+    use probe_rs_target::Architecture;
+    use std::collections::HashMap;
+
+    use super::super::VariableLocation;
+
+    fn variable(name: &str, type_name: VariableType, offset: u32) -> Variable {
+        let mut variable = Variable::new(None, None);
+        variable.name = VariableName::Named(name.to_string());
+        variable.type_name = type_name;
+        variable.memory_location = VariableLocation::Address(RAM_START + offset);
+        variable
+    }
+
+    /// A stack frame with variables in the RAM of a [`MockCore`]: `state: State { count: u32, buffer: [u8; 2] }`
+    /// at `0x2000_0008`, and `ptr: *const u32` at `0x2000_0020`, which points to a `u32` at `0x2000_0004`.
+    fn stack_frame(core: &mut Core) -> StackFrame {
+        core.write_word_32(RAM_START + 0x04, 1234).unwrap();
+        core.write_word_32(RAM_START + 0x08, 42).unwrap();
+        core.write_word_32(RAM_START + 0x20, RAM_START + 0x04)
+            .unwrap();
+
+        let mut cache = VariableCache::new();
+        let mut root = Variable::new(None, None);
+        root.name = VariableName::LocalScopeRoot;
+        let root = cache.cache_variable(None, root, core).unwrap();
+
+        let state = variable("state", VariableType::Struct("State".to_string()), 0x08);
+        let state = cache
+            .cache_variable(Some(root.variable_key), state, core)
+            .unwrap();
+        let count = variable("count", VariableType::Base("u32".to_string()), 0x08);
+        cache
+            .cache_variable(Some(state.variable_key), count, core)
+            .unwrap();
+        let buffer_type = VariableType::Array {
+            entry_type: VariableName::Named("u8".to_string()),
+            count: 2,
+        };
+        let buffer = cache
+            .cache_variable(
+                Some(state.variable_key),
+                variable("buffer", buffer_type, 0x0c),
+                core,
+            )
+            .unwrap();
+        for index in 0..2 {
+            let entry = variable(
+                &format!("__{}", index),
+                VariableType::Base("u8".to_string()),
+                0x0c + index,
+            );
+            cache
+                .cache_variable(Some(buffer.variable_key), entry, core)
+                .unwrap();
+        }
+
+        let ptr = variable(
+            "ptr",
+            VariableType::Pointer(Some("*const u32".to_string())),
+            0x20,
+        );
+        let ptr = cache
+            .cache_variable(Some(root.variable_key), ptr, core)
+            .unwrap();
+        let target = variable("*ptr", VariableType::Base("u32".to_string()), 0x04);
+        cache
+            .cache_variable(Some(ptr.variable_key), target, core)
+            .unwrap();
+
+        let mut registers = Registers {
+            register_description: &ARM_REGISTER_FILE,
+            values: HashMap::new(),
+            architecture: Architecture::Arm,
+        };
+        registers.set_program_counter(Some(0x0800_0401));
+
+        StackFrame {
+            id: 0,
+            function_name: "main".to_string(),
+            source_location: None,
+            registers,
+            pc: 0x0800_0400,
+            is_inlined: false,
+            static_variables: None,
+            local_variables: Some(cache),
+        }
+    }
+
+    fn evaluate(expression: &str) -> Result<ExpressionResult, DebugError> {
+        let mut state = CoreState::new(0);
+        let mut core = Core::new(MockCore::new(), &mut state);
+        let mut stack_frame = stack_frame(&mut core);
+        let mut context = ExpressionContext {
+            core: &mut core,
+            debug_info: None,
+            stack_frame: Some(&mut stack_frame),
+        };
+        evaluate_expression(expression, &mut context)
+    }
+
+    fn condition(expression: &str) -> Result<bool, DebugError> {
+        let mut state = CoreState::new(0);
+        let mut core = Core::new(MockCore::new(), &mut state);
+        let mut stack_frame = stack_frame(&mut core);
+        let mut context = ExpressionContext {
+            core: &mut core,
+            debug_info: None,
+            stack_frame: Some(&mut stack_frame),
+        };
+        evaluate_condition(expression, &mut context)
+    }
+
+    #[test]
+    fn evaluate_field() {
+        let result = evaluate("state.count").unwrap();
+        assert_eq!(result.value, "42");
+        assert_eq!(result.type_name, "u32");
+        assert_eq!(result.memory_address, Some(RAM_START + 0x08));
+
+        assert!(evaluate("state.missing").is_err());
+        assert!(evaluate("state.count.value").is_err());
+    }
+
+    #[test]
+    fn evaluate_index() {
+        assert_eq!(evaluate("state.buffer[1]").unwrap().value, "13");
+        assert_eq!(
+            evaluate("state.buffer[state.buffer[0] - 12]")
+                .unwrap()
+                .value,
+            "12"
+        );
+        assert_eq!(evaluate("((u16*)0x2000_000c)[1]").unwrap().value, "3854");
+
+        assert!(evaluate("state.buffer[2]").is_err());
+        assert!(evaluate("state.count[0]").is_err());
+    }
+
+    #[test]
+    fn evaluate_dereference() {
+        assert_eq!(evaluate("*ptr").unwrap().value, "1234");
+
+        let result = evaluate("*(u32*)0x2000_0004").unwrap();
+        assert_eq!(result.value, "1234");
+        assert_eq!(result.type_name, "u32");
+        assert_eq!(result.memory_address, Some(RAM_START + 0x04));
+
+        assert!(evaluate("*state.count").is_err());
+        assert!(evaluate("*0x2000_0004").is_err());
+    }
+
+    #[test]
+    fn evaluate_cast() {
+        let result = evaluate("(i8)0xff").unwrap();
+        assert_eq!(result.value, "-1");
+        assert_eq!(result.type_name, "i8");
+
+        let result = evaluate("state.count as u16").unwrap();
+        assert_eq!(result.value, "42");
+        assert_eq!(result.type_name, "u16");
+
+        assert_eq!(evaluate("(u8)(state.count + 250)").unwrap().value, "36");
+        assert!(evaluate("(State)state.count").is_err());
+    }
+
+    #[test]
+    fn evaluate_conditions() {
+        assert!(condition("state.count == 42 && *ptr > 1000").unwrap());
+        assert!(condition("state.buffer[0]").unwrap());
+        assert!(!condition("state.count - 42").unwrap());
+        assert!(condition("pc - 1 == 0x0800_0400").unwrap());
+        assert!(condition("missing == 1").is_err());
+    }
+
+    #[test]
+    fn evaluate_short_circuit() {
+        assert!(condition("state.count == 42 || missing == 1").unwrap());
+        assert!(!condition("state.count != 42 && *(missing + 1) == 1").unwrap());
+        assert!(condition("(0 && state.buffer[missing]) || *ptr == 1234").unwrap());
+        assert!(!condition("1 && (0 && missing) || 0").unwrap());
+
+        assert!(condition("state.count != 42 || missing == 1").is_err());
+        assert!(condition("state.count == 42 && missing == 1").is_err());
+        assert!(condition("1 ||").is_err());
+    }
+
+    #[test]
+    fn tokenize_expression() {
+        assert_eq!(
+            tokenize("*(u32*)0x2000_0000 + state.0.1 >= 1.5 && c != 'a'").unwrap(),
+            vec![
+                Token::Symbol("*"),
+                Token::Symbol("("),
+                Token::Identifier("u32".to_string()),
+                Token::Symbol("*"),
+                Token::Symbol(")"),
+                Token::Integer(0x2000_0000),
+                Token::Symbol("+"),
+                Token::Identifier("state".to_string()),
+                Token::Symbol("."),
+                Token::Integer(0),
+                Token::Symbol("."),
+                Token::Integer(1),
+                Token::Symbol(">="),
+                Token::Float(1.5),
+                Token::Symbol("&&"),
+                Token::Identifier("c".to_string()),
+                Token::Symbol("!="),
+                Token::Char('a'),
+            ]
+        );
+    }
+
+    #[test]
+    fn tokenize_paths_and_registers() {
+        assert_eq!(
+            tokenize("app::COUNTER->next[$r0]").unwrap(),
+            vec![
+                Token::Identifier("app::COUNTER".to_string()),
+                Token::Symbol("->"),
+                Token::Identifier("next".to_string()),
+                Token::Symbol("["),
+                Token::Identifier("$r0".to_string()),
+                Token::Symbol("]"),
+            ]
+        );
+    }
+
+    #[test]
+    fn tokenize_invalid() {
+        assert!(tokenize("a # b").is_err());
+        assert!(tokenize("0xZZ").is_err());
+        assert!(tokenize("'ab'").is_err());
+    }
+
+    #[test]
+    fn parse_integers() {
+        assert_eq!(parse_integer("42"), Some(42));
+        assert_eq!(parse_integer("0x2A"), Some(42));
+        assert_eq!(parse_integer("0b101010"), Some(42));
+        assert_eq!(parse_integer("0o52"), Some(42));
+        assert_eq!(parse_integer("4a"), None);
+    }
+
+    #[test]
+    fn wrap_to_scalar_types() {
+        assert_eq!(Scalar::U8.wrap(0x1ff), 0xff);
+        assert_eq!(Scalar::I8.wrap(0xff), -1);
+        assert_eq!(Scalar::I16.wrap(-1), -1);
+        assert_eq!(Scalar::U32.wrap(-1), 0xffff_ffff);
+        assert_eq!(Scalar::I128.wrap(-5), -5);
+    }
+
+    #[test]
+    fn decode_memory() {
+        assert_eq!(
+            Scalar::U32.decode(&[0x78, 0x56, 0x34, 0x12]).unwrap(),
+            ScalarValue::Integer(0x1234_5678)
+        );
+        assert_eq!(
+            Scalar::I16.decode(&[0xfe, 0xff]).unwrap(),
+            ScalarValue::Integer(-2)
+        );
+        assert_eq!(
+            Scalar::F32.decode(&1.5f32.to_le_bytes()).unwrap(),
+            ScalarValue::Float(1.5)
+        );
+        assert_eq!(Scalar::Bool.decode(&[1]).unwrap(), ScalarValue::Bool(true));
+        assert_eq!(
+            Scalar::Char.decode(&('x' as u32).to_le_bytes()).unwrap(),
+            ScalarValue::Char('x')
+        );
+    }
+
+    #[test]
+    fn type_names() {
+        assert_eq!(
+            Type::Pointer(Box::new(Type::Scalar(Scalar::U8))).to_string(),
+            "*const u8"
+        );
+        assert_eq!(Type::Named("Foo".to_string()).to_string(), "Foo");
+        assert_eq!(Scalar::from_name("usize"), Some(Scalar::U32));
+        assert_eq!(Scalar::from_name("uint16_t"), Some(Scalar::U16));
+        assert_eq!(Scalar::from_name("Foo"), None);
+    }
+}
@@ -10,6 +10,8 @@
 
 /// Debug information which is parsed from DWARF debugging information.
 pub mod debug_info;
+/// Evaluation of expressions, like watch expressions, against the variables of a stack frame.
+pub mod expression;
 /// References to the DIE (debug information entry) of functions.
 pub mod function_die;
 /// Target Register definitions.
@@ -26,7 +28,11 @@ pub mod variable;
 pub mod variable_cache;
 
 pub use self::{
-    debug_info::*, registers::*, stack_frame::StackFrame, variable::*,
+    debug_info::*,
+    expression::{evaluate_condition, evaluate_expression, ExpressionContext, ExpressionResult},
+    registers::*,
+    stack_frame::StackFrame,
+    variable::*,
     variable_cache::VariableCache,
 };
 use crate::{core::Core, MemoryInterface};
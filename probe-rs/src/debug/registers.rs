@@ -38,6 +38,27 @@ impl Registers {
                 }
             };
         }
+
+        // The stack pointers of Cortex-M cores are needed to unwind exception frames.
+        if core.core_type().is_cortex_m() {
+            for stack_pointer in [register_file.msp(), register_file.psp()]
+                .into_iter()
+                .flatten()
+            {
+                match core.read_core_reg(stack_pointer) {
+                    Ok(value) => {
+                        registers
+                            .values
+                            .insert(stack_pointer.address.0 as u32, value);
+                    }
+                    Err(e) => log::warn!(
+                        "Failed to read value for register {}: {}",
+                        stack_pointer.name(),
+                        e
+                    ),
+                }
+            }
+        }
         registers
     }
 
@@ -114,6 +135,34 @@ impl Registers {
         }
     }
 
+    /// Get the main stack pointer, if the core has one.
+    pub fn get_main_stack_pointer(&self) -> Option<u32> {
+        let reg_num = self.register_description.msp()?.address.0 as u32;
+
+        self.values.get(&reg_num).copied()
+    }
+
+    /// Set the main stack pointer, if the core has one.
+    pub fn set_main_stack_pointer(&mut self, value: Option<u32>) {
+        if let Some(register) = self.register_description.msp() {
+            self.set_by_dwarf_register_number(register.address.0 as u32, value);
+        }
+    }
+
+    /// Get the process stack pointer, if the core has one.
+    pub fn get_process_stack_pointer(&self) -> Option<u32> {
+        let reg_num = self.register_description.psp()?.address.0 as u32;
+
+        self.values.get(&reg_num).copied()
+    }
+
+    /// Set the process stack pointer, if the core has one.
+    pub fn set_process_stack_pointer(&mut self, value: Option<u32>) {
+        if let Some(register) = self.register_description.psp() {
+            self.set_by_dwarf_register_number(register.address.0 as u32, value);
+        }
+    }
+
     /// Get the value using the dwarf register number as an index.
     pub fn get_value_by_dwarf_register_number(&self, register_number: u32) -> Option<u32> {
         self.values.get(&register_number).copied()
@@ -123,6 +172,17 @@ impl Registers {
     pub fn get_name_by_dwarf_register_number(&self, register_number: u32) -> Option<String> {
         self.register_description
             .get_platform_register(register_number as usize)
+            .or_else(|| {
+                // The stack pointers and the floating-point registers restored from exception frames.
+                [
+                    self.register_description.msp(),
+                    self.register_description.psp(),
+                ]
+                .into_iter()
+                .flatten()
+                .chain(self.register_description.fpu_registers())
+                .find(|register| register.address.0 as u32 == register_number)
+            })
             .map(|platform_register| platform_register.name().to_string())
     }
 
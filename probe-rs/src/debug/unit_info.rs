@@ -488,6 +488,12 @@ impl<'debuginfo> UnitInfo<'debuginfo> {
 
             let mut child_nodes = parent_node.children();
             while let Some(mut child_node) = child_nodes.next()? {
+                // The arguments of a function are the only children of the `VariableName::ArgumentScopeRoot`, and are not included anywhere else.
+                let is_argument = child_node.entry().tag() == gimli::DW_TAG_formal_parameter;
+                if is_argument != (parent_variable.name == VariableName::ArgumentScopeRoot) {
+                    continue;
+                }
+
                 match child_node.entry().tag() {
                     gimli::DW_TAG_namespace => {
                         // Use these parents to extract `statics`.
@@ -546,6 +552,7 @@ impl<'debuginfo> UnitInfo<'debuginfo> {
                         }
                     }
                     gimli::DW_TAG_variable |    // Typical top-level variables.
+                    gimli::DW_TAG_formal_parameter | // Arguments of functions.
                     gimli::DW_TAG_member |      // Members of structured types.
                     gimli::DW_TAG_enumerator    // Possible values for enumerators, used by extract_type() when processing DW_TAG_enumeration_type.
                     => {
@@ -709,7 +716,6 @@ impl<'debuginfo> UnitInfo<'debuginfo> {
                     other => {
                         // One of two things are true here. Either we've encountered a DwTag that is implemented in `extract_type`, and whould be ignored, or we have encountered an unimplemented  DwTag.
                         match other {
-                            gimli::DW_TAG_formal_parameter | // Parameters to functions are not included in our processing of variables.
                             gimli::DW_TAG_inlined_subroutine | // Inlined subroutines are handled at the [StackFame] level
                             gimli::DW_TAG_base_type |
                             gimli::DW_TAG_pointer_type |
@@ -1305,7 +1311,24 @@ impl<'debuginfo> UnitInfo<'debuginfo> {
                             }
                         }
                     }
-                    gimli::AttributeValue::LocationListsRef(location_list_offset) => {
+                    location_list_attribute @ (gimli::AttributeValue::LocationListsRef(_)
+                    | gimli::AttributeValue::DebugLocListsIndex(_)) => {
+                        // DWARF 5 refers to location lists through an index into `.debug_loclists`.
+                        let location_list_offset = match self
+                            .debug_info
+                            .dwarf
+                            .attr_locations_offset(&self.unit, location_list_attribute)
+                        {
+                            Ok(Some(location_list_offset)) => location_list_offset,
+                            Ok(None) => continue,
+                            Err(error) => {
+                                child_variable.set_value(VariableValue::Error(format!(
+                                    "Error: Resolving variable Location: {:?}",
+                                    &error
+                                )));
+                                continue;
+                            }
+                        };
                         match self.debug_info.locations_section.locations(
                             location_list_offset,
                             self.unit.header.encoding(),
@@ -78,6 +78,8 @@ pub enum VariableName {
     RegistersRoot,
     /// Top-level variable for local scoped variables, child of a stack frame variable.
     LocalScopeRoot,
+    /// Top-level variable for the arguments of a function, which is cached with the local scoped variables of a stack frame.
+    ArgumentScopeRoot,
     /// Top-level variable for CMSIS-SVD file Device peripherals/registers/fields.
     PeripheralScopeRoot,
     /// Artificial variable, without a name (e.g. enum discriminant)
@@ -104,6 +106,7 @@ impl std::fmt::Display for VariableName {
             VariableName::StaticScopeRoot => write!(f, "Static Variable"),
             VariableName::RegistersRoot => write!(f, "Platform Register"),
             VariableName::LocalScopeRoot => write!(f, "Function Variable"),
+            VariableName::ArgumentScopeRoot => write!(f, "Function Argument"),
             VariableName::PeripheralScopeRoot => write!(f, "Peripheral Variable"),
             VariableName::Artifical => write!(f, "<artifical>"),
             VariableName::AnonymousNamespace => write!(f, "<anonymous_namespace>"),
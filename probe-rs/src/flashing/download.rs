@@ -90,7 +90,7 @@ pub enum FileDownloadError {
 ///
 /// options.verify = true;
 /// ```
-#[derive(Default)]
+#[derive(Default, Clone)]
 #[non_exhaustive]
 pub struct DownloadOptions<'progress> {
     /// An optional progress reporter which is used if this argument is set to `Some(...)`.
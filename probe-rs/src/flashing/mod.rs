@@ -50,11 +50,14 @@ mod error;
 mod flash_algorithm;
 mod flasher;
 mod loader;
+mod patch;
 mod progress;
+mod read;
 mod visualizer;
 
 use builder::*;
 use flasher::*;
+pub(crate) use patch::*;
 
 pub use download::*;
 pub use erase::*;
@@ -62,4 +65,5 @@ pub use error::*;
 pub use flash_algorithm::*;
 pub use loader::*;
 pub use progress::*;
+pub use read::*;
 pub use visualizer::*;
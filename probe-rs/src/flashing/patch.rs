@@ -0,0 +1,188 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use probe_rs_target::{MemoryRegion, NvmRegion};
+
+use crate::flashing::{DownloadOptions, FlashAlgorithm, FlashError, FlashLoader};
+use crate::{MemoryInterface, Session};
+
+/// Replaces the bytes at a number of flash addresses, keeping the rest of the flash contents.
+///
+/// Every sector which contains a patched byte is read, patched and written back as a whole
+/// using a [`FlashLoader`]. This is used to insert and remove software breakpoints in flash.
+///
+/// Running the flash algorithm resets the cores which access the patched regions.
+/// They are reset and halted again afterwards, but any other state of the program is lost.
+pub(crate) fn patch_flash(
+    session: &mut Session,
+    patches: &[(u32, &[u8])],
+) -> Result<(), FlashError> {
+    let mut loader = session.target().flash_loader();
+    let mut core_indices = Vec::new();
+
+    for region in session.target().memory_map.clone() {
+        let region = match region {
+            MemoryRegion::Nvm(region) => region,
+            _ => continue,
+        };
+
+        let region_patches: Vec<_> = patches
+            .iter()
+            .copied()
+            .filter(|(address, _)| region.range.contains(address))
+            .collect();
+
+        if region_patches.is_empty() {
+            continue;
+        }
+
+        let raw_algorithm = FlashLoader::get_flash_algorithm_for_region(&region, session.target())?;
+        let algorithm = FlashAlgorithm {
+            flash_properties: raw_algorithm.flash_properties.clone(),
+            ..Default::default()
+        };
+
+        let core_index = region
+            .cores
+            .first()
+            .and_then(|name| session.target().core_index_by_name(name))
+            .ok_or_else(|| FlashError::NoNvmCoreAccess(region.clone()))?;
+
+        let mut core = session.core(core_index).map_err(FlashError::Core)?;
+        let sectors = read_patched_sectors(&mut core, &region, &algorithm, &region_patches)?;
+
+        for (address, data) in sectors {
+            loader.add_data(address, &data)?;
+        }
+
+        if !core_indices.contains(&core_index) {
+            core_indices.push(core_index);
+        }
+    }
+
+    loader.commit(session, DownloadOptions::default())?;
+
+    // The flash algorithm leaves the cores in an undefined state.
+    for core_index in core_indices {
+        session
+            .core(core_index)
+            .and_then(|mut core| core.reset_and_halt(Duration::from_millis(500)))
+            .map_err(FlashError::Core)?;
+    }
+
+    Ok(())
+}
+
+/// Reads every sector of `region` which contains a byte of `patches`, and applies the patches to its contents.
+///
+/// Returns the patched contents of the sectors by their address.
+fn read_patched_sectors(
+    memory: &mut impl MemoryInterface,
+    region: &NvmRegion,
+    algorithm: &FlashAlgorithm,
+    patches: &[(u32, &[u8])],
+) -> Result<BTreeMap<u32, Vec<u8>>, FlashError> {
+    let mut sectors = BTreeMap::new();
+
+    for &(address, data) in patches {
+        // A patch can cross the border of two sectors.
+        for (byte_address, &byte) in (address..).zip(data) {
+            let sector = algorithm.sector_info(byte_address).ok_or_else(|| {
+                FlashError::AddressNotInRegion {
+                    address: byte_address,
+                    region: region.clone(),
+                }
+            })?;
+
+            if !sectors.contains_key(&sector.base_address) {
+                let mut contents = vec![0; sector.size as usize];
+                memory
+                    .read(sector.base_address, &mut contents)
+                    .map_err(FlashError::Core)?;
+                sectors.insert(sector.base_address, contents);
+            }
+
+            let contents = sectors.get_mut(&sector.base_address).unwrap();
+            contents[(byte_address - sector.base_address) as usize] = byte;
+        }
+    }
+
+    Ok(sectors)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::core::test::{MockCore, RAM_START};
+    use probe_rs_target::{FlashProperties, SectorDescription};
+
+    #[test]
+    fn patch_across_sectors() {
+        let region = NvmRegion {
+            name: None,
+            range: RAM_START..RAM_START + 0x100,
+            is_boot_memory: false,
+            cores: vec!["main".to_string()],
+        };
+        let algorithm = FlashAlgorithm {
+            flash_properties: FlashProperties {
+                address_range: region.range.clone(),
+                sectors: vec![SectorDescription {
+                    size: 0x10,
+                    address: 0x0,
+                }],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let patches: [(u32, &[u8]); 2] = [
+            (RAM_START + 0x1e, &[0x73, 0x00, 0x10, 0x00]),
+            (RAM_START + 0x24, &[0x00, 0xbe]),
+        ];
+        let sectors =
+            read_patched_sectors(&mut MockCore::new(), &region, &algorithm, &patches).unwrap();
+
+        assert_eq!(
+            sectors.keys().copied().collect::<Vec<_>>(),
+            vec![RAM_START + 0x10, RAM_START + 0x20]
+        );
+
+        let mut first: Vec<u8> = (0x10..0x20).collect();
+        first[0xe..].copy_from_slice(&[0x73, 0x00]);
+        assert_eq!(sectors[&(RAM_START + 0x10)], first);
+
+        let mut second: Vec<u8> = (0x20..0x30).collect();
+        second[..2].copy_from_slice(&[0x10, 0x00]);
+        second[4..6].copy_from_slice(&[0x00, 0xbe]);
+        assert_eq!(sectors[&(RAM_START + 0x20)], second);
+    }
+
+    #[test]
+    fn patch_outside_of_sectors() {
+        let region = NvmRegion {
+            name: None,
+            range: RAM_START..RAM_START + 0x100,
+            is_boot_memory: false,
+            cores: vec!["main".to_string()],
+        };
+        let algorithm = FlashAlgorithm {
+            flash_properties: FlashProperties {
+                address_range: RAM_START..RAM_START + 0x20,
+                sectors: vec![SectorDescription {
+                    size: 0x10,
+                    address: 0x0,
+                }],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let patches: [(u32, &[u8]); 1] = [(RAM_START + 0x40, &[0x00, 0xbe])];
+
+        assert!(matches!(
+            read_patched_sectors(&mut MockCore::new(), &region, &algorithm, &patches),
+            Err(FlashError::AddressNotInRegion { address, .. }) if address == RAM_START + 0x40
+        ));
+    }
+}
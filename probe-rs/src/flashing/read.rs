@@ -0,0 +1,259 @@
+use std::io::{self, Write};
+use std::ops::Range;
+
+use ihex::Record;
+use probe_rs_target::NvmRegion;
+
+use crate::flashing::{FlashAlgorithm, FlashError, FlashLoader};
+use crate::{MemoryInterface, Session};
+
+/// Options for reading the contents of a flash region with [`read_flash`].
+#[derive(Debug, Clone, Default)]
+pub struct FlashReadOptions {
+    /// Only read this part of the region. The whole region is read if this is `None`.
+    pub range: Option<Range<u32>>,
+    /// Leave out the sectors which are erased, instead of reading them into the contents.
+    pub skip_erased: bool,
+}
+
+/// A contiguous block of data read from flash, or from other memory of the target.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlashBlock {
+    /// The address of the first byte of the block.
+    pub address: u32,
+    /// The data of the block.
+    pub data: Vec<u8>,
+}
+
+impl FlashBlock {
+    fn end(&self) -> u32 {
+        self.address + self.data.len() as u32
+    }
+
+    /// Appends the Intel HEX records of the block, with data records of up to 16 bytes.
+    ///
+    /// An extended linear address record is added whenever the upper 16 bits of the address
+    /// differ from `upper_address`, which is updated accordingly.
+    fn hex_records(&self, records: &mut Vec<Record>, upper_address: &mut Option<u32>) {
+        let mut offset = 0;
+
+        while offset < self.data.len() {
+            let address = self.address + offset as u32;
+            if *upper_address != Some(address >> 16) {
+                *upper_address = Some(address >> 16);
+                records.push(Record::ExtendedLinearAddress((address >> 16) as u16));
+            }
+
+            // A data record must not cross a 64 KiB boundary.
+            let length = (16 - address as usize % 16)
+                .min(0x1_0000 - (address & 0xffff) as usize)
+                .min(self.data.len() - offset);
+            records.push(Record::Data {
+                offset: address as u16,
+                value: self.data[offset..offset + length].to_vec(),
+            });
+            offset += length;
+        }
+    }
+}
+
+/// The contents of a flash region, as read by [`read_flash`].
+#[derive(Debug, Clone)]
+pub struct FlashContents {
+    /// The region the contents were read from.
+    pub region: NvmRegion,
+    /// The range of the region which was read.
+    pub range: Range<u32>,
+    /// The value of the bytes of erased flash.
+    pub erased_byte_value: u8,
+    /// The data read from the range, sorted by address. Erased sectors are
+    /// missing if [`FlashReadOptions::skip_erased`] was set.
+    pub blocks: Vec<FlashBlock>,
+}
+
+impl FlashContents {
+    /// Returns the data of the whole range, with skipped sectors filled with the erased byte value.
+    pub fn to_bin(&self) -> Vec<u8> {
+        let mut bin = vec![self.erased_byte_value; (self.range.end - self.range.start) as usize];
+
+        for block in &self.blocks {
+            let start = (block.address - self.range.start) as usize;
+            bin[start..start + block.data.len()].copy_from_slice(&block.data);
+        }
+
+        bin
+    }
+}
+
+/// Reads the contents of a flash region through the memory interface of the first core which can access it.
+///
+/// The flash is read sector by sector, so that erased sectors can be left out
+/// of the contents with [`FlashReadOptions::skip_erased`]. The flash algorithm is not
+/// loaded to the target, only its description of the sectors is used.
+pub fn read_flash(
+    session: &mut Session,
+    region: &NvmRegion,
+    options: &FlashReadOptions,
+) -> Result<FlashContents, FlashError> {
+    let range = match &options.range {
+        Some(range) => {
+            let start = range.start.max(region.range.start);
+            let end = range.end.min(region.range.end);
+            if start >= end {
+                return Err(FlashError::AddressNotInRegion {
+                    address: range.start,
+                    region: region.clone(),
+                });
+            }
+            start..end
+        }
+        None => region.range.clone(),
+    };
+
+    let raw_algorithm = FlashLoader::get_flash_algorithm_for_region(region, session.target())?;
+    let algorithm = FlashAlgorithm {
+        flash_properties: raw_algorithm.flash_properties.clone(),
+        ..Default::default()
+    };
+
+    let core_index = region
+        .cores
+        .first()
+        .and_then(|name| session.target().core_index_by_name(name))
+        .ok_or_else(|| FlashError::NoNvmCoreAccess(region.clone()))?;
+    let mut core = session.core(core_index).map_err(FlashError::Core)?;
+
+    let mut blocks: Vec<FlashBlock> = Vec::new();
+
+    for sector in algorithm.iter_sectors() {
+        if sector.base_address >= range.end {
+            break;
+        }
+
+        let start = sector.base_address.max(range.start);
+        let end = (sector.base_address + sector.size).min(range.end);
+        if start >= end {
+            continue;
+        }
+
+        let mut data = vec![0; (end - start) as usize];
+        core.read(start, &mut data).map_err(FlashError::Core)?;
+
+        if options.skip_erased && algorithm.is_erased(&data) {
+            log::debug!("Skipping the erased sector {:08x}-{:08x}", start, end);
+            continue;
+        }
+
+        log::debug!("Read the sector {:08x}-{:08x}", start, end);
+
+        match blocks.last_mut() {
+            Some(last) if last.end() == start => last.data.extend_from_slice(&data),
+            _ => blocks.push(FlashBlock {
+                address: start,
+                data,
+            }),
+        }
+    }
+
+    Ok(FlashContents {
+        region: region.clone(),
+        range,
+        erased_byte_value: algorithm.flash_properties.erased_byte_value,
+        blocks,
+    })
+}
+
+/// Writes blocks of data, for example the [`FlashContents::blocks`] of one or more flash regions,
+/// as a single, sparse Intel HEX file.
+///
+/// Only the given blocks are written, so sectors which were skipped because
+/// they are erased do not take up space in the file.
+pub fn write_hex<'a>(
+    blocks: impl IntoIterator<Item = &'a FlashBlock>,
+    writer: &mut dyn Write,
+) -> io::Result<()> {
+    let mut records = Vec::new();
+    let mut upper_address = None;
+
+    for block in blocks {
+        block.hex_records(&mut records, &mut upper_address);
+    }
+    records.push(Record::EndOfFile);
+
+    let hex = ihex::create_object_file_representation(&records)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+    writer.write_all(hex.as_bytes())?;
+    if !hex.ends_with('\n') {
+        writer.write_all(b"\n")?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn contents() -> FlashContents {
+        FlashContents {
+            region: NvmRegion {
+                name: Some("FLASH".to_string()),
+                range: 0x0800_0000..0x0802_0000,
+                is_boot_memory: true,
+                cores: vec!["main".to_string()],
+            },
+            range: 0x0800_fff8..0x0801_0020,
+            erased_byte_value: 0xff,
+            blocks: vec![
+                FlashBlock {
+                    address: 0x0800_fff8,
+                    data: vec![1; 0x18],
+                },
+                FlashBlock {
+                    address: 0x0801_001c,
+                    data: vec![2; 4],
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn fill_skipped_sectors() {
+        let bin = contents().to_bin();
+
+        assert_eq!(bin.len(), 0x28);
+        assert_eq!(bin[..0x18], [1; 0x18]);
+        assert_eq!(bin[0x18..0x24], [0xff; 12]);
+        assert_eq!(bin[0x24..], [2; 4]);
+    }
+
+    #[test]
+    fn sparse_hex_records() {
+        let mut records = Vec::new();
+        let mut upper_address = None;
+        for block in &contents().blocks {
+            block.hex_records(&mut records, &mut upper_address);
+        }
+
+        assert_eq!(
+            records,
+            vec![
+                Record::ExtendedLinearAddress(0x0800),
+                Record::Data {
+                    offset: 0xfff8,
+                    value: vec![1; 8],
+                },
+                Record::ExtendedLinearAddress(0x0801),
+                Record::Data {
+                    offset: 0x0000,
+                    value: vec![1; 16],
+                },
+                Record::Data {
+                    offset: 0x001c,
+                    value: vec![2; 4],
+                },
+            ]
+        );
+    }
+}
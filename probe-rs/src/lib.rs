@@ -82,13 +82,16 @@ mod memory;
 #[warn(missing_docs)]
 mod probe;
 #[warn(missing_docs)]
+pub mod semihosting;
+#[warn(missing_docs)]
 mod session;
 
 pub use crate::config::{CoreType, InstructionSet, Target};
 pub use crate::core::{
-    Architecture, BreakpointId, CommunicationInterface, Core, CoreInformation, CoreInterface,
-    CoreRegister, CoreRegisterAddress, CoreState, CoreStatus, HaltReason, RegisterFile,
-    SpecificCoreState,
+    Architecture, BreakpointId, CommunicationInterface, Core, CoreDump, CoreDumpError,
+    CoreInformation, CoreInterface, CoreRegister, CoreRegisterAddress, CoreState, CoreStatus,
+    DumpedCore, DumpedMemory, FaultCause, FaultException, FaultInfo, HaltReason, RegisterFile,
+    SpecificCoreState, VectorCatchCondition, Watchpoint, WatchpointKind,
 };
 pub use crate::error::Error;
 pub use crate::memory::{Memory, MemoryInterface};
@@ -4,7 +4,7 @@ use crate::{
         memory::adi_v5_memory_interface::ArmProbe,
         ApAddress,
     },
-    CoreRegisterAddress,
+    CoreRegisterAddress, DebugProbeError,
 };
 use crate::{
     architecture::arm::{communication_interface::Initialized, ArmCommunicationInterface},
@@ -22,6 +22,19 @@ pub trait MemoryInterface {
     /// Returns `AccessPortError::MemoryNotAligned` if this does not hold true.
     fn read_word_32(&mut self, address: u32) -> Result<u32, error::Error>;
 
+    /// Read a 16bit word of at `address`.
+    ///
+    /// The address where the read should be performed at has to be halfword aligned.
+    /// Returns `AccessPortError::MemoryNotAligned` if this does not hold true.
+    ///
+    /// The default implementation returns an error, because splitting the access into two
+    /// 8bit accesses would not work for registers which only support 16bit accesses.
+    fn read_word_16(&mut self, _address: u32) -> Result<u16, error::Error> {
+        Err(error::Error::Probe(DebugProbeError::NotImplemented(
+            "16bit memory access",
+        )))
+    }
+
     /// Read an 8bit word of at `address`.
     fn read_word_8(&mut self, address: u32) -> Result<u8, error::Error>;
 
@@ -32,6 +45,18 @@ pub trait MemoryInterface {
     /// Returns `AccessPortError::MemoryNotAligned` if this does not hold true.
     fn read_32(&mut self, address: u32, data: &mut [u32]) -> Result<(), error::Error>;
 
+    /// Read a block of 16bit words at `address`.
+    ///
+    /// The number of words read is `data.len()`.
+    /// Every word is read with a separate 16bit access, and the address has to be halfword aligned.
+    fn read_16(&mut self, address: u32, data: &mut [u16]) -> Result<(), error::Error> {
+        for (offset, word) in (0..).step_by(2).zip(data.iter_mut()) {
+            *word = self.read_word_16(address + offset)?;
+        }
+
+        Ok(())
+    }
+
     /// Read a block of 8bit words at `address`.
     fn read_8(&mut self, address: u32, data: &mut [u8]) -> Result<(), error::Error>;
 
@@ -77,6 +102,18 @@ pub trait MemoryInterface {
     /// Returns `AccessPortError::MemoryNotAligned` if this does not hold true.
     fn write_word_32(&mut self, address: u32, data: u32) -> Result<(), error::Error>;
 
+    /// Write a 16bit word at `address`.
+    ///
+    /// The address where the write should be performed at has to be halfword aligned.
+    /// Returns `AccessPortError::MemoryNotAligned` if this does not hold true.
+    ///
+    /// The default implementation returns an error, like [`MemoryInterface::read_word_16`].
+    fn write_word_16(&mut self, _address: u32, _data: u16) -> Result<(), error::Error> {
+        Err(error::Error::Probe(DebugProbeError::NotImplemented(
+            "16bit memory access",
+        )))
+    }
+
     /// Write an 8bit word at `address`.
     fn write_word_8(&mut self, address: u32, data: u8) -> Result<(), error::Error>;
 
@@ -87,6 +124,18 @@ pub trait MemoryInterface {
     /// Returns `AccessPortError::MemoryNotAligned` if this does not hold true.
     fn write_32(&mut self, address: u32, data: &[u32]) -> Result<(), error::Error>;
 
+    /// Write a block of 16bit words at `address`.
+    ///
+    /// The number of words written is `data.len()`.
+    /// Every word is written with a separate 16bit access, and the address has to be halfword aligned.
+    fn write_16(&mut self, address: u32, data: &[u16]) -> Result<(), error::Error> {
+        for (offset, word) in (0..).step_by(2).zip(data.iter()) {
+            self.write_word_16(address + offset, *word)?;
+        }
+
+        Ok(())
+    }
+
     /// Write a block of 8bit words at `address`.
     fn write_8(&mut self, address: u32, data: &[u8]) -> Result<(), error::Error>;
 
@@ -107,6 +156,10 @@ where
         (*self).read_word_32(address)
     }
 
+    fn read_word_16(&mut self, address: u32) -> Result<u16, error::Error> {
+        (*self).read_word_16(address)
+    }
+
     fn read_word_8(&mut self, address: u32) -> Result<u8, error::Error> {
         (*self).read_word_8(address)
     }
@@ -115,6 +168,10 @@ where
         (*self).read_32(address, data)
     }
 
+    fn read_16(&mut self, address: u32, data: &mut [u16]) -> Result<(), error::Error> {
+        (*self).read_16(address, data)
+    }
+
     fn read_8(&mut self, address: u32, data: &mut [u8]) -> Result<(), error::Error> {
         (*self).read_8(address, data)
     }
@@ -123,6 +180,10 @@ where
         (*self).write_word_32(address, data)
     }
 
+    fn write_word_16(&mut self, address: u32, data: u16) -> Result<(), error::Error> {
+        (*self).write_word_16(address, data)
+    }
+
     fn write_word_8(&mut self, address: u32, data: u8) -> Result<(), error::Error> {
         (*self).write_word_8(address, data)
     }
@@ -131,6 +192,10 @@ where
         (*self).write_32(address, data)
     }
 
+    fn write_16(&mut self, address: u32, data: &[u16]) -> Result<(), error::Error> {
+        (*self).write_16(address, data)
+    }
+
     fn write_8(&mut self, address: u32, data: &[u8]) -> Result<(), error::Error> {
         (*self).write_8(address, data)
     }
@@ -163,6 +228,14 @@ impl<'probe> Memory<'probe> {
         Ok(buff[0])
     }
 
+    /// Reads a 16 bit word from `address`.
+    pub fn read_word_16(&mut self, address: u32) -> Result<u16, error::Error> {
+        let mut buff = [0];
+        self.inner.read_16(self.ap_sel, address, &mut buff)?;
+
+        Ok(buff[0])
+    }
+
     /// Reads an 8 bit word from `address`.
     pub fn read_word_8(&mut self, address: u32) -> Result<u8, error::Error> {
         let mut buff = [0];
@@ -176,6 +249,11 @@ impl<'probe> Memory<'probe> {
         self.inner.read_32(self.ap_sel, address, data)
     }
 
+    /// Reads `data.len()` 16 bit words from `address` into `data`.
+    pub fn read_16(&mut self, address: u32, data: &mut [u16]) -> Result<(), error::Error> {
+        self.inner.read_16(self.ap_sel, address, data)
+    }
+
     /// Reads `data.len()` 8 bit words from `address` into `data`.
     pub fn read_8(&mut self, address: u32, data: &mut [u8]) -> Result<(), error::Error> {
         self.inner.read_8(self.ap_sel, address, data)
@@ -186,6 +264,11 @@ impl<'probe> Memory<'probe> {
         self.inner.write_32(self.ap_sel, address, &[data])
     }
 
+    /// Writes a 16 bit word to `address`.
+    pub fn write_word_16(&mut self, address: u32, data: u16) -> Result<(), error::Error> {
+        self.inner.write_16(self.ap_sel, address, &[data])
+    }
+
     /// Writes a 8 bit word to `address`.
     pub fn write_word_8(&mut self, address: u32, data: u8) -> Result<(), error::Error> {
         self.inner.write_8(self.ap_sel, address, &[data])
@@ -196,6 +279,11 @@ impl<'probe> Memory<'probe> {
         self.inner.write_32(self.ap_sel, address, data)
     }
 
+    /// Writes `data.len()` 16 bit words from `data` to `address`.
+    pub fn write_16(&mut self, address: u32, data: &[u16]) -> Result<(), error::Error> {
+        self.inner.write_16(self.ap_sel, address, data)
+    }
+
     /// Writes `data.len()` 8 bit words from `data` to `address`.
     pub fn write_8(&mut self, address: u32, data: &[u8]) -> Result<(), error::Error> {
         self.inner.write_8(self.ap_sel, address, data)
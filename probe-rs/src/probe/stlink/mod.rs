@@ -920,6 +920,107 @@ impl<D: StLinkUsb> StLink<D> {
         Ok(receive_buffer)
     }
 
+    fn read_mem_16bit(
+        &mut self,
+        address: u32,
+        data: &mut [u8],
+        apsel: u8,
+    ) -> Result<(), DebugProbeError> {
+        self.select_ap(apsel)?;
+
+        log::debug!(
+            "Read mem 16 bit, address={:08x}, length={}",
+            address,
+            data.len()
+        );
+
+        // Ensure maximum read length is not exceeded.
+        assert!(
+            data.len() <= STLINK_MAX_READ_LEN,
+            "Maximum read length for STLink is {} bytes",
+            STLINK_MAX_READ_LEN
+        );
+
+        assert!(
+            data.len() % 2 == 0,
+            "Data length has to be a multiple of 2 for 16 bit reads"
+        );
+
+        if address % 2 != 0 {
+            return Err(StlinkError::UnalignedAddress).map_err(DebugProbeError::from);
+        }
+
+        let data_length = data.len().to_le_bytes();
+
+        let addbytes = address.to_le_bytes();
+        self.device.write(
+            &[
+                commands::JTAG_COMMAND,
+                commands::JTAG_READMEM_16BIT,
+                addbytes[0],
+                addbytes[1],
+                addbytes[2],
+                addbytes[3],
+                data_length[0],
+                data_length[1],
+                apsel,
+            ],
+            &[],
+            data,
+            TIMEOUT,
+        )?;
+
+        self.get_last_rw_status()
+    }
+
+    fn write_mem_16bit(
+        &mut self,
+        address: u32,
+        data: &[u8],
+        apsel: u8,
+    ) -> Result<(), DebugProbeError> {
+        self.select_ap(apsel)?;
+
+        log::trace!("write_mem_16bit");
+        let length = data.len();
+
+        assert!(
+            length <= STLINK_MAX_WRITE_LEN,
+            "Maximum write length for STLink is {} bytes",
+            STLINK_MAX_WRITE_LEN
+        );
+
+        assert!(
+            data.len() % 2 == 0,
+            "Data length has to be a multiple of 2 for 16 bit writes"
+        );
+
+        if address % 2 != 0 {
+            return Err(StlinkError::UnalignedAddress).map_err(DebugProbeError::from);
+        }
+
+        let addbytes = address.to_le_bytes();
+        let lenbytes = length.to_le_bytes();
+        self.device.write(
+            &[
+                commands::JTAG_COMMAND,
+                commands::JTAG_WRITEMEM_16BIT,
+                addbytes[0],
+                addbytes[1],
+                addbytes[2],
+                addbytes[3],
+                lenbytes[0],
+                lenbytes[1],
+                apsel,
+            ],
+            data,
+            &mut [],
+            TIMEOUT,
+        )?;
+
+        self.get_last_rw_status()
+    }
+
     fn write_mem_32bit(
         &mut self,
         address: u32,
@@ -1418,6 +1519,44 @@ impl ArmProbe for StLinkMemoryInterface<'_> {
         Ok(())
     }
 
+    fn read_16(
+        &mut self,
+        ap: MemoryAp,
+        address: u32,
+        data: &mut [u16],
+    ) -> Result<(), ProbeRsError> {
+        for (index, chunk) in data.chunks_mut(STLINK_MAX_READ_LEN / 2).enumerate() {
+            let mut buff = vec![0u8; 2 * chunk.len()];
+
+            self.probe.probe.read_mem_16bit(
+                address + (index * STLINK_MAX_READ_LEN) as u32,
+                &mut buff,
+                ap.ap_address().ap,
+            )?;
+
+            for (index, word) in buff.chunks_exact(2).enumerate() {
+                chunk[index] = u16::from_le_bytes(word.try_into().unwrap());
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_16(&mut self, ap: MemoryAp, address: u32, data: &[u16]) -> Result<(), ProbeRsError> {
+        let tx_buffer: Vec<u8> = data.iter().flat_map(|word| word.to_le_bytes()).collect();
+
+        // The maximum write length is not a multiple of 4, but it is one of 2.
+        for (index, chunk) in tx_buffer.chunks(STLINK_MAX_WRITE_LEN).enumerate() {
+            self.probe.probe.write_mem_16bit(
+                address + (index * STLINK_MAX_WRITE_LEN) as u32,
+                chunk,
+                ap.ap_address().ap,
+            )?;
+        }
+
+        Ok(())
+    }
+
     fn read_8(&mut self, ap: MemoryAp, address: u32, data: &mut [u8]) -> Result<(), ProbeRsError> {
         // Read needs to be chunked into chunks of appropriate max length of the probe
         let chunk_size = if self.probe.probe.hw_version < 3 {
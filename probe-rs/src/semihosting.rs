@@ -0,0 +1,725 @@
+//! Host side of semihosting.
+//!
+//! Semihosting allows the firmware on a target to use the I/O facilities of the host,
+//! for example to print to the console, to access files or to report an exit code.
+//! The target requests an operation by executing a special breakpoint instruction,
+//! with the operation number in the first and a parameter in the second argument register.
+//! On ARM, this is `BKPT 0xAB`. On RISC-V, it is an `ebreak` surrounded by the
+//! `slli x0, x0, 0x1f` and `srai x0, x0, 7` instructions.
+//!
+//! The debugger has to call [`SemihostingHost::enable`] before the core is resumed,
+//! and [`SemihostingHost::handle`] whenever the core halts on a breakpoint,
+//! which services the request and resumes the core.
+//!
+//! See the [semihosting specification](https://github.com/ARM-software/abi-aa/blob/main/semihosting/semihosting.rst)
+//! for details about the operations.
+
+use crate::{Architecture, Core, CoreStatus, Error, HaltReason, InstructionSet, MemoryInterface};
+use anyhow::anyhow;
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::time::Instant;
+
+/// The `BKPT 0xAB` instruction, which is used for semihosting on Cortex-M cores.
+const THUMB_SEMIHOSTING_BKPT: [u8; 2] = [0xab, 0xbe];
+
+/// The `slli x0, x0, 0x1f` instruction, which precedes the `ebreak` of a semihosting request on RISC-V.
+const RISCV_SEMIHOSTING_ENTRY: u32 = 0x01f0_1013;
+/// The `ebreak` instruction.
+const RISCV_EBREAK: u32 = 0x0010_0073;
+/// The `srai x0, x0, 7` instruction, which follows the `ebreak` of a semihosting request on RISC-V.
+const RISCV_SEMIHOSTING_EXIT: u32 = 0x4070_5013;
+
+const SYS_OPEN: u32 = 0x01;
+const SYS_CLOSE: u32 = 0x02;
+const SYS_WRITE0: u32 = 0x04;
+const SYS_WRITE: u32 = 0x05;
+const SYS_READ: u32 = 0x06;
+const SYS_CLOCK: u32 = 0x10;
+const SYS_GET_CMDLINE: u32 = 0x15;
+const SYS_EXIT: u32 = 0x18;
+const SYS_EXIT_EXTENDED: u32 = 0x20;
+
+/// The reason code of `SYS_EXIT` for a regular exit of the application.
+const ADP_STOPPED_APPLICATION_EXIT: u32 = 0x20026;
+
+/// The value returned to the target if an operation failed.
+const FAILURE: u32 = -1i32 as u32;
+
+/// Handles of the console, which is opened with the special file name `:tt`.
+const STDIN_HANDLE: u32 = 1;
+const STDOUT_HANDLE: u32 = 2;
+const STDERR_HANDLE: u32 = 3;
+const FIRST_FILE_HANDLE: u32 = 4;
+
+/// Strings written with `SYS_WRITE0` are limited to this length,
+/// to avoid reading all of the memory if the terminating zero is missing.
+const MAX_STRING_LENGTH: usize = 0x1_0000;
+
+/// Options for the host side of semihosting.
+#[derive(Debug, Clone, Default)]
+pub struct SemihostingOptions {
+    /// The directory the target can access files in.
+    ///
+    /// Only relative paths inside this directory can be opened.
+    /// If it is `None`, the target can only access the console.
+    pub root: Option<PathBuf>,
+    /// The command line which is returned by `SYS_GET_CMDLINE`.
+    pub command_line: String,
+}
+
+/// The result of a semihosting request which was serviced by the host.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SemihostingEvent {
+    /// The target wrote the data to its standard output. The core was resumed.
+    Stdout(Vec<u8>),
+    /// The target wrote the data to its standard error. The core was resumed.
+    Stderr(Vec<u8>),
+    /// A request without console output was serviced. The core was resumed.
+    Handled,
+    /// The target exited with the given exit code. The core is kept halted.
+    Exit(i32),
+}
+
+/// A semihosting request of the target.
+struct SemihostingCall {
+    operation: u32,
+    parameter: u32,
+    /// The address execution continues at after the request was serviced.
+    return_address: u32,
+}
+
+/// The host side of semihosting, which services requests of the target.
+///
+/// The host keeps track of the files which were opened by the target,
+/// so the same instance has to be used for all requests of a debug session.
+pub struct SemihostingHost {
+    options: SemihostingOptions,
+    files: HashMap<u32, File>,
+    next_handle: u32,
+    start: Instant,
+}
+
+impl Default for SemihostingHost {
+    fn default() -> Self {
+        Self::new(SemihostingOptions::default())
+    }
+}
+
+impl SemihostingHost {
+    /// Creates a new semihosting host.
+    ///
+    /// The time reported by `SYS_CLOCK` starts with the creation of the host.
+    pub fn new(options: SemihostingOptions) -> Self {
+        Self {
+            options,
+            files: HashMap::new(),
+            next_handle: FIRST_FILE_HANDLE,
+            start: Instant::now(),
+        }
+    }
+
+    /// Makes sure that semihosting requests halt the core.
+    ///
+    /// On RISC-V, an `ebreak` only halts the core if this is enabled in `dcsr`,
+    /// which is cleared by a reset. So this has to be called before the core is resumed after a reset.
+    pub fn enable(&self, core: &mut Core) -> Result<(), Error> {
+        match core.architecture() {
+            // A `BKPT` always halts the core, once halting debug is enabled.
+            Architecture::Arm => Ok(()),
+            Architecture::Riscv => core.enable_breakpoints(true),
+        }
+    }
+
+    /// Services the semihosting request the core is halted on.
+    ///
+    /// Returns `None` if the core is not halted on a semihosting request,
+    /// for example because it hit a regular breakpoint.
+    /// Otherwise the core is resumed, unless the target requested to exit.
+    pub fn handle(&mut self, core: &mut Core) -> Result<Option<SemihostingEvent>, Error> {
+        let call = match semihosting_call(core)? {
+            Some(call) => call,
+            None => return Ok(None),
+        };
+
+        log::debug!(
+            "Semihosting operation {:#04x} with parameter {:#010x}",
+            call.operation,
+            call.parameter
+        );
+
+        let (result, event) = match call.operation {
+            SYS_OPEN => (Some(self.open(core, call.parameter)?), None),
+            SYS_CLOSE => (Some(self.close(core, call.parameter)?), None),
+            SYS_WRITE0 => {
+                let data = read_string(core, call.parameter)?;
+                (None, Some(SemihostingEvent::Stdout(data)))
+            }
+            SYS_WRITE => {
+                let (result, event) = self.write(core, call.parameter)?;
+                (Some(result), event)
+            }
+            SYS_READ => (Some(self.read(core, call.parameter)?), None),
+            SYS_CLOCK => {
+                let centiseconds = self.start.elapsed().as_millis() / 10;
+                (Some(centiseconds as u32), None)
+            }
+            SYS_GET_CMDLINE => (Some(self.get_command_line(core, call.parameter)?), None),
+            SYS_EXIT => {
+                // On 32-bit targets, the parameter is the reason code itself.
+                let code = if call.parameter == ADP_STOPPED_APPLICATION_EXIT {
+                    0
+                } else {
+                    1
+                };
+                return Ok(Some(SemihostingEvent::Exit(code)));
+            }
+            SYS_EXIT_EXTENDED => {
+                let mut block = [0u32; 2];
+                core.read_32(call.parameter, &mut block)?;
+
+                let code = if block[0] == ADP_STOPPED_APPLICATION_EXIT {
+                    block[1] as i32
+                } else {
+                    1
+                };
+                return Ok(Some(SemihostingEvent::Exit(code)));
+            }
+            other => {
+                log::warn!("Semihosting operation {:#04x} is not supported", other);
+                (Some(FAILURE), None)
+            }
+        };
+
+        if let Some(result) = result {
+            let result_register = core.registers().result_register(0).into();
+            core.write_core_reg(result_register, result)?;
+        }
+
+        let pc_register = core.registers().program_counter().into();
+        core.write_core_reg(pc_register, call.return_address)?;
+        core.run()?;
+
+        Ok(Some(event.unwrap_or(SemihostingEvent::Handled)))
+    }
+
+    /// `SYS_OPEN`, the parameter points to the file name, the mode and the length of the file name.
+    fn open(&mut self, core: &mut Core, parameter: u32) -> Result<u32, Error> {
+        let mut block = [0u32; 3];
+        core.read_32(parameter, &mut block)?;
+        let [name_address, mode, name_length] = block;
+
+        let mut name = vec![0; name_length as usize];
+        core.read_8(name_address, &mut name)?;
+        let name = String::from_utf8_lossy(&name);
+
+        if name == ":tt" {
+            // The mode selects the stream of the console: reading, writing or appending.
+            return Ok(match mode {
+                0..=3 => STDIN_HANDLE,
+                4..=7 => STDOUT_HANDLE,
+                _ => STDERR_HANDLE,
+            });
+        }
+
+        let path = match self.sandboxed_path(&name) {
+            Some(path) => path,
+            None => {
+                log::warn!("Semihosting: access to '{}' is not allowed", name);
+                return Ok(FAILURE);
+            }
+        };
+
+        let mut options = OpenOptions::new();
+        match mode {
+            0 | 1 => options.read(true),
+            2 | 3 => options.read(true).write(true),
+            4 | 5 => options.write(true).create(true).truncate(true),
+            6 | 7 => options.read(true).write(true).create(true).truncate(true),
+            8 | 9 => options.append(true).create(true),
+            10 | 11 => options.read(true).append(true).create(true),
+            _ => return Ok(FAILURE),
+        };
+
+        match options.open(&path) {
+            Ok(file) => {
+                let handle = self.next_handle;
+                self.next_handle += 1;
+                self.files.insert(handle, file);
+                Ok(handle)
+            }
+            Err(e) => {
+                log::warn!("Semihosting: unable to open {:?}: {}", path, e);
+                Ok(FAILURE)
+            }
+        }
+    }
+
+    /// `SYS_CLOSE`, the parameter points to the handle.
+    fn close(&mut self, core: &mut Core, parameter: u32) -> Result<u32, Error> {
+        let handle = core.read_word_32(parameter)?;
+
+        match handle {
+            STDIN_HANDLE | STDOUT_HANDLE | STDERR_HANDLE => Ok(0),
+            handle if self.files.remove(&handle).is_some() => Ok(0),
+            _ => Ok(FAILURE),
+        }
+    }
+
+    /// `SYS_WRITE`, the parameter points to the handle, the data and the length of the data.
+    ///
+    /// Returns the number of bytes which were not written.
+    fn write(
+        &mut self,
+        core: &mut Core,
+        parameter: u32,
+    ) -> Result<(u32, Option<SemihostingEvent>), Error> {
+        let mut block = [0u32; 3];
+        core.read_32(parameter, &mut block)?;
+        let [handle, data_address, length] = block;
+
+        let mut data = vec![0; length as usize];
+        core.read_8(data_address, &mut data)?;
+
+        match handle {
+            STDOUT_HANDLE => Ok((0, Some(SemihostingEvent::Stdout(data)))),
+            STDERR_HANDLE => Ok((0, Some(SemihostingEvent::Stderr(data)))),
+            handle => match self.files.get_mut(&handle) {
+                Some(file) => match file.write_all(&data) {
+                    Ok(()) => Ok((0, None)),
+                    Err(e) => {
+                        log::warn!("Semihosting: unable to write to file: {}", e);
+                        Ok((length, None))
+                    }
+                },
+                None => Ok((length, None)),
+            },
+        }
+    }
+
+    /// `SYS_READ`, the parameter points to the handle, the buffer and the length of the buffer.
+    ///
+    /// Returns the number of bytes which were not read.
+    fn read(&mut self, core: &mut Core, parameter: u32) -> Result<u32, Error> {
+        let mut block = [0u32; 3];
+        core.read_32(parameter, &mut block)?;
+        let [handle, buffer_address, length] = block;
+
+        // Reading from the console is not supported, so the target sees the end of the input.
+        let file = match self.files.get_mut(&handle) {
+            Some(file) => file,
+            None => return Ok(length),
+        };
+
+        let mut buffer = vec![0; length as usize];
+        let mut read = 0;
+        while read < buffer.len() {
+            match file.read(&mut buffer[read..]) {
+                Ok(0) => break,
+                Ok(n) => read += n,
+                Err(e) => {
+                    log::warn!("Semihosting: unable to read from file: {}", e);
+                    return Ok(FAILURE);
+                }
+            }
+        }
+
+        core.write_8(buffer_address, &buffer[..read])?;
+
+        Ok(length - read as u32)
+    }
+
+    /// `SYS_GET_CMDLINE`, the parameter points to the buffer and the length of the buffer.
+    ///
+    /// The length is updated with the length of the command line.
+    fn get_command_line(&mut self, core: &mut Core, parameter: u32) -> Result<u32, Error> {
+        let mut block = [0u32; 2];
+        core.read_32(parameter, &mut block)?;
+        let [buffer_address, length] = block;
+
+        let mut command_line = self.options.command_line.as_bytes().to_vec();
+        if command_line.len() + 1 > length as usize {
+            return Ok(FAILURE);
+        }
+
+        let command_line_length = command_line.len() as u32;
+        command_line.push(0);
+
+        core.write_8(buffer_address, &command_line)?;
+        core.write_word_32(parameter + 4, command_line_length)?;
+
+        Ok(0)
+    }
+
+    /// Resolves `name` in the root directory.
+    ///
+    /// Returns `None` if no root directory is set, or if `name` could refer to a file outside of it.
+    fn sandboxed_path(&self, name: &str) -> Option<PathBuf> {
+        let root = self.options.root.as_ref()?;
+        let path = Path::new(name);
+
+        if path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        {
+            Some(root.join(path))
+        } else {
+            None
+        }
+    }
+}
+
+/// Determines whether the core is halted on a semihosting request.
+fn semihosting_call(core: &mut Core) -> Result<Option<SemihostingCall>, Error> {
+    if !matches!(
+        core.status()?,
+        CoreStatus::Halted(HaltReason::Breakpoint | HaltReason::Multiple)
+    ) {
+        return Ok(None);
+    }
+
+    let pc = core.read_core_reg(core.registers().program_counter())?;
+
+    let return_address = match core.instruction_set()? {
+        InstructionSet::Thumb2 => {
+            let mut instruction = [0u8; 2];
+            core.read_8(pc, &mut instruction)?;
+
+            if instruction != THUMB_SEMIHOSTING_BKPT {
+                return Ok(None);
+            }
+
+            pc + 2
+        }
+        InstructionSet::RV32 => {
+            // The sequence is made of uncompressed instructions only,
+            // so it can be distinguished from a regular `ebreak`.
+            let mut instructions = [0u8; 12];
+            core.read_8(pc.wrapping_sub(4), &mut instructions)?;
+
+            let instruction = |index: usize| {
+                let mut bytes = [0u8; 4];
+                bytes.copy_from_slice(&instructions[index * 4..index * 4 + 4]);
+                u32::from_le_bytes(bytes)
+            };
+
+            if instruction(0) != RISCV_SEMIHOSTING_ENTRY
+                || instruction(1) != RISCV_EBREAK
+                || instruction(2) != RISCV_SEMIHOSTING_EXIT
+            {
+                return Ok(None);
+            }
+
+            // The `srai` after the `ebreak` has no effect, so execution can continue with it.
+            pc + 4
+        }
+        _ => return Ok(None),
+    };
+
+    Ok(Some(SemihostingCall {
+        operation: core.read_core_reg(core.registers().argument_register(0))?,
+        parameter: core.read_core_reg(core.registers().argument_register(1))?,
+        return_address,
+    }))
+}
+
+/// Reads the zero terminated string at `address`.
+///
+/// The string is read in chunks, which are clamped to the memory region containing `address`,
+/// so a string at the end of a region is read without accessing the memory after it.
+fn read_string(core: &mut Core, address: u32) -> Result<Vec<u8>, Error> {
+    let mut string = Vec::new();
+    let mut buffer = [0u8; 64];
+
+    // The end of the address space does not fit into an u32.
+    let region_end = core
+        .memory_range(address)
+        .map_or(1 << 32, |range| range.end as u64);
+
+    while string.len() < MAX_STRING_LENGTH {
+        let chunk_address = match address.checked_add(string.len() as u32) {
+            Some(chunk_address) if (chunk_address as u64) < region_end => chunk_address,
+            _ => break,
+        };
+
+        let length = (region_end - chunk_address as u64).min(buffer.len() as u64) as usize;
+        let chunk = &mut buffer[..length];
+        core.read_8(chunk_address, chunk)?;
+
+        match chunk.iter().position(|&byte| byte == 0) {
+            Some(end) => {
+                string.extend_from_slice(&chunk[..end]);
+                return Ok(string);
+            }
+            None => string.extend_from_slice(chunk),
+        }
+    }
+
+    Err(Error::Other(anyhow!(
+        "The string at {:#010x} is not terminated",
+        address
+    )))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::core::test::{MockCore, RAM_START};
+    use crate::CoreState;
+    use std::time::Duration;
+
+    fn host_with_root() -> SemihostingHost {
+        SemihostingHost::new(SemihostingOptions {
+            root: Some(PathBuf::from("/tmp/semihosting")),
+            command_line: String::new(),
+        })
+    }
+
+    fn core_state() -> CoreState {
+        let mut state = CoreState::new(0);
+        state.memory_ranges = vec![RAM_START..RAM_START + 0x100];
+        state
+    }
+
+    /// A core which is halted on a breakpoint.
+    fn halted(mut mock: MockCore) -> MockCore {
+        mock.halt_reason = HaltReason::Breakpoint;
+        mock
+    }
+
+    /// Halts the core on a semihosting request at the start of the RAM.
+    fn request(core: &mut Core, operation: u32, parameter: u32) {
+        core.halt(Duration::from_millis(100)).unwrap();
+
+        let pc = match core.architecture() {
+            Architecture::Arm => {
+                core.write_8(RAM_START, &THUMB_SEMIHOSTING_BKPT).unwrap();
+                RAM_START
+            }
+            Architecture::Riscv => {
+                let instructions = [
+                    RISCV_SEMIHOSTING_ENTRY,
+                    RISCV_EBREAK,
+                    RISCV_SEMIHOSTING_EXIT,
+                ];
+                core.write_32(RAM_START, &instructions).unwrap();
+                RAM_START + 4
+            }
+        };
+
+        let registers = core.registers();
+        core.write_core_reg(registers.program_counter().into(), pc)
+            .unwrap();
+        core.write_core_reg(registers.argument_register(0).into(), operation)
+            .unwrap();
+        core.write_core_reg(registers.argument_register(1).into(), parameter)
+            .unwrap();
+    }
+
+    fn pc(core: &mut Core) -> u32 {
+        core.read_core_reg(core.registers().program_counter())
+            .unwrap()
+    }
+
+    fn result(core: &mut Core) -> u32 {
+        core.read_core_reg(core.registers().result_register(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn sandboxed_path_inside_root() {
+        assert_eq!(
+            host_with_root().sandboxed_path("out/result.txt"),
+            Some(PathBuf::from("/tmp/semihosting/out/result.txt"))
+        );
+    }
+
+    #[test]
+    fn sandboxed_path_outside_root() {
+        let host = host_with_root();
+
+        assert_eq!(host.sandboxed_path("../secret"), None);
+        assert_eq!(host.sandboxed_path("/etc/passwd"), None);
+        assert_eq!(host.sandboxed_path("out/../../secret"), None);
+    }
+
+    #[test]
+    fn sandboxed_path_without_root() {
+        assert_eq!(
+            SemihostingHost::default().sandboxed_path("result.txt"),
+            None
+        );
+    }
+
+    #[test]
+    fn write0_prints_string() {
+        let mut state = core_state();
+        let mut core = Core::new(halted(MockCore::new()), &mut state);
+        let mut host = SemihostingHost::default();
+
+        core.write_8(RAM_START + 0x10, b"hello\0").unwrap();
+        request(&mut core, SYS_WRITE0, RAM_START + 0x10);
+
+        assert_eq!(
+            host.handle(&mut core).unwrap(),
+            Some(SemihostingEvent::Stdout(b"hello".to_vec()))
+        );
+        assert_eq!(pc(&mut core), RAM_START + 2);
+        assert_eq!(core.status().unwrap(), CoreStatus::Running);
+    }
+
+    #[test]
+    fn write0_at_end_of_memory() {
+        let mut state = core_state();
+        let mut core = Core::new(halted(MockCore::new()), &mut state);
+        let mut host = SemihostingHost::default();
+
+        // The RAM of the mock core ends at `RAM_START + 0x100`, with non-zero bytes.
+        request(&mut core, SYS_WRITE0, RAM_START + 0xfc);
+        assert!(host.handle(&mut core).is_err());
+
+        core.write_8(RAM_START + 0xfc, b"abc\0").unwrap();
+        request(&mut core, SYS_WRITE0, RAM_START + 0xfc);
+        assert_eq!(
+            host.handle(&mut core).unwrap(),
+            Some(SemihostingEvent::Stdout(b"abc".to_vec()))
+        );
+    }
+
+    #[test]
+    fn write_to_console() {
+        let mut state = core_state();
+        let mut core = Core::new(halted(MockCore::new()), &mut state);
+        let mut host = SemihostingHost::default();
+
+        core.write_32(RAM_START + 0x40, &[STDOUT_HANDLE, RAM_START + 0x10, 3])
+            .unwrap();
+        request(&mut core, SYS_WRITE, RAM_START + 0x40);
+        assert_eq!(
+            host.handle(&mut core).unwrap(),
+            Some(SemihostingEvent::Stdout(vec![0x10, 0x11, 0x12]))
+        );
+        assert_eq!(result(&mut core), 0);
+
+        core.write_32(RAM_START + 0x40, &[STDERR_HANDLE, RAM_START + 0x20, 2])
+            .unwrap();
+        request(&mut core, SYS_WRITE, RAM_START + 0x40);
+        assert_eq!(
+            host.handle(&mut core).unwrap(),
+            Some(SemihostingEvent::Stderr(vec![0x20, 0x21]))
+        );
+
+        // Nothing is written to a handle which is not open.
+        core.write_32(RAM_START + 0x40, &[FIRST_FILE_HANDLE, RAM_START + 0x20, 2])
+            .unwrap();
+        request(&mut core, SYS_WRITE, RAM_START + 0x40);
+        assert_eq!(
+            host.handle(&mut core).unwrap(),
+            Some(SemihostingEvent::Handled)
+        );
+        assert_eq!(result(&mut core), 2);
+    }
+
+    #[test]
+    fn open_and_read_file() {
+        let root =
+            std::env::temp_dir().join(format!("probe-rs-semihosting-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("data.txt"), b"semihosting").unwrap();
+
+        let mut state = core_state();
+        let mut core = Core::new(halted(MockCore::new()), &mut state);
+        let mut host = SemihostingHost::new(SemihostingOptions {
+            root: Some(root.clone()),
+            command_line: String::new(),
+        });
+
+        core.write_8(RAM_START + 0x10, b"data.txt").unwrap();
+        core.write_32(RAM_START + 0x40, &[RAM_START + 0x10, 0, 8])
+            .unwrap();
+        request(&mut core, SYS_OPEN, RAM_START + 0x40);
+        assert_eq!(
+            host.handle(&mut core).unwrap(),
+            Some(SemihostingEvent::Handled)
+        );
+        assert_eq!(result(&mut core), FIRST_FILE_HANDLE);
+
+        core.write_32(RAM_START + 0x40, &[FIRST_FILE_HANDLE, RAM_START + 0x80, 16])
+            .unwrap();
+        request(&mut core, SYS_READ, RAM_START + 0x40);
+        host.handle(&mut core).unwrap();
+
+        std::fs::remove_dir_all(&root).unwrap();
+
+        // The result is the number of bytes which were not read.
+        assert_eq!(result(&mut core), 5);
+        let mut data = [0; 11];
+        core.read_8(RAM_START + 0x80, &mut data).unwrap();
+        assert_eq!(&data, b"semihosting");
+    }
+
+    #[test]
+    fn exit_keeps_core_halted() {
+        let mut state = core_state();
+        let mut core = Core::new(halted(MockCore::new()), &mut state);
+        let mut host = SemihostingHost::default();
+
+        request(&mut core, SYS_EXIT, ADP_STOPPED_APPLICATION_EXIT);
+        assert_eq!(
+            host.handle(&mut core).unwrap(),
+            Some(SemihostingEvent::Exit(0))
+        );
+        assert_eq!(pc(&mut core), RAM_START);
+        assert_eq!(
+            core.status().unwrap(),
+            CoreStatus::Halted(HaltReason::Breakpoint)
+        );
+
+        request(&mut core, SYS_EXIT, 0x20023);
+        assert_eq!(
+            host.handle(&mut core).unwrap(),
+            Some(SemihostingEvent::Exit(1))
+        );
+
+        core.write_32(RAM_START + 0x40, &[ADP_STOPPED_APPLICATION_EXIT, 3])
+            .unwrap();
+        request(&mut core, SYS_EXIT_EXTENDED, RAM_START + 0x40);
+        assert_eq!(
+            host.handle(&mut core).unwrap(),
+            Some(SemihostingEvent::Exit(3))
+        );
+    }
+
+    #[test]
+    fn riscv_request_continues_after_ebreak() {
+        let mut state = core_state();
+        let mut core = Core::new(halted(MockCore::riscv()), &mut state);
+        let mut host = SemihostingHost::default();
+
+        core.write_8(RAM_START + 0x10, b"hi\0").unwrap();
+        request(&mut core, SYS_WRITE0, RAM_START + 0x10);
+
+        assert_eq!(
+            host.handle(&mut core).unwrap(),
+            Some(SemihostingEvent::Stdout(b"hi".to_vec()))
+        );
+        assert_eq!(pc(&mut core), RAM_START + 8);
+    }
+
+    #[test]
+    fn regular_breakpoint_is_not_handled() {
+        let mut state = core_state();
+        let mut core = Core::new(halted(MockCore::new()), &mut state);
+        let mut host = SemihostingHost::default();
+
+        core.write_8(RAM_START, &[0x00, 0xbe]).unwrap();
+
+        assert_eq!(host.handle(&mut core).unwrap(), None);
+        assert_eq!(
+            core.status().unwrap(),
+            CoreStatus::Halted(HaltReason::Breakpoint)
+        );
+    }
+}
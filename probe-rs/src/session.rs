@@ -1,7 +1,10 @@
 use crate::architecture::arm::sequences::DefaultArmSequence;
 use crate::architecture::arm::{ApAddress, DpAddress};
-use crate::config::{ChipInfo, MemoryRegion, RegistryError, Target, TargetSelector};
-use crate::core::{Architecture, CoreState, SpecificCoreState};
+use crate::config::{MemoryRegion, RegistryError, Target, TargetSelector};
+use crate::core::{
+    Architecture, CoreState, SharedSoftwareBreakpoints, SoftwareBreakpoint, SpecificCoreState,
+};
+use crate::flashing;
 use crate::{
     architecture::{
         arm::{
@@ -122,15 +125,39 @@ impl Session {
     ) -> Result<Self, Error> {
         let (mut probe, target) = get_target_from_selector(target, attach_method, probe)?;
 
+        // Software breakpoints in flash have to be inserted by reprogramming the flash.
+        let nvm_ranges: Vec<_> = target
+            .memory_map
+            .iter()
+            .filter_map(|region| match region {
+                MemoryRegion::Nvm(region) => Some(region.range.clone()),
+                _ => None,
+            })
+            .collect();
+
+        let memory_ranges: Vec<_> = target
+            .memory_map
+            .iter()
+            .map(|region| match region {
+                MemoryRegion::Ram(region) => region.range.clone(),
+                MemoryRegion::Generic(region) => region.range.clone(),
+                MemoryRegion::Nvm(region) => region.range.clone(),
+            })
+            .collect();
+
+        let software_breakpoints = SharedSoftwareBreakpoints::default();
+
         let cores = target
             .cores
             .iter()
             .enumerate()
             .map(|(id, core)| {
-                (
-                    SpecificCoreState::from_core_type(core.core_type),
-                    Core::create_state(id),
-                )
+                let mut state = Core::create_state(id);
+                state.nvm_ranges = nvm_ranges.clone();
+                state.memory_ranges = memory_ranges.clone();
+                state.software_breakpoints = software_breakpoints.clone();
+
+                (SpecificCoreState::from_core_type(core.core_type), state)
             })
             .collect();
 
@@ -292,6 +319,11 @@ impl Session {
 
         session.clear_all_hw_breakpoints()?;
 
+        // Not all cores have watchpoint units, and the attach should not fail because of them.
+        if let Err(err) = session.clear_all_hw_watchpoints() {
+            log::warn!("Could not clear all hardware watchpoints: {:?}", err);
+        }
+
         Ok(session)
     }
 
@@ -387,6 +419,14 @@ impl Session {
     /// This will recursively parse the Romtable of the attached target
     /// and create a list of all the contained components.
     pub fn get_arm_components(&mut self) -> Result<Vec<Component>, Error> {
+        // The target description can override the ROM table base of the AP of the first core.
+        let rom_table_override = self.target.rom_table_base.and_then(|base| {
+            match &self.target.cores[0].core_access_options {
+                probe_rs_target::CoreAccessOptions::Arm(options) => Some((options.ap, base)),
+                probe_rs_target::CoreAccessOptions::Riscv(_) => None,
+            }
+        });
+
         let interface = self.get_arm_interface()?;
 
         let mut components = Vec::new();
@@ -395,10 +435,19 @@ impl Session {
         let dp = DpAddress::Default;
 
         for ap_index in 0..(interface.num_access_ports(dp)? as u8) {
-            let ap_information = interface
+            let mut ap_information = interface
                 .ap_information(GenericAp::new(ApAddress { dp, ap: ap_index }))?
                 .clone();
 
+            if let (Some((ap, base)), ApInformation::MemoryAp(information)) =
+                (rom_table_override, &mut ap_information)
+            {
+                if ap == ap_index {
+                    log::debug!("Using ROM table base {:#x} for AP {}", base, ap);
+                    information.debug_base_address = base;
+                }
+            }
+
             let component = match ap_information {
                 ApInformation::MemoryAp(MemoryApInformation {
                     debug_base_address: 0,
@@ -490,6 +539,38 @@ impl Session {
         crate::architecture::arm::component::remove_swv_data_trace(&mut core, &components, unit)
     }
 
+    /// Begin sampling the program counter over SWV, about every `interval` cycles.
+    ///
+    /// The DWT only supports intervals which are multiples of 64 cycles up to 1024 cycles,
+    /// and multiples of 1024 cycles up to 16384 cycles. The interval which is used is returned.
+    pub fn enable_swv_pc_sampling(
+        &mut self,
+        core_index: usize,
+        interval: u32,
+    ) -> Result<u32, Error> {
+        let cyctap = interval > 16 * 64;
+        let tap_cycles = if cyctap { 1024 } else { 64 };
+        let postpreset = ((interval + tap_cycles / 2) / tap_cycles).clamp(1, 16) - 1;
+
+        let components = self.get_arm_components()?;
+        let mut core = self.core(core_index)?;
+        crate::architecture::arm::component::enable_swv_pc_sampling(
+            &mut core,
+            &components,
+            cyctap,
+            postpreset as u8,
+        )?;
+
+        Ok((postpreset + 1) * tap_cycles)
+    }
+
+    /// Stop sampling the program counter over SWV.
+    pub fn disable_swv_pc_sampling(&mut self, core_index: usize) -> Result<(), Error> {
+        let components = self.get_arm_components()?;
+        let mut core = self.core(core_index)?;
+        crate::architecture::arm::component::disable_swv_pc_sampling(&mut core, &components)
+    }
+
     /// Returns the memory map of the target.
     #[deprecated = "Use the Session::target function instead"]
     pub fn memory_map(&self) -> &[MemoryRegion] {
@@ -511,6 +592,160 @@ impl Session {
                 .and_then(|mut core| core.clear_all_hw_breakpoints())
         })
     }
+
+    /// Clears all hardware watchpoints on all cores
+    pub fn clear_all_hw_watchpoints(&mut self) -> Result<(), Error> {
+        { 0..self.cores.len() }.try_for_each(|n| {
+            self.core(n)
+                .and_then(|mut core| core.clear_all_hw_watchpoints())
+        })
+    }
+
+    /// Set a breakpoint on core `core_index`
+    ///
+    /// A hardware breakpoint is used as long as the core has a free comparator unit.
+    /// Once all of them are in use, a software breakpoint is inserted instead.
+    /// In RAM, the instruction at `address` is patched directly, see [`Core::set_breakpoint`].
+    /// In flash, the sector containing `address` is read, patched and written back using a
+    /// [`FlashLoader`](crate::flashing::FlashLoader).
+    ///
+    /// Reprogramming flash runs the flash algorithm on the target, which resets the core.
+    /// The core is halted after the reset, but any other state of the program is lost.
+    /// A core which halted on a software breakpoint in flash can not be resumed until the breakpoint is cleared.
+    pub fn set_breakpoint(&mut self, core_index: usize, address: u32) -> Result<(), Error> {
+        let (original_instruction, breakpoint_instruction) = {
+            let mut core = self.core(core_index)?;
+
+            if core.sw_breakpoints().contains(&address)
+                || !core.is_nvm_address(address)
+                || core.hw_breakpoint_available(address)?
+            {
+                return core.set_breakpoint(address);
+            }
+
+            core.read_sw_breakpoint_instruction(address)?
+        };
+
+        log::warn!(
+            "Setting a software breakpoint in flash at {:#010x}, this resets core {}",
+            address,
+            core_index
+        );
+
+        flashing::patch_flash(self, &[(address, breakpoint_instruction)])
+            .map_err(|error| Error::Other(error.into()))?;
+
+        let mut core = self.core(core_index)?;
+        core.software_breakpoints().insert(
+            address,
+            SoftwareBreakpoint {
+                original_instruction,
+                breakpoint_instruction,
+                in_flash: true,
+            },
+        );
+        core.enable_breakpoints(true)
+    }
+
+    /// Clear the breakpoint at `address` on core `core_index`
+    ///
+    /// This clears hardware and software breakpoints set by [`Session::set_breakpoint`].
+    /// Clearing a software breakpoint in flash reprograms the flash, which resets the core.
+    pub fn clear_breakpoint(&mut self, core_index: usize, address: u32) -> Result<(), Error> {
+        let flash_breakpoint = self
+            .core(core_index)?
+            .software_breakpoints()
+            .get(&address)
+            .filter(|breakpoint| breakpoint.in_flash)
+            .is_some();
+
+        if flash_breakpoint {
+            log::warn!(
+                "Clearing a software breakpoint in flash at {:#010x}, this resets core {}",
+                address,
+                core_index
+            );
+
+            self.clear_flash_breakpoints(core_index, &[address])
+        } else {
+            self.core(core_index)?.clear_breakpoint(address)
+        }
+    }
+
+    /// Clears all software breakpoints, restoring the original instructions
+    ///
+    /// Software breakpoints are shared by all cores, so they are cleared using the first core which can be attached.
+    /// If there are software breakpoints in flash, the flash is reprogrammed once to remove all of them.
+    pub fn clear_all_sw_breakpoints(&mut self) -> Result<(), Error> {
+        let mut result = Ok(None);
+
+        for n in 0..self.cores.len() {
+            match self
+                .core(n)
+                .and_then(|mut core| core.clear_all_sw_breakpoints())
+            {
+                Ok(()) => {
+                    result = Ok(Some(n));
+                    break;
+                }
+                Err(error) => result = Err(error),
+            }
+        }
+
+        let core_index = match result? {
+            Some(core_index) => core_index,
+            None => return Ok(()),
+        };
+
+        // Only the breakpoints in flash are left now.
+        let addresses = self.core(core_index)?.sw_breakpoints();
+
+        if addresses.is_empty() {
+            Ok(())
+        } else {
+            self.clear_flash_breakpoints(core_index, &addresses)
+        }
+    }
+
+    /// Restores the original instructions of the software breakpoints in flash at `addresses`,
+    /// by reprogramming the flash once.
+    fn clear_flash_breakpoints(
+        &mut self,
+        core_index: usize,
+        addresses: &[u32],
+    ) -> Result<(), Error> {
+        let breakpoints: Vec<(u32, SoftwareBreakpoint)> = {
+            let core = self.core(core_index)?;
+            let software_breakpoints = core.software_breakpoints();
+
+            let breakpoints = addresses
+                .iter()
+                .filter_map(|address| {
+                    software_breakpoints
+                        .get(address)
+                        .map(|breakpoint| (*address, breakpoint.clone()))
+                })
+                .collect();
+            breakpoints
+        };
+
+        let patches: Vec<(u32, &[u8])> = breakpoints
+            .iter()
+            .map(|(address, breakpoint)| (*address, breakpoint.original_instruction.as_slice()))
+            .collect();
+
+        flashing::patch_flash(self, &patches).map_err(|error| Error::Other(error.into()))?;
+
+        let core = self.core(core_index)?;
+        let mut software_breakpoints = core.software_breakpoints();
+        for (address, _) in &breakpoints {
+            software_breakpoints.remove(address);
+        }
+
+        log::debug!("Cleared SW breakpoints in flash at {:#010x?}", addresses);
+
+        Ok(())
+    }
 }
 
 // This test ensures that [Session] is fully [Send] + [Sync].
@@ -519,6 +754,10 @@ static_assertions::assert_impl_all!(Session: Send);
 // TODO tiwalun: Enable again, after rework of Session::new is done.
 impl Drop for Session {
     fn drop(&mut self) {
+        if let Err(err) = self.clear_all_sw_breakpoints() {
+            log::warn!("Could not clear all software breakpoints: {:?}", err);
+        }
+
         let result = { 0..self.cores.len() }.try_for_each(|i| {
             self.core(i)
                 .and_then(|mut core| core.clear_all_hw_breakpoints())
@@ -527,6 +766,10 @@ impl Drop for Session {
         if let Err(err) = result {
             log::warn!("Could not clear all hardware breakpoints: {:?}", err);
         }
+
+        if let Err(err) = self.clear_all_hw_watchpoints() {
+            log::warn!("Could not clear all hardware watchpoints: {:?}", err);
+        }
     }
 }
 
@@ -546,7 +789,10 @@ fn get_target_from_selector(
         TargetSelector::Unspecified(name) => crate::config::get_target_by_name(name)?,
         TargetSelector::Specified(target) => target,
         TargetSelector::Auto => {
-            let mut found_chip = None;
+            let mut found_target = None;
+            // A specific error, e.g. for a part number from the ROM table which is not in the
+            // registry, is returned instead of the generic `ChipAutodetectFailed`.
+            let mut detection_error = None;
 
             // At this point we do not know what the target is, so we cannot use the chip specific reset sequence.
             // Thus, we try just using a normal reset for target detection if we want to do so under reset.
@@ -564,14 +810,15 @@ fn get_target_from_selector(
                         // TODO:
                         let dp = DpAddress::Default;
 
-                        let found_arm_chip = interface
-                            .read_chip_info_from_rom_table(dp)
-                            .unwrap_or_else(|e| {
-                                log::info!("Error during auto-detection of ARM chips: {}", e);
-                                None
-                            });
-
-                        found_chip = found_arm_chip.map(ChipInfo::from);
+                        found_target =
+                            match crate::config::identify_arm_target(interface.as_mut(), dp) {
+                                Ok(target) => Some(target),
+                                Err(e) => {
+                                    log::info!("Error during auto-detection of ARM chips: {}", e);
+                                    detection_error = Some(e);
+                                    None
+                                }
+                            };
 
                         probe = interface.close();
                     }
@@ -584,12 +831,21 @@ fn get_target_from_selector(
                 log::debug!("No ARM interface was present. Skipping Riscv autodetect.");
             }
 
-            if found_chip.is_none() && probe.has_riscv_interface() {
+            if found_target.is_none() && probe.has_riscv_interface() {
                 match probe.try_into_riscv_interface() {
                     Ok(mut interface) => {
-                        let idcode = interface.read_idcode();
-
-                        log::debug!("ID Code read over JTAG: {:x?}", idcode);
+                        found_target = match crate::config::identify_riscv_target(&mut interface) {
+                            Ok(target) => Some(target),
+                            Err(e) => {
+                                log::info!("Error during auto-detection of RISCV chips: {}", e);
+                                if !matches!(e, RegistryError::ChipAutodetectFailed)
+                                    || detection_error.is_none()
+                                {
+                                    detection_error = Some(e);
+                                }
+                                None
+                            }
+                        };
 
                         probe = interface.close();
                     }
@@ -605,10 +861,13 @@ fn get_target_from_selector(
             // Now we can deassert reset in case we asserted it before. This is always okay.
             probe.target_reset_deassert()?;
 
-            if let Some(chip) = found_chip {
-                crate::config::get_target_by_chip_info(chip)?
-            } else {
-                return Err(Error::ChipNotFound(RegistryError::ChipAutodetectFailed));
+            match found_target {
+                Some(target) => target,
+                None => {
+                    return Err(Error::ChipNotFound(
+                        detection_error.unwrap_or(RegistryError::ChipAutodetectFailed),
+                    ))
+                }
             }
         }
     };
@@ -111,9 +111,54 @@ impl Channel {
         let mut block = [0u32; 2];
         core.read_32(self.ptr + Self::O_WRITE as u32, block.as_mut())?;
 
-        let write: u32 = block[0];
-        let read: u32 = block[1];
+        self.validate_pointers(block[0], block[1], dir)
+    }
+
+    /// Returns the write and read pointers of the channel from a snapshot of the control block.
+    fn snapshot_pointers(
+        &self,
+        snapshot: &ChannelSnapshot,
+        dir: &'static str,
+    ) -> Result<(u32, u32), Error> {
+        if snapshot.core_id != self.core_id {
+            return Err(Error::IncorrectCoreSpecified(
+                self.core_id,
+                snapshot.core_id,
+            ));
+        }
+
+        let descriptor = snapshot.descriptor(self.ptr).ok_or_else(|| {
+            Error::ControlBlockCorrupted(format!(
+                "{} channel {} is not part of the snapshot of the control block",
+                dir, self.number
+            ))
+        })?;
+
+        // The target re-initialized RTT, e.g. after a reset, and the cached channel is stale.
+        if descriptor[Self::O_BUFFER_PTR / 4] != self.buffer_ptr
+            || descriptor[Self::O_SIZE / 4] != self.size
+        {
+            return Err(Error::ControlBlockCorrupted(format!(
+                "The buffer of {} channel {} ({}) has changed",
+                dir,
+                self.number,
+                self.name().unwrap_or("no name"),
+            )));
+        }
 
+        self.validate_pointers(
+            descriptor[Self::O_WRITE / 4],
+            descriptor[Self::O_READ / 4],
+            dir,
+        )
+    }
+
+    fn validate_pointers(
+        &self,
+        write: u32,
+        read: u32,
+        dir: &'static str,
+    ) -> Result<(u32, u32), Error> {
         let validate = |which, value| {
             if value >= self.size {
                 Err(Error::ControlBlockCorrupted(format!(
@@ -137,6 +182,38 @@ impl Channel {
     }
 }
 
+/// The descriptors of all channels of a control block, read with a single memory transfer by
+/// [`ControlBlock::snapshot`](crate::ControlBlock::snapshot).
+///
+/// The write pointers of the up channels in a snapshot may lag behind the target,
+/// which only delays the data written in the meantime until the next snapshot.
+#[derive(Debug)]
+pub struct ChannelSnapshot {
+    core_id: usize,
+    array_ptr: u32,
+    words: Vec<u32>,
+}
+
+impl ChannelSnapshot {
+    pub(crate) fn new(core_id: usize, array_ptr: u32, words: Vec<u32>) -> Self {
+        Self {
+            core_id,
+            array_ptr,
+            words,
+        }
+    }
+
+    /// Returns the words of the channel descriptor at `ptr`.
+    fn descriptor(&self, ptr: u32) -> Option<&[u32]> {
+        let offset = ptr.checked_sub(self.array_ptr)? as usize;
+        if offset % Channel::SIZE != 0 {
+            return None;
+        }
+
+        self.words.get(offset / 4..(offset + Channel::SIZE) / 4)
+    }
+}
+
 /// RTT up (target to host) channel.
 #[derive(Debug)]
 pub struct UpChannel(pub(crate) Channel);
@@ -189,10 +266,21 @@ impl UpChannel {
         Ok(())
     }
 
-    fn read_core(&self, core: &mut Core, mut buf: &mut [u8]) -> Result<(u32, usize), Error> {
+    fn read_core(&self, core: &mut Core, buf: &mut [u8]) -> Result<(u32, usize), Error> {
         self.0.validate_core_id(core)?;
-        let (write, mut read) = self.0.read_pointers(core, "up")?;
+        let (write, read) = self.0.read_pointers(core, "up")?;
 
+        self.read_range(core, write, read, buf)
+    }
+
+    /// Reads the data between the `read` and `write` pointers, as far as it fits into `buf`.
+    fn read_range(
+        &self,
+        core: &mut Core,
+        write: u32,
+        mut read: u32,
+        mut buf: &mut [u8],
+    ) -> Result<(u32, usize), Error> {
         let mut total = 0;
 
         // Read while buffer contains data and output buffer has space (maximum of two iterations)
@@ -235,6 +323,33 @@ impl UpChannel {
         Ok(total)
     }
 
+    /// Reads some bytes from the channel like [`read`](UpChannel::read), but takes the write and
+    /// read pointers from a snapshot of the control block instead of reading them from the target.
+    ///
+    /// Only the part of the buffer which contains new data is read,
+    /// and the target is not accessed at all if the channel is empty.
+    pub fn read_snapshot(
+        &self,
+        core: &mut Core,
+        snapshot: &ChannelSnapshot,
+        buf: &mut [u8],
+    ) -> Result<usize, Error> {
+        self.0.validate_core_id(core)?;
+        let (write, read) = self.0.snapshot_pointers(snapshot, "up")?;
+
+        if write == read {
+            return Ok(0);
+        }
+
+        let (read, total) = self.read_range(core, write, read, buf)?;
+
+        if total > 0 {
+            core.write_word_32(self.0.ptr + Channel::O_READ as u32, read)?;
+        }
+
+        Ok(total)
+    }
+
     /// Peeks at the current data in the channel buffer, copies data into the specified buffer and
     /// returns how many bytes were read.
     ///
@@ -36,7 +36,7 @@ use std::ops::Range;
 ///         * RTT Channel names are correct, but no data, or corrupted data, will be reported from RTT, because the buffer sizes are incorrect.
 #[derive(Debug)]
 pub struct Rtt {
-    ptr: u32,
+    control_block: ControlBlock,
     up_channels: Channels<UpChannel>,
     down_channels: Channels<DownChannel>,
 }
@@ -154,7 +154,11 @@ impl Rtt {
         }
 
         Ok(Some(Rtt {
-            ptr,
+            control_block: ControlBlock {
+                ptr,
+                max_up_channels,
+                max_down_channels,
+            },
             up_channels: Channels(up_channels),
             down_channels: Channels(down_channels),
         }))
@@ -237,7 +241,7 @@ impl Rtt {
 
         if instances.len() > 1 {
             return Err(Error::MultipleControlBlocksFound(
-                instances.into_iter().map(|i| i.ptr).collect(),
+                instances.into_iter().map(|i| i.ptr()).collect(),
             ));
         }
 
@@ -246,7 +250,15 @@ impl Rtt {
 
     /// Returns the memory address of the control block in target memory.
     pub fn ptr(&self) -> u32 {
-        self.ptr
+        self.control_block.ptr
+    }
+
+    /// Returns the location of the channel descriptors in target memory.
+    ///
+    /// Unlike the channels, the [`ControlBlock`] can be copied, so it remains available after the
+    /// channels were taken out of the [`Rtt`].
+    pub fn control_block(&self) -> ControlBlock {
+        self.control_block
     }
 
     /// Gets the detected up channels.
@@ -260,6 +272,35 @@ impl Rtt {
     }
 }
 
+/// The location and layout of an RTT control block in target memory.
+///
+/// Use [`ControlBlock::snapshot`] to read the state of all channels with a single memory transfer,
+/// and pass the snapshot to [`UpChannel::read_snapshot`] instead of reading each channel separately.
+#[derive(Clone, Copy, Debug)]
+pub struct ControlBlock {
+    ptr: u32,
+    max_up_channels: usize,
+    max_down_channels: usize,
+}
+
+impl ControlBlock {
+    /// Returns the memory address of the control block in target memory.
+    pub fn ptr(&self) -> u32 {
+        self.ptr
+    }
+
+    /// Reads the descriptors of all up and down channels in one block transfer.
+    pub fn snapshot(&self, core: &mut Core) -> Result<ChannelSnapshot, Error> {
+        let mut words =
+            vec![0u32; (self.max_up_channels + self.max_down_channels) * Channel::SIZE / 4];
+        let array_ptr = self.ptr + Rtt::O_CHANNEL_ARRAYS as u32;
+
+        core.read_32(array_ptr, &mut words)?;
+
+        Ok(ChannelSnapshot::new(core.id(), array_ptr, words))
+    }
+}
+
 /// Used to specify which memory regions to scan for the RTT control block.
 #[derive(Clone, Debug)]
 pub enum ScanRegion {
@@ -1,10 +1,15 @@
 use probe_rs::Permissions;
 use probe_rs::{config::TargetSelector, DebugProbeInfo, Probe};
+use probe_rs_cli_util::rtt::{
+    sink::{RttSink, RttSinkConfig},
+    RttConfig,
+};
 use probe_rs_rtt::{Channels, Rtt, RttChannel, ScanRegion};
 
 use clap::Parser;
 use std::io::prelude::*;
 use std::io::{stdin, stdout};
+use std::path::PathBuf;
 use std::sync::mpsc::{channel, Receiver};
 use std::thread;
 
@@ -98,6 +103,13 @@ struct Opts {
         parse(try_from_str=parse_scan_region),
         help = "Memory region to scan for control block. You can specify either an exact starting address '0x1000' or a range such as '0x0000..0x1000'. Both decimal and hex are accepted.")]
     scan_region: ScanRegion,
+
+    #[clap(
+        long,
+        parse(from_os_str),
+        help = "RTT configuration file (TOML, or JSON as in the debugger configuration). The sinks configured for the up channel receive its data in addition to stdout."
+    )]
+    config: Option<PathBuf>,
 }
 
 fn main() {
@@ -215,6 +227,34 @@ fn run() -> i32 {
         rtt.down_channels().take(0)
     };
 
+    let sink_configs = match &opts.config {
+        Some(path) => match RttConfig::from_file(path) {
+            Ok(config) => up_channel
+                .as_ref()
+                .and_then(|up_channel| {
+                    config
+                        .channels
+                        .into_iter()
+                        .find(|channel| channel.channel_number == Some(up_channel.number()))
+                })
+                .map(|channel| channel.sinks)
+                .unwrap_or_default(),
+            Err(err) => {
+                eprintln!("Error reading the RTT configuration: {}", err);
+                return 1;
+            }
+        },
+        None => vec![],
+    };
+
+    let mut sinks = match open_sinks(&sink_configs) {
+        Ok(sinks) => sinks,
+        Err(err) => {
+            eprintln!("Error opening RTT sink: {}", err);
+            return 1;
+        }
+    };
+
     let stdin = down_channel.as_ref().map(|_| stdin_channel());
 
     eprintln!("Found control block at 0x{:08x}", rtt.ptr());
@@ -232,6 +272,16 @@ fn run() -> i32 {
                 }
             };
 
+            if count > 0 {
+                let formatted = String::from_utf8_lossy(&up_buf[..count]);
+                for sink in sinks.iter_mut() {
+                    if let Err(err) = sink.write(&up_buf[..count], &formatted) {
+                        eprintln!("\nError writing to RTT sink: {}", err);
+                        return 1;
+                    }
+                }
+            }
+
             match stdout().write_all(&up_buf[..count]) {
                 Ok(_) => {
                     stdout().flush().ok();
@@ -299,6 +349,10 @@ fn list_channels(channels: &Channels<impl RttChannel>) {
     }
 }
 
+fn open_sinks(configs: &[RttSinkConfig]) -> std::io::Result<Vec<RttSink>> {
+    configs.iter().map(RttSink::open).collect()
+}
+
 fn stdin_channel() -> Receiver<Vec<u8>> {
     let (tx, rx) = channel();
 
@@ -13,6 +13,8 @@ use probe_rs::{Architecture, CoreType};
 use probe_rs_target::{ArmCoreAccessOptions, CoreAccessOptions, RiscvCoreAccessOptions};
 use tokio::runtime::Builder;
 
+use crate::sequences::{extract_debug_description, DebugDescription};
+
 pub(crate) enum Kind<'a, T>
 where
     T: std::io::Seek + std::io::Read,
@@ -23,6 +25,7 @@ where
 
 pub(crate) fn handle_package<T>(
     pdsc: Package,
+    debug_description: DebugDescription,
     mut kind: Kind<T>,
     families: &mut Vec<ChipFamily>,
 ) -> Result<()>
@@ -47,12 +50,18 @@ where
                 manufacturer: None,
                 variants: Vec::new(),
                 flash_algorithms: Vec::new(),
+                debug_sequences: Vec::new(),
                 source: probe_rs::config::TargetDescriptionSource::BuiltIn,
             });
             // This unwrap is always safe as we insert at least one item previously.
             families.last_mut().unwrap()
         };
 
+        // The debug sequences are defined for the whole pack.
+        if family.debug_sequences.is_empty() {
+            family.debug_sequences = debug_description.sequences.clone();
+        }
+
         // Extract the RAM info from the .pdsc file.
         let ram = get_ram(&device);
 
@@ -133,11 +142,14 @@ where
             .collect::<Result<Vec<_>>>()?;
 
         family.variants.push(Chip {
-            name: device_name,
+            name: device_name.clone(),
             part: None,
             cores,
             memory_map,
             flash_algorithms: flash_algorithm_names,
+            debug_vars: debug_description.debug_vars.get(&device_name).cloned(),
+            debug_sequence: None,
+            detection: vec![],
         });
     }
 
@@ -192,8 +204,11 @@ pub(crate) fn visit_dirs(path: &Path, families: &mut Vec<ChipFamily>) -> Result<
             if extension == "pdsc" {
                 log::info!("Found .pdsc file: {}", path.display());
 
+                let pdsc = fs::read_to_string(entry.path())?;
+
                 handle_package::<std::fs::File>(
-                    Package::from_path(&entry.path())?,
+                    Package::from_string(&pdsc)?,
+                    debug_description(&pdsc),
                     Kind::Directory(path),
                     families,
                 )
@@ -232,7 +247,12 @@ pub(crate) fn visit_file(path: &Path, families: &mut Vec<ChipFamily>) -> Result<
 
     drop(pdsc_file);
 
-    handle_package(package, Kind::Archive(&mut archive), families)
+    handle_package(
+        package,
+        debug_description(&pdsc),
+        Kind::Archive(&mut archive),
+        families,
+    )
 }
 
 pub(crate) fn visit_arm_files(families: &mut Vec<ChipFamily>) -> Result<()> {
@@ -337,7 +357,12 @@ pub(crate) async fn visit_arm_file(pack: &PdscRef) -> Vec<ChipFamily> {
 
     let mut families = vec![];
 
-    match handle_package(package, Kind::Archive(&mut archive), &mut families) {
+    match handle_package(
+        package,
+        debug_description(&pdsc),
+        Kind::Archive(&mut archive),
+        &mut families,
+    ) {
         Ok(_) => {}
         Err(err) => log::error!("Something went wrong while handling pack {}: {}", url, err),
     };
@@ -345,6 +370,16 @@ pub(crate) async fn visit_arm_file(pack: &PdscRef) -> Vec<ChipFamily> {
     families
 }
 
+/// Extracts the debug sequences and debug variables from a pdsc.
+///
+/// Packs with malformed sequences are still converted, just without any sequences.
+fn debug_description(pdsc: &str) -> DebugDescription {
+    extract_debug_description(pdsc).unwrap_or_else(|error| {
+        log::warn!("Failed to extract the debug sequences: {}", error);
+        DebugDescription::default()
+    })
+}
+
 /// Extracts the pdsc out of a ZIP archive.
 pub(crate) fn find_pdsc_in_archive<T>(
     archive: &mut zip::ZipArchive<T>,
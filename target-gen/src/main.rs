@@ -3,6 +3,7 @@ pub mod fetch;
 pub mod flash_device;
 pub mod generate;
 pub mod parser;
+pub mod sequences;
 
 use std::{
     fs::{create_dir, File, OpenOptions},
@@ -186,8 +187,12 @@ fn cmd_elf(
                     }),
                 ],
                 flash_algorithms: vec![algorithm_name],
+                debug_vars: None,
+                debug_sequence: None,
+                detection: vec![],
             }],
             flash_algorithms: vec![algorithm],
+            debug_sequences: vec![],
             source: BuiltIn,
         };
 
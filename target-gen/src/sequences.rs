@@ -0,0 +1,215 @@
+//! Extraction of debug sequences and debug variables from `.pdsc` files.
+//!
+//! The `cmsis-pack` crate does not parse the `<sequences>` and `<debugvars>` elements,
+//! so they are read from the raw XML here.
+
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+use probe_rs_target::{SequenceBlock, SequenceControl, SequenceDescription, SequenceElement};
+use roxmltree::{Document, Node};
+
+/// The debug description of a CMSIS-Pack.
+#[derive(Debug, Default)]
+pub(crate) struct DebugDescription {
+    /// All debug sequences of the pack.
+    pub(crate) sequences: Vec<SequenceDescription>,
+    /// The debug variables of each device, by device name.
+    pub(crate) debug_vars: HashMap<String, String>,
+}
+
+/// Extracts the debug sequences and the debug variables of all devices from the contents of a `.pdsc` file.
+pub(crate) fn extract_debug_description(pdsc: &str) -> Result<DebugDescription> {
+    let document = Document::parse(pdsc)?;
+    let package = document.root_element();
+
+    let sequences = children(package, "sequences")
+        .flat_map(|sequences| children(sequences, "sequence"))
+        .map(parse_sequence)
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut debug_vars = HashMap::new();
+    for family in children(package, "devices").flat_map(|devices| children(devices, "family")) {
+        collect_debug_vars(family, None, &mut debug_vars);
+    }
+
+    Ok(DebugDescription {
+        sequences,
+        debug_vars,
+    })
+}
+
+fn children<'a, 'input: 'a>(
+    node: Node<'a, 'input>,
+    tag_name: &'a str,
+) -> impl Iterator<Item = Node<'a, 'input>> + 'a {
+    node.children()
+        .filter(move |child| child.has_tag_name(tag_name))
+}
+
+fn parse_sequence(node: Node) -> Result<SequenceDescription> {
+    let name = node
+        .attribute("name")
+        .ok_or_else(|| anyhow!("Debug sequence without a name"))?;
+
+    Ok(SequenceDescription {
+        name: name.to_owned(),
+        processor: node.attribute("Pname").map(str::to_owned),
+        disabled: is_true(node.attribute("disable")),
+        body: parse_elements(node)
+            .map_err(|e| anyhow!("Failed to parse debug sequence '{}': {}", name, e))?,
+    })
+}
+
+fn parse_elements(node: Node) -> Result<Vec<SequenceElement>> {
+    node.children()
+        .filter(|child| child.is_element())
+        .map(|child| match child.tag_name().name() {
+            "block" => Ok(SequenceElement::Block(SequenceBlock {
+                atomic: is_true(child.attribute("atomic")),
+                code: text(child),
+            })),
+            "control" => Ok(SequenceElement::Control(SequenceControl {
+                if_condition: child.attribute("if").map(str::to_owned),
+                while_condition: child.attribute("while").map(str::to_owned),
+                timeout: child
+                    .attribute("timeout")
+                    .map(|timeout| {
+                        timeout
+                            .trim()
+                            .parse()
+                            .map_err(|_| anyhow!("Invalid timeout '{}'", timeout))
+                    })
+                    .transpose()?,
+                body: parse_elements(child)?,
+            })),
+            other => Err(anyhow!("Unexpected element <{}>", other)),
+        })
+        .collect()
+}
+
+/// Walks the `<family>`, `<subFamily>`, `<device>` and `<variant>` hierarchy.
+/// Debug variables are inherited, and the innermost `<debugvars>` element takes precedence.
+fn collect_debug_vars(
+    node: Node,
+    inherited: Option<&str>,
+    debug_vars: &mut HashMap<String, String>,
+) {
+    let own = children(node, "debugvars")
+        .map(text)
+        .reduce(|all, vars| format!("{}\n{}", all, vars));
+    let current = own.as_deref().or(inherited);
+
+    let name = match node.tag_name().name() {
+        "device" => node.attribute("Dname"),
+        "variant" => node.attribute("Dvariant"),
+        _ => None,
+    };
+
+    if let (Some(name), Some(vars)) = (name, current) {
+        debug_vars.insert(name.to_owned(), vars.to_owned());
+    }
+
+    for child in node.children().filter(|child| {
+        child.has_tag_name("subFamily")
+            || child.has_tag_name("device")
+            || child.has_tag_name("variant")
+    }) {
+        collect_debug_vars(child, current, debug_vars);
+    }
+}
+
+/// Returns all text inside an element, with surrounding whitespace removed.
+fn text(node: Node) -> String {
+    node.descendants()
+        .filter(|child| child.is_text())
+        .filter_map(|child| child.text())
+        .collect::<String>()
+        .trim()
+        .to_owned()
+}
+
+fn is_true(attribute: Option<&str>) -> bool {
+    matches!(attribute, Some("1") | Some("true"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const PDSC: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<package schemaVersion="1.7.7">
+  <devices>
+    <family Dfamily="Example Series" Dvendor="Example:0">
+      <debugvars>__var FamilyVar = 1;</debugvars>
+      <device Dname="EX1"/>
+      <subFamily DsubFamily="EX2">
+        <debugvars>
+          __var SubFamilyVar = 2;
+        </debugvars>
+        <device Dname="EX2A"/>
+      </subFamily>
+    </family>
+  </devices>
+  <sequences>
+    <sequence name="ResetSystem">
+      <block atomic="1">
+        Write32(0xE000ED0C, 0x05FA0004);
+      </block>
+      <control while="(Read32(0xE000EDF0) &amp; 0x02000000) != 0" timeout="500000"/>
+    </sequence>
+    <sequence name="DebugCoreStart" Pname="cm4" disable="true"/>
+  </sequences>
+</package>
+"#;
+
+    #[test]
+    fn extract_sequences() {
+        let description = extract_debug_description(PDSC).unwrap();
+
+        assert_eq!(
+            description.sequences,
+            vec![
+                SequenceDescription {
+                    name: "ResetSystem".to_owned(),
+                    processor: None,
+                    disabled: false,
+                    body: vec![
+                        SequenceElement::Block(SequenceBlock {
+                            atomic: true,
+                            code: "Write32(0xE000ED0C, 0x05FA0004);".to_owned(),
+                        }),
+                        SequenceElement::Control(SequenceControl {
+                            if_condition: None,
+                            while_condition: Some(
+                                "(Read32(0xE000EDF0) & 0x02000000) != 0".to_owned()
+                            ),
+                            timeout: Some(500000),
+                            body: vec![],
+                        }),
+                    ],
+                },
+                SequenceDescription {
+                    name: "DebugCoreStart".to_owned(),
+                    processor: Some("cm4".to_owned()),
+                    disabled: true,
+                    body: vec![],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn inherit_debug_vars() {
+        let description = extract_debug_description(PDSC).unwrap();
+
+        assert_eq!(
+            description.debug_vars.get("EX1").map(String::as_str),
+            Some("__var FamilyVar = 1;")
+        );
+        assert_eq!(
+            description.debug_vars.get("EX2A").map(String::as_str),
+            Some("__var SubFamilyVar = 2;")
+        );
+    }
+}